@@ -0,0 +1,49 @@
+//! Standalone N42 consensus client.
+//!
+//! Seals `APos` blocks for an execution node started with `--consensus.external`, talking to it
+//! over the authenticated Engine API so the signer key can live on a separate host.
+
+use alloy_primitives::B256;
+use alloy_rpc_types_engine::JwtSecret;
+use alloy_signer_local::PrivateKeySigner;
+use clap::Parser;
+use consensus_client::remote;
+use reth_tracing::{RethTracer, Tracer};
+use std::{path::PathBuf, time::Duration};
+
+/// Seals N42 blocks for an execution node running with `--consensus.external`.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// Authenticated Engine API endpoint of the execution node.
+    #[arg(long = "engine.url", default_value = "http://127.0.0.1:8551")]
+    engine_url: String,
+
+    /// Path to the hex encoded JWT secret shared with the execution node.
+    #[arg(long = "engine.jwt-secret", value_name = "PATH")]
+    jwt_secret: PathBuf,
+
+    /// The signer private key to seal blocks with.
+    #[arg(
+        long = "consensus-signer-private-key",
+        env = "CONSENSUS_SIGNER_PRIVATE_KEY",
+        value_name = "CONSENSUS_SIGNER_PRIVATE_KEY"
+    )]
+    signer_private_key: B256,
+
+    /// Delay between two attempts to build on the head of the execution node.
+    #[arg(long = "poll-interval", default_value = "500ms", value_parser = humantime::parse_duration)]
+    poll_interval: Duration,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let _guard = RethTracer::new().init()?;
+    let args = Args::parse();
+
+    let secret = JwtSecret::from_file(&args.jwt_secret)?;
+    let signer = PrivateKeySigner::from_bytes(&args.signer_private_key)?;
+    let sealer = remote::connect(&args.engine_url, secret, signer, args.poll_interval)?;
+    sealer.run().await;
+    Ok(())
+}
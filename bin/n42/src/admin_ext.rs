@@ -0,0 +1,124 @@
+//! `admin` namespace methods managing the bans of the peer set.
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::{error::INTERNAL_ERROR_CODE, ErrorObject, ErrorObjectOwned}};
+use reth_network_api::{BanEntry, BanTarget, Peers};
+use std::time::Duration;
+
+/// Reason recorded for the bans without one.
+const DEFAULT_BAN_REASON: &str = "banned by admin";
+
+/// Ban management methods of the `admin` namespace.
+#[cfg_attr(not(test), rpc(server, namespace = "admin"))]
+#[cfg_attr(test, rpc(server, client, namespace = "admin"))]
+pub trait AdminBansApi {
+    /// Bans a peer id, enode URL, IP address or CIDR subnet for `duration` seconds, or until it is
+    /// unbanned if omitted. Matching peers are disconnected.
+    #[method(name = "banPeer")]
+    async fn ban_peer(
+        &self,
+        target: BanTarget,
+        duration: Option<u64>,
+        reason: Option<String>,
+        ) -> RpcResult<bool>;
+
+    /// Lifts a ban, returns `false` if the target wasn't banned.
+    #[method(name = "unbanPeer")]
+    async fn unban_peer(
+        &self,
+        target: BanTarget,
+        ) -> RpcResult<bool>;
+
+    /// The current bans, with their reason and expiry.
+    #[method(name = "listBans")]
+    async fn list_bans(
+        &self,
+        ) -> RpcResult<Vec<BanEntry>>;
+}
+
+/// The type that implements the ban methods of the `admin` namespace
+pub struct AdminBans<Net> {
+    pub network: Net,
+}
+
+#[jsonrpsee::core::async_trait]
+impl<Net> AdminBansApiServer for AdminBans<Net>
+where
+    Net: Peers + 'static,
+{
+    async fn ban_peer(
+        &self,
+        target: BanTarget,
+        duration: Option<u64>,
+        reason: Option<String>,
+        ) -> RpcResult<bool> {
+        let reason = reason.unwrap_or_else(|| DEFAULT_BAN_REASON.to_string());
+        self.network.ban(target, duration.map(Duration::from_secs), reason);
+        Ok(true)
+    }
+
+    async fn unban_peer(
+        &self,
+        target: BanTarget,
+        ) -> RpcResult<bool> {
+        self.network.unban(target).await.map_err(internal_error)
+    }
+
+    async fn list_bans(
+        &self,
+        ) -> RpcResult<Vec<BanEntry>> {
+        self.network.bans().await.map_err(internal_error)
+    }
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> ErrorObjectOwned {
+    ErrorObject::owned(INTERNAL_ERROR_CODE, err.to_string(), Option::<()>::None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::{http_client::HttpClientBuilder, server::ServerBuilder};
+    use reth_chainspec::MAINNET;
+    use reth_network::{
+        config::rng_secret_key, read_bans_file, NetworkConfigBuilder, NetworkManager,
+        BANNED_PEERS_FILE_NAME,
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_call_bans_http() {
+        let datadir = tempfile::tempdir().unwrap();
+        let bans_file = datadir.path().join(BANNED_PEERS_FILE_NAME);
+        let config = NetworkConfigBuilder::eth(rng_secret_key())
+            .listener_port(0)
+            .disable_discovery()
+            .bans_file(Some(bans_file.clone()))
+            .build_with_noop_provider(MAINNET.clone());
+        let network = NetworkManager::new(config).await.unwrap();
+        let handle = network.handle().clone();
+        tokio::spawn(network);
+
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let server_handle = server.start(AdminBans { network: handle }.into_rpc());
+        tokio::spawn(server_handle.stopped());
+
+        let client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+        let subnet: BanTarget = "10.0.0.0/8".parse().unwrap();
+        assert!(AdminBansApiClient::ban_peer(&client, subnet, Some(60), Some("spam".to_string()))
+            .await
+            .unwrap());
+
+        let bans = AdminBansApiClient::list_bans(&client).await.unwrap();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].target, subnet);
+        assert_eq!(bans[0].reason, "spam");
+        assert!(bans[0].expires_at.is_some());
+        // the ban is written as soon as the network handled it
+        assert_eq!(read_bans_file(&bans_file).unwrap(), bans);
+
+        assert!(AdminBansApiClient::unban_peer(&client, subnet).await.unwrap());
+        assert!(!AdminBansApiClient::unban_peer(&client, subnet).await.unwrap());
+        assert_eq!(AdminBansApiClient::list_bans(&client).await.unwrap(), vec![]);
+        assert_eq!(read_bans_file(&bans_file).unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,411 @@
+//! `consensusExt` subscriptions for `APos` consensus events.
+//!
+//! The events are derived from the canonical chain notifications and the forkchoice updates, so
+//! clients no longer have to poll `consensusExt_get_snapshot` every block.
+
+use alloy_primitives::{Address, BlockHash, BlockNumber, B256, B64};
+use jsonrpsee::{core::SubscriptionResult, proc_macros::rpc, PendingSubscriptionSink};
+use n42_clique::NONCE_AUTH_VOTE;
+use reth_chain_state::ForkChoiceSubscriptions;
+use reth_chainspec::AposChainSpec;
+use reth_consensus::{ConsensusError, FullConsensus};
+use reth_ethereum_primitives::EthPrimitives;
+use reth_primitives::Header;
+use reth_primitives_traits::header::clique_utils::recover_address_generic;
+use reth_provider::{CanonStateSubscriptions, ChainSpecProvider};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_stream::StreamExt;
+use tracing::{debug, warn};
+
+/// Number of events buffered for slow subscribers before they start missing events.
+pub const CONSENSUS_EVENTS_CAPACITY: usize = 1024;
+
+/// Kind of a [`ConsensusEvent`], used to filter a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsensusEventKind {
+    /// See [`ConsensusEvent::SignersChanged`].
+    SignersChanged,
+    /// See [`ConsensusEvent::VoteCast`].
+    VoteCast,
+    /// See [`ConsensusEvent::ProposalPassed`].
+    ProposalPassed,
+    /// See [`ConsensusEvent::EpochCheckpoint`].
+    EpochCheckpoint,
+    /// See [`ConsensusEvent::BlockSealed`].
+    BlockSealed,
+    /// See [`ConsensusEvent::SafeHead`].
+    SafeHead,
+    /// See [`ConsensusEvent::FinalizedHead`].
+    FinalizedHead,
+}
+
+/// An `APos` consensus event, as sent to `consensusExt_subscribe` subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ConsensusEvent {
+    /// The signer set changed with a canonical block.
+    SignersChanged {
+        /// Block that changed the signer set.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+        /// The signer set after the block.
+        signers: Vec<Address>,
+    },
+    /// A canonical block carries a vote.
+    VoteCast {
+        /// Block carrying the vote.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+        /// Sealer of the block, who cast the vote.
+        signer: Address,
+        /// The address voted on.
+        address: Address,
+        /// Whether the vote authorizes or drops `address`.
+        authorize: bool,
+    },
+    /// A proposal reached a majority and `address` was added to or removed from the signers.
+    ProposalPassed {
+        /// Block that passed the proposal.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+        /// The address voted on.
+        address: Address,
+        /// Whether `address` was authorized or dropped.
+        authorize: bool,
+    },
+    /// A canonical epoch checkpoint block, at which the pending votes are reset.
+    EpochCheckpoint {
+        /// Checkpoint block.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+        /// The signers listed in the checkpoint.
+        signers: Vec<Address>,
+    },
+    /// A block sealed by the local signer became canonical.
+    BlockSealed {
+        /// Sealed block.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+    },
+    /// The safe head was updated.
+    SafeHead {
+        /// New safe block.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+    },
+    /// The finalized head was updated.
+    FinalizedHead {
+        /// New finalized block.
+        number: BlockNumber,
+        /// Hash of the block.
+        hash: BlockHash,
+    },
+}
+
+impl ConsensusEvent {
+    /// Returns the kind of the event.
+    pub const fn kind(&self) -> ConsensusEventKind {
+        match self {
+            Self::SignersChanged { .. } => ConsensusEventKind::SignersChanged,
+            Self::VoteCast { .. } => ConsensusEventKind::VoteCast,
+            Self::ProposalPassed { .. } => ConsensusEventKind::ProposalPassed,
+            Self::EpochCheckpoint { .. } => ConsensusEventKind::EpochCheckpoint,
+            Self::BlockSealed { .. } => ConsensusEventKind::BlockSealed,
+            Self::SafeHead { .. } => ConsensusEventKind::SafeHead,
+            Self::FinalizedHead { .. } => ConsensusEventKind::FinalizedHead,
+        }
+    }
+}
+
+/// Pubsub interface of the `consensusExt` namespace.
+#[cfg_attr(not(test), rpc(server, namespace = "consensusExt"))]
+#[cfg_attr(test, rpc(server, client, namespace = "consensusExt"))]
+pub trait ConsensusExtPubSubApi {
+    /// Subscribes to consensus events, optionally only to the given kinds.
+    #[subscription(
+        name = "subscribe" => "subscription",
+        unsubscribe = "unsubscribe",
+        item = ConsensusEvent
+    )]
+    async fn subscribe(&self, kinds: Option<Vec<ConsensusEventKind>>) -> SubscriptionResult;
+}
+
+/// The type that implements the `consensusExt` pubsub trait.
+#[derive(Debug, Clone)]
+pub struct ConsensusExtPubSub {
+    events: broadcast::Sender<ConsensusEvent>,
+}
+
+impl ConsensusExtPubSub {
+    /// Creates the pubsub handler for the events sent on `events`.
+    pub const fn new(events: broadcast::Sender<ConsensusEvent>) -> Self {
+        Self { events }
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl ConsensusExtPubSubApiServer for ConsensusExtPubSub {
+    async fn subscribe(
+        &self,
+        pending: PendingSubscriptionSink,
+        kinds: Option<Vec<ConsensusEventKind>>,
+    ) -> SubscriptionResult {
+        let mut events = self.events.subscribe();
+        let sink = pending.accept().await?;
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!(target: "rpc::consensus", skipped, "consensus event subscriber lagged");
+                        continue
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if kinds.as_ref().is_some_and(|kinds| !kinds.contains(&event.kind())) {
+                    continue
+                }
+                let Ok(msg) = serde_json::value::to_raw_value(&event) else { continue };
+                if sink.send(msg).await.is_err() {
+                    break
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Derives [`ConsensusEvent`]s from the canonical chain and sends them to the subscribers.
+#[derive(Debug)]
+pub struct ConsensusEventsService<Cons, Provider> {
+    consensus: Cons,
+    provider: Provider,
+    events: broadcast::Sender<ConsensusEvent>,
+    /// Hash and signers of the last processed block.
+    head: Option<(B256, Vec<Address>)>,
+}
+
+impl<Cons, Provider> ConsensusEventsService<Cons, Provider>
+where
+    Cons: FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + 'static,
+    Provider: CanonStateSubscriptions<Primitives = EthPrimitives>
+        + ForkChoiceSubscriptions<Header = Header>
+        + ChainSpecProvider<ChainSpec: AposChainSpec>,
+{
+    /// Creates the service sending to `events`.
+    pub const fn new(
+        consensus: Cons,
+        provider: Provider,
+        events: broadcast::Sender<ConsensusEvent>,
+    ) -> Self {
+        Self { consensus, provider, events, head: None }
+    }
+
+    /// Runs until the canonical state notifications end.
+    pub async fn run(mut self) {
+        let mut canonical = self.provider.canonical_state_stream();
+        let mut safe = self.provider.safe_block_stream();
+        let mut finalized = self.provider.finalized_block_stream();
+        loop {
+            tokio::select! {
+                notification = canonical.next() => {
+                    let Some(notification) = notification else { break };
+                    for block in notification.committed().blocks_iter() {
+                        self.on_canonical_header(block.hash(), block.header()).await;
+                    }
+                }
+                Some(header) = safe.next() => {
+                    let block = header.num_hash();
+                    self.send(ConsensusEvent::SafeHead { number: block.number, hash: block.hash });
+                }
+                Some(header) = finalized.next() => {
+                    let block = header.num_hash();
+                    self.send(ConsensusEvent::FinalizedHead { number: block.number, hash: block.hash });
+                }
+            }
+        }
+    }
+
+    async fn on_canonical_header(&mut self, hash: B256, header: &Header) {
+        if header.number == 0 {
+            return
+        }
+        let parent_signers = match self.head.take() {
+            Some((head, signers)) if head == header.parent_hash => Some(signers),
+            // first block or reorg, the snapshot of the parent is needed to tell what changed
+            _ => self.signers_at(header.number - 1, header.parent_hash).await,
+        };
+        let Some(signers) = self.signers_at(header.number, hash).await else { return };
+        let sealer = match recover_address_generic(header) {
+            Ok(sealer) => sealer,
+            Err(err) => {
+                warn!(target: "rpc::consensus", %hash, %err, "failed to recover block sealer");
+                return
+            }
+        };
+        let local = self.consensus.get_eth_signer_address().ok().flatten();
+        let epoch = self.provider.chain_spec().apos_epoch_at(header.number);
+
+        for event in block_events(
+            hash,
+            header,
+            sealer,
+            local,
+            epoch,
+            parent_signers.as_deref(),
+            &signers,
+        ) {
+            self.send(event);
+        }
+        self.head = Some((hash, signers));
+    }
+
+    async fn signers_at(&self, number: BlockNumber, hash: B256) -> Option<Vec<Address>> {
+        // a snapshot miss walks the headers back to a checkpoint in the database
+        let consensus = self.consensus.clone();
+        let snapshot =
+            tokio::task::spawn_blocking(move || consensus.snapshot(number, hash, None)).await;
+        match snapshot {
+            Ok(Ok(snapshot)) => Some(snapshot.signers),
+            Ok(Err(err)) => {
+                warn!(target: "rpc::consensus", number, %hash, %err, "failed to load snapshot");
+                None
+            }
+            Err(err) => {
+                warn!(target: "rpc::consensus", number, %hash, %err, "snapshot task failed");
+                None
+            }
+        }
+    }
+
+    fn send(&self, event: ConsensusEvent) {
+        // an error only means there are no subscribers
+        let _ = self.events.send(event);
+    }
+}
+
+/// Returns the events of a canonical block sealed by `sealer`.
+///
+/// `parent_signers` and `signers` are the signer sets before and after the block; the signer set
+/// changes are only reported if the parent set is known.
+fn block_events(
+    hash: B256,
+    header: &Header,
+    sealer: Address,
+    local: Option<Address>,
+    epoch: u64,
+    parent_signers: Option<&[Address]>,
+    signers: &[Address],
+) -> Vec<ConsensusEvent> {
+    let number = header.number;
+    let mut events = Vec::new();
+
+    if number % epoch == 0 {
+        events.push(ConsensusEvent::EpochCheckpoint { number, hash, signers: signers.to_vec() });
+    } else if !header.beneficiary.is_zero() {
+        events.push(ConsensusEvent::VoteCast {
+            number,
+            hash,
+            signer: sealer,
+            address: header.beneficiary,
+            authorize: header.nonce == B64::from(NONCE_AUTH_VOTE),
+        });
+    }
+
+    if let Some(parent_signers) = parent_signers {
+        let added = signers.iter().filter(|signer| !parent_signers.contains(signer));
+        let removed = parent_signers.iter().filter(|signer| !signers.contains(signer));
+        let passed = added
+            .map(|address| (*address, true))
+            .chain(removed.map(|address| (*address, false)))
+            .map(|(address, authorize)| ConsensusEvent::ProposalPassed {
+                number,
+                hash,
+                address,
+                authorize,
+            })
+            .collect::<Vec<_>>();
+        if !passed.is_empty() {
+            events.extend(passed);
+            events.push(ConsensusEvent::SignersChanged { number, hash, signers: signers.to_vec() });
+        }
+    }
+
+    if local.is_some_and(|local| !local.is_zero() && local == sealer) {
+        events.push(ConsensusEvent::BlockSealed { number, hash });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::{core::client::Subscription, server::ServerBuilder, ws_client::WsClientBuilder};
+    use n42_clique::NONCE_DROP_VOTE;
+
+    #[test]
+    fn vote_and_passed_proposal() {
+        let (a, b, c) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let header = Header {
+            number: 5,
+            beneficiary: c,
+            nonce: NONCE_AUTH_VOTE.into(),
+            ..Default::default()
+        };
+        let hash = B256::with_last_byte(5);
+
+        let events = block_events(hash, &header, a, Some(a), 30, Some(&[a, b]), &[a, b, c]);
+        assert_eq!(
+            events,
+            vec![
+                ConsensusEvent::VoteCast { number: 5, hash, signer: a, address: c, authorize: true },
+                ConsensusEvent::ProposalPassed { number: 5, hash, address: c, authorize: true },
+                ConsensusEvent::SignersChanged { number: 5, hash, signers: vec![a, b, c] },
+                ConsensusEvent::BlockSealed { number: 5, hash },
+            ]
+        );
+    }
+
+    #[test]
+    fn checkpoint_carries_no_vote() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let header = Header { number: 30, nonce: NONCE_DROP_VOTE.into(), ..Default::default() };
+        let hash = B256::with_last_byte(30);
+
+        let events = block_events(hash, &header, a, Some(b), 30, None, &[a, b]);
+        assert_eq!(
+            events,
+            vec![ConsensusEvent::EpochCheckpoint { number: 30, hash, signers: vec![a, b] }]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_subscribe_ws() {
+        let (events, _) = broadcast::channel(CONSENSUS_EVENTS_CAPACITY);
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let server_handle = server.start(ConsensusExtPubSub::new(events.clone()).into_rpc());
+        tokio::spawn(server_handle.stopped());
+
+        let client = WsClientBuilder::default().build(format!("ws://{addr}")).await.unwrap();
+        let mut sub: Subscription<ConsensusEvent> =
+            ConsensusExtPubSubApiClient::subscribe(&client, Some(vec![ConsensusEventKind::SafeHead]))
+                .await
+                .unwrap();
+
+        let hash = B256::with_last_byte(1);
+        events.send(ConsensusEvent::FinalizedHead { number: 1, hash }).unwrap();
+        events.send(ConsensusEvent::SafeHead { number: 1, hash }).unwrap();
+
+        let event = sub.next().await.unwrap().unwrap();
+        assert_eq!(event, ConsensusEvent::SafeHead { number: 1, hash });
+    }
+}
@@ -1,11 +1,65 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+use reth_chainspec::AposChainSpec;
 use reth_consensus::{ConsensusError, FullConsensus};
 use reth_ethereum_primitives::{EthPrimitives};
 use alloy_primitives::Sealable;
-use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::{error::INVALID_PARAMS_CODE, ErrorObject}};
-use alloy_primitives::Address;
-use n42_primitives::Snapshot;
-use reth_provider::HeaderProvider;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::{error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE}, ErrorObject, ErrorObjectOwned}, RpcModule};
+use alloy_primitives::{Address, BlockHash, BlockNumber};
+use reth_primitives_traits::{header::clique_utils::recover_address_generic, AlloyBlockHeader};
+use n42_primitives::{APosConfig, SignerLiveness, Snapshot};
+use reth_provider::{BlockNumReader, ChainSpecProvider, HeaderProvider, SignerBlocksProvider};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of blocks `getBlocksBySigner` scans in one call.
+pub const MAX_SIGNER_BLOCKS_RANGE: u64 = 100_000;
+
+/// Number of recovered block sealers kept by `getBlockSealer`, a recovery costs an ecrecover.
+const SEALER_CACHE_SIZE: u32 = 4096;
+
+/// Methods changing the votes of the local signer, served on the authenticated endpoint only.
+const VOTE_METHODS: [&str; 2] = ["consensusExt_propose", "consensusExt_discard"];
+
+/// A canonical block sealed by a signer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerBlock {
+    /// Block number.
+    pub number: BlockNumber,
+    /// Block hash.
+    pub hash: BlockHash,
+}
+
+/// Producer and vote target of a block.
+///
+/// `APos` headers carry the vote target in the beneficiary field, which `eth_getBlockByNumber`
+/// reports as `miner`. The block producer is the signer of the seal at the end of the extra data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSealer {
+    /// Block number.
+    pub number: BlockNumber,
+    /// Block hash.
+    pub hash: BlockHash,
+    /// Signer of the block seal, `None` for the unsealed genesis.
+    pub miner: Option<Address>,
+    /// The raw header beneficiary, the address voted on by the block.
+    pub vote_target: Address,
+}
+
+/// Participation of the signers in one epoch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochStats {
+    /// First block of the epoch, its checkpoint.
+    pub start: BlockNumber,
+    /// Last block of the epoch, or the chain tip while the epoch is in progress.
+    pub end: BlockNumber,
+    /// Number of blocks sealed by each signer, signers of the epoch that sealed nothing are
+    /// listed with zero.
+    pub sealed: BTreeMap<Address, u64>,
+}
 
 /// trait interface for a custom rpc namespace: `consensus`
 ///
@@ -28,7 +82,7 @@ pub trait ConsensusExtApi {
         ) -> RpcResult<()>;
 
     /// GetSnapshot in the clique consensus.
-    #[method(name = "get_snapshot")]
+    #[method(name = "get_snapshot", blocking)]
     fn get_snapshot(
         &self,
         number: u64,
@@ -39,19 +93,98 @@ pub trait ConsensusExtApi {
     fn proposals(
         &self,
         ) -> RpcResult<HashMap<Address, bool>>;
+
+    /// Canonical blocks sealed by `signer` between `from` and `to`, inclusive.
+    #[method(name = "getBlocksBySigner")]
+    fn get_blocks_by_signer(
+        &self,
+        signer: Address,
+        from: BlockNumber,
+        to: BlockNumber,
+        ) -> RpcResult<Vec<SignerBlock>>;
+
+    /// Sealer and vote target of the canonical block `number`, `None` if the block is unknown.
+    #[method(name = "getBlockSealer")]
+    fn get_block_sealer(
+        &self,
+        number: BlockNumber,
+        ) -> RpcResult<Option<BlockSealer>>;
+
+    /// Participation stats of the epoch containing block `number`.
+    #[method(name = "getEpochStats", blocking)]
+    fn get_epoch_stats(
+        &self,
+        number: BlockNumber,
+        ) -> RpcResult<EpochStats>;
+
+    /// The `APos` parameters in effect for block `number` built on a parent with `timestamp`.
+    #[method(name = "getAposConfig")]
+    fn get_apos_config(
+        &self,
+        number: BlockNumber,
+        timestamp: u64,
+        ) -> RpcResult<APosConfig>;
+
+    /// Last sealed block and missed in-turn slots of the current signers.
+    #[method(name = "getSignerLiveness")]
+    fn get_signer_liveness(
+        &self,
+        ) -> RpcResult<BTreeMap<Address, SignerLiveness>>;
 }
 
 /// The type that implements the `consensus` rpc namespace trait
+#[derive(Clone)]
 pub struct ConsensusExt<Cons, Provider> {
     pub consensus: Cons,
     pub provider: Provider,
+    /// Recovered sealers of recently requested blocks, by block hash.
+    sealers: Arc<Mutex<schnellru::LruMap<BlockHash, Address>>>,
+}
+
+impl<Cons, Provider> ConsensusExt<Cons, Provider> {
+    /// Creates the `consensusExt` namespace for the given consensus and provider.
+    pub fn new(consensus: Cons, provider: Provider) -> Self {
+        let sealers = schnellru::LruMap::new(schnellru::ByLength::new(SEALER_CACHE_SIZE));
+        Self { consensus, provider, sealers: Arc::new(Mutex::new(sealers)) }
+    }
+
+    /// Recovers the signer of a sealed header, caching it by block hash.
+    fn sealer<H: reth_primitives_traits::BlockHeader>(
+        &self,
+        hash: BlockHash,
+        header: &H,
+    ) -> Result<Address, ErrorObjectOwned> {
+        if let Some(sealer) = self.sealers.lock().unwrap().get(&hash) {
+            return Ok(*sealer)
+        }
+        let sealer = recover_address_generic(header).map_err(internal_error)?;
+        self.sealers.lock().unwrap().insert(hash, sealer);
+        Ok(sealer)
+    }
+
+    /// The namespace without the vote methods, for the transports open to everyone.
+    pub fn into_read_only_rpc(self) -> RpcModule<Self>
+    where
+        Self: ConsensusExtApiServer,
+    {
+        let mut module = self.into_rpc();
+        for method in VOTE_METHODS {
+            module.remove_method(method);
+        }
+        module
+    }
 }
 
 impl<Cons, Provider> ConsensusExtApiServer for ConsensusExt<Cons, Provider>
 where
     Cons:
         FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + Unpin + 'static,
-    Provider: HeaderProvider + Clone + 'static,
+    Provider: HeaderProvider
+        + BlockNumReader
+        + SignerBlocksProvider
+        + ChainSpecProvider<ChainSpec: AposChainSpec>
+        + Clone
+        + 'static,
 {
     fn propose(&self,
         address: Address,
@@ -78,6 +211,83 @@ where
         ) -> RpcResult<HashMap<Address, bool>> {
         Ok(self.consensus.proposals().unwrap_or_default())
     }
+
+    fn get_blocks_by_signer(
+        &self,
+        signer: Address,
+        from: BlockNumber,
+        to: BlockNumber,
+        ) -> RpcResult<Vec<SignerBlock>> {
+        if from > to || to - from >= MAX_SIGNER_BLOCKS_RANGE {
+            return Err(invalid_params(format!("block range must be ascending and at most {MAX_SIGNER_BLOCKS_RANGE} blocks")))
+        }
+        let blocks = self.provider.signer_blocks(signer, from..=to).map_err(internal_error)?;
+        Ok(blocks.into_iter().map(|block| SignerBlock { number: block.number, hash: block.hash }).collect())
+    }
+
+    fn get_block_sealer(
+        &self,
+        number: BlockNumber,
+        ) -> RpcResult<Option<BlockSealer>> {
+        let Some(header) = self.provider.header_by_number(number).map_err(internal_error)? else {
+            return Ok(None)
+        };
+        let hash = header.hash_slow();
+        let vote_target = header.beneficiary();
+        // the genesis is not sealed, its extra data only carries the signer list
+        if number == 0 {
+            return Ok(Some(BlockSealer { number, hash, miner: None, vote_target }))
+        }
+        let miner = self.sealer(hash, &header)?;
+        Ok(Some(BlockSealer { number, hash, miner: Some(miner), vote_target }))
+    }
+
+    fn get_epoch_stats(
+        &self,
+        number: BlockNumber,
+        ) -> RpcResult<EpochStats> {
+        let tip = self.provider.best_block_number().map_err(internal_error)?;
+        if number > tip {
+            return Err(invalid_params(format!("block {number} is above the chain tip {tip}")))
+        }
+        let epoch = self.provider.chain_spec().apos_epoch_at(number);
+        let start = number - number % epoch;
+        let end = (start + epoch - 1).min(tip);
+
+        // every signer of the epoch is listed, including the ones that sealed nothing
+        let mut sealed = BTreeMap::new();
+        let checkpoint = self.provider.header_by_number(start).map_err(internal_error)?;
+        if let Some(checkpoint) = checkpoint {
+            let snapshot = self.consensus.snapshot(start, checkpoint.hash_slow(), None).map_err(internal_error)?;
+            sealed.extend(snapshot.signers.into_iter().map(|signer| (signer, 0)));
+        }
+        for (_, signer) in self.provider.block_signers(start..=end).map_err(internal_error)? {
+            *sealed.entry(signer).or_default() += 1;
+        }
+        Ok(EpochStats { start, end, sealed })
+    }
+
+    fn get_apos_config(
+        &self,
+        number: BlockNumber,
+        timestamp: u64,
+        ) -> RpcResult<APosConfig> {
+        Ok(self.provider.chain_spec().apos_config_at(number, timestamp))
+    }
+
+    fn get_signer_liveness(
+        &self,
+        ) -> RpcResult<BTreeMap<Address, SignerLiveness>> {
+        self.consensus.signer_liveness().map_err(internal_error)
+    }
+}
+
+fn invalid_params(message: String) -> ErrorObjectOwned {
+    ErrorObject::owned(INVALID_PARAMS_CODE, message, Option::<()>::None)
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> ErrorObjectOwned {
+    ErrorObject::owned(INTERNAL_ERROR_CODE, err.to_string(), Option::<()>::None)
 }
 
  mod tests {
@@ -113,6 +323,80 @@ where
          assert_eq!(result, Snapshot::default());
      }
 
+     #[tokio::test(flavor = "multi_thread")]
+     async fn test_call_get_blocks_by_signer_http() {
+         let server_addr = start_server().await;
+         let uri = format!("http://{}", server_addr);
+         let client = HttpClientBuilder::default().build(&uri).unwrap();
+         let result = ConsensusExtApiClient::get_blocks_by_signer(&client, Address::random(), 0, 10).await.unwrap();
+         assert_eq!(result, vec![]);
+
+         let result = ConsensusExtApiClient::get_blocks_by_signer(&client, Address::random(), 0, MAX_SIGNER_BLOCKS_RANGE).await;
+         assert!(result.is_err());
+     }
+
+     #[tokio::test(flavor = "multi_thread")]
+     async fn test_call_get_block_sealer_http() {
+         let server_addr = start_server().await;
+         let uri = format!("http://{}", server_addr);
+         let client = HttpClientBuilder::default().build(&uri).unwrap();
+         let result = ConsensusExtApiClient::get_block_sealer(&client, 1).await.unwrap();
+         assert_eq!(result, None);
+     }
+
+     #[test]
+     fn recovers_block_sealer() {
+         let signer = alloy_signer_local::PrivateKeySigner::random();
+         let mut header = reth_primitives_traits::Header {
+             number: 1,
+             beneficiary: Address::with_last_byte(1),
+             extra_data: vec![0u8; 32 + 65].into(),
+             ..Default::default()
+         };
+         n42_clique::seal_header(&signer, &mut header).unwrap();
+         let hash = header.hash_slow();
+
+         let api = ConsensusExt::new((), ());
+         assert_eq!(api.sealer(hash, &header).unwrap(), signer.address());
+         // served from the cache without recovering the seal again
+         header.extra_data = Default::default();
+         assert_eq!(api.sealer(hash, &header).unwrap(), signer.address());
+     }
+
+     #[tokio::test(flavor = "multi_thread")]
+     async fn test_read_only_rpc_http() {
+         let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+         let uri = format!("http://{}", server.local_addr().unwrap());
+         let api = ConsensusExt::new(NoopConsensus::default(), NoopProvider::default());
+         tokio::spawn(server.start(api.into_read_only_rpc()).stopped());
+         let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+         assert!(ConsensusExtApiClient::propose(&client, Address::random(), true).await.is_err());
+         assert!(ConsensusExtApiClient::discard(&client, Address::random()).await.is_err());
+         let result = ConsensusExtApiClient::get_block_sealer(&client, 1).await.unwrap();
+         assert_eq!(result, None);
+         let result = ConsensusExtApiClient::proposals(&client).await.unwrap();
+         assert_eq!(result, HashMap::default());
+     }
+
+     #[tokio::test(flavor = "multi_thread")]
+     async fn test_call_get_apos_config_http() {
+         let server_addr = start_server().await;
+         let uri = format!("http://{}", server_addr);
+         let client = HttpClientBuilder::default().build(&uri).unwrap();
+         let result = ConsensusExtApiClient::get_apos_config(&client, 1, 0).await.unwrap();
+         assert_eq!(result, APosConfig::default());
+     }
+
+     #[tokio::test(flavor = "multi_thread")]
+     async fn test_call_get_signer_liveness_http() {
+         let server_addr = start_server().await;
+         let uri = format!("http://{}", server_addr);
+         let client = HttpClientBuilder::default().build(&uri).unwrap();
+         let result = ConsensusExtApiClient::get_signer_liveness(&client).await.unwrap();
+         assert_eq!(result, BTreeMap::default());
+     }
+
      #[tokio::test(flavor = "multi_thread")]
      async fn test_call_proposals_http() {
          let server_addr = start_server().await;
@@ -127,7 +411,7 @@ where
          let addr = server.local_addr().unwrap();
          let consensus = NoopConsensus::default();
          let provider = NoopProvider::default();
-         let api = ConsensusExt { consensus, provider };
+         let api = ConsensusExt::new(consensus, provider);
          let server_handle = server.start(api.into_rpc());
 
          tokio::spawn(server_handle.stopped());
@@ -0,0 +1,38 @@
+//! `debug` namespace methods inspecting the block propagation of the node.
+
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::{error::INTERNAL_ERROR_CODE, ErrorObject},
+};
+use reth_network_api::{BlockAnnounceProvider, BlockPropagation};
+
+/// Number of blocks `blockPropagation` returns if no count is given.
+const DEFAULT_PROPAGATION_COUNT: usize = 16;
+
+/// Block propagation methods of the `debug` namespace.
+#[rpc(server, namespace = "debug")]
+pub trait DebugBlockPropagationApi {
+    /// Returns, newest first, where the `count` most recently seen blocks came from and which
+    /// peers they were relayed to, with the delays since each block was first seen.
+    #[method(name = "blockPropagation")]
+    async fn block_propagation(&self, count: Option<usize>) -> RpcResult<Vec<BlockPropagation>>;
+}
+
+/// The type that implements the block propagation methods of the `debug` namespace
+pub struct DebugBlockPropagation<Net> {
+    pub network: Net,
+}
+
+#[jsonrpsee::core::async_trait]
+impl<Net> DebugBlockPropagationApiServer for DebugBlockPropagation<Net>
+where
+    Net: BlockAnnounceProvider + Send + Sync + 'static,
+{
+    async fn block_propagation(&self, count: Option<usize>) -> RpcResult<Vec<BlockPropagation>> {
+        let count = count.unwrap_or(DEFAULT_PROPAGATION_COUNT);
+        self.network.block_propagation(count).await.map_err(|err| {
+            ErrorObject::owned(INTERNAL_ERROR_CODE, err.to_string(), Option::<()>::None)
+        })
+    }
+}
@@ -29,8 +29,16 @@
 
 pub mod cli;
 
+pub mod admin_ext;
+
 pub mod consensus_ext;
 
+pub mod consensus_events;
+
+pub mod debug_ext;
+
+pub mod private_tx_ext;
+
 /// Re-exported utils.
 pub mod utils {
     pub use reth_db::open_db_read_only;
@@ -5,12 +5,21 @@ static ALLOC: reth_cli_util::allocator::Allocator = reth_cli_util::allocator::ne
 
 use clap::Parser;
 use n42::{args::RessArgs, cli::Cli, ress::install_ress_subprotocol};
-use n42_engine_types::{N42Node};
+use n42_engine_types::{N42Node, N42TransactionPolicy};
 use reth_ethereum_cli::chainspec::EthereumChainSpecParser;
 use reth_node_builder::NodeHandle;
 use reth_node_ethereum::EthereumNode;
 use tracing::info;
+use n42::admin_ext::{AdminBans, AdminBansApiServer};
 use n42::consensus_ext::{ConsensusExtApiServer, ConsensusExt};
+use n42::debug_ext::{DebugBlockPropagation, DebugBlockPropagationApiServer};
+use n42::private_tx_ext::{N42PrivateTx, N42PrivateTxApiServer};
+use n42::consensus_events::{
+    ConsensusEventsService, ConsensusExtPubSub, ConsensusExtPubSubApiServer,
+    CONSENSUS_EVENTS_CAPACITY,
+};
+use reth_node_builder::FullNodeComponents;
+use reth_rpc_server_types::RethRpcModule;
 
 fn main() {
     reth_cli_util::sigsegv_handler::install();
@@ -23,20 +32,50 @@ fn main() {
     if let Err(err) =
         Cli::<EthereumChainSpecParser, RessArgs>::parse().run(async move |builder, ress_args| {
             info!(target: "reth::cli", "Launching node");
+            let n42_node = N42Node::default();
+            let private_txs = n42_node.private_transactions().clone();
             let NodeHandle { node, node_exit_future } =
-                builder.node(N42Node::default())
-                        .extend_rpc_modules(|ctx| {
+                builder.node(n42_node)
+                        .extend_rpc_modules(move |ctx| {
 
                             let consensus = ctx.consensus().clone();
                             let provider = ctx.provider().clone();
 
-                            let ext = ConsensusExt { consensus, provider };
+                            let ext = ConsensusExt::new(consensus.clone(), provider.clone());
+                            let private = N42PrivateTx {
+                                consensus: consensus.clone(),
+                                provider: provider.clone(),
+                                private_txs,
+                                policy: N42TransactionPolicy::from_args(&ctx.config().builder)?,
+                            };
 
-                            // now we merge our extension namespace into all configured transports
+                            // the read-only methods go to all configured transports, the votes of
+                            // the local signer to the authenticated endpoint only
+                            ctx.modules.merge_configured(ext.clone().into_read_only_rpc())?;
                             ctx.auth_module.merge_auth_methods(ext.into_rpc())?;
 
+                            // consensus event subscriptions need a persistent connection
+                            let (events, _) = tokio::sync::broadcast::channel(CONSENSUS_EVENTS_CAPACITY);
+                            let service = ConsensusEventsService::new(consensus, provider, events.clone());
+                            ctx.node().task_executor().spawn(Box::pin(service.run()));
+                            let pubsub = ConsensusExtPubSub::new(events).into_rpc();
+                            ctx.modules.merge_ws(pubsub.clone())?;
+                            ctx.modules.merge_ipc(pubsub)?;
+
                             println!("consensus rpc extension enabled");
 
+                            // private transactions go straight to the in-turn signers
+                            ctx.modules.merge_configured(private.into_rpc())?;
+
+                            // ban management next to the built-in admin methods
+                            let bans = AdminBans { network: ctx.network().clone() }.into_rpc();
+                            ctx.modules.merge_if_module_configured(RethRpcModule::Admin, bans)?;
+
+                            let propagation =
+                                DebugBlockPropagation { network: ctx.network().clone() }.into_rpc();
+                            ctx.modules
+                                .merge_if_module_configured(RethRpcModule::Debug, propagation)?;
+
                             Ok(())
                         })
                 .launch_with_debug_capabilities().await?;
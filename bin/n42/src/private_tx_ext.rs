@@ -0,0 +1,131 @@
+//! `n42` namespace method handing transactions privately to the in-turn signers.
+
+use alloy_consensus::Transaction;
+use alloy_eips::{Decodable2718, Typed2718};
+use alloy_primitives::{Address, Bytes, TxHash};
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::{
+        error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE, INVALID_REQUEST_CODE},
+        ErrorObject, ErrorObjectOwned,
+    },
+};
+use n42_engine_types::{
+    BlockSelection, N42TransactionPolicy, PrivateTransactions, TransactionPolicy,
+    DEFAULT_PRIVATE_TX_LIFETIME, MAX_PRIVATE_TX_LIFETIME,
+};
+use reth_consensus::{ConsensusError, FullConsensus};
+use reth_ethereum_primitives::{EthPrimitives, TransactionSigned};
+use reth_primitives_traits::SignedTransaction;
+use reth_provider::{BlockNumReader, HeaderProvider};
+use std::collections::HashSet;
+
+/// Number of upcoming blocks whose in-turn signers a private transaction is handed to.
+pub const PRIVATE_TX_INTURN_BLOCKS: u64 = 2;
+
+/// Private transaction methods of the `n42` namespace.
+#[rpc(server, namespace = "n42")]
+pub trait N42PrivateTxApi {
+    /// Hands the signed transaction to the in-turn signers of the next blocks only, without
+    /// adding it to the pool or gossiping it, and returns its hash.
+    ///
+    /// The transaction must pass the block policy and the admission rules of the pool. Signers
+    /// only accept private transactions from other signers, so the call fails on a node that isn't
+    /// an authorized signer.
+    ///
+    /// The signers include it in their next blocks until `maxBlocks` blocks, 20 by default, were
+    /// added on top of the current head.
+    #[method(name = "sendPrivateTransaction", blocking)]
+    fn send_private_transaction(&self, tx: Bytes, max_blocks: Option<u64>) -> RpcResult<TxHash>;
+}
+
+/// The type that implements the private transaction methods of the `n42` namespace
+pub struct N42PrivateTx<Cons, Provider> {
+    pub consensus: Cons,
+    pub provider: Provider,
+    pub private_txs: PrivateTransactions,
+    /// The block policy of the payload builder.
+    pub policy: N42TransactionPolicy,
+}
+
+impl<Cons, Provider> N42PrivateTxApiServer for N42PrivateTx<Cons, Provider>
+where
+    Cons: FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + Unpin + 'static,
+    Provider: HeaderProvider + BlockNumReader + Clone + 'static,
+{
+    fn send_private_transaction(&self, tx: Bytes, max_blocks: Option<u64>) -> RpcResult<TxHash> {
+        let Some(local_signer) = self.private_txs.local_signer() else {
+            return Err(not_a_signer())
+        };
+        let tx = TransactionSigned::decode_2718(&mut tx.as_ref())
+            .map_err(|err| invalid_params(format!("invalid transaction: {err}")))?;
+        if tx.is_eip4844() {
+            return Err(invalid_params("blob transactions can't be sent privately".to_string()))
+        }
+        let tx = tx
+            .try_into_recovered()
+            .map_err(|_| invalid_params("invalid transaction signature".to_string()))?;
+        let (hash, sender) = (*tx.tx_hash(), tx.signer());
+
+        let number = self.provider.best_block_number().map_err(internal_error)?;
+        let head = self
+            .provider
+            .sealed_header(number)
+            .map_err(internal_error)?
+            .ok_or_else(|| internal_error(format!("missing header of block {number}")))?;
+
+        // the transaction must be one the signers would include
+        self.policy
+            .admit(&BlockSelection::new(head.gas_limit), sender, tx.inner())
+            .map_err(|violation| invalid_params(violation.to_string()))?;
+        let admission = self.private_txs.admission_control();
+        if let Some(admission) = admission {
+            admission
+                .check(sender, tx.kind(), hash)
+                .map_err(|err| invalid_params(err.to_string()))?;
+        }
+
+        let snapshot =
+            self.consensus.snapshot(number, head.hash(), None).map_err(internal_error)?;
+        let signers = &snapshot.signers;
+        if !signers.contains(&local_signer) {
+            return Err(not_a_signer())
+        }
+
+        // the signers in turn for the next block and the ones after it
+        let targets: HashSet<Address> = (number + 1..=number + PRIVATE_TX_INTURN_BLOCKS)
+            .filter_map(|block| signers.iter().find(|signer| snapshot.inturn(block, signer)))
+            .copied()
+            .collect();
+
+        let lifetime =
+            max_blocks.unwrap_or(DEFAULT_PRIVATE_TX_LIFETIME).clamp(1, MAX_PRIVATE_TX_LIFETIME);
+        let reached = self.private_txs.submit(tx, number + lifetime, &targets);
+        if reached.is_empty() {
+            return Err(internal_error(format!(
+                "none of the in-turn signers {targets:?} is connected"
+            )))
+        }
+        if let Some(admission) = admission {
+            admission.record(sender, hash);
+        }
+        Ok(hash)
+    }
+}
+
+fn invalid_params(message: String) -> ErrorObjectOwned {
+    ErrorObject::owned(INVALID_PARAMS_CODE, message, Option::<()>::None)
+}
+
+fn not_a_signer() -> ErrorObjectOwned {
+    ErrorObject::owned(
+        INVALID_REQUEST_CODE,
+        "private transactions can only be sent through an authorized signer",
+        Option::<()>::None,
+    )
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> ErrorObjectOwned {
+    ErrorObject::owned(INTERNAL_ERROR_CODE, err.to_string(), Option::<()>::None)
+}
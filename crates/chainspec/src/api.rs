@@ -53,6 +53,9 @@ pub trait EthChainSpec: Send + Sync + Unpin + Debug {
     /// The bootnodes for the chain, if any.
     fn bootnodes(&self) -> Option<Vec<NodeRecord>>;
 
+    /// The link to the EIP-1459 DNS tree listing the nodes of the chain, if any.
+    fn dns_discovery(&self) -> Option<&str>;
+
     /// Returns `true` if this chain contains Optimism configuration.
     fn is_optimism(&self) -> bool {
         self.chain().is_optimism()
@@ -124,6 +127,10 @@ impl EthChainSpec for ChainSpec {
         self.bootnodes()
     }
 
+    fn dns_discovery(&self) -> Option<&str> {
+        self.dns_discovery()
+    }
+
     fn is_optimism(&self) -> bool {
         false
     }
@@ -0,0 +1,539 @@
+use crate::ChainSpec;
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_genesis::Genesis;
+use core::str::FromStr;
+use n42_primitives::{APosConfig, APosConfigUpdate};
+use reth_ethereum_forks::{ForkCondition, Hardfork, N42Hardfork, N42Hardforks};
+use reth_network_peers::NodeRecord;
+
+/// Chain spec accessors for the parameters of the `APos` consensus engine.
+#[auto_impl::auto_impl(&, Arc)]
+pub trait AposChainSpec: N42Hardforks {
+    /// Returns the `APos` parameters configured at genesis.
+    fn apos_genesis_config(&self) -> APosConfig;
+
+    /// Returns the `APos` parameters in effect for a block with the given number and timestamp.
+    ///
+    /// These are the genesis parameters with the updates of every active N42 hardfork applied in
+    /// activation order.
+    fn apos_config_at(&self, number: u64, timestamp: u64) -> APosConfig;
+
+    /// Returns the epoch length in effect at block `number`.
+    ///
+    /// Checkpoints have to be known before their header is, so only forks activated by block
+    /// number can change the epoch length.
+    fn apos_epoch_at(&self, number: u64) -> u64;
+
+    /// Returns the number of blocks between two vote snapshots persisted to disk.
+    fn apos_checkpoint_interval(&self) -> u64 {
+        self.apos_genesis_config().checkpoint_interval
+    }
+}
+
+impl N42Hardforks for ChainSpec {}
+
+impl AposChainSpec for ChainSpec {
+    fn apos_genesis_config(&self) -> APosConfig {
+        self.apos_config.clone()
+    }
+
+    fn apos_config_at(&self, number: u64, timestamp: u64) -> APosConfig {
+        let mut config = self.apos_config.clone();
+        for (fork, update) in &self.apos_config_updates {
+            if self.is_n42_fork_active_at(*fork, number, timestamp) {
+                config.apply_update(update);
+            }
+        }
+        config.epoch = self.apos_epoch_at(number);
+        config
+    }
+
+    fn apos_epoch_at(&self, number: u64) -> u64 {
+        let mut epoch = self.apos_config.epoch;
+        for (fork, update) in &self.apos_config_updates {
+            if let Some(new_epoch) = update.epoch {
+                if self.n42_fork_activation(*fork).active_at_block(number) {
+                    epoch = new_epoch;
+                }
+            }
+        }
+        epoch
+    }
+
+    fn apos_checkpoint_interval(&self) -> u64 {
+        self.apos_config.checkpoint_interval
+    }
+}
+
+/// Errors in the `APos` configuration of a genesis file.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AposConfigError {
+    /// A field could not be deserialized.
+    #[error("invalid `{field}` in genesis config: {message}")]
+    Malformed {
+        /// The genesis config key.
+        field: String,
+        /// The deserialization error.
+        message: String,
+    },
+    /// A parameter that must be positive is zero.
+    #[error("apos `{0}` must be greater than zero")]
+    Zero(&'static str),
+    /// The `clique` and `apos` sections disagree on a parameter.
+    #[error("clique.{field} = {clique} conflicts with apos.{field} = {apos}")]
+    ConflictsWithClique {
+        /// The conflicting parameter.
+        field: &'static str,
+        /// Value in the `clique` section.
+        clique: u64,
+        /// Value in the `apos` section.
+        apos: u64,
+    },
+    /// `aposForks` names a fork that does not exist.
+    #[error("unknown N42 hardfork `{0}` in aposForks")]
+    UnknownHardfork(String),
+    /// `aposForks` configures a fork that has no activation block or time.
+    #[error("aposForks configures {0}, but the fork is not scheduled")]
+    UnscheduledHardfork(N42Hardfork),
+    /// A fork activated by timestamp changes the epoch length.
+    #[error("{0} is activated by timestamp and can not change the epoch length")]
+    EpochChangeByTimestamp(N42Hardfork),
+    /// A fork changes the epoch length on a block that isn't a checkpoint of both the old and the
+    /// new epoch length.
+    #[error("{fork} changes the epoch length from {old} to {new} at block {block}, which must be a multiple of both")]
+    MisalignedEpochChange {
+        /// The fork changing the epoch length.
+        fork: N42Hardfork,
+        /// The activation block of the fork.
+        block: u64,
+        /// The epoch length before the fork.
+        old: u64,
+        /// The epoch length from the fork on.
+        new: u64,
+    },
+    /// A fork changes a parameter that can only be set at genesis.
+    #[error("{fork} can not change `{field}`, it can only be set at genesis")]
+    GenesisOnly {
+        /// The fork changing the parameter.
+        fork: N42Hardfork,
+        /// The parameter.
+        field: &'static str,
+    },
+    /// `dnsDiscovery` is not an EIP-1459 tree link.
+    #[error("dnsDiscovery must be an `enrtree://<public key>@<domain>` link, got {0:?}")]
+    InvalidDnsDiscovery(String),
+}
+
+/// N42 specific configuration read from the `config` section of a genesis file.
+///
+/// The `APos` parameters are read from `apos`, falling back to `clique` for the period and epoch
+/// and to [`APosConfig::default`] for everything else. Forks are activated with geth style
+/// `<fork>Block` or `<fork>Time` keys, and the parameters they change are listed per fork under
+/// `aposForks`. A fork changing the epoch length must activate on a block that is a multiple of
+/// both the old and the new length:
+///
+/// ```json
+/// "apos": { "period": 8, "epoch": 3000, "rewardEpoch": 10800, "checkpointInterval": 2048 },
+/// "beijingBlock": 102000,
+/// "aposForks": { "beijing": { "period": 4, "epoch": 6000 } }
+/// ```
+///
+/// The network is bootstrapped from the enode URLs under `bootnodes` and the EIP-1459 tree linked
+/// by `dnsDiscovery`:
+///
+/// ```json
+/// "bootnodes": ["enode://6f76...5331@5.161.199.154:30303"],
+/// "dnsDiscovery": "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@nodes.n42.example"
+/// ```
+#[derive(Debug, Default)]
+pub(crate) struct N42GenesisInfo {
+    /// The `APos` parameters at genesis.
+    pub(crate) apos_config: APosConfig,
+    /// Activation conditions of the N42 hardforks present in the genesis.
+    pub(crate) hardforks: Vec<(N42Hardfork, ForkCondition)>,
+    /// `APos` parameter updates, ordered by hardfork.
+    pub(crate) apos_config_updates: Vec<(N42Hardfork, APosConfigUpdate)>,
+    /// Nodes to bootstrap the network from.
+    pub(crate) bootnodes: Vec<NodeRecord>,
+    /// Link to the EIP-1459 DNS tree of the network.
+    pub(crate) dns_discovery: Option<String>,
+}
+
+impl N42GenesisInfo {
+    /// Extracts and validates the N42 configuration from the genesis config.
+    pub(crate) fn try_extract_from(genesis: &Genesis) -> Result<Self, AposConfigError> {
+        let fields = &genesis.config.extra_fields;
+        let get = |key: &str| fields.get(key).cloned();
+
+        let mut apos_config = clique_config(genesis);
+        if let Some(apos) = deserialize::<APosConfigUpdate>("apos", get("apos"))? {
+            if let Some(clique) = genesis.config.clique {
+                for (field, clique, apos) in [
+                    ("period", clique.period, apos.period),
+                    ("epoch", clique.epoch, apos.epoch),
+                ] {
+                    if let (Some(clique), Some(apos)) = (clique, apos) {
+                        if clique != apos {
+                            return Err(AposConfigError::ConflictsWithClique { field, clique, apos })
+                        }
+                    }
+                }
+            }
+            apos_config.apply_update(&apos);
+        }
+        for (field, value) in [
+            ("epoch", apos_config.epoch),
+            ("rewardEpoch", apos_config.reward_epoch),
+            ("checkpointInterval", apos_config.checkpoint_interval),
+        ] {
+            if value == 0 {
+                return Err(AposConfigError::Zero(field))
+            }
+        }
+
+        let mut hardforks = Vec::new();
+        for fork in N42Hardfork::VARIANTS {
+            let name = fork_key(*fork);
+            let block_key = alloc::format!("{name}Block");
+            let time_key = alloc::format!("{name}Time");
+            let condition = match deserialize::<u64>(&block_key, get(&block_key))? {
+                Some(block) => Some(ForkCondition::Block(block)),
+                None => deserialize::<u64>(&time_key, get(&time_key))?.map(ForkCondition::Timestamp),
+            };
+            if let Some(condition) = condition {
+                hardforks.push((*fork, condition));
+            }
+        }
+
+        let mut apos_config_updates = BTreeMap::new();
+        let updates = deserialize::<BTreeMap<String, APosConfigUpdate>>("aposForks", get("aposForks"))?;
+        for (name, update) in updates.unwrap_or_default() {
+            let fork = N42Hardfork::from_str(&name).map_err(|_| AposConfigError::UnknownHardfork(name))?;
+            let Some((_, condition)) = hardforks.iter().find(|(f, _)| *f == fork) else {
+                return Err(AposConfigError::UnscheduledHardfork(fork))
+            };
+            if update.epoch.is_some() && matches!(condition, ForkCondition::Timestamp(_)) {
+                return Err(AposConfigError::EpochChangeByTimestamp(fork))
+            }
+            if update.checkpoint_interval.is_some() {
+                return Err(AposConfigError::GenesisOnly { fork, field: "checkpointInterval" })
+            }
+            for (field, value) in [("epoch", update.epoch), ("rewardEpoch", update.reward_epoch)] {
+                if value == Some(0) {
+                    return Err(AposConfigError::Zero(field))
+                }
+            }
+            apos_config_updates.insert(fork, update);
+        }
+
+        // checkpoints are the multiples of the epoch length in effect, so an epoch change must
+        // land on a checkpoint of both lengths for the checkpoints around it to agree
+        let epoch_changes: Vec<_> = apos_config_updates
+            .iter()
+            .filter_map(|(fork, update)| {
+                let block = hardforks.iter().find(|(f, _)| f == fork)?.1.as_block()?;
+                Some((*fork, block, update.epoch?))
+            })
+            .collect();
+        let epoch_at = |number: u64| {
+            epoch_changes
+                .iter()
+                .filter(|(_, block, _)| *block <= number)
+                .fold(apos_config.epoch, |_, (_, _, epoch)| *epoch)
+        };
+        for &(fork, block, new) in &epoch_changes {
+            let old = block.checked_sub(1).map_or(apos_config.epoch, epoch_at);
+            if block % old != 0 || block % new != 0 {
+                return Err(AposConfigError::MisalignedEpochChange { fork, block, old, new })
+            }
+        }
+
+        let bootnodes = deserialize::<Vec<NodeRecord>>("bootnodes", get("bootnodes"))?;
+        let dns_discovery = deserialize::<String>("dnsDiscovery", get("dnsDiscovery"))?;
+        if let Some(link) = &dns_discovery {
+            if !is_dns_tree_link(link) {
+                return Err(AposConfigError::InvalidDnsDiscovery(link.clone()))
+            }
+        }
+
+        Ok(Self {
+            apos_config,
+            hardforks,
+            apos_config_updates: apos_config_updates.into_iter().collect(),
+            bootnodes: bootnodes.unwrap_or_default(),
+            dns_discovery,
+        })
+    }
+}
+
+impl ChainSpec {
+    /// Returns the `APos` parameters configured at genesis.
+    pub const fn apos_config(&self) -> &APosConfig {
+        &self.apos_config
+    }
+
+    /// Checks the `APos` configuration in the genesis config of this chain.
+    ///
+    /// Chain specs built with [`ChainSpec::try_from_genesis`] have already been checked, this is
+    /// for specs constructed by other means.
+    pub fn validate_apos_config(&self) -> Result<(), AposConfigError> {
+        N42GenesisInfo::try_extract_from(&self.genesis).map(|_| ())
+    }
+}
+
+/// Returns the default `APos` parameters with the period and epoch of the `clique` section.
+fn clique_config(genesis: &Genesis) -> APosConfig {
+    let mut config = APosConfig::default();
+    if let Some(clique) = genesis.config.clique {
+        if let Some(period) = clique.period {
+            config.period = period;
+        }
+        if let Some(epoch) = clique.epoch {
+            config.epoch = epoch;
+        }
+    }
+    config
+}
+
+/// Deserializes an optional genesis config value.
+fn deserialize<T: serde::de::DeserializeOwned>(
+    field: &str,
+    value: Option<serde_json::Value>,
+) -> Result<Option<T>, AposConfigError> {
+    value
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|err| AposConfigError::Malformed { field: field.into(), message: err.to_string() })
+}
+
+/// Returns whether `link` is an EIP-1459 tree link, `enrtree://<key>@<domain>`, whose key is the
+/// unpadded base32 encoding of a compressed public key, the way the DNS discovery service parses
+/// it.
+///
+/// Without `std` the public key isn't decoded.
+fn is_dns_tree_link(link: &str) -> bool {
+    link.strip_prefix("enrtree://")
+        .and_then(|rest| rest.split_once('@'))
+        .is_some_and(|(key, domain)| !domain.is_empty() && is_dns_tree_key(key))
+}
+
+#[cfg(feature = "std")]
+fn is_dns_tree_key(key: &str) -> bool {
+    data_encoding::BASE32_NOPAD
+        .decode(key.as_bytes())
+        .is_ok_and(|key| secp256k1::PublicKey::from_slice(&key).is_ok())
+}
+
+#[cfg(not(feature = "std"))]
+fn is_dns_tree_key(key: &str) -> bool {
+    !key.is_empty()
+}
+
+/// Returns the camel case prefix used for `fork` in genesis files, e.g. `beijing`.
+fn fork_key(fork: N42Hardfork) -> String {
+    let name = fork.name();
+    let mut key = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    if let Some(first) = chars.next() {
+        key.extend(first.to_lowercase());
+    }
+    key.extend(chars);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_ethereum_forks::Hardforks;
+
+    const GENESIS: &str = r#"{
+        "config": {
+            "chainId": 4242,
+            "londonBlock": 0,
+            "clique": { "period": 8, "epoch": 3000 },
+            "apos": { "rewardEpoch": 500, "checkpointInterval": 1024 },
+            "beijingBlock": 6000,
+            "aposForks": { "beijing": { "period": 4, "epoch": 6000 } }
+        },
+        "difficulty": "0x1",
+        "gasLimit": "0x1c9c380",
+        "alloc": {}
+    }"#;
+
+    fn genesis_with(config: serde_json::Value) -> Genesis {
+        let mut genesis: serde_json::Value = serde_json::from_str(GENESIS).unwrap();
+        genesis["config"] = config;
+        serde_json::from_value(genesis).unwrap()
+    }
+
+    #[test]
+    fn parses_n42_forks_from_genesis() {
+        let genesis: Genesis = serde_json::from_str(GENESIS).unwrap();
+        let spec = ChainSpec::try_from_genesis(genesis).unwrap();
+
+        assert_eq!(spec.fork(N42Hardfork::Beijing), ForkCondition::Block(6000));
+        assert!(!spec.is_beijing_active_at(5999, 0));
+        assert!(spec.is_beijing_active_at(6000, 0));
+    }
+
+    #[test]
+    fn parses_apos_section() {
+        let genesis: Genesis = serde_json::from_str(GENESIS).unwrap();
+        let spec = ChainSpec::try_from_genesis(genesis).unwrap();
+        spec.validate_apos_config().unwrap();
+
+        let config = spec.apos_config();
+        assert_eq!((config.period, config.epoch), (8, 3000));
+        assert_eq!(config.reward_epoch, 500);
+        assert_eq!(spec.apos_checkpoint_interval(), 1024);
+        assert_eq!(config.reward_limit, APosConfig::default().reward_limit);
+    }
+
+    #[test]
+    fn apos_config_changes_at_fork() {
+        let genesis: Genesis = serde_json::from_str(GENESIS).unwrap();
+        let spec = ChainSpec::try_from_genesis(genesis).unwrap();
+
+        let before = spec.apos_config_at(5999, 0);
+        assert_eq!((before.period, before.epoch), (8, 3000));
+
+        let after = spec.apos_config_at(6000, 0);
+        assert_eq!((after.period, after.epoch), (4, 6000));
+        assert_eq!(after.reward_epoch, before.reward_epoch);
+    }
+
+    #[test]
+    fn heartbeat_interval_set_at_fork() {
+        let genesis = genesis_with(serde_json::json!({
+            "chainId": 4242,
+            "clique": { "period": 2, "epoch": 3000 },
+            "beijingBlock": 100,
+            "aposForks": { "beijing": { "heartbeatInterval": 60 } }
+        }));
+        let spec = ChainSpec::try_from_genesis(genesis).unwrap();
+        spec.validate_apos_config().unwrap();
+
+        let before = spec.apos_config_at(99, 0);
+        assert_eq!(before.heartbeat_interval, 0);
+        assert!(before.allows_empty_block(1000, 1002));
+
+        let after = spec.apos_config_at(100, 0);
+        assert_eq!((after.period, after.heartbeat_interval), (2, 60));
+        assert!(!after.allows_empty_block(1000, 1002));
+        assert!(after.allows_empty_block(1000, 1060));
+    }
+
+    #[test]
+    fn rejects_invalid_apos_config() {
+        let cases = [
+            (
+                serde_json::json!({ "chainId": 1, "apos": { "epoch": 0 } }),
+                AposConfigError::Zero("epoch"),
+            ),
+            (
+                serde_json::json!({ "chainId": 1, "clique": { "period": 8 }, "apos": { "period": 4 } }),
+                AposConfigError::ConflictsWithClique { field: "period", clique: 8, apos: 4 },
+            ),
+            (
+                serde_json::json!({ "chainId": 1, "aposForks": { "tokyo": {} } }),
+                AposConfigError::UnknownHardfork("tokyo".into()),
+            ),
+            (
+                serde_json::json!({ "chainId": 1, "aposForks": { "beijing": { "period": 2 } } }),
+                AposConfigError::UnscheduledHardfork(N42Hardfork::Beijing),
+            ),
+            (
+                serde_json::json!({ "chainId": 1, "beijingTime": 10, "aposForks": { "beijing": { "epoch": 10 } } }),
+                AposConfigError::EpochChangeByTimestamp(N42Hardfork::Beijing),
+            ),
+            (
+                serde_json::json!({ "chainId": 1, "clique": { "epoch": 3000 }, "beijingBlock": 100, "aposForks": { "beijing": { "epoch": 6000 } } }),
+                AposConfigError::MisalignedEpochChange {
+                    fork: N42Hardfork::Beijing,
+                    block: 100,
+                    old: 3000,
+                    new: 6000,
+                },
+            ),
+            (
+                serde_json::json!({ "chainId": 1, "clique": { "epoch": 3000 }, "beijingBlock": 3000, "aposForks": { "beijing": { "epoch": 2000 } } }),
+                AposConfigError::MisalignedEpochChange {
+                    fork: N42Hardfork::Beijing,
+                    block: 3000,
+                    old: 3000,
+                    new: 2000,
+                },
+            ),
+        ];
+        for (config, expected) in cases {
+            assert_eq!(ChainSpec::try_from_genesis(genesis_with(config)).unwrap_err(), expected);
+        }
+
+        let err = ChainSpec::try_from_genesis(genesis_with(
+            serde_json::json!({ "chainId": 1, "apos": { "period": "eight" } }),
+        ))
+        .unwrap_err();
+        assert!(matches!(err, AposConfigError::Malformed { .. }));
+    }
+
+    #[test]
+    fn parses_network_bootstrap() {
+        let enode = "enode://6f7655869fe8be864b1621f7df44334235845fb0b4c9113716b1373e7d7130cc0ff4d1296f742824eb5ecb75b5d50d04aed0d8677fa433be796fa36666395331@5.161.199.154:30303";
+        let link = "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@nodes.n42.example";
+        let spec = ChainSpec::try_from_genesis(genesis_with(serde_json::json!({
+            "chainId": 4242,
+            "bootnodes": [enode],
+            "dnsDiscovery": link,
+        })))
+        .unwrap();
+        spec.validate_apos_config().unwrap();
+
+        assert_eq!(spec.bootnodes(), Some(vec![enode.parse().unwrap()]));
+        assert_eq!(spec.dns_discovery(), Some(link));
+
+        let err = ChainSpec::try_from_genesis(genesis_with(
+            serde_json::json!({ "chainId": 4242, "dnsDiscovery": "nodes.n42.example" }),
+        ))
+        .unwrap_err();
+        assert_eq!(err, AposConfigError::InvalidDnsDiscovery("nodes.n42.example".into()));
+        // the key of the link must be a public key
+        let invalid_key = "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHW@nodes.n42.example";
+        let err = ChainSpec::try_from_genesis(genesis_with(
+            serde_json::json!({ "chainId": 4242, "dnsDiscovery": invalid_key }),
+        ))
+        .unwrap_err();
+        assert_eq!(err, AposConfigError::InvalidDnsDiscovery(invalid_key.into()));
+        let err = ChainSpec::try_from_genesis(genesis_with(
+            serde_json::json!({ "chainId": 4242, "bootnodes": ["127.0.0.1:30303"] }),
+        ))
+        .unwrap_err();
+        assert!(matches!(err, AposConfigError::Malformed { .. }));
+    }
+
+    #[test]
+    fn built_in_chains_activate_genesis_forks() {
+        let mut genesis: Genesis =
+            serde_json::from_str(include_str!("../res/genesis/n42_devnet.json")).unwrap();
+        genesis.config.extra_fields.insert("beijingBlock".into(), 10.into());
+        genesis
+            .config
+            .extra_fields
+            .insert("aposForks".into(), serde_json::json!({ "beijing": { "period": 1 } }));
+        let spec = crate::spec::make_chain_spec(genesis, alloy_chains::Chain::from_id(4242));
+
+        assert_eq!(spec.fork(N42Hardfork::Beijing), ForkCondition::Block(10));
+        assert_eq!(spec.apos_config_at(10, 0).period, 1);
+    }
+
+    #[test]
+    fn n42_testnet_schedules_no_apos_changes() {
+        let spec = crate::N42.clone();
+        spec.validate_apos_config().unwrap();
+        assert_eq!(spec.fork(N42Hardfork::Beijing), ForkCondition::Never);
+        assert_eq!(spec.apos_config_at(u64::MAX, u64::MAX), spec.apos_genesis_config());
+    }
+}
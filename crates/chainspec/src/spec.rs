@@ -2,6 +2,7 @@ pub use alloy_eips::eip1559::BaseFeeParams;
 use alloy_evm::eth::spec::EthExecutorSpec;
 
 use crate::{
+    apos::{AposConfigError, N42GenesisInfo},
     constants::{MAINNET_DEPOSIT_CONTRACT, MAINNET_PRUNE_DELETE_LIMIT},
     EthChainSpec,
 };
@@ -24,14 +25,15 @@ use core::fmt::Debug;
 use derive_more::From;
 use reth_ethereum_forks::{
     ChainHardforks, DisplayHardforks, EthereumHardfork, EthereumHardforks, ForkCondition,
-    ForkFilter, ForkFilterKey, ForkHash, ForkId, Hardfork, Hardforks, Head, DEV_HARDFORKS,
-    N42_HARDFORKS,
+    ForkFilter, ForkFilterKey, ForkHash, ForkId, Hardfork, Hardforks, Head, N42Hardfork,
+    DEV_HARDFORKS, N42_HARDFORKS,
 };
 use reth_network_peers::{
     holesky_nodes, hoodi_nodes, mainnet_nodes, op_nodes, op_testnet_nodes, sepolia_nodes,
     n42_testnet_nodes,
     NodeRecord,
 };
+use n42_primitives::{APosConfig, APosConfigUpdate};
 use reth_primitives_traits::{sync::LazyLock, SealedHeader};
 
 /// Helper method building a [`Header`] given [`Genesis`] and [`ChainHardforks`].
@@ -93,8 +95,15 @@ pub const N42_GENESIS_HASH: B256 =
 pub const N42_TESTNET_CHAINID: u64 = 1142;
 pub const N42_DEVNET_CHAINID: u64 = 1143;
 
-fn make_chain_spec(genesis: Genesis, chain: Chain) -> ChainSpec {
-    let hardforks = N42_HARDFORKS.clone();
+pub(crate) fn make_chain_spec(genesis: Genesis, chain: Chain) -> ChainSpec {
+    let n42_info = N42GenesisInfo::try_extract_from(&genesis)
+        .unwrap_or_else(|err| panic!("invalid N42 config in built-in genesis: {err}"));
+    // the built-in networks schedule their N42 forks in the genesis, on top of the fixed
+    // ethereum forks
+    let mut hardforks = N42_HARDFORKS.clone();
+    for (fork, condition) in n42_info.hardforks {
+        hardforks.insert(fork, condition);
+    }
     //let genesis_hash = alloy_primitives::keccak256(alloy_rlp::encode(genesis.header()));
     let genesis_header = SealedHeader::new_unhashed(
             make_genesis_header(&genesis, &hardforks),
@@ -115,6 +124,10 @@ fn make_chain_spec(genesis: Genesis, chain: Chain) -> ChainSpec {
         //max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
         prune_delete_limit: 0,
         blob_params: Default::default(),
+        apos_config: n42_info.apos_config,
+        apos_config_updates: n42_info.apos_config_updates,
+        bootnodes: n42_info.bootnodes,
+        dns_discovery: n42_info.dns_discovery,
     };
     spec.genesis.config.dao_fork_support = true;
     let state_root = state_root_ref_unhashed(&spec.genesis.alloc);
@@ -159,6 +172,10 @@ pub static MAINNET: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
         base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
         prune_delete_limit: MAINNET_PRUNE_DELETE_LIMIT,
         blob_params: BlobScheduleBlobParams::default(),
+        apos_config: APosConfig::default(),
+        apos_config_updates: Vec::new(),
+        bootnodes: Vec::new(),
+        dns_discovery: None,
     };
     spec.genesis.config.dao_fork_support = true;
     spec.into()
@@ -188,6 +205,10 @@ pub static SEPOLIA: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
         base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
         prune_delete_limit: 10000,
         blob_params: BlobScheduleBlobParams::default(),
+        apos_config: APosConfig::default(),
+        apos_config_updates: Vec::new(),
+        bootnodes: Vec::new(),
+        dns_discovery: None,
     };
     spec.genesis.config.dao_fork_support = true;
     spec.into()
@@ -215,6 +236,10 @@ pub static HOLESKY: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
         base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
         prune_delete_limit: 10000,
         blob_params: BlobScheduleBlobParams::default(),
+        apos_config: APosConfig::default(),
+        apos_config_updates: Vec::new(),
+        bootnodes: Vec::new(),
+        dns_discovery: None,
     };
     spec.genesis.config.dao_fork_support = true;
     spec.into()
@@ -244,6 +269,10 @@ pub static HOODI: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
         base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
         prune_delete_limit: 10000,
         blob_params: BlobScheduleBlobParams::default(),
+        apos_config: APosConfig::default(),
+        apos_config_updates: Vec::new(),
+        bootnodes: Vec::new(),
+        dns_discovery: None,
     };
     spec.genesis.config.dao_fork_support = true;
     spec.into()
@@ -351,6 +380,18 @@ pub struct ChainSpec {
 
     /// The settings passed for blob configurations for specific hardforks.
     pub blob_params: BlobScheduleBlobParams,
+
+    /// The `APos` parameters at genesis.
+    pub apos_config: APosConfig,
+
+    /// The `APos` parameter changes scheduled at N42 hardforks, in activation order.
+    pub apos_config_updates: Vec<(N42Hardfork, APosConfigUpdate)>,
+
+    /// The bootnodes configured in the genesis, they take precedence over the built-in ones.
+    pub bootnodes: Vec<NodeRecord>,
+
+    /// Link to the EIP-1459 DNS tree listing the nodes of the network, `enrtree://<key>@<domain>`.
+    pub dns_discovery: Option<String>,
 }
 
 impl Default for ChainSpec {
@@ -365,14 +406,20 @@ impl Default for ChainSpec {
             base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
             prune_delete_limit: MAINNET_PRUNE_DELETE_LIMIT,
             blob_params: Default::default(),
+            apos_config: Default::default(),
+            apos_config_updates: Default::default(),
+            bootnodes: Default::default(),
+            dns_discovery: Default::default(),
         }
     }
 }
 
 impl ChainSpec {
-    /// Converts the given [`Genesis`] into a [`ChainSpec`].
-    pub fn from_genesis(genesis: Genesis) -> Self {
-        genesis.into()
+    /// Converts the given [`Genesis`] into a [`ChainSpec`], returning an error if its `apos`,
+    /// N42 fork or discovery configuration is invalid.
+    pub fn try_from_genesis(genesis: Genesis) -> Result<Self, AposConfigError> {
+        let n42_info = N42GenesisInfo::try_extract_from(&genesis)?;
+        Ok(Self::from_genesis_with(genesis, n42_info))
     }
 
     /// Get information about the chain itself
@@ -669,8 +716,11 @@ impl ChainSpec {
         ChainSpecBuilder::default()
     }
 
-    /// Returns the known bootnode records for the given chain.
+    /// Returns the bootnode records of the genesis, or the known ones for the given chain.
     pub fn bootnodes(&self) -> Option<Vec<NodeRecord>> {
+        if !self.bootnodes.is_empty() {
+            return Some(self.bootnodes.clone());
+        }
         if self.is_n42_testnet() {
             return Some(n42_testnet_nodes());
         }
@@ -693,10 +743,14 @@ impl ChainSpec {
             _ => None,
         }
     }
-}
 
-impl From<Genesis> for ChainSpec {
-    fn from(genesis: Genesis) -> Self {
+    /// Returns the link to the EIP-1459 DNS tree of the chain configured in the genesis, if any.
+    pub fn dns_discovery(&self) -> Option<&str> {
+        self.dns_discovery.as_deref()
+    }
+
+    /// Builds the chain spec of `genesis` with its already extracted N42 configuration.
+    fn from_genesis_with(genesis: Genesis, n42_info: N42GenesisInfo) -> Self {
         // Block-based hardforks
         let hardfork_opts = [
             (EthereumHardfork::Frontier.boxed(), Some(0)),
@@ -760,6 +814,9 @@ impl From<Genesis> for ChainSpec {
 
         hardforks.append(&mut time_hardforks);
 
+        // N42 hardforks and the `APos` parameters they change
+        hardforks.extend(n42_info.hardforks.into_iter().map(|(fork, cond)| (fork.boxed(), cond)));
+
         // Ordered Hardforks
         let mainnet_hardforks: ChainHardforks = EthereumHardfork::mainnet().into();
         let mainnet_order = mainnet_hardforks.forks_iter();
@@ -795,11 +852,23 @@ impl From<Genesis> for ChainSpec {
             paris_block_and_final_difficulty,
             deposit_contract,
             blob_params,
+            apos_config: n42_info.apos_config,
+            apos_config_updates: n42_info.apos_config_updates,
+            bootnodes: n42_info.bootnodes,
+            dns_discovery: n42_info.dns_discovery,
             ..Default::default()
         }
     }
 }
 
+impl TryFrom<Genesis> for ChainSpec {
+    type Error = AposConfigError;
+
+    fn try_from(genesis: Genesis) -> Result<Self, Self::Error> {
+        Self::try_from_genesis(genesis)
+    }
+}
+
 impl Hardforks for ChainSpec {
     fn fork<H: Hardfork>(&self, fork: H) -> ForkCondition {
         self.hardforks.fork(fork)
@@ -1958,7 +2027,7 @@ Post-merge hard forks (timestamp based):
         "#;
 
         let genesis: Genesis = serde_json::from_str(geth_genesis).unwrap();
-        let chainspec = ChainSpec::from(genesis);
+        let chainspec = ChainSpec::try_from_genesis(genesis).unwrap();
 
         // assert a bunch of hardforks that should be set
         assert_eq!(
@@ -2120,7 +2189,7 @@ Post-merge hard forks (timestamp based):
         "#;
 
         let genesis = serde_json::from_str::<Genesis>(hive_json).unwrap();
-        let chainspec: ChainSpec = genesis.into();
+        let chainspec = ChainSpec::try_from_genesis(genesis).unwrap();
         assert_eq!(chainspec.chain, Chain::from_named(NamedChain::Optimism));
         let expected_state_root: B256 =
             hex!("0x9a6049ac535e3dc7436c189eaa81c73f35abd7f282ab67c32944ff0301d63360").into();
@@ -2262,7 +2331,7 @@ Post-merge hard forks (timestamp based):
 
         // check that it deserializes properly
         let genesis: Genesis = serde_json::from_str(hive_paris).unwrap();
-        let chainspec = ChainSpec::from(genesis);
+        let chainspec = ChainSpec::try_from_genesis(genesis).unwrap();
 
         // make sure we are at ForkHash("bc0c2605") with Head post-cancun
         let expected_forkid = ForkId { hash: ForkHash([0xbc, 0x0c, 0x26, 0x05]), next: 0 };
@@ -2501,7 +2570,7 @@ Post-merge hard forks (timestamp based):
             ..Default::default()
         };
 
-        let chain_spec: ChainSpec = genesis.into();
+        let chain_spec = ChainSpec::try_from_genesis(genesis).unwrap();
 
         let hardforks: Vec<_> = chain_spec.hardforks.forks_iter().map(|(h, _)| h).collect();
         let expected_hardforks = vec![
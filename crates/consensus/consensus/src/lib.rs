@@ -22,8 +22,8 @@ use reth_primitives_traits::{
     SealedHeader,
 };
 
-use n42_primitives::Snapshot;
-use std::collections::HashMap;
+use n42_primitives::{SignerLiveness, Snapshot};
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
  
 /// A consensus implementation that does nothing.
@@ -82,6 +82,15 @@ pub trait Consensus<B: Block>: HeaderValidator<B::Header> {
         Ok(Header::default())
     }
 
+    /// for N42, prepares the header of a block sealed by an external consensus client, which sets
+    /// the difficulty of its own turn
+    fn prepare_external(
+        &self,
+        parent_header: &SealedHeader,
+    ) -> Result<Header, ConsensusError> {
+        Ok(Header::default())
+    }
+
     /// for N42
     fn seal(
         &self,
@@ -133,6 +142,21 @@ pub trait Consensus<B: Block>: HeaderValidator<B::Header> {
         Ok(HashMap::new())
     }
 
+    /// for N42, accounts for a header that became part of the canonical chain
+    fn on_canonical_header(
+        &self,
+        header: &SealedHeader,
+    ) -> Result<(), ConsensusError> {
+        Ok(())
+    }
+
+    /// Liveness of the current signers, as seen in the canonical headers.
+    fn signer_liveness(
+        &self,
+    ) -> Result<BTreeMap<Address, SignerLiveness>, ConsensusError> {
+        Ok(BTreeMap::new())
+    }
+
     fn total_difficulty(
         &self,
         hash: B256,
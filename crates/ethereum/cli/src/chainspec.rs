@@ -18,7 +18,7 @@ pub fn chain_value_parser(s: &str) -> eyre::Result<Arc<ChainSpec>, eyre::Error>
         "n42" => N42.clone(),
         "n42-devnet" => N42_DEVNET.clone(),
         "dev" => DEV.clone(),
-        _ => Arc::new(parse_genesis(s)?.into()),
+        _ => Arc::new(ChainSpec::try_from_genesis(parse_genesis(s)?)?),
     })
 }
 
@@ -103,4 +103,15 @@ mod tests {
         assert!(spec.is_prague_active_at_timestamp(0));
         assert!(spec.is_osaka_active_at_timestamp(0));
     }
+
+    #[test]
+    fn parse_rejects_invalid_apos_config() {
+        let s = r#"{"alloc": {}, "config": {"chainId": 1, "apos": {"epoch": 0}}}"#;
+        assert!(<EthereumChainSpecParser as ChainSpecParser>::parse(s).is_err());
+
+        let s = r#"{"alloc": {}, "config": {"chainId": 1, "apos": {"period": 2, "epoch": 10}}}"#;
+        let spec = <EthereumChainSpecParser as ChainSpecParser>::parse(s).unwrap();
+        assert_eq!(spec.apos_config().period, 2);
+        assert_eq!(spec.apos_config().epoch, 10);
+    }
 }
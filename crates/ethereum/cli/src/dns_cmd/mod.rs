@@ -0,0 +1,29 @@
+//! `reth dns` command. Tools for the EIP-1459 DNS trees listing the nodes of a network.
+
+use clap::{Parser, Subcommand};
+
+mod publish;
+pub mod tree;
+
+/// `reth dns` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[command(subcommand)]
+    command: Subcommands,
+}
+
+/// `reth dns` subcommands
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    /// Sign a DNS tree of the known peers of a node and write its TXT records.
+    Publish(publish::Command),
+}
+
+impl Command {
+    /// Execute `dns` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            Subcommands::Publish(command) => command.execute().await,
+        }
+    }
+}
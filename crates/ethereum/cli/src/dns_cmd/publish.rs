@@ -0,0 +1,94 @@
+//! Command that signs a DNS tree of the known peers of a node.
+
+use super::tree::{tree_link, DnsTree};
+use clap::Parser;
+use enr::Enr;
+use eyre::{ensure, WrapErr};
+use reth_dns_discovery::tree::LinkEntry;
+use secp256k1::SecretKey;
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+/// `reth dns publish` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The signed records of the known peers, which the node writes to `known-enrs.json` next to
+    /// its known peers file on shutdown.
+    #[arg(long, value_name = "PATH")]
+    enrs: PathBuf,
+
+    /// Domain the tree is published under, e.g. `nodes.n42.example`.
+    #[arg(long)]
+    domain: String,
+
+    /// Hex encoded secp256k1 key signing the tree, generated if the file does not exist.
+    ///
+    /// The public key is part of the `enrtree://` link clients are configured with, so the same key
+    /// has to sign every update of the tree.
+    #[arg(long, value_name = "PATH")]
+    key: PathBuf,
+
+    /// Sequence number of the tree, defaults to the current unix time. Clients ignore trees with a
+    /// lower sequence number than the one they know.
+    #[arg(long)]
+    seq: Option<u64>,
+
+    /// `enrtree://` links to other trees to include, may be repeated.
+    #[arg(long = "link", value_name = "ENRTREE", value_parser = parse_link)]
+    links: Vec<String>,
+
+    /// Write the TXT records to this file instead of stdout, as a JSON object from record name to
+    /// content.
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+impl Command {
+    /// Execute `dns publish` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        let key = reth_cli_util::get_secret_key(&self.key)?;
+
+        let content = reth_fs_util::read_to_string(&self.enrs)?;
+        let records: Vec<String> = serde_json::from_str(&content)
+            .wrap_err_with(|| format!("invalid records file {}", self.enrs.display()))?;
+        let enrs: Vec<Enr<SecretKey>> = records
+            .iter()
+            .filter_map(|record| match record.parse() {
+                Ok(enr) => Some(enr),
+                Err(err) => {
+                    warn!(target: "reth::cli", %err, record, "Skipping invalid node record");
+                    None
+                }
+            })
+            .collect();
+        ensure!(!enrs.is_empty(), "no valid node records in {}", self.enrs.display());
+
+        let seq = self.seq.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+        });
+        let txt_records = DnsTree::new(&enrs, &self.links, seq).sign(&key, &self.domain);
+        let json = serde_json::to_string_pretty(&txt_records)?;
+
+        match &self.output {
+            Some(path) => reth_fs_util::write(path, json)?,
+            None => println!("{json}"),
+        }
+        info!(
+            target: "reth::cli",
+            nodes = enrs.len(),
+            seq,
+            link = %tree_link(&key, &self.domain),
+            "Signed DNS tree, publish its TXT records and set the link as `dnsDiscovery` in the genesis"
+        );
+        Ok(())
+    }
+}
+
+/// Checks that `s` is a valid `enrtree://` link.
+fn parse_link(s: &str) -> eyre::Result<String> {
+    s.parse::<LinkEntry>().wrap_err_with(|| format!("invalid tree link {s}"))?;
+    Ok(s.to_string())
+}
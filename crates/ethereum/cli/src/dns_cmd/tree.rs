@@ -0,0 +1,179 @@
+//! EIP-1459 node trees.
+//!
+//! A tree is a set of TXT records under a domain: a signed root at the domain itself, pointing to
+//! a tree of `enrtree-branch` records whose leaves are node records, and to a second tree of links
+//! to other trees. Every record but the root lives at a subdomain named after its hash.
+//!
+//! See <https://eips.ethereum.org/EIPS/eip-1459>.
+
+use alloy_primitives::keccak256;
+use data_encoding::{BASE32_NOPAD, BASE64URL_NOPAD};
+use enr::Enr;
+use secp256k1::{Message, SecretKey, SECP256K1};
+use std::collections::BTreeMap;
+
+/// Maximum number of children of a branch record, keeping the record within the 370 bytes that
+/// fit into a single DNS response next to the other records.
+const MAX_BRANCH_CHILDREN: usize = 370 / (SUBDOMAIN_LENGTH + 1);
+
+/// Length of the subdomains of the records, the base32 encoding of 16 hash bytes.
+const SUBDOMAIN_LENGTH: usize = 26;
+
+/// The records of a node tree, before the root is signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsTree {
+    /// Subdomain of the root of the node records subtree.
+    enr_root: String,
+    /// Subdomain of the root of the links subtree.
+    link_root: String,
+    /// Sequence number of the tree, clients only accept roots with a higher one.
+    seq: u64,
+    /// All records but the root, by subdomain.
+    entries: BTreeMap<String, String>,
+}
+
+impl DnsTree {
+    /// Builds the tree of the node records `enrs` and of the `enrtree://` links to other trees.
+    pub fn new(enrs: &[Enr<SecretKey>], links: &[String], seq: u64) -> Self {
+        let mut entries = BTreeMap::new();
+
+        let mut leaves: Vec<_> = enrs.iter().map(Enr::to_base64).collect();
+        leaves.sort_unstable();
+        leaves.dedup();
+        let enr_root = build_subtree(&mut entries, leaves);
+
+        let mut links: Vec<_> = links.to_vec();
+        links.sort_unstable();
+        links.dedup();
+        let link_root = build_subtree(&mut entries, links);
+
+        Self { enr_root, link_root, seq, entries }
+    }
+
+    /// Returns the content of the root record signed with the tree key.
+    fn root_content(&self) -> String {
+        format!("enrtree-root:v1 e={} l={} seq={}", self.enr_root, self.link_root, self.seq)
+    }
+
+    /// Signs the root with `key` and returns the TXT records to publish under `domain`, by name.
+    pub fn sign(&self, key: &SecretKey, domain: &str) -> BTreeMap<String, String> {
+        let content = self.root_content();
+        let message = Message::from_digest(keccak256(content.as_bytes()).0);
+        let (recovery_id, signature) =
+            SECP256K1.sign_ecdsa_recoverable(&message, key).serialize_compact();
+        let mut sig = signature.to_vec();
+        sig.push(i32::from(recovery_id) as u8);
+
+        let root = format!("{content} sig={}", BASE64URL_NOPAD.encode(&sig));
+        let mut records = BTreeMap::new();
+        records.insert(domain.to_string(), root);
+        for (subdomain, entry) in &self.entries {
+            records.insert(format!("{subdomain}.{domain}"), entry.clone());
+        }
+        records
+    }
+}
+
+/// Returns the `enrtree://` link to the tree signed with `key` published under `domain`.
+pub fn tree_link(key: &SecretKey, domain: &str) -> String {
+    let public_key = key.public_key(SECP256K1).serialize();
+    format!("enrtree://{}@{domain}", BASE32_NOPAD.encode(&public_key))
+}
+
+/// Adds the records of a subtree holding `leaves` to `entries` and returns the subdomain of its
+/// root.
+fn build_subtree(entries: &mut BTreeMap<String, String>, leaves: Vec<String>) -> String {
+    for leaf in &leaves {
+        entries.insert(subdomain(leaf), leaf.clone());
+    }
+
+    let mut level = leaves;
+    loop {
+        if level.len() == 1 {
+            return subdomain(&level[0])
+        }
+        if level.len() <= MAX_BRANCH_CHILDREN {
+            let branch = branch(&level);
+            let root = subdomain(&branch);
+            entries.insert(root.clone(), branch);
+            return root
+        }
+        level = level
+            .chunks(MAX_BRANCH_CHILDREN)
+            .map(|children| {
+                let branch = branch(children);
+                entries.insert(subdomain(&branch), branch.clone());
+                branch
+            })
+            .collect();
+    }
+}
+
+/// Returns the branch record pointing to `children`.
+fn branch(children: &[String]) -> String {
+    let hashes: Vec<_> = children.iter().map(|child| subdomain(child)).collect();
+    format!("enrtree-branch:{}", hashes.join(","))
+}
+
+/// Returns the subdomain of `record`, the base32 encoding of the first 16 bytes of its hash.
+fn subdomain(record: &str) -> String {
+    BASE32_NOPAD.encode(&keccak256(record.as_bytes())[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use reth_dns_discovery::{
+        resolver::MapResolver, tree::LinkEntry, DnsDiscoveryConfig, DnsDiscoveryService,
+    };
+    use std::{collections::HashSet, net::Ipv4Addr, sync::Arc, time::Duration};
+
+    fn random_enr(port: u16) -> Enr<SecretKey> {
+        let (key, _) = SECP256K1.generate_keypair(&mut rand_08::thread_rng());
+        Enr::builder().ip4(Ipv4Addr::LOCALHOST).tcp4(port).udp4(port).build(&key).unwrap()
+    }
+
+    #[test]
+    fn branches_fit_in_a_record() {
+        let enrs: Vec<_> = (0..40).map(|i| random_enr(30303 + i)).collect();
+        let (key, _) = SECP256K1.generate_keypair(&mut rand_08::thread_rng());
+        let records = DnsTree::new(&enrs, &[], 1).sign(&key, "nodes.example.org");
+
+        let leaves = records.values().filter(|record| record.starts_with("enr:")).count();
+        assert_eq!(leaves, 40);
+        for record in records.values().filter(|record| record.starts_with("enrtree-branch:")) {
+            assert!(record.len() <= 370);
+        }
+        assert!(records["nodes.example.org"].starts_with("enrtree-root:v1 e="));
+    }
+
+    #[tokio::test]
+    async fn discovers_published_nodes() {
+        let enrs: Vec<_> = (0..20).map(|i| random_enr(30303 + i)).collect();
+        let (key, _) = SECP256K1.generate_keypair(&mut rand_08::thread_rng());
+        let domain = "nodes.example.org";
+
+        // the resolver stands in for the DNS, serving the published records
+        let resolver = MapResolver::default();
+        for (name, record) in DnsTree::new(&enrs, &[], 1).sign(&key, domain) {
+            resolver.insert(name, record);
+        }
+
+        let link: LinkEntry = tree_link(&key, domain).parse().unwrap();
+        let (mut service, handle) =
+            DnsDiscoveryService::new_pair(Arc::new(resolver), DnsDiscoveryConfig::default());
+        let mut updates = service.node_record_stream();
+        let _service = service.spawn();
+        handle.sync_tree_with_link(link);
+
+        let mut discovered = HashSet::new();
+        while discovered.len() < enrs.len() {
+            let update = tokio::time::timeout(Duration::from_secs(5), updates.next())
+                .await
+                .expect("all published nodes are discovered")
+                .unwrap();
+            discovered.insert(update.node_record.id);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! `reth genesis` command. Tools for bootstrapping new N42 networks.
+
+use clap::{Parser, Subcommand};
+
+mod new;
+pub use new::validate_apos_genesis;
+
+/// `reth genesis` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[command(subcommand)]
+    command: Subcommands,
+}
+
+/// `reth genesis` subcommands
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    /// Generate a genesis JSON for a new `APos` chain.
+    New(new::Command),
+}
+
+impl Command {
+    /// Execute `genesis` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            Subcommands::New(command) => command.execute().await,
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! Command that generates a genesis JSON for a new `APos` chain.
+
+use alloy_genesis::{ChainConfig, CliqueConfig, Genesis, GenesisAccount};
+use alloy_primitives::{Address, U256};
+use clap::Parser;
+use eyre::{bail, ensure, WrapErr};
+use n42_clique::{checkpoint_extra_data, extract_checkpoint_signers, DIFF_NO_TURN};
+use n42_primitives::APosConfig;
+use reth_chainspec::{Hardfork, N42Hardfork};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+/// Default gas limit of the genesis block.
+const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+
+/// `reth genesis new` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// Chain id of the new network.
+    #[arg(long = "chain-id", value_name = "CHAIN_ID")]
+    chain_id: u64,
+
+    /// Addresses of the initial signers, comma separated or repeated.
+    #[arg(long = "signer", value_name = "ADDRESS", value_delimiter = ',')]
+    signers: Vec<Address>,
+
+    /// Encrypted keystore files of the initial signers, the address is read from the keystore.
+    #[arg(long = "keystore", value_name = "PATH", value_delimiter = ',')]
+    keystores: Vec<PathBuf>,
+
+    /// Minimum number of seconds between two consecutive blocks.
+    #[arg(long, default_value_t = APosConfig::default().period)]
+    period: u64,
+
+    /// Number of blocks after which votes are reset and the signer list is checkpointed.
+    #[arg(long, default_value_t = APosConfig::default().epoch)]
+    epoch: u64,
+
+    /// Prefunded account in the form `<address>=<balance in wei>`, may be repeated.
+    #[arg(long = "alloc", value_name = "ADDRESS=WEI", value_parser = parse_alloc)]
+    allocs: Vec<(Address, U256)>,
+
+    /// JSON file with additional genesis allocations, in the same format as the `alloc` section
+    /// of a genesis file.
+    #[arg(long = "alloc-file", value_name = "PATH")]
+    alloc_file: Option<PathBuf>,
+
+    /// Gas limit of the genesis block.
+    #[arg(long = "gas-limit", default_value_t = DEFAULT_GAS_LIMIT)]
+    gas_limit: u64,
+
+    /// Base fee of the genesis block.
+    #[arg(long = "base-fee", default_value_t = alloy_eips::eip1559::INITIAL_BASE_FEE)]
+    base_fee: u64,
+
+    /// Timestamp of the genesis block, defaults to the current time.
+    #[arg(long)]
+    timestamp: Option<u64>,
+
+    /// Shanghai activation timestamp.
+    #[arg(long = "shanghai-time", value_name = "TIMESTAMP")]
+    shanghai_time: Option<u64>,
+
+    /// Cancun activation timestamp.
+    #[arg(long = "cancun-time", value_name = "TIMESTAMP")]
+    cancun_time: Option<u64>,
+
+    /// Prague activation timestamp.
+    #[arg(long = "prague-time", value_name = "TIMESTAMP")]
+    prague_time: Option<u64>,
+
+    /// Activation block of an N42 hardfork in the form `<fork>=<block>`, e.g. `beijing=1000`,
+    /// may be repeated.
+    ///
+    /// N42 hardforks that are not scheduled here or with `--n42-fork-time` are left out of the
+    /// genesis and never activate. Their `APos` parameter changes can be added to the `aposForks`
+    /// section of the written file.
+    #[arg(long = "n42-fork-block", value_name = "FORK=BLOCK", value_parser = parse_n42_fork)]
+    n42_fork_blocks: Vec<(N42Hardfork, u64)>,
+
+    /// Activation timestamp of an N42 hardfork in the form `<fork>=<timestamp>`, may be
+    /// repeated.
+    #[arg(long = "n42-fork-time", value_name = "FORK=TIMESTAMP", value_parser = parse_n42_fork)]
+    n42_fork_times: Vec<(N42Hardfork, u64)>,
+
+    /// Write the genesis to this file instead of stdout.
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+impl Command {
+    /// Execute `genesis new` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        let genesis = self.build_genesis()?;
+        let signers = validate_apos_genesis(&genesis)?;
+        let json = serde_json::to_string_pretty(&genesis)?;
+
+        match &self.output {
+            Some(path) => {
+                reth_fs_util::write(path, json)?;
+                info!(target: "reth::cli", ?path, ?signers, "Wrote genesis");
+            }
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+
+    /// Assembles the genesis from the command arguments.
+    fn build_genesis(&self) -> eyre::Result<Genesis> {
+        let mut signers = self.signers.clone();
+        for keystore in &self.keystores {
+            signers.push(keystore_address(keystore)?);
+        }
+        ensure!(!signers.is_empty(), "at least one signer is required, use --signer or --keystore");
+        // Keep the signer order deterministic, the in-turn rotation follows this order.
+        signers.sort_unstable();
+
+        ensure!(
+            self.shanghai_time <= self.cancun_time || self.cancun_time.is_none(),
+            "cancun must not activate before shanghai"
+        );
+        ensure!(
+            self.cancun_time <= self.prague_time || self.prague_time.is_none(),
+            "prague must not activate before cancun"
+        );
+        if self.cancun_time.is_some() && self.shanghai_time.is_none() {
+            bail!("cancun requires shanghai to be scheduled");
+        }
+        if self.prague_time.is_some() && self.cancun_time.is_none() {
+            bail!("prague requires cancun to be scheduled");
+        }
+
+        for (fork, _) in &self.n42_fork_blocks {
+            ensure!(
+                !self.n42_fork_times.iter().any(|(f, _)| f == fork),
+                "{fork} is scheduled both by block and by timestamp"
+            );
+        }
+
+        let mut alloc = BTreeMap::new();
+        if let Some(path) = &self.alloc_file {
+            let content = reth_fs_util::read_to_string(path)?;
+            let accounts: BTreeMap<Address, GenesisAccount> = serde_json::from_str(&content)
+                .wrap_err_with(|| format!("invalid alloc file {}", path.display()))?;
+            alloc.extend(accounts);
+        }
+        for (address, balance) in &self.allocs {
+            alloc.insert(*address, GenesisAccount::default().with_balance(*balance));
+        }
+
+        let timestamp = match self.timestamp {
+            Some(timestamp) => timestamp,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+
+        // APos keeps the block rewards and difficulty of clique, but runs every pre-merge fork
+        // from genesis and treats the merge as already passed, like `N42_HARDFORKS`.
+        let mut config = ChainConfig {
+            chain_id: self.chain_id,
+            homestead_block: Some(0),
+            eip150_block: Some(0),
+            eip155_block: Some(0),
+            eip158_block: Some(0),
+            byzantium_block: Some(0),
+            constantinople_block: Some(0),
+            petersburg_block: Some(0),
+            istanbul_block: Some(0),
+            muir_glacier_block: Some(0),
+            berlin_block: Some(0),
+            london_block: Some(0),
+            arrow_glacier_block: Some(0),
+            merge_netsplit_block: Some(0),
+            terminal_total_difficulty: Some(U256::ZERO),
+            terminal_total_difficulty_passed: true,
+            shanghai_time: self.shanghai_time,
+            cancun_time: self.cancun_time,
+            prague_time: self.prague_time,
+            clique: Some(CliqueConfig { period: Some(self.period), epoch: Some(self.epoch) }),
+            ..Default::default()
+        };
+        // N42 forks use the same `<fork>Block` and `<fork>Time` keys as the ethereum ones.
+        for (fork, block) in &self.n42_fork_blocks {
+            config.extra_fields.insert(format!("{}Block", fork_key(*fork)), (*block).into());
+        }
+        for (fork, timestamp) in &self.n42_fork_times {
+            config.extra_fields.insert(format!("{}Time", fork_key(*fork)), (*timestamp).into());
+        }
+
+        Ok(Genesis {
+            config,
+            nonce: 0,
+            timestamp,
+            extra_data: checkpoint_extra_data(&signers),
+            gas_limit: self.gas_limit,
+            difficulty: DIFF_NO_TURN,
+            coinbase: Address::ZERO,
+            alloc,
+            base_fee_per_gas: Some(self.base_fee as u128),
+            ..Default::default()
+        })
+    }
+}
+
+/// Checks that `genesis` can be used as the first `APos` checkpoint and returns its signers.
+///
+/// These are the same rules `APos::snapshot_inner` and `validate_header` apply to checkpoint
+/// headers, so a genesis passing this check is accepted by every node on startup.
+pub fn validate_apos_genesis(genesis: &Genesis) -> eyre::Result<Vec<Address>> {
+    let Some(clique) = genesis.config.clique.as_ref() else {
+        bail!("genesis is missing the clique config");
+    };
+    ensure!(clique.period.is_some(), "clique period is not set");
+    ensure!(clique.epoch.is_some_and(|epoch| epoch > 0), "clique epoch must be greater than zero");
+
+    ensure!(genesis.coinbase.is_zero(), "checkpoint beneficiary must be zero");
+    ensure!(genesis.nonce == 0, "checkpoint nonce must be zero");
+    ensure!(!genesis.difficulty.is_zero(), "genesis difficulty must not be zero");
+
+    let signers = extract_checkpoint_signers(&genesis.extra_data)
+        .map_err(|err| eyre::eyre!("invalid extraData: {err}"))?;
+    let mut seen = HashSet::with_capacity(signers.len());
+    for signer in &signers {
+        ensure!(!signer.is_zero(), "zero address can not be a signer");
+        ensure!(seen.insert(*signer), "duplicate signer {signer}");
+    }
+    Ok(signers)
+}
+
+/// Reads the address of an encrypted keystore file without decrypting it.
+fn keystore_address(path: &Path) -> eyre::Result<Address> {
+    let content = reth_fs_util::read_to_string(path)?;
+    let keystore: serde_json::Value = serde_json::from_str(&content)
+        .wrap_err_with(|| format!("invalid keystore {}", path.display()))?;
+    let Some(address) = keystore.get("address").and_then(|a| a.as_str()) else {
+        bail!("keystore {} has no address field", path.display());
+    };
+    Address::from_str(address).wrap_err_with(|| format!("invalid address in {}", path.display()))
+}
+
+/// Parses an `<address>=<balance>` allocation.
+fn parse_alloc(s: &str) -> eyre::Result<(Address, U256)> {
+    let Some((address, balance)) = s.split_once('=') else {
+        bail!("expected <address>=<balance>, got {s}");
+    };
+    Ok((Address::from_str(address.trim())?, U256::from_str(balance.trim())?))
+}
+
+/// Parses a `<fork>=<activation>` N42 hardfork schedule.
+fn parse_n42_fork(s: &str) -> eyre::Result<(N42Hardfork, u64)> {
+    let Some((fork, activation)) = s.split_once('=') else {
+        bail!("expected <fork>=<activation>, got {s}");
+    };
+    let fork = N42Hardfork::from_str(fork.trim()).map_err(|err| eyre::eyre!(err))?;
+    Ok((fork, activation.trim().parse()?))
+}
+
+/// Returns the genesis config key prefix of `fork`, e.g. `beijing`.
+fn fork_key(fork: N42Hardfork) -> String {
+    let name = fork.name();
+    let mut key = name[..1].to_lowercase();
+    key.push_str(&name[1..]);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chainspec::chain_value_parser;
+    use alloy_primitives::address;
+    use reth_chainspec::{ForkCondition, Hardforks};
+
+    const SIGNER_A: Address = address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+    const SIGNER_B: Address = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+
+    fn command(args: &[&str]) -> Command {
+        Command::parse_from(["new"].iter().chain(args))
+    }
+
+    #[test]
+    fn builds_valid_genesis() {
+        let cmd = command(&[
+            "--chain-id",
+            "4242",
+            "--signer",
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266,0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "--period",
+            "3",
+            "--epoch",
+            "100",
+            "--alloc",
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266=1000",
+            "--shanghai-time",
+            "0",
+            "--cancun-time",
+            "0",
+            "--timestamp",
+            "1",
+        ]);
+        let genesis = cmd.build_genesis().unwrap();
+
+        assert_eq!(validate_apos_genesis(&genesis).unwrap(), vec![SIGNER_A, SIGNER_B]);
+        assert_eq!(genesis.extra_data.len(), 32 + 2 * 20 + 65);
+        assert_eq!(genesis.difficulty, DIFF_NO_TURN);
+        assert_eq!(genesis.config.clique.unwrap().epoch, Some(100));
+        assert_eq!(genesis.alloc[&SIGNER_B].balance, U256::from(1000));
+
+        // the output must be loadable as a custom chain
+        let json = serde_json::to_string(&genesis).unwrap();
+        let spec = chain_value_parser(&json).unwrap();
+        assert_eq!(spec.chain.id(), 4242);
+    }
+
+    #[test]
+    fn rejects_missing_signers() {
+        let err = command(&["--chain-id", "1"]).build_genesis().unwrap_err();
+        assert!(err.to_string().contains("at least one signer"), "{err}");
+    }
+
+    #[test]
+    fn schedules_n42_forks() {
+        let cmd = command(&[
+            "--chain-id",
+            "4242",
+            "--signer",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "--n42-fork-block",
+            "beijing=100",
+        ]);
+        let genesis = cmd.build_genesis().unwrap();
+        assert_eq!(genesis.config.extra_fields.get("beijingBlock"), Some(&100.into()));
+
+        let json = serde_json::to_string(&genesis).unwrap();
+        let spec = chain_value_parser(&json).unwrap();
+        assert_eq!(spec.fork(N42Hardfork::Beijing), ForkCondition::Block(100));
+
+        let cmd = command(&[
+            "--chain-id",
+            "1",
+            "--signer",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "--n42-fork-block",
+            "beijing=1",
+            "--n42-fork-time",
+            "beijing=1",
+        ]);
+        assert!(cmd.build_genesis().is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_signers() {
+        let cmd = command(&[
+            "--chain-id",
+            "1",
+            "--signer",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "--signer",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+        ]);
+        assert!(validate_apos_genesis(&cmd.build_genesis().unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_order_forks() {
+        let cmd = command(&["--chain-id", "1", "--shanghai-time", "10", "--cancun-time", "5"]);
+        assert!(cmd.build_genesis().is_err());
+    }
+
+    #[test]
+    fn reads_keystore_address() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.json");
+        std::fs::write(
+            &path,
+            r#"{"address":"70997970c51812dc3a010c7d01b50e0d17dc79c8","crypto":{}}"#,
+        )
+        .unwrap();
+        assert_eq!(keystore_address(&path).unwrap(), SIGNER_A);
+    }
+}
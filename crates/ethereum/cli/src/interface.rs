@@ -1,6 +1,6 @@
 //! CLI definition and entrypoint to executable
 
-use crate::{chainspec::EthereumChainSpecParser, debug_cmd};
+use crate::{chainspec::EthereumChainSpecParser, debug_cmd, dns_cmd, genesis_cmd};
 use clap::{Parser, Subcommand};
 use reth_chainspec::ChainSpec;
 use reth_cli::chainspec::ChainSpecParser;
@@ -182,6 +182,8 @@ impl<C: ChainSpecParser<ChainSpec = ChainSpec>, Ext: clap::Args + fmt::Debug> Cl
             #[cfg(feature = "dev")]
             Commands::TestVectors(command) => runner.run_until_ctrl_c(command.execute()),
             Commands::Config(command) => runner.run_until_ctrl_c(command.execute()),
+            Commands::Genesis(command) => runner.run_until_ctrl_c(command.execute()),
+            Commands::Dns(command) => runner.run_until_ctrl_c(command.execute()),
             Commands::Debug(command) => {
                 runner.run_command_until_exit(|ctx| command.execute::<EthereumNode>(ctx))
             }
@@ -242,6 +244,12 @@ pub enum Commands<C: ChainSpecParser, Ext: clap::Args + fmt::Debug> {
     /// Write config to stdout
     #[command(name = "config")]
     Config(config_cmd::Command),
+    /// Generate genesis files for new N42 networks
+    #[command(name = "genesis")]
+    Genesis(genesis_cmd::Command),
+    /// Publish the known peers of N42 nodes as DNS trees
+    #[command(name = "dns")]
+    Dns(dns_cmd::Command),
     /// Various debug routines
     #[command(name = "debug")]
     Debug(Box<debug_cmd::Command<C>>),
@@ -270,6 +278,8 @@ impl<C: ChainSpecParser, Ext: clap::Args + fmt::Debug> Commands<C, Ext> {
             #[cfg(feature = "dev")]
             Self::TestVectors(_) => None,
             Self::Config(_) => None,
+            Self::Genesis(_) => None,
+            Self::Dns(_) => None,
             Self::Debug(cmd) => cmd.chain_spec(),
             Self::Recover(cmd) => cmd.chain_spec(),
             Self::Prune(cmd) => cmd.chain_spec(),
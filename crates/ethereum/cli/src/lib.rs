@@ -11,6 +11,8 @@
 /// Chain specification parser.
 pub mod chainspec;
 pub mod debug_cmd;
+pub mod dns_cmd;
+pub mod genesis_cmd;
 pub mod interface;
 pub use interface::Cli;
 
@@ -2,7 +2,7 @@ mod dev;
 pub use dev::DEV_HARDFORKS;
 
 mod n42;
-pub use n42::N42_HARDFORKS;
+pub use n42::{N42Hardfork, N42Hardforks, N42_HARDFORKS};
 
 use crate::{ForkCondition, ForkFilter, ForkId, Hardfork, Head};
 #[cfg(feature = "std")]
@@ -1,4 +1,5 @@
 use alloc::vec;
+use core::{fmt, str::FromStr};
 
 use alloy_primitives::U256;
 
@@ -8,7 +9,71 @@ use once_cell::sync::Lazy as LazyLock;
 #[cfg(feature = "std")]
 use std::sync::LazyLock;
 
-use crate::{ChainHardforks, EthereumHardfork, ForkCondition, Hardfork};
+use crate::{ChainHardforks, EthereumHardfork, ForkCondition, Hardfork, Hardforks};
+
+/// Hardforks that only exist on N42 networks.
+///
+/// Besides enabling new consensus rules, an N42 hardfork can change the `APos` parameters that
+/// are in effect from its activation on, see `AposChainSpec` in `reth-chainspec`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub enum N42Hardfork {
+    /// Beijing: enforces the clique header rules on mix digest, uncles and block spacing.
+    Beijing,
+}
+
+impl N42Hardfork {
+    /// All N42 hardforks in activation order.
+    pub const VARIANTS: &'static [Self] = &[Self::Beijing];
+}
+
+impl Hardfork for N42Hardfork {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Beijing => "Beijing",
+        }
+    }
+}
+
+impl fmt::Display for N42Hardfork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for N42Hardfork {
+    type Err = alloc::string::String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::VARIANTS
+            .iter()
+            .find(|fork| fork.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| alloc::format!("unknown N42 hardfork: {s}"))
+    }
+}
+
+/// Helper methods for N42 specific forks.
+#[auto_impl::auto_impl(&, Arc)]
+pub trait N42Hardforks: Hardforks {
+    /// Retrieves [`ForkCondition`] by an [`N42Hardfork`]. If `fork` is not present, returns
+    /// [`ForkCondition::Never`].
+    fn n42_fork_activation(&self, fork: N42Hardfork) -> ForkCondition {
+        self.fork(fork)
+    }
+
+    /// Returns `true` if `fork` is active for a block with the given number and timestamp.
+    ///
+    /// N42 forks may be scheduled either by block or by timestamp, so both are checked.
+    fn is_n42_fork_active_at(&self, fork: N42Hardfork, number: u64, timestamp: u64) -> bool {
+        let condition = self.n42_fork_activation(fork);
+        condition.active_at_block(number) || condition.active_at_timestamp(timestamp)
+    }
+
+    /// Convenience method to check if [`N42Hardfork::Beijing`] is active.
+    fn is_beijing_active_at(&self, number: u64, timestamp: u64) -> bool {
+        self.is_n42_fork_active_at(N42Hardfork::Beijing, number, timestamp)
+    }
+}
 
 /// N42 hardforks
 pub static N42_HARDFORKS: LazyLock<ChainHardforks> = LazyLock::new(|| {
@@ -25,7 +90,6 @@ pub static N42_HARDFORKS: LazyLock<ChainHardforks> = LazyLock::new(|| {
         (EthereumHardfork::Berlin.boxed(), ForkCondition::Block(0)),
         (EthereumHardfork::London.boxed(), ForkCondition::Block(0)),
         (EthereumHardfork::ArrowGlacier.boxed(), ForkCondition::Block(0)),
-        //(EthereumHardfork::Beijing.boxed(), ForkCondition::Block(0)),
         (
             EthereumHardfork::Paris.boxed(),
             ForkCondition::TTD {
@@ -37,5 +101,6 @@ pub static N42_HARDFORKS: LazyLock<ChainHardforks> = LazyLock::new(|| {
         (EthereumHardfork::Shanghai.boxed(), ForkCondition::Timestamp(1746576000)),
         (EthereumHardfork::Cancun.boxed(), ForkCondition::Timestamp(1746576000)),
         (EthereumHardfork::Prague.boxed(), ForkCondition::Timestamp(1748930400)),
+        (N42Hardfork::Beijing.boxed(), ForkCondition::Never),
     ])
 });
@@ -0,0 +1,208 @@
+#![allow(missing_docs)]
+//! Snapshot lookups of [`APos`] over a database provider.
+//!
+//! `hit` looks up a cached snapshot on an idle engine, `hit_during_misses` does the same while
+//! another thread keeps asking for snapshots the cache evicted, whose walks read headers from the
+//! database. The looked up snapshot stays cached, eviction keeps the entries looked up since it
+//! last visited them.
+//!
+//! The `snapshot_map` group compares cached lookups contended by other threads against the cache
+//! used before, an LRU updated by every lookup under an exclusive lock.
+
+use alloy_primitives::{Address, B256};
+use criterion::{criterion_group, criterion_main, Criterion};
+use n42_clique::{checkpoint_extra_data, APos, RecentMap};
+use n42_primitives::Snapshot;
+use reth_chainspec::{make_genesis_header, ChainSpec, N42};
+use reth_consensus::Consensus;
+use reth_ethereum_primitives::Block;
+use reth_primitives_traits::{
+    header::clique_utils::{public_key_to_address, seal_hash_generic},
+    Header, RecoveredBlock, SealedBlock, SealedHeader,
+};
+use reth_provider::{
+    providers::BlockchainProvider,
+    test_utils::{create_test_provider_factory_with_chain_spec, MockNodeTypesWithDB},
+    BlockWriter, StorageLocation,
+};
+use schnellru::{ByLength, LruMap};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+};
+
+/// Number of blocks on top of the genesis, more than the engine keeps snapshots of.
+const BLOCKS: u64 = 512;
+
+/// Number of snapshots the maps hold, as many as the engine keeps.
+const CACHED: u32 = 128;
+
+/// Number of threads looking up snapshots besides the measured one.
+const READERS: usize = 3;
+
+type Engine = APos<BlockchainProvider<MockNodeTypesWithDB>, ChainSpec>;
+
+criterion_group!(benches, snapshot_lookup, snapshot_map);
+criterion_main!(benches);
+
+/// Returns an empty block sealed by `key` the way `APos` signers seal them.
+fn signed_block(
+    key: &secp256k1::SecretKey,
+    number: u64,
+    parent_hash: B256,
+) -> RecoveredBlock<Block> {
+    let mut header =
+        Header { number, parent_hash, extra_data: vec![0; 32 + 65].into(), ..Default::default() };
+    let message = secp256k1::Message::from_digest(seal_hash_generic(&header).0);
+    let (recovery_id, signature) =
+        secp256k1::SECP256K1.sign_ecdsa_recoverable(&message, key).serialize_compact();
+    let mut extra_data = vec![0; 32];
+    extra_data.extend_from_slice(&signature);
+    extra_data.push(i32::from(recovery_id) as u8);
+    header.extra_data = extra_data.into();
+    RecoveredBlock::new_unhashed(Block { header, body: Default::default() }, vec![])
+}
+
+/// Returns an engine over a database holding a chain of [`BLOCKS`] blocks sealed in turn by two
+/// signers, with the hashes of its blocks.
+fn engine() -> (Arc<Engine>, Vec<B256>) {
+    let keys = [0xa, 0xb].map(|byte| secp256k1::SecretKey::from_slice(&[byte; 32]).unwrap());
+    let signers: Vec<Address> = keys
+        .iter()
+        .map(|key| public_key_to_address(key.public_key(secp256k1::SECP256K1)))
+        .collect();
+
+    let mut chain_spec = ChainSpec::clone(&N42);
+    chain_spec.genesis.extra_data = checkpoint_extra_data(&signers);
+    chain_spec.genesis_header =
+        SealedHeader::new_unhashed(make_genesis_header(&chain_spec.genesis, &chain_spec.hardforks));
+    let chain_spec = Arc::new(chain_spec);
+
+    let factory = create_test_provider_factory_with_chain_spec(chain_spec.clone());
+    let provider_rw = factory.provider_rw().unwrap();
+    let genesis = chain_spec.genesis_header.clone();
+    let mut hashes = vec![genesis.hash()];
+    provider_rw
+        .insert_block(
+            RecoveredBlock::new_sealed(
+                SealedBlock::from_sealed_parts(genesis, Default::default()),
+                vec![],
+            ),
+            StorageLocation::Database,
+        )
+        .unwrap();
+    for number in 1..=BLOCKS {
+        let block = signed_block(&keys[number as usize % 2], number, hashes[number as usize - 1]);
+        hashes.push(block.hash());
+        provider_rw.insert_block(block, StorageLocation::Database).unwrap();
+    }
+    provider_rw.commit().unwrap();
+
+    let provider = BlockchainProvider::new(factory).unwrap();
+    (Arc::new(APos::new(provider, chain_spec, None)), hashes)
+}
+
+/// Looks up the snapshot of block `number` the way the node does.
+fn lookup(engine: &Engine, hashes: &[B256], number: u64) -> Snapshot {
+    <Engine as Consensus<Block>>::snapshot(engine, number, hashes[number as usize], None).unwrap()
+}
+
+/// Snapshots cached by the hash of their block.
+trait SnapshotMap: Send + Sync {
+    fn insert(&self, hash: B256, snapshot: Snapshot);
+    fn get(&self, hash: &B256) -> Option<Snapshot>;
+}
+
+impl SnapshotMap for RecentMap<B256, Snapshot> {
+    fn insert(&self, hash: B256, snapshot: Snapshot) {
+        Self::insert(self, hash, snapshot)
+    }
+
+    fn get(&self, hash: &B256) -> Option<Snapshot> {
+        Self::get(self, hash)
+    }
+}
+
+/// The cache used before, whose lookups update the LRU order and so take the write lock.
+struct LockedLru(RwLock<LruMap<B256, Snapshot>>);
+
+impl SnapshotMap for LockedLru {
+    fn insert(&self, hash: B256, snapshot: Snapshot) {
+        self.0.write().unwrap().insert(hash, snapshot);
+    }
+
+    fn get(&self, hash: &B256) -> Option<Snapshot> {
+        self.0.write().unwrap().get(hash).cloned()
+    }
+}
+
+/// Measures lookups of a cached snapshot in `map` while [`READERS`] other threads look up the
+/// others.
+fn contended_get(c: &mut Criterion, name: &str, map: Arc<dyn SnapshotMap>, snapshot: &Snapshot) {
+    let hashes: Vec<B256> =
+        (0..CACHED as u64).map(|n| B256::left_padding_from(&n.to_be_bytes())).collect();
+    for hash in &hashes {
+        map.insert(*hash, snapshot.clone());
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..READERS)
+        .map(|reader| {
+            let (map, hashes, stop) = (map.clone(), hashes.clone(), stop.clone());
+            thread::spawn(move || {
+                let mut index = reader;
+                while !stop.load(Ordering::Relaxed) {
+                    index = (index + 1) % hashes.len();
+                    map.get(&hashes[index]).unwrap();
+                }
+            })
+        })
+        .collect();
+    c.benchmark_group("snapshot_map")
+        .bench_function(name, |b| b.iter(|| map.get(&hashes[0]).unwrap()));
+    stop.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
+
+fn snapshot_map(c: &mut Criterion) {
+    let (engine, hashes) = engine();
+    let snapshot = lookup(&engine, &hashes, BLOCKS);
+
+    let recent: Arc<dyn SnapshotMap> = Arc::new(RecentMap::new(CACHED as usize));
+    contended_get(c, "recent_map", recent, &snapshot);
+    let locked: Arc<dyn SnapshotMap> =
+        Arc::new(LockedLru(RwLock::new(LruMap::new(ByLength::new(CACHED)))));
+    contended_get(c, "locked_lru", locked, &snapshot);
+}
+
+fn snapshot_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apos_snapshot");
+    let (engine, hashes) = engine();
+    let hit = BLOCKS;
+    lookup(&engine, &hashes, hit);
+
+    group.bench_function("hit", |b| b.iter(|| lookup(&engine, &hashes, hit)));
+
+    // walks the chain from its start, each snapshot evicted again before its turn comes back
+    let stop = Arc::new(AtomicBool::new(false));
+    let walker = {
+        let (engine, hashes, stop) = (engine.clone(), hashes.clone(), stop.clone());
+        thread::spawn(move || {
+            let mut number = 0;
+            while !stop.load(Ordering::Relaxed) {
+                number = number % (BLOCKS - 1) + 1;
+                lookup(&engine, &hashes, number);
+            }
+        })
+    };
+    group.bench_function("hit_during_misses", |b| b.iter(|| lookup(&engine, &hashes, hit)));
+    stop.store(true, Ordering::Relaxed);
+    walker.join().unwrap();
+
+    group.finish();
+}
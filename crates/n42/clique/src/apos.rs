@@ -5,19 +5,21 @@ use reth_primitives_traits::{Block as BlockTrait, BlockHeader as BlockHeaderTrai
 use std::error::Error;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::sync::atomic::{AtomicBool, Ordering};
 use alloy_primitives::{U256, hex, BlockHash, B64, B256, Address, Bytes, FixedBytes};
 use bytes::BytesMut;
 use rand::prelude::SliceRandom;
-use reth_chainspec::{EthChainSpec, EthereumHardforks};
+use reth_chainspec::{AposChainSpec, EthChainSpec, EthereumHardforks};
+use alloy_consensus::constants::{EMPTY_OMMER_ROOT_HASH, EMPTY_ROOT_HASH};
 use reth_primitives::{SealedBlock, SealedHeader, BlockWithSenders};
 use reth_execution_types::BlockExecutionResult;
 use reth_primitives_traits::{RecoveredBlock, Header, header::clique_utils::{recover_address_generic, SIGNATURE_LENGTH, seal_hash}};
 use reth_provider::{BlockIdReader, BlockReaderIdExt, HeaderProvider, SnapshotProvider};
 use tracing::{info, warn, debug, error};
-use n42_primitives::{APosConfig, Snapshot};
+use n42_primitives::{SignerLiveness, SignerLivenessTracker, Snapshot};
+use crate::snapshot_cache::{InFlight, RecentMap};
 
 use alloy_signer_local::{LocalSigner, PrivateKeySigner};
 use k256::ecdsa::SigningKey;
@@ -28,8 +30,7 @@ use reth_node_api::{FullNodeTypes, PrimitivesTy};
 use std::str::FromStr;
 
 //
-const CHECKPOINT_INTERVAL: u64 = 2048; // Number of blocks after which to save the vote snapshot to the database
-const INMEMORY_SNAPSHOTS: u32 = 128; // Number of recent vote snapshots to keep in memory
+const INMEMORY_SNAPSHOTS: usize = 128; // Number of recent vote snapshots to keep in memory
 const INMEMORY_TDS: u32 = 1024; // Number of recent total difficulty records to keep in memory
 
 const WIGGLE_TIME: Duration = Duration::from_millis(500); // Random delay (per signer) to allow concurrent signers
@@ -37,9 +38,6 @@ const WIGGLE_TIME: Duration = Duration::from_millis(500); // Random delay (per s
 
 // APos proof-of-authority protocol constants
 
-/// Default number of blocks after which to checkpoint and reset the pending votes
-pub const EPOCH_LENGTH: u64 = 30000;
-
 /// Fixed number of extra-data prefix bytes reserved for signer vanity
 pub const EXTRA_VANITY: usize = 32;
 /// Fixed number of extra-data prefix bytes reserved for seal
@@ -98,6 +96,8 @@ pub enum AposError {
     RecentlySigned,
     /// `UnTransion`,
     UnTransion,
+    /// `EarlyEmptyBlock`,
+    EarlyEmptyBlock,
 }
 
 impl std::fmt::Display for AposError {
@@ -124,6 +124,7 @@ impl std::fmt::Display for AposError {
                 Self::UnauthorizedSigner => "unauthorized signer",
                 Self::RecentlySigned => "recently signed",
                 Self::UnTransion => "sealing paused while waiting for transactions",
+                Self::EarlyEmptyBlock => "empty block before the heartbeat interval elapsed",
             }
         )
     }
@@ -136,20 +137,23 @@ impl Error for AposError {}
 pub struct APos<Provider, ChainSpec>
 where
     Provider: HeaderProvider<Header = reth_primitives_traits::Header> + SnapshotProvider + SnapshotProviderWriter + BlockIdReader  + BlockReaderIdExt + Clone + Unpin + 'static,
-    ChainSpec: EthChainSpec + EthereumHardforks
+    ChainSpec: EthChainSpec + EthereumHardforks + AposChainSpec
 {
-    config: APosConfig,          // Consensus engine configuration parameters
-    /// Chain spec
+    /// Chain spec, the source of the consensus engine parameters at each height
     chain_spec: Arc<ChainSpec>,
-    recents: RwLock<schnellru::LruMap<B256, Snapshot>>,    // Snapshots for recent block to speed up reorgs
+    recents: RecentMap<B256, Arc<Snapshot>>,    // Snapshots for recent block to speed up reorgs, evicted in insertion order
+    snapshots_in_flight: InFlight<(B256, bool), Result<Snapshot, ConsensusError>>,    // Snapshot walks in progress, by block and whether parents were given
     proposals: Arc<RwLock<HashMap<Address, bool>>>,   // Current list of proposals we are pushing
     signer: RwLock<Option<Address>>, // Ethereum address of the signing key
     eth_signer: RwLock<Option<LocalSigner<SigningKey>>>,
     //  Provider,
     provider: Provider,
-    recent_headers: RwLock<schnellru::LruMap<B256, Provider::Header>>,    // Recent headers for snapshot
+    recent_headers: RecentMap<B256, Provider::Header>,    // Recent headers for snapshot
     recent_tds: RwLock<schnellru::LruMap<B256, U256>>,
     recent_tds_inited: AtomicBool,
+    liveness: RwLock<SignerLivenessTracker>,    // Sealed blocks and missed turns of the signers
+    inactive_signer_rounds: Option<u64>,    // Propose to drop signers missing more consecutive rounds
+    auto_drops: RwLock<HashSet<Address>>,    // Drop proposals made by the inactive signer policy
 }
 
 
@@ -158,7 +162,7 @@ where
 impl<Provider, ChainSpec> APos<Provider, ChainSpec>
 where
     Provider: HeaderProvider<Header = reth_primitives_traits::Header> + SnapshotProvider + SnapshotProviderWriter + BlockIdReader  + BlockReaderIdExt + Clone + Unpin + 'static,
-    ChainSpec: EthChainSpec + EthereumHardforks,
+    ChainSpec: EthChainSpec + EthereumHardforks + AposChainSpec,
 {
     /// new
     pub fn new(
@@ -167,8 +171,10 @@ where
         signer_private_key: Option<String>,
     ) -> Self
     {
-        let recents = RwLock::new(schnellru::LruMap::new(schnellru::ByLength::new(INMEMORY_SNAPSHOTS)));
-        let recent_headers = RwLock::new(schnellru::LruMap::new(schnellru::ByLength::new(CHECKPOINT_INTERVAL as u32 * 2)));
+        let recents = RecentMap::new(INMEMORY_SNAPSHOTS);
+        // Headers back to the previous on-disk checkpoint are enough to rebuild any snapshot
+        let checkpoint_interval = chain_spec.apos_checkpoint_interval();
+        let recent_headers = RecentMap::new(checkpoint_interval.saturating_mul(2).min(u32::MAX as u64) as usize);
         let recent_tds = RwLock::new(schnellru::LruMap::new(schnellru::ByLength::new(INMEMORY_TDS)));
         let recent_tds_inited = AtomicBool::new(false);
 
@@ -177,20 +183,10 @@ where
         let eth_signer_address = eth_signer.clone().map(|signer| {signer.address()});
         info!(target: "consensus::apos", "apos set signer address {:?}", eth_signer_address);
 
-        let mut config = APosConfig::default();
-        if let Some(clique) = chain_spec.genesis().config.clique {
-            if let Some(period) = clique.period {
-                config.period = period;
-            }
-            if let Some(epoch) = clique.epoch {
-                config.epoch = epoch;
-            }
-        }
-
         Self {
-            config,
             chain_spec,
             recents,
+            snapshots_in_flight: InFlight::default(),
             recent_headers,
             recent_tds,
             recent_tds_inited,
@@ -198,6 +194,47 @@ where
             signer: RwLock::new(eth_signer_address),
             eth_signer: RwLock::new(eth_signer),
             provider,
+            liveness: RwLock::new(SignerLivenessTracker::default()),
+            inactive_signer_rounds: None,
+            auto_drops: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Automatically proposes to drop signers that missed more than `rounds` consecutive in-turn
+    /// slots, the proposal is discarded again once the signer seals or is dropped.
+    pub const fn with_inactive_signer_rounds(mut self, rounds: Option<u64>) -> Self {
+        self.inactive_signer_rounds = rounds;
+        self
+    }
+
+    /// Updates the signer liveness with a canonical block and applies the inactive signer policy.
+    fn track_liveness(&self, snap: &Snapshot, number: u64, sealer: Address) {
+        let mut liveness = self.liveness.write().unwrap();
+        if !liveness.on_block(&snap.signers, number, sealer) {
+            return
+        }
+        let Some(rounds) = self.inactive_signer_rounds else { return };
+        let local = *self.signer.read().unwrap();
+        let inactive: HashSet<Address> = liveness.inactive(rounds).filter(|signer| Some(*signer) != local).collect();
+        drop(liveness);
+
+        let mut auto_drops = self.auto_drops.write().unwrap();
+        let mut proposals = self.proposals.write().unwrap();
+        // withdraw the drop votes of signers that are back or already gone
+        for signer in auto_drops.difference(&inactive) {
+            if proposals.get(signer) == Some(&false) {
+                proposals.remove(signer);
+                info!(target: "consensus::apos", %signer, "withdrawing drop proposal for signer");
+            }
+        }
+        auto_drops.retain(|signer| inactive.contains(signer));
+        for signer in inactive {
+            // operator proposals take precedence
+            if proposals.contains_key(&signer) || !auto_drops.insert(signer) {
+                continue
+            }
+            proposals.insert(signer, false);
+            warn!(target: "consensus::apos", %signer, rounds, "proposing to drop inactive signer");
         }
     }
 
@@ -240,18 +277,12 @@ where
 
         #[cfg(debug_assertions)]
         {
-            self.provider.save_signer_by_hash(&header.hash_slow(), signer).map_err(|_| ConsensusError::UnknownBlock)?;
+            self.provider.save_signer_by_hash(&header.hash_slow(), header.number(), signer).map_err(|_| ConsensusError::UnknownBlock)?;
         }
 
        //Check the list of recent signatories
-        for (seen, recent) in &snap.recents {
-            if *recent == signer {
-                //If the signer is in the recent list, ensure that the current block can be removed
-                let limit = (snap.signers.len() as u64 / 2) + 1;
-                if header.number() < limit || *seen > header.number() - limit {
-                    return Err(AposError::RecentlySigned.into());
-                }
-            }
+        if snap.recently_signed(header.number(), &signer) {
+            return Err(AposError::RecentlySigned.into());
         }
 
        //Ensure that the difficulty corresponds to the signer's round
@@ -338,31 +369,46 @@ where
     }
 
     /// snapshot retrieves the authorization snapshot at a given point in time.
+    ///
+    /// Concurrent calls for the same block share a single walk, which holds no lock while it
+    /// reads the headers. A walk given parents can reach blocks the provider does not know yet,
+    /// so only calls that agree on whether they give parents share one.
     fn snapshot_inner(
         &self,
         number: u64,
         hash: B256,
         parents: Option<Vec<Provider::Header>>,
     ) -> Result<Snapshot, ConsensusError> {
+        if let Some(snap) = self.recents.get(&hash) {
+            return Ok(Snapshot::clone(&snap))
+        }
+        let has_parents = parents.as_ref().is_some_and(|parents| !parents.is_empty());
+        self.snapshots_in_flight
+            .run((hash, has_parents), || self.walk_snapshot(number, hash, parents))
+    }
 
-        let mut headers: Vec<Provider::Header> = Vec::new();
+    /// Builds the snapshot at `hash` from the closest known snapshot and the headers since.
+    fn walk_snapshot(
+        &self,
+        number: u64,
+        hash: B256,
+        parents: Option<Vec<Provider::Header>>,
+    ) -> Result<Snapshot, ConsensusError> {
+        let mut headers: Vec<SealedHeader<Provider::Header>> = Vec::new();
         let mut snap: Option<Snapshot> = None;
         let mut hash = hash;
         let mut number = number;
         let mut parents = parents;
 
-        let mut recents = self.recents.write().unwrap(); //
-        let mut recent_headers = self.recent_headers.write().unwrap();
-
         while snap.is_none() {
             //Attempt to retrieve a snapshot from memory
-            if let Some(cached_snap) = recents.get(&hash) {
-                snap = Some(cached_snap.clone());
+            if let Some(cached_snap) = self.recents.get(&hash) {
+                snap = Some(Snapshot::clone(&cached_snap));
                 break;
             }
 
             // Attempt to obtain a snapshot from the disk
-            if number != 0 && number % CHECKPOINT_INTERVAL == 0 {
+            if number != 0 && number % self.chain_spec.apos_checkpoint_interval() == 0 {
                 if let Ok(Some(s)) = self.provider.load_snapshot_by_hash(&hash) {
                     snap = Some(s);
                     break;
@@ -374,27 +420,21 @@ where
             // at a checkpoint block without a parent (light client CHT), or we have piled
             // up more headers than allowed to be reorged (chain reinit from a freezer),
             // consider the checkpoint trusted and snapshot it.
-            if number == 0 || (number % self.config.epoch == 0 && (headers.len() > FULL_IMMUTABILITY_THRESHOLD || self.provider.header_by_number(number -1).unwrap().is_none())) {
+            if number == 0 || (number % self.chain_spec.apos_epoch_at(number) == 0 && (headers.len() > FULL_IMMUTABILITY_THRESHOLD || self.provider.header_by_number(number -1).unwrap().is_none())) {
                 if let Ok(Some(checkpoint)) = self.provider.header_by_number(number) {
                     debug!(target: "consensus::apos", "checkpoint={:?}", checkpoint);
                     let hash = checkpoint.hash_slow();
                     //info!(target: "consensus::apos", "snapshot() : number={}, hash_slow hash={:?}", number, hash);
             
                     //Calculate the list of signatories
-                    let signers_count = (checkpoint.extra_data().len() - EXTRA_VANITY - SIGNATURE_LENGTH) /  Address::len_bytes();
-
-                    let mut signers = Vec::with_capacity(signers_count);
-            
-                    for i in 0..signers_count {
-                        let start = EXTRA_VANITY + i * Address::len_bytes();
-                        let end = start + Address::len_bytes();
-                        signers.push(Address::from_slice(&checkpoint.extra_data()[start..end]));
-                    }
+                    let signers = extract_checkpoint_signers(checkpoint.extra_data())
+                        .map_err(|_| ConsensusError::InvalidCheckpointSigners)?;
                     debug!(target: "consensus::apos", ?signers,
                         "genesis signers:"
                     );
                    
-                    let s = Snapshot::new_snapshot(self.config.clone(), number, hash, signers);
+                    let config = self.chain_spec.apos_config_at(number, checkpoint.timestamp());
+                    let s = Snapshot::new_snapshot(config, number, hash, signers);
                     // todo
                     self.provider.save_snapshot_by_hash(&hash, s.clone()).map_err(|_| ConsensusError::UnknownBlock)?;
                     snap = Option::from(s);
@@ -408,18 +448,19 @@ where
                 }
             }
 
-            // No snapshot for this header, gather the header and move backward
+            // No snapshot for this header, gather the header and move backward. Headers looked up
+            // by hash keep that hash, only the given parents are hashed.
             let header = if parents.is_some() && !parents.as_ref().unwrap().is_empty() {
-                let header = parents.as_mut().unwrap().pop().unwrap();
-                if header.hash_slow() != hash || header.number() != number {
-                    error!(target: "consensus::apos", "parent hash check failed: {:?}, {:?}, {:?}, {:?}", header.hash_slow(), hash, header.number(), number);
+                let header = SealedHeader::seal_slow(parents.as_mut().unwrap().pop().unwrap());
+                if header.hash() != hash || header.number() != number {
+                    error!(target: "consensus::apos", "parent hash check failed: {:?}, {:?}, {:?}, {:?}", header.hash(), hash, header.number(), number);
                     return Err(ConsensusError::UnknownBlock);
                 }
                 header
-            } else if let Some(v) = recent_headers.get(&hash) {
-                v.clone()
+            } else if let Some(v) = self.recent_headers.get(&hash) {
+                SealedHeader::new(v, hash)
             } else if let Some(header) = self.provider.header_by_hash_or_number(hash.into()).map_err(|_| ConsensusError::UnknownBlock)? {
-               header
+               SealedHeader::new(header, hash)
             } else {
                 error!(target: "consensus::apos", "hash not found: {:?}", hash);
                 return Err(ConsensusError::UnknownBlock);
@@ -438,15 +479,29 @@ where
             headers.swap(i, headers_len - 1 - i);
         }
 
-        let snap = snap.unwrap().apply::<_, Provider::Header>(headers, |header| {
-            let signer = recover_address_generic(&header)?;
-            Ok(signer)
-        }).map_err(|_| ConsensusError::InvalidDifficulty)?;
+        // Apply the headers in runs that share the same parameters, so that votes are reset at
+        // the epoch boundaries in effect at each height.
+        let mut snap = snap.unwrap();
+        let mut pending = headers;
+        while !pending.is_empty() {
+            let config = self.chain_spec.apos_config_at(pending[0].number(), pending[0].timestamp());
+            let split = pending
+                .iter()
+                .position(|h| self.chain_spec.apos_config_at(h.number(), h.timestamp()) != config)
+                .unwrap_or(pending.len());
+            let rest = pending.split_off(split);
+            snap.config = config;
+            snap = snap.apply::<_, Provider::Header>(pending, |header| {
+                let signer = recover_address_generic(header.header())?;
+                Ok(signer)
+            }).map_err(|_| ConsensusError::InvalidDifficulty)?;
+            pending = rest;
+        }
 
-        recents.insert(snap.hash, snap.clone());
+        self.recents.insert(snap.hash, Arc::new(snap.clone()));
 
         //If a new checkpoint snapshot is generated, save it to disk
-        if snap.number % CHECKPOINT_INTERVAL == 0 && headers_len > 0 {
+        if snap.number % self.chain_spec.apos_checkpoint_interval() == 0 && headers_len > 0 {
             self.provider.save_snapshot_by_hash(&snap.hash, snap.clone()).map_err(|_|ConsensusError::SaveSnapshotError)?;
             debug!(
                 "Stored voting snapshot to disk, number: {}, hash: {}",
@@ -458,7 +513,135 @@ where
         Ok(snap)
     }
 
+    /// Prepares the consensus fields of the header of the block on top of `parent_header`, sealed
+    /// by `signer`, or by an external consensus client if `None`.
+    fn prepare_header(
+        &self,
+        parent_header: &SealedHeader,
+        signer: Option<Address>,
+    ) -> Result<Header, ConsensusError> {
+        let mut header = Header::default();
+        //If the block is not a checkpoint, vote randomly
+        header.beneficiary = Address::ZERO;
+        header.nonce = B64::from(0u64);
+        header.number = parent_header.number + 1;
+        header.parent_hash = parent_header.hash();
+
+
+
+        //Assemble voting snapshots to check which votes are meaningful
+        let snap = self.snapshot_inner(
+            parent_header.number, parent_header.hash(), None).map_err(|_| ConsensusError::UnknownBlock)?;
+
+        let epoch = self.chain_spec.apos_epoch_at(header.number);
+        if header.number % epoch != 0 {
+            //Collect all proposals to be voted on
+            let proposals_lock = self.proposals.read().unwrap();
+            let addresses: Vec<Address> = proposals_lock.iter()
+                //.filter(|(&address, &authorize)| snap.valid_vote(address, authorize))
+                .map(|(address, _)| *address)
+                .collect();
+
+            //If there are proposals to be voted on, proceed with the vote
+            if !addresses.is_empty() {
+                header.beneficiary = *addresses.choose(&mut rand::rng()).unwrap();
+                if let Some(&authorize) = proposals_lock.get(&header.beneficiary) {
+                    if authorize {
+                        header.nonce = NONCE_AUTH_VOTE.into();
+                    } else {
+                        header.nonce = NONCE_DROP_VOTE.into();
+                    }
+                }
+            }
+        }
+
+        debug!(target: "consensus::apos", ?snap, "snap");
+        //Set the correct difficulty level
+        header.difficulty = match signer {
+            Some(signer) => calc_difficulty(&snap, &signer),
+            None => DIFF_NO_TURN,
+        };
+
+        let mut extra_data_mut = BytesMut::from(&header.extra_data[..]);
+        //Ensure that the additional data has all its components
+        extra_data_mut.resize(EXTRA_VANITY, 0x00);
+
+        if header.number % epoch == 0 {
+            for signer in snap.signers {
+                extra_data_mut.extend(signer.iter());
+            }
+        }
+        extra_data_mut.resize(extra_data_mut.len() + SIGNATURE_LENGTH, 0x00);
+        header.extra_data = Bytes::from(extra_data_mut.freeze());
+
+        header.mix_hash = Default::default();
+
+        // Ensure the timestamp has the correct delay
+        if let Ok(Some(parent)) = self.provider.header_by_hash_or_number(header.parent_hash.into()) {
+            let parent_time = parent.timestamp();
+            header.timestamp = parent_time + self.chain_spec.apos_config_at(header.number, parent_time).period;
+        }
+
+        Ok(header)
+    }
+}
+
+/// Random delay of an out-of-turn signer before sealing, up to `WIGGLE_TIME` per signer, which
+/// gives the in-turn signer a head start (EIP-225).
+pub fn out_of_turn_wiggle(signers: usize) -> Duration {
+    WIGGLE_TIME.mul_f64(rand::random::<f64>() * signers as f64)
+}
+
+/// Returns the signer list embedded in the extra-data of a checkpoint header.
+///
+/// The extra-data must be laid out as `vanity (32) | signers (20 * n) | seal (65)` with at least
+/// one signer, which is what `snapshot_inner` expects from genesis and trusted checkpoints.
+pub fn extract_checkpoint_signers(extra_data: &[u8]) -> Result<Vec<Address>, AposError> {
+    if extra_data.len() < EXTRA_VANITY {
+        return Err(AposError::MissingVanity);
+    }
+    if extra_data.len() < EXTRA_VANITY + EXTRA_SEAL {
+        return Err(AposError::MissingSignature);
+    }
+    let signers_bytes = &extra_data[EXTRA_VANITY..extra_data.len() - EXTRA_SEAL];
+    if signers_bytes.is_empty() || signers_bytes.len() % Address::len_bytes() != 0 {
+        return Err(AposError::InvalidCheckpointSigners);
+    }
+    Ok(signers_bytes.chunks_exact(Address::len_bytes()).map(Address::from_slice).collect())
+}
+
+/// Encodes the extra-data of a checkpoint header: zero vanity, the signer list and an empty seal.
+pub fn checkpoint_extra_data(signers: &[Address]) -> Bytes {
+    let mut extra = Vec::with_capacity(EXTRA_VANITY + signers.len() * Address::len_bytes() + EXTRA_SEAL);
+    extra.extend_from_slice(&[0u8; EXTRA_VANITY]);
+    for signer in signers {
+        extra.extend_from_slice(signer.as_slice());
+    }
+    extra.extend_from_slice(&[0u8; EXTRA_SEAL]);
+    extra.into()
+}
+
+/// Signs `header` with `signer`, writing the seal into the end of its extra-data.
+pub fn seal_header(signer: &PrivateKeySigner, header: &mut Header) -> Result<(), ConsensusError> {
+    let sighash = signer.sign_hash_sync(&seal_hash(header)).map_err(|_| ConsensusError::SignHeaderError)?;
+
+    let mut extra_data_mut = BytesMut::from(&header.extra_data[..]);
+    extra_data_mut[header.extra_data.len().saturating_sub(SIGNATURE_LENGTH)..].copy_from_slice(&sighash.as_bytes());
+    *extra_data_mut.last_mut().unwrap() -= 27;
+    header.extra_data = Bytes::from(extra_data_mut.freeze());
+    Ok(())
+}
 
+/// Checks the clique header rules that are enforced from the Beijing fork on: an empty mix
+/// digest and no uncles.
+fn verify_beijing_fields<H: BlockHeaderTrait>(header: &H) -> Result<(), AposError> {
+    if header.mix_hash().is_some_and(|mix_hash| mix_hash != B256::ZERO) {
+        return Err(AposError::InvalidMixDigest);
+    }
+    if header.ommers_hash() != EMPTY_OMMER_ROOT_HASH {
+        return Err(AposError::InvalidUncleHash);
+    }
+    Ok(())
 }
 
 fn calc_difficulty(snap: &Snapshot, signer: &Address) -> U256 {
@@ -471,7 +654,7 @@ fn calc_difficulty(snap: &Snapshot, signer: &Address) -> U256 {
 
 impl<Provider, ChainSpec> Debug for APos<Provider, ChainSpec>
 where
-    ChainSpec: EthChainSpec + EthereumHardforks,
+    ChainSpec: EthChainSpec + EthereumHardforks + AposChainSpec,
     Provider: 'static + Clone + HeaderProvider<Header = reth_primitives_traits::Header> + SnapshotProvider + SnapshotProviderWriter + BlockIdReader  + BlockReaderIdExt + Unpin,
 {
     fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -481,7 +664,7 @@ where
 
 impl<Provider, ChainSpec> HeaderValidator for APos<Provider, ChainSpec>
 where
-    ChainSpec: EthChainSpec + EthereumHardforks,
+    ChainSpec: EthChainSpec + EthereumHardforks + AposChainSpec,
     Provider: 'static + Clone + HeaderProvider<Header = reth_primitives_traits::Header> + SnapshotProvider + SnapshotProviderWriter + BlockIdReader  + BlockReaderIdExt + Unpin,
 {
     fn validate_header(&self, header: &SealedHeader) -> Result<(), ConsensusError> {
@@ -504,7 +687,7 @@ where
         }
 
         // Checkpoint blocks need to enforce zero beneficiary
-        let checkpoint = (number % self.config.epoch) == 0;
+        let checkpoint = (number % self.chain_spec.apos_epoch_at(number)) == 0;
         if checkpoint && header.beneficiary() != Address::ZERO {
             return Err(ConsensusError::InvalidCheckpointBeneficiary);
         }
@@ -544,6 +727,10 @@ where
             return Err(ConsensusError::InvalidDifficulty);
         }
 
+        if self.chain_spec.is_beijing_active_at(number, header.timestamp()) {
+            verify_beijing_fields(header).map_err(|e| ConsensusError::AposErrorDetail { detail: e.to_string() })?;
+        }
+
         //todo All basic checks passed, verify cascading fields
         Ok(())
     }
@@ -562,8 +749,24 @@ where
             return Ok(());
         }
 
+        // Blocks must honour the period in effect, enforced from the Beijing fork on
+        if self.chain_spec.is_beijing_active_at(number, header.timestamp()) {
+            let period = self.chain_spec.apos_config_at(number, header.timestamp()).period;
+            if header.timestamp() < parent.timestamp() + period {
+                return Err(ConsensusError::AposErrorDetail { detail: AposError::InvalidTimestamp.to_string() });
+            }
+        }
+
+        // Without transactions, a block is only a heartbeat and must wait for its interval
+        let config = self.chain_spec.apos_config_at(number, header.timestamp());
+        if header.transactions_root() == EMPTY_ROOT_HASH &&
+            !config.allows_empty_block(parent.timestamp(), header.timestamp())
+        {
+            return Err(ConsensusError::AposErrorDetail { detail: AposError::EarlyEmptyBlock.to_string() });
+        }
+
         let snap = self.snapshot_inner(number - 1, header.parent_hash(), Some(vec![parent.header().clone()]))?;
-        if number % self.config.epoch == 0 {
+        if number % self.chain_spec.apos_epoch_at(number) == 0 {
             let signers: Vec<u8> = snap.signers
                 .iter()
                 .flat_map(|signer| signer.as_slice().to_vec())
@@ -575,8 +778,7 @@ where
         }
 
         self.verify_seal(&snap, header, None).map_err(|e| {ConsensusError::AposErrorDetail {detail: e.to_string()}})?;
-        let mut recent_headers = self.recent_headers.write().unwrap();
-        recent_headers.insert(header_hash, header.clone());
+        self.recent_headers.insert(header_hash, header.clone());
 
         self.save_total_difficulty(header);
 
@@ -601,7 +803,7 @@ where
 impl<Provider, ChainSpec, N> FullConsensus<N> for APos<Provider, ChainSpec>
 where
     Provider: HeaderProvider<Header = reth_primitives_traits::Header> +SnapshotProvider + SnapshotProviderWriter  + BlockIdReader  + BlockReaderIdExt + Clone + Unpin + 'static,
-    ChainSpec: EthChainSpec + EthereumHardforks,
+    ChainSpec: EthChainSpec + EthereumHardforks + AposChainSpec,
     N: NodePrimitives,
     APos<Provider, ChainSpec>: HeaderValidator<<N as NodePrimitives>::BlockHeader>,
 {
@@ -618,7 +820,7 @@ where
 impl<Provider, ChainSpec, B> Consensus<B> for APos<Provider, ChainSpec>
 where
     Provider: HeaderProvider<Header = reth_primitives_traits::Header> +SnapshotProvider + SnapshotProviderWriter  + BlockIdReader  + BlockReaderIdExt + Clone + Unpin + 'static,
-    ChainSpec: EthChainSpec + EthereumHardforks,
+    ChainSpec: EthChainSpec + EthereumHardforks + AposChainSpec,
     B: BlockTrait,
     APos<Provider, ChainSpec>: HeaderValidator<<B as reth_primitives_traits::Block>::Header>,
 {
@@ -639,72 +841,14 @@ where
     /// Prepare implements consensus.Engine, preparing all the consensus fields of the
     /// header for running the transactions on top.
     fn prepare(&self, parent_header: &SealedHeader) -> Result<Header, ConsensusError> {
+        let signer = self.signer.read().unwrap().ok_or(ConsensusError::NoSignerSet)?;
+        self.prepare_header(parent_header, Some(signer))
+    }
 
-        let mut header = Header::default(); 
-        //If the block is not a checkpoint, vote randomly
-        header.beneficiary = Address::ZERO;
-        header.nonce = B64::from(0u64);
-        header.number = parent_header.number + 1;
-        header.parent_hash = parent_header.hash();
-
-
-
-        //Assemble voting snapshots to check which votes are meaningful
-        let snap = self.snapshot_inner(
-            parent_header.number, parent_header.hash(), None).map_err(|_| ConsensusError::UnknownBlock)?;
-
-        if header.number %self.config.epoch != 0 {
-            //Collect all proposals to be voted on
-            let proposals_lock = self.proposals.read().unwrap();
-            let addresses: Vec<Address> = proposals_lock.iter()
-                //.filter(|(&address, &authorize)| snap.valid_vote(address, authorize))
-                .map(|(address, _)| *address)
-                .collect();
-
-            //If there are proposals to be voted on, proceed with the vote
-            if !addresses.is_empty() {
-                header.beneficiary = *addresses.choose(&mut rand::rng()).unwrap();
-                if let Some(&authorize) = proposals_lock.get(&header.beneficiary) {
-                    if authorize {
-                        header.nonce = NONCE_AUTH_VOTE.into();
-                    } else {
-                        header.nonce = NONCE_DROP_VOTE.into();
-                    }
-                }
-            }
-        }
-
-        debug!(target: "consensus::apos", ?snap, "snap");
-        //Copy the signer to prevent data competition
-        let signer_guard = self.signer.read().unwrap();
-        if let Some(signer) = *signer_guard {
-            //Set the correct difficulty level
-            header.difficulty = calc_difficulty(&snap, &signer);
-        } else {
-            return Err(ConsensusError::NoSignerSet);
-        }
-
-        let mut extra_data_mut = BytesMut::from(&header.extra_data[..]);
-        //Ensure that the additional data has all its components
-        extra_data_mut.resize(EXTRA_VANITY, 0x00);
-
-        if header.number % self.config.epoch == 0 {
-            for signer in snap.signers {
-                extra_data_mut.extend(signer.iter());
-            }
-        }
-        extra_data_mut.resize(extra_data_mut.len() + SIGNATURE_LENGTH, 0x00);
-        header.extra_data = Bytes::from(extra_data_mut.freeze());
-
-        header.mix_hash = Default::default();
-
-        // Ensure the timestamp has the correct delay
-        if let Ok(Some(parent)) = self.provider.header_by_hash_or_number(header.parent_hash.into()) {
-            let parent_time = parent.timestamp();
-            header.timestamp = parent_time + self.config.period;
-        }
-
-        Ok(header)
+    /// Prepares the header like [`Self::prepare`] without a local signer, leaving the difficulty
+    /// of its turn to the external consensus client sealing it.
+    fn prepare_external(&self, parent_header: &SealedHeader) -> Result<Header, ConsensusError> {
+        self.prepare_header(parent_header, None)
     }
 
     fn seal(&self, header: &mut Header) -> Result<(), ConsensusError> {
@@ -714,12 +858,15 @@ where
             return Err(ConsensusError::UnknownBlock);
         }
 
-        // For 0-period chains, refuse to seal empty blocks (no reward but would spin sealing)
-        // if self.config.period == 0 && header.body.transactions.is_empty() {
-        //     return Err(AposError::UnTransion.into());
-        // }
-        //todo
-
+        // Refuse to seal empty blocks until the heartbeat interval elapsed
+        if header.transactions_root == EMPTY_ROOT_HASH {
+            if let Ok(Some(parent)) = self.provider.header_by_hash_or_number(header.parent_hash.into()) {
+                let config = self.chain_spec.apos_config_at(header.number, header.timestamp);
+                if !config.allows_empty_block(parent.timestamp(), header.timestamp) {
+                    return Err(ConsensusError::AposErrorDetail { detail: AposError::EarlyEmptyBlock.to_string() });
+                }
+            }
+        }
 
         let signer = self.signer.read().unwrap().ok_or(ConsensusError::NoSignerSet)?;
         debug!(target: "consensus::apos", "seal() signer={:?}", signer);
@@ -732,34 +879,22 @@ where
         }
 
         // If we're amongst the recent signers, wait for the next block
-        for (seen, recent) in &snap.recents {
-            if *recent == signer {
-                let limit = (snap.signers.len() as u64 / 2) + 1;
-                if header.number < limit || *seen > header.number - limit {
-                    error!(target: "consensus::engine", "Signed recently, must wait for others: limit: {}, seen: {}, number: {}, signer: {}", limit, seen, header.number, signer);
-                    return Err(ConsensusError::RecentlySigned);
-                }
-            }
+        if snap.recently_signed(header.number, &signer) {
+            error!(target: "consensus::engine", "Signed recently, must wait for others: number: {}, signer: {}", header.number, signer);
+            return Err(ConsensusError::RecentlySigned);
         }
 
-        // // Beijing hard fork logic (if applicable)
-        // if self.chain_spec.is_beijing_active_at_block(block.number) 
-        //
-        // }
+        // Don't sign headers that the rest of the network would reject after Beijing
+        if self.chain_spec.is_beijing_active_at(header.number, header.timestamp) {
+            verify_beijing_fields(header).map_err(|e| ConsensusError::AposErrorDetail { detail: e.to_string() })?;
+        }
 
         let eth_signer_guard = self.eth_signer.read().unwrap();
         let eth_signer = eth_signer_guard.as_ref().ok_or(ConsensusError::NoSignerSet)?;
         // Sign all the things!
-        let header_bytes = seal_hash(header);
-        let sighash = eth_signer.sign_hash_sync(&header_bytes).map_err(|_| ConsensusError::SignHeaderError)?;
-
-        let mut extra_data_mut = BytesMut::from(&header.extra_data[..]);
-        extra_data_mut[header.extra_data.len().saturating_sub(SIGNATURE_LENGTH)..].copy_from_slice(&sighash.as_bytes());
-        *extra_data_mut.last_mut().unwrap() -= 27;
-        header.extra_data = Bytes::from(extra_data_mut.freeze());
+        seal_header(eth_signer, header)?;
 
-        let mut recent_headers = self.recent_headers.write().unwrap();
-        recent_headers.insert(header.hash_slow(), header.clone());
+        self.recent_headers.insert(header.hash_slow(), header.clone());
 
         self.save_total_difficulty(header);
 
@@ -820,6 +955,26 @@ where
         Ok(proposals_guard.clone())
     }
 
+    fn on_canonical_header(
+        &self,
+        header: &SealedHeader,
+    ) -> Result<(), ConsensusError> {
+        let number = header.number();
+        if number == 0 {
+            return Ok(());
+        }
+        let snap = self.snapshot_inner(number - 1, header.parent_hash(), None)?;
+        let sealer = recover_address_generic(header.header()).map_err(|e| {ConsensusError::AposErrorDetail {detail: e.to_string()}})?;
+        self.track_liveness(&snap, number, sealer);
+        Ok(())
+    }
+
+    fn signer_liveness(
+        &self,
+    ) -> Result<BTreeMap<Address, SignerLiveness>, ConsensusError> {
+        Ok(self.liveness.read().unwrap().signers().clone())
+    }
+
     fn total_difficulty(
         &self,
         hash: B256,
@@ -845,7 +1000,7 @@ where
             // https://eips.ethereum.org/EIPS/eip-225
             // If the signer is out-of-turn, delay signing by rand(SIGNER_COUNT * 500ms)
             if difficulty != DIFF_IN_TURN {
-                wiggle = Duration::from_millis((rand::random::<f64>() * snapshot.signers.len() as f64 * WIGGLE_TIME.as_millis() as f64) as u64)
+                wiggle = out_of_turn_wiggle(snapshot.signers.len())
             }
         }
         wiggle
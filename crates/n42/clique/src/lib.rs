@@ -2,3 +2,6 @@
 
 mod apos;
 pub use apos::*;
+
+mod snapshot_cache;
+pub use snapshot_cache::{InFlight, RecentMap};
@@ -0,0 +1,245 @@
+//! Caches shared by the threads asking [`APos`](crate::APos) for snapshots.
+//!
+//! Snapshots are requested while validating blocks, while building and sealing them, and from
+//! RPC. Lookups only take a shared lock, the expensive part of a miss, walking the headers back
+//! to a known snapshot and reading them from disk, runs without holding any lock, and concurrent
+//! misses for the same block wait for the first one instead of repeating its walk.
+
+use parking_lot::{Condvar, Mutex, RwLock};
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Bounded map whose lookups only take a shared lock.
+///
+/// Eviction approximates an LRU with the CLOCK algorithm: a lookup only flags its entry as
+/// referenced, which lets concurrent lookups proceed together, and once full, the entries are
+/// visited in insertion order, the flagged ones are kept for another round and the first unflagged
+/// one is evicted. An entry looked up since the last round, such as the snapshot of the head, is
+/// never evicted in favour of one that was not.
+#[derive(Debug)]
+pub struct RecentMap<K, V> {
+    inner: RwLock<RecentMapInner<K, V>>,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct RecentMapInner<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    order: VecDeque<K>,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    /// Whether the entry was looked up since eviction last visited it
+    referenced: AtomicBool,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> RecentMap<K, V> {
+    /// Creates a map holding up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: RwLock::new(RecentMapInner { entries: HashMap::new(), order: VecDeque::new() }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Returns a copy of the value of `key`.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let inner = self.inner.read();
+        let entry = inner.entries.get(key)?;
+        entry.referenced.store(true, Ordering::Relaxed);
+        Some(entry.value.clone())
+    }
+
+    /// Inserts `value` for `key`, evicting the least recently used entries beyond the capacity.
+    pub fn insert(&self, key: K, value: V) {
+        let mut inner = self.inner.write();
+        let entry = Entry { value, referenced: AtomicBool::new(false) };
+        if inner.entries.insert(key.clone(), entry).is_none() {
+            inner.order.push_back(key);
+        }
+        while inner.order.len() > self.capacity {
+            let Some(oldest) = inner.order.pop_front() else { break };
+            // each entry is kept at most once per round, so this ends within two rounds
+            let referenced = inner
+                .entries
+                .get(&oldest)
+                .is_some_and(|entry| entry.referenced.swap(false, Ordering::Relaxed));
+            if referenced {
+                inner.order.push_back(oldest);
+            } else {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.inner.read().entries.len()
+    }
+
+    /// Whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Computations in progress, keyed by what they compute.
+///
+/// The first caller asking for a key runs the computation, later callers asking for the same key
+/// meanwhile wait for its result.
+#[derive(Debug)]
+pub struct InFlight<K, T> {
+    pending: Mutex<HashMap<K, Arc<Pending<T>>>>,
+}
+
+#[derive(Debug)]
+struct Pending<T> {
+    /// `None` until done, then `Some(None)` if the computation panicked
+    result: Mutex<Option<Option<T>>>,
+    done: Condvar,
+}
+
+impl<K, T> Default for InFlight<K, T> {
+    fn default() -> Self {
+        Self { pending: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> InFlight<K, T> {
+    /// Returns the result of `compute` for `key`, shared with the concurrent callers for the same
+    /// key.
+    ///
+    /// Blocks the thread while another caller computes the result, async callers run it on the
+    /// blocking pool.
+    pub fn run(&self, key: K, compute: impl FnOnce() -> T) -> T {
+        let (pending, leader) = {
+            let mut all = self.pending.lock();
+            match all.get(&key) {
+                Some(pending) => (pending.clone(), false),
+                None => {
+                    let pending =
+                        Arc::new(Pending { result: Mutex::new(None), done: Condvar::new() });
+                    all.insert(key.clone(), pending.clone());
+                    (pending, true)
+                }
+            }
+        };
+
+        if !leader {
+            let mut result = pending.result.lock();
+            while result.is_none() {
+                pending.done.wait(&mut result);
+            }
+            if let Some(Some(value)) = result.as_ref() {
+                return value.clone()
+            }
+            // the computation panicked, try again on our own
+            drop(result);
+            return compute()
+        }
+
+        let mut finish = Finish { in_flight: self, key, pending, result: None };
+        let value = compute();
+        finish.result = Some(value.clone());
+        value
+    }
+
+    /// Number of computations in progress.
+    pub fn len(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Whether no computation is in progress.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Publishes the result of a computation to its waiters, also when it panics.
+struct Finish<'a, K: Hash + Eq, T> {
+    in_flight: &'a InFlight<K, T>,
+    key: K,
+    pending: Arc<Pending<T>>,
+    result: Option<T>,
+}
+
+impl<K: Hash + Eq, T> Drop for Finish<'_, K, T> {
+    fn drop(&mut self) {
+        self.in_flight.pending.lock().remove(&self.key);
+        *self.pending.result.lock() = Some(self.result.take());
+        self.pending.done.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Barrier,
+        },
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn evicts_least_recently_used_entries() {
+        let map = RecentMap::new(2);
+        map.insert(1, "a");
+        map.insert(2, "b");
+        map.insert(1, "c");
+        map.insert(3, "d");
+
+        // without lookups, the entry inserted first is evicted
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), Some("b"));
+
+        // a looked up entry outlives the ones inserted after it
+        map.insert(4, "e");
+        assert_eq!(map.get(&3), None);
+        map.insert(5, "f");
+        assert_eq!(map.get(&4), None);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&2), Some("b"));
+        assert_eq!(map.get(&5), Some("f"));
+    }
+
+    #[test]
+    fn coalesces_concurrent_misses() {
+        let in_flight = InFlight::<u64, u64>::default();
+        let runs = AtomicUsize::new(0);
+        let barrier = Barrier::new(8);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        in_flight.run(7, || {
+                            runs.fetch_add(1, Ordering::SeqCst);
+                            thread::sleep(Duration::from_millis(100));
+                            42
+                        })
+                    })
+                })
+                .collect();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), 42);
+            }
+        });
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(in_flight.is_empty());
+    }
+}
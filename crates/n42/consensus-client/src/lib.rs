@@ -1,3 +1,4 @@
 //! Consensus client
 
 pub mod miner;
+pub mod remote;
@@ -12,7 +12,7 @@ use eyre::OptionExt;
 use futures_util::{stream::Fuse, StreamExt};
 use itertools::Itertools;
 use reth_engine_primitives::BeaconConsensusEngineHandle;
-use reth_chainspec::EthereumHardforks;
+use reth_chainspec::{AposChainSpec, EthereumHardforks};
 use reth_consensus::{FullConsensus, ConsensusError};
 use reth_payload_primitives::{EngineApiMessageVersion};
 use reth_eth_wire_types::{NewBlock, NetworkPrimitives};
@@ -24,9 +24,11 @@ use reth_payload_builder::PayloadBuilderHandle;
 use reth_payload_primitives::{
     BuiltPayload, PayloadAttributesBuilder, PayloadKind, PayloadTypes,
 };
-use reth_primitives::{Block, Header, SealedBlock};
+use reth_primitives::{Block, Header, SealedBlock, SealedHeader};
+use n42_clique::{DIFF_IN_TURN, DIFF_NO_TURN};
+use n42_primitives::Snapshot;
 use reth_primitives_traits::{Block as BlockTrait, header::clique_utils::{recover_address, recover_address_generic}};
-use reth_provider::{BlockIdReader, BlockReader, ChainSpecProvider};
+use reth_provider::{BlockIdReader, BlockReader, CanonStateSubscriptions, ChainSpecProvider};
 use reth_transaction_pool::TransactionPool;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -37,7 +39,7 @@ use std::{
     time::{Duration, UNIX_EPOCH},
 };
 use tokio::sync::mpsc;
-use tokio::time::{interval_at, sleep, Instant, Interval};
+use tokio::time::{interval_at, sleep, sleep_until, Instant, Interval};
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{trace, debug, error, info, warn};
 
@@ -51,6 +53,9 @@ pub enum MiningMode {
     Interval(Interval),
     /// In this mode no block is built by the node.
     NoMining,
+    /// In this mode blocks are sealed by an external consensus client over the Engine API, the
+    /// node announces the ones that become canonical.
+    External,
 }
 
 impl MiningMode {
@@ -86,7 +91,7 @@ impl Future for MiningMode {
                 }
                 Poll::Pending
             }
-            Self::NoMining => Poll::Pending,
+            Self::NoMining | Self::External => Poll::Pending,
         }
     }
 }
@@ -110,8 +115,13 @@ pub struct N42Miner<T: PayloadTypes, Provider, B, Network> {
     consensus: Arc<dyn FullConsensus<<T::BuiltPayload as BuiltPayload>::Primitives, Error = ConsensusError>>,
     recent_blocks: schnellru::LruMap<B256, SealedBlock<<<T::BuiltPayload as BuiltPayload>::Primitives as NodePrimitives>::Block>>,
     recent_num_to_td: schnellru::LruMap<u64, U256>,
-    new_block_tx: mpsc::Sender<(NewBlock, BlockHash)>,
-    new_block_rx: mpsc::Receiver<(NewBlock, BlockHash)>,
+    /// Payloads resolved at the deadline of the slot they were built for, with their block number
+    built_payload_tx: mpsc::Sender<(u64, eyre::Result<T::BuiltPayload>)>,
+    built_payload_rx: mpsc::Receiver<(u64, eyre::Result<T::BuiltPayload>)>,
+    /// Number of the block whose payload is being built
+    building: Option<u64>,
+    /// How long after its slot the payload builder keeps a payload job alive
+    payload_deadline: Duration,
 
     num_generated_blocks: u64,
     num_skipped_new_block: u64,
@@ -129,6 +139,10 @@ const SYNC_DOWNLOAD_BLOCKS_UNIT: u64 = 512;
 const DIFFICULTY_DELTA_CLAMP: u64 = 50;
 const MAX_NUM_LOCAL_BLOCKS_TO_CHECK: u64 = 256;
 const MIN_NO_BLOCK_TIMESTAMP_GAP: u64 = 300;
+/// Building of the next block starts this fraction of the period ahead of its slot.
+const PAYLOAD_BUILD_LEAD_DIVISOR: u32 = 2;
+/// A payload is resolved at least this long before its job expires.
+const PAYLOAD_RESOLVE_MARGIN: Duration = Duration::from_millis(500);
 
 impl<T, Provider, B, Network> N42Miner<T, Provider, B, Network>
 where
@@ -138,7 +152,8 @@ where
     Provider: 
         BlockReader
         + BlockIdReader
-        + ChainSpecProvider<ChainSpec: EthereumHardforks>
+        + ChainSpecProvider<ChainSpec: EthereumHardforks + AposChainSpec>
+        + CanonStateSubscriptions<Primitives = <T::BuiltPayload as BuiltPayload>::Primitives>
         + 'static,
     B: PayloadAttributesBuilder<<T as PayloadTypes>::PayloadAttributes>,
     Network: FullNetwork,
@@ -157,8 +172,9 @@ where
         payload_builder: PayloadBuilderHandle<T>,
         network: Network,
         consensus: Arc<dyn FullConsensus<<T::BuiltPayload as BuiltPayload>::Primitives, Error = ConsensusError>>,
+        payload_deadline: Duration,
     ) {
-        let (new_block_tx, new_block_rx) = mpsc::channel::<(NewBlock, BlockHash)>(128);
+        let (built_payload_tx, built_payload_rx) = mpsc::channel(128);
         let miner = Self {
             provider,
             payload_attributes_builder,
@@ -175,8 +191,10 @@ where
             num_long_delayed_blocks: 0,
             num_fetched_blocks: 0,
             order_stats: HashMap::new(),
-            new_block_tx,
-            new_block_rx,
+            built_payload_tx,
+            built_payload_rx,
+            building: None,
+            payload_deadline,
         };
 
         // Spawn the miner
@@ -185,19 +203,20 @@ where
 
     /// Runs the [`N42Miner`] in a loop, polling the miner and building payloads.
     async fn run(mut self) -> eyre::Result<()> {
-        if !(self.get_best_block_num_signers() == 1 && self.is_among_signers()?) {
+        if !(self.get_best_block_num_signers().await == 1 && self.is_among_signers().await?) {
             self.initial_sync().await;
         }
 
         let mut new_block_event_stream = self.network.subscribe_block();
         let mut network_event_stream = self.network.event_listener();
+        let mut canonical_stream = self.provider.canonical_state_stream();
 
         loop {
             tokio::select! {
-                Some((new_block, hash)) = self.new_block_rx.recv() => {
-                    let insert_ok = self.recent_num_to_td.insert(new_block.block.number, U256::from(new_block.td));
-                    debug!(target: "consensus-client", insert_ok, number=?new_block.block.number, "recent_num_to_td insert");
-                    self.network.announce_block(new_block, hash);
+                Some((number, payload)) = self.built_payload_rx.recv() => {
+                    if let Err(e) = self.on_built_payload(number, payload).await {
+                        error!(target: "consensus-client", "Error sealing the built payload: {:?}", e);
+                    }
                 }
                 _ = &mut self.mode => {
                     if let Err(e) = self.advance().await {
@@ -215,6 +234,9 @@ where
                 network_event = &mut network_event_stream.next() => {
                     debug!(target: "consensus-client", "network_event={:?}", network_event);
                 }
+                Some(notification) = canonical_stream.next(), if matches!(self.mode, MiningMode::External) => {
+                    self.announce_external_block(notification.tip().clone_sealed_block());
+                }
             }
         }
     }
@@ -288,7 +310,12 @@ where
         let now = std::time::SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("cannot be earlier than UNIX_EPOCH");
-        if now.as_secs() > latest_header.timestamp() + MIN_NO_BLOCK_TIMESTAMP_GAP {
+        let heartbeat_interval = self
+            .provider
+            .chain_spec()
+            .apos_config_at(latest_header.number() + 1, latest_header.timestamp())
+            .heartbeat_interval;
+        if now.as_secs() > latest_header.timestamp() + heartbeat_interval + MIN_NO_BLOCK_TIMESTAMP_GAP {
             warn!(target: "consensus-client", latest_header_timestamp=?latest_header.timestamp(), ?now, "long_time_no_block_generated");
             true
         } else {
@@ -296,13 +323,28 @@ where
         }
     }
 
+    /// Whether an empty block on top of the best block would be sealed before its heartbeat.
+    fn heartbeat_pending(&self) -> bool {
+        let Ok(Some(header)) = self.provider.sealed_header(self.provider.best_block_number().unwrap_or_default()) else {
+            return false;
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("cannot be earlier than UNIX_EPOCH");
+        !self
+            .provider
+            .chain_spec()
+            .apos_config_at(header.number() + 1, header.timestamp())
+            .allows_empty_block(header.timestamp(), now.as_secs())
+    }
+
     async fn exit_if_lagged_progress(&self, block: &SealedBlock) -> eyre::Result<()> {
         const MAX_PROGRESS_GAP: u64 = 100;
 
         let best_block_number = self.provider.best_block_number().unwrap();
         debug!(target: "consensus-client", my_number=?best_block_number, received_number=?block.header().number,"exit_if_lagged_progress");
         if block.header().number > best_block_number + MAX_PROGRESS_GAP {
-            let current_signers = self.get_best_block_signers();
+            let current_signers = self.get_best_block_signers().await;
             let signer = match recover_address(block.header()) {
                 Ok(v) => v,
                 Err(err) => {
@@ -392,7 +434,7 @@ where
             }
 
             if new_payload_ok {
-                let forkchoice_state = self.forkchoice_state_with_head(block.hash());
+                let forkchoice_state = self.forkchoice_state_with_head(block.hash()).await;
                 match self
                     .beacon_engine_handle
                     .fork_choice_updated(forkchoice_state, None, EngineApiMessageVersion::default())
@@ -410,23 +452,44 @@ where
         Ok(())
     }
 
-    fn get_best_block_signers(&self) -> Vec<Address> {
+    /// Announces a canonical block that was neither sealed by this node nor received from the
+    /// network, which leaves the blocks inserted by an external consensus client.
+    fn announce_external_block(&mut self, block: SealedBlock<<<T::BuiltPayload as BuiltPayload>::Primitives as NodePrimitives>::Block>) {
+        if self.recent_blocks.get(&block.hash()).is_some() {
+            return;
+        }
+        let hash = block.hash();
+        let td = self.consensus.total_difficulty(hash);
+        debug!(target: "consensus-client", number=block.number(), ?hash, ?td, "announcing externally sealed block");
+        self.recent_num_to_td.insert(block.number(), td);
+        self.recent_blocks.insert(hash, block.clone());
+        self.network.announce_block(NewBlock { block: block.unseal(), td: td.to::<U128>() }, hash);
+    }
+
+    /// Loads the snapshot of block `number` on the blocking pool: a miss walks the headers in the
+    /// database, or waits for a concurrent walk of the same block.
+    async fn snapshot(&self, number: u64, hash: B256) -> eyre::Result<Snapshot> {
+        let consensus = self.consensus.clone();
+        Ok(tokio::task::spawn_blocking(move || consensus.snapshot(number, hash, None)).await??)
+    }
+
+    async fn get_best_block_signers(&self) -> Vec<Address> {
         let header = self
             .provider
             .sealed_header(self.provider.best_block_number().unwrap())
             .unwrap()
             .unwrap();
         let snapshot = self
-            .consensus
-            .snapshot(header.number(), header.hash_slow(), None)
+            .snapshot(header.number(), header.hash_slow())
+            .await
             .unwrap();
         
 
         snapshot.signers
     }
 
-    fn get_best_block_num_signers(&self) -> u64 {
-        let num_signers: u64 = self.get_best_block_signers().len() as u64;
+    async fn get_best_block_num_signers(&self) -> u64 {
+        let num_signers: u64 = self.get_best_block_signers().await.len() as u64;
 
         num_signers
     }
@@ -451,7 +514,7 @@ where
         }
     }
 
-    fn determine_safe_block(&mut self) -> BlockNumHash {
+    async fn determine_safe_block(&mut self) -> BlockNumHash {
         let mut safe_block_number = self
             .provider
             .safe_block_number()
@@ -467,7 +530,7 @@ where
         const NUM_SAMPLE_ROUNDS: u64 = 2;
         const NUM_CONFIRM_ROUNDS: u64 = 1;
 
-        let num_signers = self.get_best_block_num_signers();
+        let num_signers = self.get_best_block_num_signers().await;
         let best_block_number = self.provider.best_block_number().unwrap();
         let mut active_signers = (best_block_number.saturating_sub(NUM_SAMPLE_ROUNDS * num_signers)
             ..best_block_number)
@@ -524,10 +587,10 @@ where
     }
 
     /// Returns current forkchoice state.
-    fn forkchoice_state(&mut self) -> ForkchoiceState {
+    async fn forkchoice_state(&mut self) -> ForkchoiceState {
         let (_, max_td_hash) = self.max_td_and_hash();
 
-        let safe_block_num_hash = self.determine_safe_block();
+        let safe_block_num_hash = self.determine_safe_block().await;
         let safe_block_hash = safe_block_num_hash.hash;
         ForkchoiceState {
             head_block_hash: max_td_hash,
@@ -550,7 +613,7 @@ where
     /// through newPayload.
     async fn advance(&mut self) -> eyre::Result<()> {
         let (in_order_count, out_of_order_count, order_ratio) = self.get_order_stats();
-        let num_signers = self.get_best_block_num_signers();
+        let num_signers = self.get_best_block_num_signers().await;
         
         let interval = match self.mode {
             MiningMode::Instant(_) => {
@@ -559,44 +622,52 @@ where
             MiningMode::Interval(ref mut v) => v,
             _ => return Ok(()),
         };
-        let block_time = interval.period().as_secs();
         info!(target: "consensus-client", num_generated_blocks=self.num_generated_blocks, num_skipped_new_block=self.num_skipped_new_block, num_should_skip_block_generation=self.num_should_skip_block_generation, num_long_delayed_blocks=self.num_long_delayed_blocks, num_fetched_blocks=self.num_fetched_blocks, in_order_count, out_of_order_count, order_ratio);
         let header = self
             .provider
             .sealed_header(self.provider.best_block_number().unwrap())
             .unwrap()
             .unwrap();
+        // The period can change at N42 hardforks, so it is read for every block
+        let apos_config = self.provider.chain_spec().apos_config_at(header.number() + 1, header.timestamp());
+        let block_time = apos_config.period.max(1);
+        let period = Duration::from_secs(block_time);
         debug!(target: "consensus-client", block_time, "advance");
         let now = std::time::SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("cannot be earlier than UNIX_EPOCH");
         let expected_next_timestamp = Duration::from_secs(header.header().timestamp() + block_time);
-        if expected_next_timestamp > now {
+        // start building ahead of the slot, so the payload can be improved until the deadline
+        let build_lead = period / PAYLOAD_BUILD_LEAD_DIVISOR;
+        if expected_next_timestamp > now + build_lead {
             *interval = interval_at(
-                Instant::now() + (expected_next_timestamp - now),
-                interval.period(),
+                Instant::now() + (expected_next_timestamp - build_lead - now),
+                period,
             );
             return Ok(());
         }
 
-        if expected_next_timestamp + Duration::from_secs(block_time * num_signers) <= now {
-            warn!(target: "consensus-client", number=header.number() + 1, ?expected_next_timestamp, ?now, "not seeing new blocks for a long time, try generating a block again");
-            self.recent_num_to_td.remove(&(header.header().number() + 1));
+        let number = header.number() + 1;
+        // without transactions, the next block is only due at the heartbeat
+        let quiet_gap = Duration::from_secs(apos_config.heartbeat_interval.saturating_sub(block_time));
+        if expected_next_timestamp + quiet_gap + Duration::from_secs(block_time * num_signers) <= now {
+            warn!(target: "consensus-client", number, ?expected_next_timestamp, ?now, "not seeing new blocks for a long time, try generating a block again");
+            self.recent_num_to_td.remove(&number);
             self.num_long_delayed_blocks += 1;
             *interval = interval_at(
                 Instant::now() + Duration::from_secs(block_time),
-                interval.period(),
+                period,
             );
-        } else if self.recent_num_to_td.get(&(header.header().number() + 1)).is_some() {
-            debug!(target: "consensus-client", number=header.header().number() + 1, "skip generating block");
+        } else if self.recent_num_to_td.get(&number).is_some() || self.building == Some(number) {
+            debug!(target: "consensus-client", number, "skip generating block");
             self.num_should_skip_block_generation += 1;
             return Ok(());
         }
 
-        let timestamp = now;
+        let timestamp = expected_next_timestamp.max(now);
         debug!(target: "consensus-client", ?timestamp, "advance: PayloadAttributes timestamp");
 
-        let forkchoice_state = self.forkchoice_state();
+        let forkchoice_state = self.forkchoice_state().await;
         let res = self
             .beacon_engine_handle
             .fork_choice_updated(
@@ -609,71 +680,104 @@ where
             eyre::bail!("Error advancing the chain: fork_choice_updated with PayloadAttributes status is not valid: {:?}", res);
         }
         let payload_id = res.payload_id.ok_or_eyre("No payload id")?;
+        self.building = Some(number);
+
+        // The in-turn signer seals at its slot, an out-of-turn signer holds its payload for the
+        // wiggle window and keeps improving it meanwhile.
+        let difficulty =
+            if self.is_in_turn(&header, number).await? { DIFF_IN_TURN } else { DIFF_NO_TURN };
+        let consensus = self.consensus.clone();
+        let (parent_number, parent_hash) = (header.number(), header.hash());
+        let wiggle = tokio::task::spawn_blocking(move || {
+            consensus.wiggle(parent_number, parent_hash, difficulty)
+        })
+        .await?;
+        let slot = Instant::now() + timestamp.saturating_sub(now);
+        let deadline = slot + wiggle;
+        // The job expires `payload_deadline` after the slot, a wiggle running past it is served
+        // with the payload resolved just before.
+        let resolve_at =
+            deadline.min(slot + self.payload_deadline.saturating_sub(PAYLOAD_RESOLVE_MARGIN));
+        debug!(target: "consensus::apos",
+            "wiggle {:?}, timestamp {:?}, number {}",
+            wiggle, timestamp, number
+        );
 
-        let payload = match self
-            .payload_builder
-            .resolve_kind(payload_id, PayloadKind::WaitForPending)
-            .await
-        {
-            Some(Ok(payload)) => payload,
-            Some(Err(err)) => {
-                if self.is_among_signers()? && self.long_time_no_block_generated() {
-                    exit_by_sigint();
+        let payload_builder = self.payload_builder.clone();
+        let built_payload_tx = self.built_payload_tx.clone();
+        tokio::spawn(async move {
+            sleep_until(resolve_at).await;
+            let payload = match payload_builder.resolve_kind(payload_id, PayloadKind::Earliest).await {
+                Some(Ok(payload)) => Ok(payload),
+                Some(Err(err)) => Err(eyre::eyre!("Failed to resolve payload: {}", err)),
+                None => Err(eyre::eyre!("No payload")),
+            };
+            sleep_until(deadline).await;
+            let _ = built_payload_tx.send((number, payload)).await;
+        });
+        Ok(())
+    }
+
+    /// Announces the payload resolved at the slot deadline, unless a block with at least the same
+    /// total difficulty was imported while it was being built.
+    async fn on_built_payload(
+        &mut self,
+        number: u64,
+        payload: eyre::Result<T::BuiltPayload>,
+    ) -> eyre::Result<()> {
+        if self.building == Some(number) {
+            self.building = None;
+        }
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(err) => {
+                if self.heartbeat_pending() {
+                    debug!(target: "consensus-client", %err, "no transactions to seal before the heartbeat");
+                    return Ok(());
                 }
-                eyre::bail!("Failed to resolve payload: {}", err);
-            }
-            None => {
-                if self.is_among_signers()? && self.long_time_no_block_generated() {
+                if self.is_among_signers().await? && self.long_time_no_block_generated() {
                     exit_by_sigint();
                 }
-                eyre::bail!("No payload");
+                return Err(err);
             }
         };
 
         let block = payload.block();
-        let max_td = self.consensus.total_difficulty(block.header().hash_slow());
-        debug!(target: "consensus-client", ?max_td, "advance: new_block hash {:?}", block.header().hash_slow());
+        let block_hash = block.hash_slow();
+        let max_td = self.consensus.total_difficulty(block_hash);
+        debug!(target: "consensus-client", ?max_td, "advance: new_block hash {:?}", block_hash);
         trace!(target: "consensus-client", ?block);
+        let (head_td, _) = self.max_td_and_hash();
+        if head_td >= max_td {
+            debug!(target: "consensus-client", number=block.number(), ?head_td, "slot filled while building, dropping the payload");
+            return Ok(());
+        }
 
-        self.recent_blocks.insert(block.hash_slow(), block.clone());
-
-        let wiggle = self.consensus.wiggle(
-            block.header().number() - 1,
-            block.header().parent_hash(),
-            block.header().difficulty(),
-        );
-        debug!(target: "consensus::apos",
-            "wiggle {:?}, timestamp {:?}, number {}",
-            wiggle, timestamp, block.number()
+        self.recent_blocks.insert(block_hash, block.clone());
+        let insert_ok = self.recent_num_to_td.insert(block.number(), max_td);
+        debug!(target: "consensus-client", insert_ok, number=block.number(), "recent_num_to_td insert");
+        self.network.announce_block(
+            NewBlock { block: block.clone().unseal(), td: max_td.to::<U128>() },
+            block_hash,
         );
 
-        let new_block_tx = self.new_block_tx.clone();
-        let block_clone = block.clone();
-        let block_hash = block.hash_slow();
-        tokio::spawn(async move {
-            sleep(wiggle).await;
-            new_block_tx
-                .send((
-                    NewBlock {
-                        block: block_clone.unseal(),
-                        td: max_td.to::<U128>(),
-                    },
-                    block_hash,
-                ))
-                .await
-                .unwrap();
-        });
-
         self.num_generated_blocks += 1;
-        if num_signers == 1 {
-            self.new_payload(&block).await?;
+        if self.get_best_block_num_signers().await == 1 {
+            self.new_payload(block).await?;
             self.fcu_hash(block_hash).await?;
         }
         Ok(())
     }
 
-    fn forkchoice_state_with_head(&mut self, head_block_hash: B256) -> ForkchoiceState {
-        let safe_block_num_hash = self.determine_safe_block();
+    /// Whether the local signer is in turn for block `number` on top of `parent`.
+    async fn is_in_turn(&self, parent: &SealedHeader, number: u64) -> eyre::Result<bool> {
+        let Some(signer) = self.consensus.get_eth_signer_address()? else { return Ok(false) };
+        let snapshot = self.snapshot(parent.number(), parent.hash()).await?;
+        Ok(snapshot.inturn(number, &signer))
+    }
+
+    async fn forkchoice_state_with_head(&mut self, head_block_hash: B256) -> ForkchoiceState {
+        let safe_block_num_hash = self.determine_safe_block().await;
         let safe_block_hash = safe_block_num_hash.hash;
         ForkchoiceState {
             head_block_hash,
@@ -712,7 +816,7 @@ where
     }
 
     async fn fcu_hash(&mut self, block_hash: BlockHash) -> eyre::Result<()> {
-        let forkchoice_state = self.forkchoice_state_with_head(block_hash);
+        let forkchoice_state = self.forkchoice_state_with_head(block_hash).await;
         match self
             .beacon_engine_handle
             .fork_choice_updated(forkchoice_state, None, EngineApiMessageVersion::default())
@@ -926,9 +1030,9 @@ where
         (td, header.hash())
     }
 
-    fn is_among_signers(&self) -> eyre::Result<bool> {
+    async fn is_among_signers(&self) -> eyre::Result<bool> {
         if let Some(address) = self.consensus.get_eth_signer_address()? {
-            Ok(self.get_best_block_signers().contains(&address))
+            Ok(self.get_best_block_signers().await.contains(&address))
         } else {
             Ok(false)
         }
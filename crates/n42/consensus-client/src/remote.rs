@@ -0,0 +1,239 @@
+//! Seals `APos` blocks from a separate process, driving an execution node over the
+//! authenticated Engine API.
+//!
+//! The execution node runs with `--consensus.external`: it builds the payloads, validates and
+//! announces the blocks, while the signer key only lives here.
+
+use alloy_eips::{eip7685::Requests, BlockNumberOrTag};
+use alloy_primitives::{Address, BlockHash, BlockNumber, Sealable, B256};
+use alloy_rpc_types_engine::{
+    CancunPayloadFields, ExecutionPayload, ExecutionPayloadEnvelopeV3, ExecutionPayloadEnvelopeV4,
+    ExecutionPayloadSidecar, ExecutionPayloadV3, ForkchoiceState, ForkchoiceUpdated, JwtSecret,
+    PayloadAttributes, PayloadStatus, PraguePayloadFields,
+};
+use alloy_rpc_types_eth::Header as RpcHeader;
+use alloy_signer_local::PrivateKeySigner;
+use eyre::OptionExt;
+use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder, rpc_params};
+use n42_clique::{out_of_turn_wiggle, seal_header, DIFF_IN_TURN, DIFF_NO_TURN};
+use n42_primitives::{APosConfig, Snapshot};
+use reth_primitives::TransactionSigned;
+use reth_primitives_traits::BlockBody;
+use reth_rpc_layer::AuthClientLayer;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+/// Sealer running the `APos` signing logic against a remote execution node.
+#[derive(Debug)]
+pub struct RemoteSealer<C> {
+    /// Client of the execution node's authenticated endpoint.
+    client: C,
+    /// The signer key.
+    signer: PrivateKeySigner,
+    /// Delay between two attempts to build on the head.
+    poll_interval: Duration,
+}
+
+/// Connects a [`RemoteSealer`] to the authenticated endpoint at `url` with the JWT `secret`.
+pub fn connect(
+    url: &str,
+    secret: JwtSecret,
+    signer: PrivateKeySigner,
+    poll_interval: Duration,
+) -> eyre::Result<RemoteSealer<impl ClientT + Send + Sync>> {
+    let middleware = tower::ServiceBuilder::default().layer(AuthClientLayer::new(secret));
+    let client = HttpClientBuilder::default().set_http_middleware(middleware).build(url)?;
+    Ok(RemoteSealer::new(client, signer, poll_interval))
+}
+
+impl<C: ClientT + Send + Sync> RemoteSealer<C> {
+    /// Creates a sealer using `client`.
+    pub const fn new(client: C, signer: PrivateKeySigner, poll_interval: Duration) -> Self {
+        Self { client, signer, poll_interval }
+    }
+
+    /// Runs the sealer, building on the head of the execution node whenever it is our turn.
+    pub async fn run(self) {
+        info!(target: "consensus-client", signer=%self.signer.address(), "remote sealer started");
+        loop {
+            match self.seal_next().await {
+                Ok(Some(hash)) => info!(target: "consensus-client", ?hash, "sealed block"),
+                Ok(None) => {}
+                Err(err) => warn!(target: "consensus-client", %err, "failed to seal block"),
+            }
+            sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Builds, seals and inserts the block on top of the current head, if the signer may seal it.
+    ///
+    /// Returns the hash of the new head, or `None` if it was not our block to seal.
+    pub async fn seal_next(&self) -> eyre::Result<Option<BlockHash>> {
+        let head = self.header(BlockNumberOrTag::Latest).await?.ok_or_eyre("no head block")?;
+        let number = head.number + 1;
+        let signer = self.signer.address();
+        let snapshot: Snapshot =
+            self.client.request("consensusExt_get_snapshot", rpc_params![head.number]).await?;
+        if !may_seal(&snapshot, number, signer) {
+            debug!(target: "consensus-client", number, "not allowed to seal");
+            return Ok(None)
+        }
+
+        // wait for the period, and give the in-turn signer a head start
+        let config: APosConfig = self
+            .client
+            .request("consensusExt_getAposConfig", rpc_params![number, head.timestamp])
+            .await?;
+        let timestamp = head.timestamp + config.period.max(1);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if timestamp > now {
+            sleep(Duration::from_secs(timestamp - now)).await;
+        }
+        let in_turn = snapshot.inturn(number, &signer);
+        if !in_turn {
+            sleep(out_of_turn_wiggle(snapshot.signers.len())).await;
+        }
+        if self.header(BlockNumberOrTag::Latest).await?.map(|latest| latest.hash) != Some(head.hash)
+        {
+            debug!(target: "consensus-client", number, "head moved while waiting");
+            return Ok(None)
+        }
+
+        // same rule as the in-process miner uses when not all signers are active
+        let safe_number = head.number.saturating_sub(snapshot.signers.len() as u64 * 3 + 1);
+        let safe_hash =
+            self.header(safe_number.into()).await?.map(|header| header.hash).unwrap_or_default();
+        let state = ForkchoiceState {
+            head_block_hash: head.hash,
+            safe_block_hash: safe_hash,
+            finalized_block_hash: safe_hash,
+        };
+        let attributes = PayloadAttributes {
+            timestamp,
+            prev_randao: B256::ZERO,
+            suggested_fee_recipient: signer,
+            withdrawals: Some(Vec::new()),
+            parent_beacon_block_root: Some(B256::ZERO),
+        };
+        let updated: ForkchoiceUpdated = self
+            .client
+            .request("engine_forkchoiceUpdatedV3", rpc_params![state, Some(attributes)])
+            .await?;
+        if !updated.payload_status.is_valid() {
+            eyre::bail!("forkchoice update rejected: {:?}", updated.payload_status);
+        }
+        let payload_id = updated.payload_id.ok_or_eyre("no payload id")?;
+
+        // Prague payloads come with execution requests
+        let prague = head.requests_hash.is_some();
+        let (envelope, requests) = if prague {
+            let envelope: ExecutionPayloadEnvelopeV4 =
+                self.client.request("engine_getPayloadV4", rpc_params![payload_id]).await?;
+            (envelope.envelope_inner, Some(envelope.execution_requests))
+        } else {
+            let envelope: ExecutionPayloadEnvelopeV3 =
+                self.client.request("engine_getPayloadV3", rpc_params![payload_id]).await?;
+            (envelope, None)
+        };
+        let (payload, versioned_hashes) =
+            self.seal_payload(envelope.execution_payload, requests.clone(), in_turn)?;
+        let hash = payload.payload_inner.payload_inner.block_hash;
+
+        let status: PayloadStatus = match requests {
+            Some(requests) => {
+                self.client
+                    .request(
+                        "engine_newPayloadV4",
+                        rpc_params![payload, versioned_hashes, B256::ZERO, requests],
+                    )
+                    .await?
+            }
+            None => {
+                self.client
+                    .request(
+                        "engine_newPayloadV3",
+                        rpc_params![payload, versioned_hashes, B256::ZERO],
+                    )
+                    .await?
+            }
+        };
+        if status.is_invalid() {
+            eyre::bail!("sealed block {hash} rejected: {status}");
+        }
+
+        let state = ForkchoiceState { head_block_hash: hash, ..state };
+        let updated: ForkchoiceUpdated = self
+            .client
+            .request(
+                "engine_forkchoiceUpdatedV3",
+                rpc_params![state, Option::<PayloadAttributes>::None],
+            )
+            .await?;
+        debug!(target: "consensus-client", ?hash, status=?updated.payload_status, "made sealed block canonical");
+        Ok(Some(hash))
+    }
+
+    /// Sets the difficulty of our turn and seals the payload built by the execution node.
+    ///
+    /// Returns the sealed payload and the versioned hashes of its blob transactions.
+    fn seal_payload(
+        &self,
+        mut payload: ExecutionPayloadV3,
+        requests: Option<Requests>,
+        in_turn: bool,
+    ) -> eyre::Result<(ExecutionPayloadV3, Vec<B256>)> {
+        // the versioned hashes are not part of the header
+        let cancun = CancunPayloadFields::new(B256::ZERO, Vec::new());
+        let sidecar = match requests {
+            Some(requests) => {
+                ExecutionPayloadSidecar::v4(cancun, PraguePayloadFields::new(requests))
+            }
+            None => ExecutionPayloadSidecar::v3(cancun),
+        };
+        let block = ExecutionPayload::V3(payload.clone())
+            .try_into_block_with_sidecar::<TransactionSigned>(&sidecar)?;
+        let versioned_hashes = block.body.blob_versioned_hashes_iter().copied().collect();
+
+        let mut header = block.header;
+        header.difficulty = if in_turn { DIFF_IN_TURN } else { DIFF_NO_TURN };
+        seal_header(&self.signer, &mut header)?;
+
+        let inner = &mut payload.payload_inner.payload_inner;
+        inner.difficulty = header.difficulty;
+        inner.extra_data = header.extra_data.clone();
+        inner.block_hash = header.hash_slow();
+        Ok((payload, versioned_hashes))
+    }
+
+    async fn header(&self, block: BlockNumberOrTag) -> eyre::Result<Option<RpcHeader>> {
+        let block: Option<alloy_rpc_types_eth::Block> =
+            self.client.request("eth_getBlockByNumber", rpc_params![block, false]).await?;
+        Ok(block.map(|block| block.header))
+    }
+}
+
+/// Whether `signer` may seal block `number` on top of `snapshot`: it must be a signer that did
+/// not sign one of the recent blocks.
+fn may_seal(snapshot: &Snapshot, number: BlockNumber, signer: Address) -> bool {
+    snapshot.signers.contains(&signer) && !snapshot.recently_signed(number, &signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_signers_wait_for_others() {
+        let (a, b, c) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let mut snapshot = Snapshot { signers: vec![a, b, c], ..Default::default() };
+        snapshot.recents.insert(10, a);
+
+        // with three signers, a signer may seal one block in two
+        assert!(!may_seal(&snapshot, 11, a));
+        assert!(may_seal(&snapshot, 12, a));
+        assert!(may_seal(&snapshot, 11, b));
+        assert!(!may_seal(&snapshot, 11, Address::with_last_byte(4)));
+    }
+}
@@ -0,0 +1,588 @@
+//! Admission rules consulted before transactions enter the pool of permissioned N42 deployments.
+
+use crate::watched_file::WatchedFile;
+use alloy_consensus::Transaction;
+use alloy_primitives::{Address, TxHash, TxKind};
+use reth_primitives_traits::{Block, SealedBlock};
+use reth_transaction_pool::{
+    error::{InvalidPoolTransactionError, PoolTransactionError},
+    PoolTransaction, TransactionOrigin, TransactionValidationOutcome, TransactionValidator,
+};
+use serde::Deserialize;
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// How often the rules file is checked for changes.
+pub const ADMISSION_RULES_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of senders with a rate limit window above which expired windows are dropped.
+const MAX_TRACKED_SENDERS: usize = 10_000;
+
+/// Number of admitted transactions remembered, so that they aren't rate limited again when they
+/// are re-injected after a reorg.
+const MAX_TRACKED_ADMISSIONS: usize = 100_000;
+
+/// Rules deciding which transactions may enter the pool, read from a TOML file:
+///
+/// ```toml
+/// # only these senders may submit transactions, any sender if omitted
+/// allowed_senders = ["0x0000000000000000000000000000000000000001"]
+/// denied_senders = []
+/// # only calls to these contracts are accepted, any target if omitted
+/// allowed_targets = []
+/// denied_targets = ["0x0000000000000000000000000000000000000002"]
+/// # only these senders may create contracts, anyone if omitted
+/// deployers = []
+///
+/// [rate_limit]
+/// max_txs = 10
+/// period_secs = 60
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdmissionRules {
+    /// Senders allowed to submit transactions, any sender that isn't denied if `None`.
+    pub allowed_senders: Option<HashSet<Address>>,
+    /// Senders whose transactions are rejected.
+    pub denied_senders: HashSet<Address>,
+    /// Accounts transactions may call, any account that isn't denied if `None`.
+    pub allowed_targets: Option<HashSet<Address>>,
+    /// Accounts transactions may not call.
+    pub denied_targets: HashSet<Address>,
+    /// Senders allowed to create contracts, any sender if `None`.
+    pub deployers: Option<HashSet<Address>>,
+    /// Number of transactions each sender may submit in a period.
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl AdmissionRules {
+    /// Checks the sender and the target of a transaction.
+    pub fn check(&self, sender: Address, kind: TxKind) -> Result<(), AdmissionError> {
+        if self.denied_senders.contains(&sender) {
+            return Err(AdmissionError::SenderDenied(sender))
+        }
+        if self.allowed_senders.as_ref().is_some_and(|allowed| !allowed.contains(&sender)) {
+            return Err(AdmissionError::SenderNotAllowed(sender))
+        }
+        match kind {
+            TxKind::Create => {
+                if self.deployers.as_ref().is_some_and(|deployers| !deployers.contains(&sender)) {
+                    return Err(AdmissionError::CreateNotAllowed(sender))
+                }
+            }
+            TxKind::Call(to) => {
+                if self.denied_targets.contains(&to) {
+                    return Err(AdmissionError::TargetDenied(to))
+                }
+                if self.allowed_targets.as_ref().is_some_and(|allowed| !allowed.contains(&to)) {
+                    return Err(AdmissionError::TargetNotAllowed(to))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maximum number of transactions a sender may submit in a sliding window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// Transactions a sender may submit in the period
+    pub max_txs: usize,
+    /// Length of the period in seconds
+    pub period_secs: u64,
+}
+
+/// Reason the admission rules rejected a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AdmissionError {
+    /// The sender is denied.
+    #[error("sender {0} is denied")]
+    SenderDenied(Address),
+    /// The sender isn't on the list of allowed senders.
+    #[error("sender {0} is not allowed")]
+    SenderNotAllowed(Address),
+    /// The target is denied.
+    #[error("calls to {0} are denied")]
+    TargetDenied(Address),
+    /// The target isn't on the list of allowed targets.
+    #[error("calls to {0} are not allowed")]
+    TargetNotAllowed(Address),
+    /// The sender isn't an approved deployer.
+    #[error("sender {0} is not allowed to create contracts")]
+    CreateNotAllowed(Address),
+    /// The sender submitted too many transactions recently.
+    #[error("sender {sender} exceeded its limit of {max_txs} transactions per {period_secs}s")]
+    RateLimited {
+        /// Sender of the transaction
+        sender: Address,
+        /// Transactions the sender may submit in the period
+        max_txs: usize,
+        /// Length of the period in seconds
+        period_secs: u64,
+    },
+}
+
+impl PoolTransactionError for AdmissionError {
+    fn is_bad_transaction(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<AdmissionError> for InvalidPoolTransactionError {
+    fn from(err: AdmissionError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+/// [`AdmissionRules`] read from a file, with the rate limit windows of the senders.
+///
+/// The file is read again by [`Self::reload_if_changed`] when its modification time changes, a
+/// file that can't be read or parsed keeps the previous rules.
+#[derive(Debug, Clone)]
+pub struct AdmissionControl {
+    file: WatchedFile,
+    rules: Arc<RwLock<AdmissionRules>>,
+    admissions: Arc<Mutex<Admissions>>,
+}
+
+impl AdmissionControl {
+    /// Loads the rules of the file at `path`.
+    pub fn load(path: PathBuf) -> eyre::Result<Self> {
+        let file = WatchedFile::new(path);
+        let rules = read_rules(file.path())?;
+        info!(target: "txpool", path=%file.path().display(), "loaded admission rules");
+        Ok(Self { file, rules: Arc::new(RwLock::new(rules)), admissions: Default::default() })
+    }
+
+    /// Returns the current rules.
+    pub fn rules(&self) -> AdmissionRules {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Checks whether the transaction `hash` of `sender` to `kind` may enter the pool.
+    ///
+    /// The rate limit of the sender only applies to transactions that weren't admitted before,
+    /// re-injected ones are only checked against the rules. Admitted transactions are counted by
+    /// [`Self::record`], [`Self::reserve`] checks and counts them at once.
+    pub fn check(&self, sender: Address, kind: TxKind, hash: TxHash) -> Result<(), AdmissionError> {
+        let rules = self.rules.read().unwrap();
+        rules.check(sender, kind)?;
+        let Some(RateLimit { max_txs, period_secs }) = rules.rate_limit else { return Ok(()) };
+        drop(rules);
+
+        let mut admissions = self.admissions.lock().unwrap();
+        if admissions.hashes.contains(&hash) {
+            return Ok(())
+        }
+        if admissions.recent(sender, Duration::from_secs(period_secs)) >= max_txs {
+            return Err(AdmissionError::RateLimited { sender, max_txs, period_secs })
+        }
+        Ok(())
+    }
+
+    /// Checks the transaction like [`Self::check`] and, if admitted, counts it against the rate
+    /// limit of `sender` right away, so that transactions checked together can't exceed the limit.
+    ///
+    /// Returns whether a slot of the rate limit was reserved, to be given back with
+    /// [`Self::release`] if the transaction doesn't enter the pool after all.
+    pub fn reserve(
+        &self,
+        sender: Address,
+        kind: TxKind,
+        hash: TxHash,
+    ) -> Result<bool, AdmissionError> {
+        let rules = self.rules.read().unwrap();
+        rules.check(sender, kind)?;
+        let Some(RateLimit { max_txs, period_secs }) = rules.rate_limit else { return Ok(false) };
+        drop(rules);
+
+        let period = Duration::from_secs(period_secs);
+        let mut admissions = self.admissions.lock().unwrap();
+        if admissions.hashes.contains(&hash) {
+            return Ok(false)
+        }
+        if admissions.recent(sender, period) >= max_txs {
+            return Err(AdmissionError::RateLimited { sender, max_txs, period_secs })
+        }
+        Ok(admissions.insert(sender, hash, period))
+    }
+
+    /// Gives back the slot of the rate limit of `sender` reserved for the transaction `hash`.
+    pub fn release(&self, sender: Address, hash: TxHash) {
+        let mut admissions = self.admissions.lock().unwrap();
+        if !admissions.hashes.remove(&hash) {
+            return
+        }
+        if let Some(index) = admissions.order.iter().rposition(|admitted| *admitted == hash) {
+            admissions.order.remove(index);
+        }
+        if let Some(window) = admissions.windows.get_mut(&sender) {
+            window.pop_back();
+        }
+    }
+
+    /// Counts the admitted transaction `hash` against the rate limit of `sender`, once.
+    pub fn record(&self, sender: Address, hash: TxHash) {
+        let period = self.rules.read().unwrap().rate_limit.map(|limit| limit.period_secs);
+        let Some(period) = period.map(Duration::from_secs) else { return };
+        self.admissions.lock().unwrap().insert(sender, hash, period);
+    }
+
+    /// Reads the file again if it was modified since it was last read.
+    pub fn reload_if_changed(&self) {
+        let reloaded = self.file.reload_if_changed(|path| {
+            *self.rules.write().unwrap() = read_rules(path)?;
+            info!(target: "txpool", path=%path.display(), "reloaded admission rules");
+            Ok(())
+        });
+        if let Err(err) = reloaded {
+            warn!(target: "txpool", path=%self.file.path().display(), %err, "failed to load admission rules")
+        }
+    }
+}
+
+/// The transactions admitted recently.
+#[derive(Debug, Default)]
+struct Admissions {
+    /// Times of the recently admitted transactions of each sender
+    windows: HashMap<Address, VecDeque<Instant>>,
+    /// Hashes of the admitted transactions
+    hashes: HashSet<TxHash>,
+    /// The admitted hashes, oldest first
+    order: VecDeque<TxHash>,
+}
+
+impl Admissions {
+    /// Returns the number of transactions of `sender` admitted within the last `period`.
+    fn recent(&mut self, sender: Address, period: Duration) -> usize {
+        let now = Instant::now();
+        let Some(window) = self.windows.get_mut(&sender) else { return 0 };
+        while window.front().is_some_and(|first| now.duration_since(*first) >= period) {
+            window.pop_front();
+        }
+        window.len()
+    }
+
+    /// Counts the transaction `hash` of `sender`, returns `false` if it was already counted.
+    fn insert(&mut self, sender: Address, hash: TxHash, period: Duration) -> bool {
+        if !self.hashes.insert(hash) {
+            return false
+        }
+        self.order.push_back(hash);
+        if self.order.len() > MAX_TRACKED_ADMISSIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+
+        let now = Instant::now();
+        if self.windows.len() > MAX_TRACKED_SENDERS {
+            self.windows.retain(|_, window| {
+                window.back().is_some_and(|last| now.duration_since(*last) < period)
+            });
+        }
+        self.windows.entry(sender).or_default().push_back(now);
+        true
+    }
+}
+
+fn read_rules(path: &Path) -> eyre::Result<AdmissionRules> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+/// A [`TransactionValidator`] that rejects the transactions the [`AdmissionControl`] doesn't
+/// admit before handing the others to the inner validator.
+///
+/// A slot of the rate limit is reserved for each admitted transaction, and given back if the
+/// inner validator finds it invalid, so that only valid transactions count against the limits.
+#[derive(Debug, Clone)]
+pub struct AdmissionValidator<V> {
+    inner: V,
+    control: Option<AdmissionControl>,
+}
+
+impl<V> AdmissionValidator<V> {
+    /// Wraps `inner`, admitting every transaction if `control` is `None`.
+    pub const fn new(inner: V, control: Option<AdmissionControl>) -> Self {
+        Self { inner, control }
+    }
+
+    /// Returns the validator the admitted transactions are passed to.
+    pub const fn inner(&self) -> &V {
+        &self.inner
+    }
+}
+
+impl<V: TransactionValidator> AdmissionValidator<V> {
+    /// Returns the admitted transaction and whether a slot of the rate limit was reserved for it.
+    fn admit(
+        &self,
+        transaction: V::Transaction,
+    ) -> Result<(V::Transaction, bool), TransactionValidationOutcome<V::Transaction>> {
+        let Some(control) = &self.control else { return Ok((transaction, false)) };
+        match control.reserve(transaction.sender(), transaction.kind(), *transaction.hash()) {
+            Ok(reserved) => Ok((transaction, reserved)),
+            Err(err) => Err(TransactionValidationOutcome::Invalid(transaction, err.into())),
+        }
+    }
+
+    /// Gives back the slot reserved for the transaction `hash` of `sender` if it is invalid.
+    fn on_outcome(
+        &self,
+        sender: Address,
+        hash: TxHash,
+        reserved: bool,
+        outcome: &TransactionValidationOutcome<V::Transaction>,
+    ) {
+        if let Some(control) = self.control.as_ref().filter(|_| reserved) {
+            if !matches!(outcome, TransactionValidationOutcome::Valid { .. }) {
+                control.release(sender, hash);
+            }
+        }
+    }
+}
+
+impl<V: TransactionValidator> TransactionValidator for AdmissionValidator<V> {
+    type Transaction = V::Transaction;
+
+    async fn validate_transaction(
+        &self,
+        origin: TransactionOrigin,
+        transaction: Self::Transaction,
+    ) -> TransactionValidationOutcome<Self::Transaction> {
+        let (sender, hash) = (transaction.sender(), *transaction.hash());
+        match self.admit(transaction) {
+            Ok((transaction, reserved)) => {
+                let outcome = self.inner.validate_transaction(origin, transaction).await;
+                self.on_outcome(sender, hash, reserved, &outcome);
+                outcome
+            }
+            Err(rejected) => rejected,
+        }
+    }
+
+    async fn validate_transactions(
+        &self,
+        transactions: Vec<(TransactionOrigin, Self::Transaction)>,
+    ) -> Vec<TransactionValidationOutcome<Self::Transaction>> {
+        let mut rejected = Vec::with_capacity(transactions.len());
+        let mut admitted = Vec::with_capacity(transactions.len());
+        let mut admitted_ids = Vec::with_capacity(transactions.len());
+        for (origin, transaction) in transactions {
+            let (sender, hash) = (transaction.sender(), *transaction.hash());
+            match self.admit(transaction) {
+                Ok((transaction, reserved)) => {
+                    rejected.push(None);
+                    admitted.push((origin, transaction));
+                    admitted_ids.push((sender, hash, reserved));
+                }
+                Err(outcome) => rejected.push(Some(outcome)),
+            }
+        }
+
+        let validated = self.inner.validate_transactions(admitted).await;
+        for ((sender, hash, reserved), outcome) in admitted_ids.into_iter().zip(&validated) {
+            self.on_outcome(sender, hash, reserved, outcome);
+        }
+
+        // keep the outcomes in the order of the transactions
+        let mut validated = validated.into_iter();
+        rejected.into_iter().filter_map(|outcome| outcome.or_else(|| validated.next())).collect()
+    }
+
+    fn on_new_head_block<B>(&self, new_tip_block: &SealedBlock<B>)
+    where
+        B: Block,
+    {
+        self.inner.on_new_head_block(new_tip_block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watched_file::touch;
+    use reth_transaction_pool::test_utils::{MockTransaction, MockTransactionValidator};
+
+    fn address(byte: u8) -> Address {
+        Address::with_last_byte(byte)
+    }
+
+    #[test]
+    fn parses_admission_rules() {
+        let rules: AdmissionRules = toml::from_str(&format!(
+            r#"
+            allowed_senders = ["{}", "{}"]
+            denied_targets = ["{}"]
+            deployers = []
+
+            [rate_limit]
+            max_txs = 2
+            period_secs = 60
+            "#,
+            address(1),
+            address(2),
+            address(3)
+        ))
+        .unwrap();
+        assert_eq!(rules.allowed_senders, Some(HashSet::from([address(1), address(2)])));
+        assert_eq!(rules.denied_targets, HashSet::from([address(3)]));
+        assert_eq!(rules.deployers, Some(HashSet::new()));
+        assert_eq!(rules.rate_limit, Some(RateLimit { max_txs: 2, period_secs: 60 }));
+        assert_eq!(toml::from_str::<AdmissionRules>("").unwrap(), AdmissionRules::default());
+        assert!(toml::from_str::<AdmissionRules>("allowed = []").is_err());
+    }
+
+    #[test]
+    fn checks_senders_and_targets() {
+        let rules = AdmissionRules {
+            allowed_senders: Some(HashSet::from([address(1), address(2)])),
+            denied_senders: HashSet::from([address(2)]),
+            allowed_targets: Some(HashSet::from([address(10), address(11)])),
+            denied_targets: HashSet::from([address(11)]),
+            deployers: Some(HashSet::from([address(1)])),
+            rate_limit: None,
+        };
+        assert_eq!(rules.check(address(1), TxKind::Call(address(10))), Ok(()));
+        assert_eq!(rules.check(address(1), TxKind::Create), Ok(()));
+        assert_eq!(
+            rules.check(address(2), TxKind::Call(address(10))),
+            Err(AdmissionError::SenderDenied(address(2)))
+        );
+        assert_eq!(
+            rules.check(address(3), TxKind::Call(address(10))),
+            Err(AdmissionError::SenderNotAllowed(address(3)))
+        );
+        assert_eq!(
+            rules.check(address(1), TxKind::Call(address(11))),
+            Err(AdmissionError::TargetDenied(address(11)))
+        );
+        assert_eq!(
+            rules.check(address(1), TxKind::Call(address(12))),
+            Err(AdmissionError::TargetNotAllowed(address(12)))
+        );
+
+        let rules = AdmissionRules { allowed_senders: None, ..rules };
+        assert_eq!(
+            rules.check(address(3), TxKind::Create),
+            Err(AdmissionError::CreateNotAllowed(address(3)))
+        );
+    }
+
+    #[test]
+    fn rate_limits_senders_and_reloads_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("admission.toml");
+        std::fs::write(&path, "[rate_limit]\nmax_txs = 2\nperiod_secs = 3600\n").unwrap();
+
+        let control = AdmissionControl::load(path.clone()).unwrap();
+        let call = TxKind::Call(address(10));
+        let hash = TxHash::with_last_byte;
+        // only recorded admissions count
+        assert_eq!(control.check(address(1), call, hash(1)), Ok(()));
+        assert_eq!(control.check(address(1), call, hash(1)), Ok(()));
+        assert_eq!(control.check(address(1), call, hash(1)), Ok(()));
+        control.record(address(1), hash(1));
+        control.record(address(1), hash(1));
+        assert_eq!(control.check(address(1), call, hash(2)), Ok(()));
+        control.record(address(1), hash(2));
+        assert_eq!(
+            control.check(address(1), call, hash(3)),
+            Err(AdmissionError::RateLimited { sender: address(1), max_txs: 2, period_secs: 3600 })
+        );
+        // re-injected transactions aren't rate limited
+        assert_eq!(control.check(address(1), call, hash(1)), Ok(()));
+        // the limit applies to each sender
+        assert_eq!(control.check(address(2), call, hash(4)), Ok(()));
+
+        std::fs::write(&path, format!("denied_senders = [\"{}\"]\n", address(2))).unwrap();
+        touch(&path);
+        control.reload_if_changed();
+        assert_eq!(control.check(address(1), call, hash(3)), Ok(()));
+        assert_eq!(
+            control.check(address(2), call, hash(4)),
+            Err(AdmissionError::SenderDenied(address(2)))
+        );
+
+        // invalid rules keep the previous ones
+        std::fs::write(&path, "denied_senders = 1").unwrap();
+        touch(&path);
+        control.reload_if_changed();
+        assert_eq!(
+            control.check(address(2), call, hash(4)),
+            Err(AdmissionError::SenderDenied(address(2)))
+        );
+    }
+
+    /// Finds every transaction invalid.
+    #[derive(Debug)]
+    struct RejectingValidator;
+
+    impl TransactionValidator for RejectingValidator {
+        type Transaction = MockTransaction;
+
+        async fn validate_transaction(
+            &self,
+            _origin: TransactionOrigin,
+            transaction: Self::Transaction,
+        ) -> TransactionValidationOutcome<Self::Transaction> {
+            TransactionValidationOutcome::Invalid(
+                transaction,
+                InvalidPoolTransactionError::Underpriced,
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn reserves_rate_limit_within_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("admission.toml");
+        std::fs::write(&path, "[rate_limit]\nmax_txs = 2\nperiod_secs = 3600\n").unwrap();
+        let control = AdmissionControl::load(path).unwrap();
+        let batch = |count: u8| {
+            (0..count)
+                .map(|_| {
+                    let tx = MockTransaction::eip1559().with_sender(address(1));
+                    (TransactionOrigin::External, tx)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // the slots of the invalid transactions are given back
+        let validator = AdmissionValidator::new(RejectingValidator, Some(control.clone()));
+        let outcomes = validator.validate_transactions(batch(3)).await;
+        assert!(outcomes.iter().all(|outcome| matches!(
+            outcome,
+            TransactionValidationOutcome::Invalid(_, InvalidPoolTransactionError::Underpriced)
+        )));
+
+        // a batch larger than the limit only admits as many transactions as the limit
+        let validator =
+            AdmissionValidator::new(MockTransactionValidator::default(), Some(control.clone()));
+        let outcomes = validator.validate_transactions(batch(3)).await;
+        assert!(matches!(outcomes[0], TransactionValidationOutcome::Valid { .. }));
+        assert!(matches!(outcomes[1], TransactionValidationOutcome::Valid { .. }));
+        let TransactionValidationOutcome::Invalid(_, InvalidPoolTransactionError::Other(err)) =
+            &outcomes[2]
+        else {
+            panic!("the third transaction is admitted")
+        };
+        let limited =
+            AdmissionError::RateLimited { sender: address(1), max_txs: 2, period_secs: 3600 };
+        assert_eq!(err.as_any().downcast_ref::<AdmissionError>(), Some(&limited));
+        // the admitted ones count
+        assert!(control.check(address(1), TxKind::Create, TxHash::random()).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use reth_consensus::{ConsensusError, FullConsensus};
+use reth_consensus::{Consensus, ConsensusError, FullConsensus};
 use reth_ethereum_primitives::{EthPrimitives, PooledTransaction};
 use std::sync::Arc;
 use reth_node_api::FullNodeTypes;
@@ -6,22 +6,94 @@ use n42_clique::APos;
 use reth_chainspec::ChainSpec;
 use reth_node_builder::components::ConsensusBuilder;
 use reth_node_builder::{BuilderContext, NodeTypes};
+use futures_util::StreamExt;
+use reth_provider::CanonStateSubscriptions;
+use reth_tracing::tracing::debug;
+use tokio::sync::watch;
+
+/// The consensus of the node.
+pub type N42Consensus = Arc<dyn FullConsensus<EthPrimitives, Error = ConsensusError>>;
+
+/// The consensus of the node, handed to the components built before it.
+#[derive(Debug, Clone)]
+pub struct SharedConsensus(Arc<watch::Sender<Option<N42Consensus>>>);
+
+impl Default for SharedConsensus {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(None).0))
+    }
+}
+
+impl SharedConsensus {
+    /// Shares the consensus once built.
+    pub fn set(&self, consensus: N42Consensus) {
+        self.0.send_replace(Some(consensus));
+    }
+
+    /// Waits until the consensus is built.
+    pub async fn get(&self) -> N42Consensus {
+        let mut consensus = self.0.subscribe();
+        let consensus = consensus.wait_for(Option::is_some).await.expect("sender is kept alive");
+        consensus.clone().expect("consensus is set")
+    }
+}
 
 /// A basic ethereum consensus builder.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct N42ConsensusBuilder {
     // TODO add closure to modify consensus
+    /// Receives the built consensus for the other components.
+    shared: SharedConsensus,
+}
+
+impl N42ConsensusBuilder {
+    /// Creates the builder, handing the built consensus to `shared`.
+    pub const fn new(shared: SharedConsensus) -> Self {
+        Self { shared }
+    }
 }
 
 impl<Node> ConsensusBuilder<Node> for N42ConsensusBuilder
 where
     Node: FullNodeTypes<Types: NodeTypes<ChainSpec = ChainSpec, Primitives = EthPrimitives>>,
 {
-    type Consensus = Arc<dyn FullConsensus<EthPrimitives, Error = ConsensusError>>;
+    type Consensus = N42Consensus;
 
     async fn build_consensus(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Consensus> {
         //Ok(Arc::new(EthBeaconConsensus::new(ctx.chain_spec())))
-            Ok(Arc::new(APos::new(ctx.provider().clone(), ctx.chain_spec(), ctx.config().dev.consensus_signer_private_key.map(|v|v.to_string()))))
+        ctx.chain_spec().validate_apos_config()?;
+        let dev = ctx.config().dev;
+        let apos = APos::new(
+            ctx.provider().clone(),
+            ctx.chain_spec(),
+            dev.consensus_signer_private_key.map(|v| v.to_string()),
+        )
+        .with_inactive_signer_rounds(dev.drop_inactive_signers_after);
+        let consensus: Self::Consensus = Arc::new(apos);
+        self.shared.set(consensus.clone());
+
+        // the signer liveness follows the canonical chain, not every validated side chain header
+        let mut canon_state = ctx.provider().canonical_state_stream();
+        let canonical = consensus.clone();
+        ctx.task_executor().spawn(Box::pin(async move {
+            while let Some(notification) = canon_state.next().await {
+                let headers: Vec<_> = notification
+                    .committed()
+                    .blocks_iter()
+                    .map(|block| block.clone_sealed_header())
+                    .collect();
+                let consensus = canonical.clone();
+                // the parent snapshot may have to be rebuilt from the database
+                let _ = tokio::task::spawn_blocking(move || {
+                    for header in headers {
+                        if let Err(err) = consensus.on_canonical_header(&header) {
+                            debug!(target: "n42::consensus", number = header.number, %err, "failed to track signer liveness");
+                        }
+                    }
+                })
+                .await;
+            }
+        }));
+        Ok(consensus)
     }
 }
-
@@ -12,7 +12,7 @@
 //pub use engine_validator::N42EngineValidator;
 
 mod node;
-pub use node::N42Node;
+pub use node::{EthereumPoolBuilder, N42Node, N42TransactionPool};
 
 mod payload;
 //mod job_generator;
@@ -20,9 +20,30 @@ mod payload;
 //mod metrics;
 mod network;
 mod consensus;
+mod admission;
+mod policy;
+mod private_tx;
+mod relay;
+mod watched_file;
 
+pub use admission::{
+    AdmissionControl, AdmissionError, AdmissionRules, AdmissionValidator, RateLimit,
+    ADMISSION_RULES_RELOAD_INTERVAL,
+};
 pub use payload::EthereumPayloadBuilderWrapper;
 pub use payload::N42PayloadServiceBuilder;
+pub use policy::{
+    BlockSelection, DenyList, FifoTransactions, N42TransactionPolicy, PolicyViolation,
+    TransactionPolicy,
+};
+pub use private_tx::{
+    PrivateTransactions, PrivateTxProtocolHandler, DEFAULT_PRIVATE_TX_LIFETIME,
+    MAX_PRIVATE_TX_LIFETIME,
+};
+pub use relay::{
+    bid_signing_hash, registration_signing_hash, relay_bid_signing_hash, signer_pubkey, BidRequest,
+    Relay, RelayClient, RelayError,
+};
 
 
 
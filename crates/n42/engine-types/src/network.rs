@@ -1,5 +1,16 @@
-use reth_network::config::NetworkMode;
+use alloy_consensus::BlockHeader;
+use alloy_primitives::Address;
+use futures_util::StreamExt;
+use reth_network::config::{NetworkMode, SecretKey};
+use reth_network::signer_enr::SignerSet;
+use reth_network::protocol::IntoRlpxSubProtocol;
+use alloy_signer_local::PrivateKeySigner;
+use crate::{consensus::SharedConsensus, PrivateTransactions, PrivateTxProtocolHandler};
 use reth_network::{EthNetworkPrimitives, NetworkManager, NetworkHandle, PeersInfo};
+use reth_primitives_traits::Header;
+use reth_provider::{BlockReaderIdExt, CanonStateSubscriptions};
+use std::collections::HashSet;
+use tokio::sync::watch;
 use reth_node_api::{AddOnsContext, FullNodeComponents, NodeAddOns, TxTy};
 use reth_ethereum_primitives::{EthPrimitives, PooledTransaction};
 use reth_chainspec::{ChainSpec, EthChainSpec};
@@ -13,12 +24,73 @@ use reth_node_builder::{
 use reth_transaction_pool::{
     EthTransactionPool, PoolTransaction, TransactionPool, TransactionValidationTaskExecutor,
 };
-use reth_tracing::tracing::{debug, info};
+use reth_tracing::tracing::{debug, info, warn};
+
+/// Keeps `signers` at the signers of the `APos` snapshot at the canonical head.
+///
+/// Discovery verifies the signer entries of the records it finds against them, and verifies them
+/// again when they change. The snapshots are read with the consensus of the node, built after the
+/// network.
+async fn track_head_signers<Provider>(
+    consensus: SharedConsensus,
+    provider: Provider,
+    signers: watch::Sender<HashSet<Address>>,
+) where
+    Provider: BlockReaderIdExt<Header = Header> + CanonStateSubscriptions,
+{
+    let consensus = consensus.get().await;
+    let mut canon_state = provider.canonical_state_stream();
+    let mut head = match provider.latest_header() {
+        Ok(head) => head.map(|head| (head.number, head.hash())),
+        Err(err) => {
+            warn!(target: "n42::network", %err, "failed to read head for signer set");
+            None
+        }
+    };
+    loop {
+        if let Some((number, hash)) = head {
+            // a miss walks the headers in the database
+            let snapshot = {
+                let consensus = consensus.clone();
+                tokio::task::spawn_blocking(move || consensus.snapshot(number, hash, None)).await
+            };
+            match snapshot {
+                Ok(Ok(snapshot)) => {
+                    let head_signers: HashSet<_> = snapshot.signers.into_iter().collect();
+                    signers.send_if_modified(|signers| {
+                        let changed = *signers != head_signers;
+                        *signers = head_signers;
+                        changed
+                    });
+                }
+                Ok(Err(err)) => {
+                    warn!(target: "n42::network", %err, "failed to load signers at head")
+                }
+                Err(err) => warn!(target: "n42::network", %err, "signer set lookup panicked"),
+            }
+        }
+        let Some(notification) = canon_state.next().await else { return };
+        let tip = notification.tip();
+        head = Some((tip.number(), tip.hash()));
+    }
+}
 
 /// A basic ethereum payload service.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct N42NetworkBuilder {
     // TODO add closure to modify network
+    /// Private transactions exchanged over the `n42p` subprotocol.
+    private_txs: PrivateTransactions,
+    /// The consensus of the node, reading the signers that discovery verifies records against.
+    consensus: SharedConsensus,
+}
+
+impl N42NetworkBuilder {
+    /// Creates the builder, handing the private transactions received by the network to
+    /// `private_txs` and reading the signers with the consensus shared through `consensus`.
+    pub const fn new(private_txs: PrivateTransactions, consensus: SharedConsensus) -> Self {
+        Self { private_txs, consensus }
+    }
 }
 
 impl<Node, Pool> NetworkBuilder<Node, Pool> for N42NetworkBuilder
@@ -36,9 +108,51 @@ where
         ctx: &BuilderContext<Node>,
         pool: Pool,
     ) -> eyre::Result<Self::Network> {
-        let network_config_builder = ctx.network_config_builder()?.network_mode(NetworkMode::Work);
+        let (signers, signer_set) = SignerSet::new(HashSet::new());
+        ctx.task_executor().spawn(Box::pin(track_head_signers(
+            self.consensus,
+            ctx.provider().clone(),
+            signers,
+        )));
+        let mut network_config_builder = ctx
+            .network_config_builder()?
+            .network_mode(NetworkMode::Work)
+            .signer_set(signer_set.clone());
+        if let Some(signer_key) = ctx.config().dev.consensus_signer_private_key {
+            network_config_builder =
+                network_config_builder.apos_signer_key(SecretKey::from_slice(signer_key.as_slice())?);
+        }
         let network_config = ctx.build_network_config(network_config_builder);
-        let network = NetworkManager::builder(network_config).await?;
+        let mut network = NetworkManager::builder(network_config).await?;
+
+        // signers prove their identity on the `n42p` subprotocol to receive private transactions
+        let signer_key = ctx
+            .config()
+            .dev
+            .consensus_signer_private_key
+            .map(|key| PrivateKeySigner::from_bytes(&key))
+            .transpose()?;
+        let local_peer_id = *network.network().peer_id();
+        network.network_mut().add_rlpx_sub_protocol(
+            PrivateTxProtocolHandler::new(
+                local_peer_id,
+                signer_key,
+                signer_set,
+                self.private_txs.clone(),
+            )
+            .into_rlpx_sub_protocol(),
+        );
+
+        // received private transactions live for a bounded number of blocks past the head
+        let private_txs = self.private_txs;
+        private_txs.on_new_head(ctx.head().number);
+        let mut canon_state = ctx.provider().canonical_state_stream();
+        ctx.task_executor().spawn(Box::pin(async move {
+            while let Some(notification) = canon_state.next().await {
+                private_txs.on_new_head(notification.tip().number());
+            }
+        }));
+
         let handle = ctx.start_network(network, pool);
         info!(target: "n42::cli", enode=%handle.local_node_record(), "P2P networking initialized");
         Ok(handle)
@@ -10,8 +10,11 @@ use alloy_eips::{eip7840::BlobParams, merge::EPOCH_SLOTS};
 use reth_chainspec::{ChainSpec, EthChainSpec, EthereumHardforks};
 use reth_consensus::{ConsensusError, FullConsensus};
 //use reth_ethereum_consensus::EthBeaconConsensus;
-use crate::consensus::{N42ConsensusBuilder};
+use crate::consensus::{N42ConsensusBuilder, SharedConsensus};
 use crate::network::N42NetworkBuilder;
+use crate::{
+    AdmissionControl, AdmissionValidator, PrivateTransactions, ADMISSION_RULES_RELOAD_INTERVAL,
+};
 //use crate::{N42EngineTypes, N42NodeAddOns, N42PayloadServiceBuilder};
 use crate::{EthereumPayloadBuilderWrapper, N42PayloadServiceBuilder};
 use reth_ethereum_engine_primitives::{
@@ -43,20 +46,33 @@ use reth_rpc_server_types::RethRpcModule;
 use reth_tracing::tracing::{debug, info};
 use reth_transaction_pool::{
     blobstore::{DiskFileBlobStore, DiskFileBlobStoreConfig},
-    EthTransactionPool, PoolTransaction, TransactionPool, TransactionValidationTaskExecutor,
+    CoinbaseTipOrdering, EthPooledTransaction, EthTransactionValidator, PoolTransaction,
+    TransactionPool, TransactionValidationTaskExecutor,
 };
 use reth_trie_db::MerklePatriciaTrie;
 use revm::context::TxEnv;
-use std::{default::Default, sync::Arc, time::SystemTime};
+use std::{default::Default, path::PathBuf, sync::Arc, time::SystemTime};
 
 /// Type configuration for a regular Ethereum node.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 #[non_exhaustive]
-pub struct N42Node;
+pub struct N42Node {
+    /// Private transactions shared by the network, the payload builder and the RPC.
+    private_transactions: PrivateTransactions,
+    /// The consensus, shared with the network built before it.
+    consensus: SharedConsensus,
+}
 
 impl N42Node {
+    /// Returns the private transactions handed to the signers and kept for the local blocks.
+    pub const fn private_transactions(&self) -> &PrivateTransactions {
+        &self.private_transactions
+    }
+
     /// Returns a [`ComponentsBuilder`] configured for a regular Ethereum node.
-    pub fn components<Node>() -> ComponentsBuilder<
+    pub fn components<Node>(
+        &self,
+    ) -> ComponentsBuilder<
         Node,
         EthereumPoolBuilder,
         N42PayloadServiceBuilder<EthereumPayloadBuilderWrapper>,
@@ -74,11 +90,20 @@ impl N42Node {
     {
         ComponentsBuilder::default()
             .node_types::<Node>()
-            .pool(EthereumPoolBuilder::default())
+            .pool(
+                EthereumPoolBuilder::default()
+                    .with_private_transactions(self.private_transactions.clone()),
+            )
             .executor(EthereumExecutorBuilder::default())
-            .consensus(N42ConsensusBuilder::default())
-            .payload(N42PayloadServiceBuilder::default())
-            .network(N42NetworkBuilder::default())
+            .consensus(N42ConsensusBuilder::new(self.consensus.clone()))
+            .payload(N42PayloadServiceBuilder::new(
+                EthereumPayloadBuilderWrapper::default()
+                    .with_private_transactions(self.private_transactions.clone()),
+            ))
+            .network(N42NetworkBuilder::new(
+                self.private_transactions.clone(),
+                self.consensus.clone(),
+            ))
     }
 
     /// Instantiates the [`ProviderFactoryBuilder`] for an ethereum node.
@@ -271,7 +296,7 @@ where
     >;
 
     fn components_builder(&self) -> Self::ComponentsBuilder {
-        Self::components()
+        self.components()
     }
 
     fn add_ons(&self) -> Self::AddOns {
@@ -321,12 +346,40 @@ where
 ///
 /// This contains various settings that can be configured and take precedence over the node's
 /// config.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 #[non_exhaustive]
 pub struct EthereumPoolBuilder {
-    // TODO add options for txpool args
+    /// File with the [`AdmissionRules`](crate::AdmissionRules) of the pool, overriding
+    /// `--txpool.admission-rules`.
+    pub admission_rules: Option<PathBuf>,
+    /// Private transactions checked with the validator and admission rules of the pool.
+    private_txs: PrivateTransactions,
 }
 
+impl EthereumPoolBuilder {
+    /// Only admits the transactions allowed by the rules in the file at `path`.
+    pub fn with_admission_rules(mut self, path: PathBuf) -> Self {
+        self.admission_rules = Some(path);
+        self
+    }
+
+    /// Checks the private transactions with the validator and admission rules of the pool.
+    pub fn with_private_transactions(mut self, private_txs: PrivateTransactions) -> Self {
+        self.private_txs = private_txs;
+        self
+    }
+}
+
+/// The pool built by [`EthereumPoolBuilder`], validating transactions against the admission
+/// rules before the regular Ethereum checks.
+pub type N42TransactionPool<Client, S> = reth_transaction_pool::Pool<
+    AdmissionValidator<
+        TransactionValidationTaskExecutor<EthTransactionValidator<Client, EthPooledTransaction>>,
+    >,
+    CoinbaseTipOrdering<EthPooledTransaction>,
+    S,
+>;
+
 impl<Types, Node> PoolBuilder<Node> for EthereumPoolBuilder
 where
     Types: NodeTypes<
@@ -335,7 +388,7 @@ where
     >,
     Node: FullNodeTypes<Types = Types>,
 {
-    type Pool = EthTransactionPool<Node::Provider, DiskFileBlobStore>;
+    type Pool = N42TransactionPool<Node::Provider, DiskFileBlobStore>;
 
     async fn build_pool(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Pool> {
         let data_dir = ctx.config().datadir();
@@ -370,8 +423,28 @@ where
             .with_additional_tasks(ctx.config().txpool.additional_validation_tasks)
             .build_with_tasks(ctx.task_executor().clone(), blob_store.clone());
 
-        let transaction_pool =
-            reth_transaction_pool::Pool::eth_pool(validator, blob_store, pool_config);
+        let admission_rules =
+            self.admission_rules.or_else(|| ctx.config().txpool.admission_rules.clone());
+        let admission = admission_rules.map(AdmissionControl::load).transpose()?;
+        if let Some(admission) = admission.clone() {
+            self.private_txs.set_admission_control(admission.clone());
+            ctx.task_executor().spawn(Box::pin(async move {
+                let mut interval = tokio::time::interval(ADMISSION_RULES_RELOAD_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    admission.reload_if_changed();
+                }
+            }));
+        }
+        let validator = AdmissionValidator::new(validator, admission);
+        self.private_txs.set_validator(validator.clone());
+
+        let transaction_pool = reth_transaction_pool::Pool::new(
+            validator,
+            CoinbaseTipOrdering::default(),
+            blob_store,
+            pool_config,
+        );
         info!(target: "reth::cli", "Transaction pool initialized");
 
         // spawn txpool maintenance task
@@ -11,13 +11,15 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 #![allow(clippy::useless_let_if_seq)]
 
-use alloy_consensus::{Transaction, Typed2718};
-use alloy_primitives::U256;
+use alloy_consensus::{Header, Transaction, Typed2718};
+use alloy_eips::Decodable2718;
+use alloy_primitives::{keccak256, U256};
+use alloy_rpc_types_beacon::relay::SubmitBlockRequest;
 use reth_basic_payload_builder::{
-    is_better_payload, BuildArguments, BuildOutcome, MissingPayloadBehaviour, PayloadBuilder,
+    BuildArguments, BuildOutcome, MissingPayloadBehaviour, PayloadBuilder,
     PayloadConfig,
 };
-use reth_chainspec::{ChainSpec, ChainSpecProvider, EthChainSpec, EthereumHardforks};
+use reth_chainspec::{AposChainSpec, ChainSpec, ChainSpecProvider, EthChainSpec, EthereumHardforks};
 use reth_errors::{BlockExecutionError, BlockValidationError};
 use reth_ethereum_primitives::{EthPrimitives, TransactionSigned};
 use reth_evm::{
@@ -28,8 +30,8 @@ use reth_evm_ethereum::EthEvmConfig;
 use reth_payload_builder::{EthBuiltPayload, EthPayloadBuilderAttributes};
 use reth_payload_builder_primitives::PayloadBuilderError;
 use reth_payload_primitives::PayloadBuilderAttributes;
-use reth_primitives_traits::transaction::error::InvalidTransactionError;
-use reth_revm::{database::StateProviderDatabase, db::State};
+use reth_primitives_traits::{transaction::error::InvalidTransactionError, SealedHeader, SignedTransaction};
+use reth_revm::{cached::CachedReads, database::StateProviderDatabase, db::State};
 use reth_storage_api::StateProviderFactory;
 use reth_transaction_pool::{
     error::{Eip4844PoolTransactionError, InvalidPoolTransactionError},
@@ -37,7 +39,7 @@ use reth_transaction_pool::{
     ValidPoolTransaction,
 };
 use revm::context_interface::Block as _;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tracing::{debug, trace, warn};
 
 //mod config;
@@ -75,6 +77,11 @@ use reth_ethereum_engine_primitives::{
     EthPayloadAttributes,
 };
 use reth_node_api::{TxTy};
+use crate::{
+    policy::{BlockSelection, N42TransactionPolicy, TransactionPolicy},
+    private_tx::PrivateTransactions,
+    relay::{BidRequest, RelayClient, RelayError},
+};
 use reth_node_builder::{
     PayloadBuilderConfig,
     PayloadTypes,
@@ -83,9 +90,18 @@ use reth_node_builder::{
 /// A basic ethereum payload service.
 #[derive(Clone, Default, Debug)]
 #[non_exhaustive]
-pub struct EthereumPayloadBuilderWrapper;
+pub struct EthereumPayloadBuilderWrapper {
+    /// Private transactions included ahead of the pool ones.
+    private_txs: PrivateTransactions,
+}
 
 impl EthereumPayloadBuilderWrapper {
+    /// Includes the private transactions received by this node in its blocks.
+    pub fn with_private_transactions(mut self, private_txs: PrivateTransactions) -> Self {
+        self.private_txs = private_txs;
+        self
+    }
+
     /// A helper method initializing [`reth_ethereum_payload_builder::EthereumPayloadBuilder`] with
     /// the given EVM config.
     pub fn build<Types, Node, Evm, Pool, Cons>(
@@ -113,13 +129,21 @@ impl EthereumPayloadBuilderWrapper {
         let conf = ctx.payload_builder_config();
         let chain = ctx.chain_spec().chain();
         let gas_limit = conf.gas_limit_for(chain);
+        let relay = RelayClient::from_args(
+            &ctx.config().builder,
+            ctx.config().dev.consensus_signer_private_key,
+            ctx.task_executor().clone(),
+        )?;
         Ok(N42PayloadBuilder::new(
             ctx.provider().clone(),
             pool,
             evm_config,
             EthereumBuilderConfig::new().with_gas_limit(gas_limit),
             cons,
-        ))
+        )
+        .with_policy(N42TransactionPolicy::from_args(&ctx.config().builder)?)
+        .with_relay(relay)
+        .with_private_transactions(self.private_txs))
     }
 }
 
@@ -223,9 +247,9 @@ type BestTransactionsIter<Pool> = Box<
 >;
 
 /// Ethereum payload builder
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 //pub struct N42PayloadBuilder<Pool, Client, EvmConfig = EthEvmConfig, Cons> 
-pub struct N42PayloadBuilder<Pool, Client, EvmConfig, Cons> {
+pub struct N42PayloadBuilder<Pool, Client, EvmConfig, Cons, Policy = N42TransactionPolicy> {
     /// Client providing access to node state.
     client: Client,
     /// Transaction pool.
@@ -236,6 +260,12 @@ pub struct N42PayloadBuilder<Pool, Client, EvmConfig, Cons> {
     builder_config: EthereumBuilderConfig,
     /// consensus
     cons: Cons,
+    /// Transaction ordering and inclusion policy.
+    policy: Policy,
+    /// Relays bidding for the blocks, if any.
+    relay: Option<RelayClient>,
+    /// Private transactions handed to this node.
+    private_txs: PrivateTransactions,
 }
 
 impl<Pool, Client, EvmConfig, Cons> N42PayloadBuilder<Pool, Client,  EvmConfig, Cons> {
@@ -247,18 +277,109 @@ impl<Pool, Client, EvmConfig, Cons> N42PayloadBuilder<Pool, Client,  EvmConfig,
         builder_config: EthereumBuilderConfig,
         cons: Cons,
     ) -> Self {
-        Self { client, pool, evm_config, builder_config, cons }
+        Self {
+            client,
+            pool,
+            evm_config,
+            builder_config,
+            cons,
+            policy: N42TransactionPolicy::default(),
+            relay: None,
+            private_txs: PrivateTransactions::default(),
+        }
+    }
+}
+
+impl<Pool, Client, EvmConfig, Cons, Policy> N42PayloadBuilder<Pool, Client, EvmConfig, Cons, Policy> {
+    /// Sets the transaction ordering and inclusion policy.
+    pub fn with_policy<P>(self, policy: P) -> N42PayloadBuilder<Pool, Client, EvmConfig, Cons, P> {
+        let Self { client, pool, evm_config, builder_config, cons, relay, private_txs, .. } = self;
+        N42PayloadBuilder {
+            client,
+            pool,
+            evm_config,
+            builder_config,
+            cons,
+            policy,
+            relay,
+            private_txs,
+        }
+    }
+
+    /// Sets the relays asked for a block before building one locally.
+    pub fn with_relay(mut self, relay: Option<RelayClient>) -> Self {
+        self.relay = relay;
+        self
+    }
+
+    /// Sets the private transactions included ahead of the pool ones.
+    pub fn with_private_transactions(mut self, private_txs: PrivateTransactions) -> Self {
+        self.private_txs = private_txs;
+        self
+    }
+}
+
+impl<Pool, Client, EvmConfig, Cons, Policy> N42PayloadBuilder<Pool, Client, EvmConfig, Cons, Policy>
+where
+    EvmConfig: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
+    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks + AposChainSpec>,
+    Cons:
+        FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + Unpin + 'static,
+{
+    /// Returns the payload of the block delivered by the relays, if it pays more than the best
+    /// payload so far and executes as announced.
+    fn relay_payload(
+        &self,
+        relay: &RelayClient,
+        config: &PayloadConfig<EthPayloadBuilderAttributes>,
+        cached_reads: &mut CachedReads,
+        best_payload: Option<&EthBuiltPayload>,
+    ) -> Option<EthBuiltPayload> {
+        let PayloadConfig { parent_header, attributes } = config;
+        let block = relay.poll_bid(BidRequest {
+            id: attributes.id,
+            slot: parent_header.number + 1,
+            parent_hash: parent_header.hash(),
+            fee_recipient: attributes.suggested_fee_recipient,
+            gas_limit: self.builder_config.gas_limit(parent_header.gas_limit),
+        })?;
+        let bid = block.bid_trace();
+        if !is_better_n42_payload(best_payload, bid.value, bid.gas_used) {
+            return None
+        }
+
+        match relay_n42_payload(
+            self.evm_config.clone(),
+            &self.client,
+            &self.builder_config,
+            config,
+            cached_reads,
+            &block,
+            &self.cons,
+            &self.policy,
+        ) {
+            Ok(payload) => {
+                debug!(target: "payload_builder", id=%attributes.id, value=%bid.value, "using the relay block");
+                Some(payload)
+            }
+            Err(err) => {
+                warn!(target: "payload_builder", id=%attributes.id, %err, "rejecting the relay block");
+                relay.reject(attributes.id);
+                None
+            }
+        }
     }
 }
 
 // Default implementation of [PayloadBuilder] for unit type
-impl<Pool, Client, EvmConfig, Cons> PayloadBuilder for N42PayloadBuilder<Pool, Client, EvmConfig, Cons>
+impl<Pool, Client, EvmConfig, Cons, Policy> PayloadBuilder for N42PayloadBuilder<Pool, Client, EvmConfig, Cons, Policy>
 where
     EvmConfig: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
-    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks> + Clone,
+    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks + AposChainSpec> + Clone,
     Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TransactionSigned>>,
     Cons:
         FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + Unpin + 'static,
+    Policy: TransactionPolicy,
 {
     type Attributes = EthPayloadBuilderAttributes;
     type BuiltPayload = EthBuiltPayload;
@@ -267,14 +388,25 @@ where
         &self,
         args: BuildArguments<EthPayloadBuilderAttributes, EthBuiltPayload>,
     ) -> Result<BuildOutcome<EthBuiltPayload>, PayloadBuilderError> {
+        let BuildArguments { mut cached_reads, config, cancel, best_payload } = args;
+        if let Some(relay) = &self.relay {
+            if let Some(payload) =
+                self.relay_payload(relay, &config, &mut cached_reads, best_payload.as_ref())
+            {
+                return Ok(BuildOutcome::Better { payload, cached_reads })
+            }
+        }
+
         default_n42_payload(
             self.evm_config.clone(),
             self.client.clone(),
             self.pool.clone(),
             self.builder_config.clone(),
-            args,
+            BuildArguments::new(cached_reads, config, cancel, best_payload),
             |attributes| self.pool.best_transactions_with_attributes(attributes),
             self.cons.clone(),
+            &self.policy,
+            Some(&self.private_txs),
         )
     }
 
@@ -303,6 +435,8 @@ where
             args,
             |attributes| self.pool.best_transactions_with_attributes(attributes),
             self.cons.clone(),
+            &self.policy,
+            None,
         )?
         .into_payload()
         .ok_or_else(|| PayloadBuilderError::MissingPayload)
@@ -314,8 +448,11 @@ where
 /// Given build arguments including an Ethereum client, transaction pool,
 /// and configuration, this function creates a transaction payload. Returns
 /// a result indicating success with the payload or an error in case of failure.
+///
+/// The unexpired `private_txs` are included first, in nonce order, if the policy and the admission
+/// rules of the pool admit them, and are dropped once their nonce is used.
 #[inline]
-pub fn default_n42_payload<EvmConfig, Client, Pool, F, Cons>(
+pub fn default_n42_payload<EvmConfig, Client, Pool, F, Cons, Policy>(
     evm_config: EvmConfig,
     client: Client,
     pool: Pool,
@@ -323,14 +460,17 @@ pub fn default_n42_payload<EvmConfig, Client, Pool, F, Cons>(
     args: BuildArguments<EthPayloadBuilderAttributes, EthBuiltPayload>,
     best_txs: F,
     cons: Cons,
+    policy: &Policy,
+    private_txs: Option<&PrivateTransactions>,
 ) -> Result<BuildOutcome<EthBuiltPayload>, PayloadBuilderError>
 where
     EvmConfig: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
-    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks>,
+    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks + AposChainSpec>,
     Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TransactionSigned>>,
     F: FnOnce(BestTransactionsAttributes) -> BestTransactionsIter<Pool>,
     Cons:
         FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + Unpin + 'static,
+    Policy: TransactionPolicy,
 {
     let BuildArguments { mut cached_reads, config, cancel, best_payload } = args;
     let PayloadConfig { parent_header, attributes } = config;
@@ -362,19 +502,66 @@ where
     let block_gas_limit: u64 = builder.evm_mut().block().gas_limit;
     let base_fee = builder.evm_mut().block().basefee;
 
-    let mut best_txs = best_txs(BestTransactionsAttributes::new(
+    policy.on_build();
+    let mut best_txs = policy.order(best_txs(BestTransactionsAttributes::new(
         base_fee,
         builder.evm_mut().block().blob_gasprice().map(|gasprice| gasprice as u64),
-    ));
+    )));
+    let mut selection = BlockSelection::new(block_gas_limit);
     let mut total_fees = U256::ZERO;
 
-    let mut header = cons.prepare(&parent_header).map_err(|err| PayloadBuilderError::Internal(err.into()))?;
-
     builder.apply_pre_execution_changes().map_err(|err| {
         warn!(target: "payload_builder", %err, "failed to apply pre-execution changes");
         PayloadBuilderError::Internal(err.into())
     })?;
 
+    // private transactions are handed to this signer only, they go ahead of the public ones
+    let pending = private_txs.map(|txs| txs.pending(parent_header.number + 1)).unwrap_or_default();
+    let admission = private_txs.and_then(PrivateTransactions::admission_control);
+    for tx in pending {
+        if cumulative_gas_used + tx.gas_limit() > block_gas_limit {
+            continue
+        }
+        if cancel.is_cancelled() {
+            return Ok(BuildOutcome::Cancelled)
+        }
+
+        let sender = tx.signer();
+        if let Err(violation) = policy.admit(&selection, sender, tx.inner()) {
+            trace!(target: "payload_builder", tx=?tx.tx_hash(), %violation, "skipping private transaction rejected by the policy");
+            continue
+        }
+        if let Some(Err(err)) =
+            admission.map(|admission| admission.check(sender, tx.kind(), *tx.tx_hash()))
+        {
+            trace!(target: "payload_builder", tx=?tx.tx_hash(), %err, "skipping private transaction rejected by the admission rules");
+            continue
+        }
+
+        let gas_used = match builder.execute_transaction(tx.clone()) {
+            Ok(gas_used) => gas_used,
+            Err(BlockExecutionError::Validation(BlockValidationError::InvalidTx {
+                error, ..
+            })) => {
+                trace!(target: "payload_builder", %error, tx=?tx.tx_hash(), "skipping invalid private transaction");
+                // the nonce was used, by this transaction in an earlier block or by another one
+                if error.is_nonce_too_low() {
+                    if let Some(private_txs) = private_txs {
+                        private_txs.remove(tx.tx_hash());
+                    }
+                }
+                continue
+            }
+            Err(err) => return Err(PayloadBuilderError::evm(err)),
+        };
+
+        let miner_fee =
+            tx.effective_tip_per_gas(base_fee).expect("fee is always valid; execution succeeded");
+        total_fees += U256::from(miner_fee) * U256::from(gas_used);
+        cumulative_gas_used += gas_used;
+        selection.record(sender, gas_used);
+    }
+
     let mut block_blob_count = 0;
     let blob_params = chain_spec.blob_params_at_timestamp(attributes.timestamp);
     let max_blob_count =
@@ -398,6 +585,12 @@ where
             return Ok(BuildOutcome::Cancelled)
         }
 
+        if let Err(violation) = policy.admit(&selection, pool_tx.sender(), &pool_tx.transaction) {
+            trace!(target: "payload_builder", tx=?pool_tx.hash(), %violation, "skipping transaction rejected by the policy");
+            best_txs.mark_invalid(&pool_tx, violation.into());
+            continue
+        }
+
         // convert tx to a signed transaction
         let tx = pool_tx.to_consensus();
 
@@ -465,10 +658,22 @@ where
             tx.effective_tip_per_gas(base_fee).expect("fee is always valid; execution succeeded");
         total_fees += U256::from(miner_fee) * U256::from(gas_used);
         cumulative_gas_used += gas_used;
+        selection.record(pool_tx.sender(), gas_used);
+    }
+
+    // a block without transactions waits for the heartbeat interval of the chain
+    if selection.is_empty() &&
+        !chain_spec
+            .apos_config_at(parent_header.number + 1, attributes.timestamp)
+            .allows_empty_block(parent_header.timestamp, attributes.timestamp)
+    {
+        drop(builder);
+        trace!(target: "payload_builder", id=%attributes.id, "no transactions before the heartbeat, skipping empty block");
+        return Ok(BuildOutcome::Aborted { fees: total_fees, cached_reads })
     }
 
     // check if we have a better block
-    if !is_better_payload(best_payload.as_ref(), total_fees) {
+    if !is_better_n42_payload(best_payload.as_ref(), total_fees, cumulative_gas_used) {
         // Release db
         drop(builder);
         // can skip building the block
@@ -499,25 +704,7 @@ where
             .map_err(PayloadBuilderError::other)?;
     }
 
-    header.state_root =  block.header().state_root;
-    header.transactions_root =  block.header().transactions_root;
-    header.receipts_root =  block.header().receipts_root;
-    header.logs_bloom =  block.header().logs_bloom;
-    header.gas_limit =  block.header().gas_limit;
-    header.gas_used =  block.header().gas_used;
-    header.base_fee_per_gas =  block.header().base_fee_per_gas;
-    header.withdrawals_root =  block.header().withdrawals_root;
-    header.blob_gas_used =  block.header().blob_gas_used;
-    header.excess_blob_gas =  block.header().excess_blob_gas;
-    header.requests_hash =  block.header().requests_hash;
-
-    header.timestamp = attributes.timestamp;
-    header.mix_hash = attributes.prev_randao;
-    header.parent_beacon_block_root = attributes.parent_beacon_block_root;
-
-    // seal
-    cons.seal(&mut header).map_err(|err| PayloadBuilderError::Internal(err.into()))?;
-
+    let header = n42_header(&cons, &parent_header, &attributes, block.header())?;
     let sealed_block = Arc::new(SealedBlock::seal_parts(header, block.into_block().body));
 
 
@@ -528,6 +715,214 @@ where
     Ok(BuildOutcome::Better { payload, cached_reads })
 }
 
+/// Builds the payload of a block delivered by a relay.
+///
+/// The transactions of the block are executed on top of the parent, and the block is accepted
+/// only if the `policy` admits all of them and the execution results match the ones announced
+/// and credit the fee recipient with at least the bid value. The header is then prepared and
+/// sealed like a local one, so the block carries the APos signature of this node rather than the
+/// builder's.
+pub fn relay_n42_payload<EvmConfig, Client, Cons, Policy>(
+    evm_config: EvmConfig,
+    client: &Client,
+    builder_config: &EthereumBuilderConfig,
+    config: &PayloadConfig<EthPayloadBuilderAttributes>,
+    cached_reads: &mut CachedReads,
+    block: &SubmitBlockRequest,
+    cons: &Cons,
+    policy: &Policy,
+) -> Result<EthBuiltPayload, PayloadBuilderError>
+where
+    EvmConfig: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
+    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks + AposChainSpec>,
+    Cons:
+        FullConsensus<EthPrimitives, Error = ConsensusError> + Clone + Unpin + 'static,
+    Policy: TransactionPolicy,
+{
+    let PayloadConfig { parent_header, attributes } = config;
+    let invalid = |reason| PayloadBuilderError::other(RelayError::InvalidPayload(reason));
+
+    let bid = block.bid_trace();
+    let (payload, blob_count) = match block {
+        SubmitBlockRequest::Capella(block) => (&block.execution_payload, 0),
+        SubmitBlockRequest::Deneb(block) => {
+            (&block.execution_payload.payload_inner, block.blobs_bundle.commitments.len())
+        }
+        SubmitBlockRequest::Electra(block) => {
+            (&block.execution_payload.payload_inner, block.blobs_bundle.commitments.len())
+        }
+    };
+    let inner = &payload.payload_inner;
+
+    if inner.parent_hash != parent_header.hash() || inner.block_number != parent_header.number + 1
+    {
+        return Err(invalid("not a child of the parent block"))
+    }
+    if inner.timestamp != attributes.timestamp ||
+        inner.prev_randao != attributes.prev_randao ||
+        inner.fee_recipient != attributes.suggested_fee_recipient ||
+        payload.withdrawals.as_slice() != attributes.withdrawals().as_slice()
+    {
+        return Err(invalid("built with other payload attributes"))
+    }
+    if inner.block_hash != bid.block_hash ||
+        inner.gas_used != bid.gas_used ||
+        inner.gas_limit != bid.gas_limit
+    {
+        return Err(invalid("does not match its bid"))
+    }
+    if inner.gas_limit != builder_config.gas_limit(parent_header.gas_limit) {
+        return Err(invalid("unexpected gas limit"))
+    }
+    if blob_count != 0 {
+        return Err(invalid("blob transactions are not supported"))
+    }
+
+    let chain_spec = client.chain_spec();
+    if inner.transactions.is_empty() &&
+        !chain_spec
+            .apos_config_at(parent_header.number + 1, attributes.timestamp)
+            .allows_empty_block(parent_header.timestamp, attributes.timestamp)
+    {
+        return Err(invalid("empty block before the heartbeat"))
+    }
+
+    let state_provider = client.state_by_block_hash(parent_header.hash())?;
+    let balance_before =
+        state_provider.account_balance(&attributes.suggested_fee_recipient)?.unwrap_or_default();
+    let state = StateProviderDatabase::new(&state_provider);
+    let mut db =
+        State::builder().with_database(cached_reads.as_db_mut(state)).with_bundle_update().build();
+
+    let mut builder = evm_config
+        .builder_for_next_block(
+            &mut db,
+            parent_header,
+            NextBlockEnvAttributes {
+                timestamp: attributes.timestamp(),
+                suggested_fee_recipient: attributes.suggested_fee_recipient(),
+                prev_randao: attributes.prev_randao(),
+                gas_limit: inner.gas_limit,
+                parent_beacon_block_root: attributes.parent_beacon_block_root(),
+                withdrawals: Some(attributes.withdrawals().clone()),
+            },
+        )
+        .map_err(PayloadBuilderError::other)?;
+
+    builder.apply_pre_execution_changes().map_err(|err| {
+        warn!(target: "payload_builder", %err, "failed to apply pre-execution changes");
+        PayloadBuilderError::Internal(err.into())
+    })?;
+
+    policy.on_build();
+    let mut selection = BlockSelection::new(inner.gas_limit);
+    for tx in &inner.transactions {
+        let tx = TransactionSigned::decode_2718(&mut tx.as_ref())
+            .map_err(|_| invalid("undecodable transaction"))?
+            .try_into_recovered()
+            .map_err(|_| invalid("invalid transaction signature"))?;
+        let sender = tx.signer();
+        policy
+            .admit(&selection, sender, tx.inner())
+            .map_err(|violation| PayloadBuilderError::other(RelayError::from(violation)))?;
+        match builder.execute_transaction(tx) {
+            Ok(gas_used) => selection.record(sender, gas_used),
+            Err(err) => {
+                trace!(target: "payload_builder", %err, "relay block transaction failed");
+                return Err(invalid("transaction failed to execute"))
+            }
+        }
+    }
+
+    let BlockBuilderOutcome { execution_result, hashed_state, block: built, .. } =
+        builder.finish(&state_provider)?;
+
+    let executed = built.header();
+    if executed.state_root != inner.state_root ||
+        executed.receipts_root != inner.receipts_root ||
+        executed.logs_bloom != inner.logs_bloom ||
+        executed.gas_used != inner.gas_used
+    {
+        return Err(invalid("execution results differ from the announced ones"))
+    }
+
+    let balance_after = match hashed_state.accounts.get(&keccak256(attributes.suggested_fee_recipient))
+    {
+        Some(account) => account.map(|account| account.balance).unwrap_or_default(),
+        None => balance_before,
+    };
+    if balance_after.saturating_sub(balance_before) < bid.value {
+        return Err(invalid("fee recipient paid less than the bid"))
+    }
+
+    let requests = chain_spec
+        .is_prague_active_at_timestamp(attributes.timestamp)
+        .then_some(execution_result.requests);
+
+    let header = n42_header(cons, parent_header, attributes, built.header())?;
+    let sealed_block = Arc::new(SealedBlock::seal_parts(header, built.into_block().body));
+
+    Ok(EthBuiltPayload::new(attributes.id, sealed_block, bid.value, requests))
+}
+
+/// Returns the APos header of a block executed on top of `parent_header`, sealed unless the
+/// block is left to an external consensus client holding the signer key.
+fn n42_header<Cons>(
+    cons: &Cons,
+    parent_header: &SealedHeader,
+    attributes: &EthPayloadBuilderAttributes,
+    executed: &Header,
+) -> Result<Header, PayloadBuilderError>
+where
+    Cons: FullConsensus<EthPrimitives, Error = ConsensusError>,
+{
+    let local_signer =
+        cons.get_eth_signer_address().map_err(|err| PayloadBuilderError::Internal(err.into()))?;
+    let mut header = if local_signer.is_some() {
+        cons.prepare(parent_header)
+    } else {
+        cons.prepare_external(parent_header)
+    }
+    .map_err(|err| PayloadBuilderError::Internal(err.into()))?;
+
+    header.state_root = executed.state_root;
+    header.transactions_root = executed.transactions_root;
+    header.receipts_root = executed.receipts_root;
+    header.logs_bloom = executed.logs_bloom;
+    header.gas_limit = executed.gas_limit;
+    header.gas_used = executed.gas_used;
+    header.base_fee_per_gas = executed.base_fee_per_gas;
+    header.withdrawals_root = executed.withdrawals_root;
+    header.blob_gas_used = executed.blob_gas_used;
+    header.excess_blob_gas = executed.excess_blob_gas;
+    header.requests_hash = executed.requests_hash;
+
+    header.timestamp = attributes.timestamp;
+    header.mix_hash = attributes.prev_randao;
+    header.parent_beacon_block_root = attributes.parent_beacon_block_root;
+
+    if local_signer.is_some() {
+        cons.seal(&mut header).map_err(|err| PayloadBuilderError::Internal(err.into()))?;
+    }
+    Ok(header)
+}
+
+/// Checks if the new payload is better than the current best one.
+///
+/// Unlike [`reth_basic_payload_builder::is_better_payload`], a payload paying the same fees but
+/// using more gas is better too, so the zero-tip transactions arriving before the slot deadline
+/// still make it into the sealed block.
+pub fn is_better_n42_payload(
+    best_payload: Option<&EthBuiltPayload>,
+    total_fees: U256,
+    gas_used: u64,
+) -> bool {
+    best_payload.is_none_or(|best| {
+        total_fees > best.fees() ||
+            (total_fees == best.fees() && gas_used > best.block().header().gas_used)
+    })
+}
+
 /// A type that knows how to build a payload builder to plug into [`BasicPayloadServiceBuilder`].
 pub trait N42PayloadBuilderBuilder<Node: FullNodeTypes, Pool: TransactionPool,
     EvmConfig,
@@ -564,7 +959,7 @@ impl<PB> N42PayloadServiceBuilder<PB> {
 
 impl<Node, Pool, PB, EvmConfig, Cons> PayloadServiceBuilder<Node, Pool, EvmConfig, Cons> for N42PayloadServiceBuilder<PB>
 where
-    Node: FullNodeTypes,
+    Node: FullNodeTypes<Types: NodeTypes<ChainSpec: AposChainSpec>>,
     Pool: TransactionPool,
     //EvmConfig: Send,
     EvmConfig: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
@@ -583,8 +978,19 @@ where
 
         let conf = ctx.config().builder.clone();
 
+        // The payload of the in-turn signer is improved until its slot, which starts half a period
+        // after the job, so it is rebuilt a few times within that window.
+        let chain_spec = ctx.chain_spec();
+        let period = chain_spec
+            .apos_genesis_config()
+            .period
+            .min(chain_spec.apos_config_at(u64::MAX, u64::MAX).period);
+        let interval = conf.interval.min(Duration::from_secs(period.max(1)) / 4);
+
+        // Jobs expire `deadline` after the timestamp of their slot, the miner resolves them before
+        // that even when its wiggle runs longer.
         let payload_job_config = BasicPayloadJobGeneratorConfig::default()
-            .interval(conf.interval)
+            .interval(interval)
             .deadline(conf.deadline)
             .max_payload_tasks(conf.max_payload_tasks);
 
@@ -0,0 +1,395 @@
+//! Transaction ordering and inclusion policies consulted by the N42 payload builder.
+
+use crate::watched_file::WatchedFile;
+use alloy_consensus::Transaction;
+use alloy_primitives::{Address, TxKind};
+use reth_node_core::args::{PayloadBuilderArgs, PayloadTxOrdering};
+use reth_transaction_pool::{
+    error::{InvalidPoolTransactionError, PoolTransactionError},
+    identifier::SenderId,
+    BestTransactions, PoolTransaction, ValidPoolTransaction,
+};
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+use tracing::{info, warn};
+
+/// Best transactions iterator of a pool of `T` transactions.
+pub type BestPoolTransactions<T> = Box<dyn BestTransactions<Item = Arc<ValidPoolTransaction<T>>>>;
+
+/// Decides in which order the pending transactions are considered for a block, and which of them
+/// may be included.
+pub trait TransactionPolicy: Clone + fmt::Debug + Send + Sync + 'static {
+    /// Called when a payload build starts.
+    fn on_build(&self) {}
+
+    /// Returns the pending transactions in the order the builder should consider them.
+    ///
+    /// The transactions of a sender must stay in nonce order.
+    fn order<T: PoolTransaction + 'static>(
+        &self,
+        best: BestPoolTransactions<T>,
+    ) -> BestPoolTransactions<T> {
+        best
+    }
+
+    /// Checks whether `tx` of `sender` may be added to the block selected so far.
+    ///
+    /// A rejected pool transaction is skipped along with the later transactions of its sender,
+    /// private and relay transactions are checked the same way.
+    fn admit<T: Transaction>(
+        &self,
+        selection: &BlockSelection,
+        sender: Address,
+        tx: &T,
+    ) -> Result<(), PolicyViolation>;
+}
+
+/// Transactions selected so far for the block being built.
+#[derive(Debug, Clone, Default)]
+pub struct BlockSelection {
+    /// Gas limit of the block
+    pub gas_limit: u64,
+    /// Gas used by the selected transactions
+    pub gas_used: u64,
+    /// Number of selected transactions of each sender
+    pub txs_by_sender: HashMap<Address, usize>,
+}
+
+impl BlockSelection {
+    /// Creates an empty selection for a block with the given gas limit.
+    pub fn new(gas_limit: u64) -> Self {
+        Self { gas_limit, ..Default::default() }
+    }
+
+    /// Whether no transaction was selected.
+    pub fn is_empty(&self) -> bool {
+        self.txs_by_sender.is_empty()
+    }
+
+    /// Records an executed transaction of `sender`.
+    pub fn record(&mut self, sender: Address, gas_used: u64) {
+        self.gas_used += gas_used;
+        *self.txs_by_sender.entry(sender).or_default() += 1;
+    }
+}
+
+/// Reason a [`TransactionPolicy`] rejected a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    /// The sender or the recipient is on the deny-list.
+    #[error("address {0} is denied")]
+    Denied(Address),
+    /// The sender already has the maximum number of transactions in the block.
+    #[error("sender {0} reached its {1} transactions per block")]
+    SenderCapReached(Address, usize),
+    /// The transaction would use gas reserved for the system senders.
+    #[error("gas limit {gas_limit} exceeds the {available} gas left to non-system senders")]
+    ReservedGas {
+        /// Gas limit of the transaction
+        gas_limit: u64,
+        /// Gas left to non-system senders
+        available: u64,
+    },
+}
+
+impl PoolTransactionError for PolicyViolation {
+    fn is_bad_transaction(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<PolicyViolation> for InvalidPoolTransactionError {
+    fn from(violation: PolicyViolation) -> Self {
+        Self::Other(Box::new(violation))
+    }
+}
+
+/// Policy configured through the `--builder.*` arguments.
+#[derive(Debug, Clone, Default)]
+pub struct N42TransactionPolicy {
+    ordering: PayloadTxOrdering,
+    max_txs_per_sender: Option<usize>,
+    system_senders: HashSet<Address>,
+    reserved_system_gas: u64,
+    deny_list: Option<DenyList>,
+}
+
+impl N42TransactionPolicy {
+    /// Creates the policy configured by `args`, failing if the deny-list can't be loaded.
+    pub fn from_args(args: &PayloadBuilderArgs) -> eyre::Result<Self> {
+        Ok(Self {
+            ordering: args.tx_ordering,
+            max_txs_per_sender: args.max_txs_per_sender,
+            system_senders: args.system_senders.iter().copied().collect(),
+            reserved_system_gas: args.reserved_system_gas,
+            deny_list: args.deny_list.clone().map(DenyList::load).transpose()?,
+        })
+    }
+
+    /// Sets the order in which transactions are considered.
+    pub const fn with_ordering(mut self, ordering: PayloadTxOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Sets the maximum number of transactions of a sender in a block.
+    pub const fn with_max_txs_per_sender(mut self, max: usize) -> Self {
+        self.max_txs_per_sender = Some(max);
+        self
+    }
+
+    /// Reserves `gas` of each block to the `senders`.
+    pub fn with_reserved_system_gas(
+        mut self,
+        senders: impl IntoIterator<Item = Address>,
+        gas: u64,
+    ) -> Self {
+        self.system_senders = senders.into_iter().collect();
+        self.reserved_system_gas = gas;
+        self
+    }
+
+    /// Excludes the addresses listed in the file at `path`.
+    pub fn with_deny_list(mut self, path: PathBuf) -> eyre::Result<Self> {
+        self.deny_list = Some(DenyList::load(path)?);
+        Ok(self)
+    }
+}
+
+impl TransactionPolicy for N42TransactionPolicy {
+    fn on_build(&self) {
+        if let Some(deny_list) = &self.deny_list {
+            deny_list.reload_if_changed();
+        }
+    }
+
+    fn order<T: PoolTransaction + 'static>(
+        &self,
+        best: BestPoolTransactions<T>,
+    ) -> BestPoolTransactions<T> {
+        match self.ordering {
+            PayloadTxOrdering::Fee => best,
+            PayloadTxOrdering::Fifo => Box::new(FifoTransactions::new(best)),
+        }
+    }
+
+    fn admit<T: Transaction>(
+        &self,
+        selection: &BlockSelection,
+        sender: Address,
+        tx: &T,
+    ) -> Result<(), PolicyViolation> {
+        if let Some(deny_list) = &self.deny_list {
+            if deny_list.contains(&sender) {
+                return Err(PolicyViolation::Denied(sender))
+            }
+            if let TxKind::Call(to) = tx.kind() {
+                if deny_list.contains(&to) {
+                    return Err(PolicyViolation::Denied(to))
+                }
+            }
+        }
+
+        if let Some(max) = self.max_txs_per_sender {
+            if selection.txs_by_sender.get(&sender).copied().unwrap_or_default() >= max {
+                return Err(PolicyViolation::SenderCapReached(sender, max))
+            }
+        }
+
+        if self.reserved_system_gas > 0 && !self.system_senders.contains(&sender) {
+            let available = selection
+                .gas_limit
+                .saturating_sub(self.reserved_system_gas)
+                .saturating_sub(selection.gas_used);
+            if tx.gas_limit() > available {
+                return Err(PolicyViolation::ReservedGas { gas_limit: tx.gas_limit(), available })
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Deny-list of addresses read from a file, one address per line, `#` starting a comment.
+///
+/// The file is read again when its modification time changes. It must be valid when loaded, later
+/// a file that can't be read or parsed keeps the previous list.
+#[derive(Debug, Clone)]
+pub struct DenyList {
+    file: WatchedFile,
+    addresses: Arc<RwLock<HashSet<Address>>>,
+}
+
+impl DenyList {
+    /// Loads the deny-list of the file at `path`.
+    pub fn load(path: PathBuf) -> eyre::Result<Self> {
+        let file = WatchedFile::new(path);
+        let addresses = read_deny_list(file.path())?;
+        info!(target: "payload_builder", path=%file.path().display(), len=addresses.len(), "loaded deny-list");
+        Ok(Self { file, addresses: Arc::new(RwLock::new(addresses)) })
+    }
+
+    /// Whether `address` is denied.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.addresses.read().unwrap().contains(address)
+    }
+
+    /// Reads the file again if it was modified since it was last read.
+    pub fn reload_if_changed(&self) {
+        let reloaded = self.file.reload_if_changed(|path| {
+            let addresses = read_deny_list(path)?;
+            info!(target: "payload_builder", path=%path.display(), len=addresses.len(), "loaded deny-list");
+            *self.addresses.write().unwrap() = addresses;
+            Ok(())
+        });
+        if let Err(err) = reloaded {
+            warn!(target: "payload_builder", path=%self.file.path().display(), %err, "failed to load deny-list")
+        }
+    }
+}
+
+fn read_deny_list(path: &Path) -> eyre::Result<HashSet<Address>> {
+    let content = std::fs::read_to_string(path)?;
+    parse_deny_list(&content).map_err(|err| eyre::eyre!(err))
+}
+
+fn parse_deny_list(content: &str) -> Result<HashSet<Address>, String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse::<Address>().map_err(|err| format!("invalid address {line}: {err}")))
+        .collect()
+}
+
+/// Pending transactions in the order they arrived in the pool.
+///
+/// A sender's transactions keep their nonce order: a sender comes next when its lowest nonce
+/// transaction is the earliest arrival among the senders' lowest nonce transactions.
+pub struct FifoTransactions<T: PoolTransaction> {
+    /// Remaining transactions of each sender, in nonce order
+    by_sender: HashMap<SenderId, VecDeque<Arc<ValidPoolTransaction<T>>>>,
+    /// Arrival of the next transaction of each sender
+    next: BTreeSet<(Instant, SenderId)>,
+    skip_blobs: bool,
+}
+
+impl<T: PoolTransaction> FifoTransactions<T> {
+    /// Collects the transactions yielded by `best`.
+    pub fn new(mut best: BestPoolTransactions<T>) -> Self {
+        best.no_updates();
+        let mut by_sender: HashMap<SenderId, VecDeque<_>> = HashMap::new();
+        for tx in best {
+            by_sender.entry(tx.sender_id()).or_default().push_back(tx);
+        }
+        let next = by_sender
+            .iter()
+            .filter_map(|(sender, txs)| Some((txs.front()?.timestamp, *sender)))
+            .collect();
+        Self { by_sender, next, skip_blobs: false }
+    }
+
+    fn remove_sender(&mut self, sender: SenderId) {
+        if let Some(txs) = self.by_sender.remove(&sender) {
+            if let Some(tx) = txs.front() {
+                self.next.remove(&(tx.timestamp, sender));
+            }
+        }
+    }
+}
+
+impl<T: PoolTransaction> fmt::Debug for FifoTransactions<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FifoTransactions")
+            .field("senders", &self.by_sender.len())
+            .field("skip_blobs", &self.skip_blobs)
+            .finish()
+    }
+}
+
+impl<T: PoolTransaction> Iterator for FifoTransactions<T> {
+    type Item = Arc<ValidPoolTransaction<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (_, sender) = self.next.pop_first()?;
+            let txs = self.by_sender.get_mut(&sender)?;
+            let tx = txs.pop_front()?;
+            if self.skip_blobs && tx.is_eip4844() {
+                // the later nonces of the sender can't be included without it
+                self.by_sender.remove(&sender);
+                continue
+            }
+            match txs.front() {
+                Some(next) => {
+                    self.next.insert((next.timestamp, sender));
+                }
+                None => {
+                    self.by_sender.remove(&sender);
+                }
+            }
+            return Some(tx)
+        }
+    }
+}
+
+impl<T: PoolTransaction> BestTransactions for FifoTransactions<T> {
+    fn mark_invalid(&mut self, transaction: &Self::Item, _kind: InvalidPoolTransactionError) {
+        self.remove_sender(transaction.sender_id());
+    }
+
+    fn no_updates(&mut self) {}
+
+    fn set_skip_blobs(&mut self, skip_blobs: bool) {
+        self.skip_blobs = skip_blobs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watched_file::touch;
+
+    #[test]
+    fn parses_deny_list() {
+        let content = format!(
+            "# denied\n{}\n\n  {} # trailing comment\n",
+            Address::with_last_byte(1),
+            Address::with_last_byte(2)
+        );
+        let addresses = parse_deny_list(&content).unwrap();
+        assert_eq!(
+            addresses,
+            HashSet::from([Address::with_last_byte(1), Address::with_last_byte(2)])
+        );
+        assert!(parse_deny_list("0x1234").is_err());
+    }
+
+    #[test]
+    fn reloads_changed_deny_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deny.txt");
+        assert!(DenyList::load(path.clone()).is_err());
+        std::fs::write(&path, "0x1234").unwrap();
+        assert!(DenyList::load(path.clone()).is_err());
+        std::fs::write(&path, Address::with_last_byte(1).to_string()).unwrap();
+
+        let deny_list = DenyList::load(path.clone()).unwrap();
+        assert!(deny_list.contains(&Address::with_last_byte(1)));
+
+        std::fs::write(&path, Address::with_last_byte(2).to_string()).unwrap();
+        touch(&path);
+        deny_list.reload_if_changed();
+        assert!(!deny_list.contains(&Address::with_last_byte(1)));
+        assert!(deny_list.contains(&Address::with_last_byte(2)));
+    }
+}
@@ -0,0 +1,624 @@
+//! Private transactions, handed directly to the in-turn signers instead of being gossiped.
+//!
+//! Nodes speak the `n42p` RLPx subprotocol next to `eth`. Each side opens the connection with a
+//! hello carrying, if the node is an `APos` signer, a signature of its signer key over the ids of
+//! both peers, which tells the remote which signer it is connected to. Private transactions are
+//! then only sent to the peers proven to be one of the signers they are meant for, and a signer
+//! keeps them aside from its pool until they are included or expire.
+//!
+//! A signer only takes private transactions from peers proven to be authorized signers, and keeps
+//! those the validator of its pool accepts against the state at its head.
+
+use crate::AdmissionControl;
+use alloy_consensus::{Transaction as _, Typed2718};
+use alloy_eips::{Decodable2718, Encodable2718};
+use alloy_primitives::{
+    bytes::{Buf, BufMut, BytesMut},
+    keccak256, Address, BlockNumber, Signature, TxHash, B256,
+};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use futures_util::{future::BoxFuture, FutureExt, Stream, StreamExt};
+use reth_eth_wire::{
+    capability::SharedCapabilities, multiplex::ProtocolConnection, protocol::Protocol, Capability,
+};
+use reth_ethereum_primitives::TransactionSigned;
+use reth_network::{
+    protocol::{ConnectionHandler, OnNotSupported, ProtocolHandler},
+    signer_enr::SignerSet,
+};
+use reth_network_api::{Direction, PeerId};
+use reth_primitives_traits::{Recovered, SignedTransaction};
+use reth_transaction_pool::{
+    EthPooledTransaction, PoolTransaction, TransactionOrigin, TransactionValidationOutcome,
+    TransactionValidator,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
+use tracing::{debug, trace};
+
+/// Number of blocks a private transaction is kept for if the sender doesn't say otherwise.
+pub const DEFAULT_PRIVATE_TX_LIFETIME: u64 = 20;
+
+/// Maximum number of blocks a private transaction is kept for.
+pub const MAX_PRIVATE_TX_LIFETIME: u64 = 256;
+
+/// Maximum number of private transactions a signer keeps.
+const MAX_PRIVATE_TXS: usize = 4096;
+
+/// Prefix of the message signed by the signer key in the hello.
+const HELLO_DOMAIN: &[u8] = b"n42-private-tx";
+
+/// Private transactions kept for the blocks of this node, and the connected peers they can be
+/// handed to.
+#[derive(Debug, Clone, Default)]
+pub struct PrivateTransactions {
+    inner: Arc<PrivateTransactionsInner>,
+}
+
+#[derive(Debug, Default)]
+struct PrivateTransactionsInner {
+    /// The signer of this node, if it is one.
+    local_signer: OnceLock<Address>,
+    /// Number of the canonical head.
+    head: AtomicU64,
+    /// Validates the received transactions, with the validator of the pool.
+    validator: OnceLock<PoolValidator>,
+    /// The admission rules of the pool, if any.
+    admission: OnceLock<AdmissionControl>,
+    /// Transactions to include in the blocks of this node, by hash.
+    pending: Mutex<HashMap<TxHash, PendingPrivateTransaction>>,
+    /// The peers speaking the protocol.
+    peers: Mutex<HashMap<PeerId, PrivateTxPeer>>,
+    /// Id of the next connection, telling a reconnected peer apart from its old connection.
+    next_connection_id: AtomicU64,
+}
+
+/// Validates a transaction against the state at the head, as the pool would.
+#[derive(Clone)]
+struct PoolValidator(
+    Arc<
+        dyn Fn(Recovered<TransactionSigned>) -> BoxFuture<'static, Result<(), String>>
+            + Send
+            + Sync,
+    >,
+);
+
+impl fmt::Debug for PoolValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolValidator").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct PendingPrivateTransaction {
+    tx: Recovered<TransactionSigned>,
+    /// Last block the transaction may be included in.
+    expires_at: BlockNumber,
+}
+
+#[derive(Debug)]
+struct PrivateTxPeer {
+    connection_id: u64,
+    /// The signer the peer proved to hold the key of.
+    signer: Option<Address>,
+    to_connection: mpsc::UnboundedSender<BytesMut>,
+}
+
+impl PrivateTransactions {
+    /// Returns the signer of this node, if it is one.
+    pub fn local_signer(&self) -> Option<Address> {
+        self.inner.local_signer.get().copied()
+    }
+
+    /// Validates the received transactions with `validator`, the validator of the pool.
+    ///
+    /// Received transactions are kept without validation until it is set.
+    pub fn set_validator<V>(&self, validator: V)
+    where
+        V: TransactionValidator<Transaction = EthPooledTransaction> + 'static,
+    {
+        let validator = Arc::new(validator);
+        let validate = move |tx: Recovered<TransactionSigned>| {
+            let validator = validator.clone();
+            async move {
+                let tx =
+                    EthPooledTransaction::try_from_consensus(tx).map_err(|err| err.to_string())?;
+                match validator.validate_transaction(TransactionOrigin::Private, tx).await {
+                    TransactionValidationOutcome::Valid { .. } => Ok(()),
+                    TransactionValidationOutcome::Invalid(_, err) => Err(err.to_string()),
+                    TransactionValidationOutcome::Error(_, err) => Err(err.to_string()),
+                }
+            }
+            .boxed()
+        };
+        self.inner.validator.set(PoolValidator(Arc::new(validate))).ok();
+    }
+
+    /// Checks the private transactions against the admission rules of the pool.
+    pub fn set_admission_control(&self, admission: AdmissionControl) {
+        self.inner.admission.set(admission).ok();
+    }
+
+    /// Returns the admission rules of the pool, if any.
+    pub fn admission_control(&self) -> Option<&AdmissionControl> {
+        self.inner.admission.get()
+    }
+
+    /// Returns the number of the canonical head.
+    pub fn head(&self) -> BlockNumber {
+        self.inner.head.load(Ordering::Relaxed)
+    }
+
+    /// Sets the canonical head to block `number`, dropping the transactions expired with it.
+    pub fn on_new_head(&self, number: BlockNumber) {
+        self.inner.head.store(number, Ordering::Relaxed);
+        self.inner.pending.lock().unwrap().retain(|_, tx| tx.expires_at > number);
+    }
+
+    /// Keeps `tx` for the blocks of this node up to block `expires_at`, returns `false` if it was
+    /// already known or too many transactions are kept.
+    pub fn insert(&self, tx: Recovered<TransactionSigned>, expires_at: BlockNumber) -> bool {
+        let mut pending = self.inner.pending.lock().unwrap();
+        if pending.len() >= MAX_PRIVATE_TXS || pending.contains_key(tx.tx_hash()) {
+            return false
+        }
+        pending.insert(*tx.tx_hash(), PendingPrivateTransaction { tx, expires_at });
+        true
+    }
+
+    /// Returns the transactions that may be included in block `number`, by sender and nonce.
+    ///
+    /// Expired transactions are dropped.
+    pub fn pending(&self, number: BlockNumber) -> Vec<Recovered<TransactionSigned>> {
+        let mut pending = self.inner.pending.lock().unwrap();
+        pending.retain(|_, tx| tx.expires_at >= number);
+        let mut txs: Vec<_> = pending.values().map(|pending| pending.tx.clone()).collect();
+        txs.sort_unstable_by_key(|tx| (tx.signer(), tx.nonce()));
+        txs
+    }
+
+    /// Whether the transaction is kept for the blocks of this node.
+    pub fn contains(&self, hash: &TxHash) -> bool {
+        self.inner.pending.lock().unwrap().contains_key(hash)
+    }
+
+    /// Drops the transaction, once included or found invalid.
+    pub fn remove(&self, hash: &TxHash) {
+        self.inner.pending.lock().unwrap().remove(hash);
+    }
+
+    /// Number of transactions kept for the blocks of this node.
+    pub fn len(&self) -> usize {
+        self.inner.pending.lock().unwrap().len()
+    }
+
+    /// Whether no transaction is kept for the blocks of this node.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hands `tx` to the `signers`, keeping it locally if this node is one of them, and returns
+    /// the signers that got it.
+    pub fn submit(
+        &self,
+        tx: Recovered<TransactionSigned>,
+        expires_at: BlockNumber,
+        signers: &HashSet<Address>,
+    ) -> HashSet<Address> {
+        let mut reached = HashSet::new();
+        if let Some(local) = self.local_signer().filter(|local| signers.contains(local)) {
+            self.insert(tx.clone(), expires_at);
+            reached.insert(local);
+        }
+
+        let message = PrivateTxMessage::Transaction { expires_at, tx: tx.into_inner() }.encoded();
+        let peers = self.inner.peers.lock().unwrap();
+        for (peer_id, peer) in peers.iter() {
+            let Some(signer) = peer.signer.filter(|signer| signers.contains(signer)) else {
+                continue
+            };
+            if peer.to_connection.send(message.clone()).is_ok() {
+                trace!(target: "n42::private_tx", %peer_id, %signer, "sent private transaction");
+                reached.insert(signer);
+            }
+        }
+        reached
+    }
+
+    /// Keeps `tx`, received from a signer, up to block `expires_at` once the validator of the pool
+    /// accepted it.
+    fn insert_valid(&self, tx: Recovered<TransactionSigned>, expires_at: BlockNumber) {
+        let Some(validator) = self.inner.validator.get().cloned() else {
+            self.insert(tx, expires_at);
+            return
+        };
+        let this = self.clone();
+        tokio::spawn(async move {
+            match (validator.0)(tx.clone()).await {
+                Ok(()) => {
+                    this.insert(tx, expires_at);
+                }
+                Err(err) => {
+                    trace!(target: "n42::private_tx", hash=%tx.tx_hash(), %err, "dropped invalid private transaction");
+                }
+            }
+        });
+    }
+
+    fn register(&self, peer_id: PeerId, to_connection: mpsc::UnboundedSender<BytesMut>) -> u64 {
+        let connection_id = self.inner.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .peers
+            .lock()
+            .unwrap()
+            .insert(peer_id, PrivateTxPeer { connection_id, signer: None, to_connection });
+        connection_id
+    }
+
+    fn set_peer_signer(&self, peer_id: &PeerId, connection_id: u64, signer: Address) {
+        if let Some(peer) = self.inner.peers.lock().unwrap().get_mut(peer_id) {
+            if peer.connection_id == connection_id {
+                peer.signer = Some(signer);
+            }
+        }
+    }
+
+    fn unregister(&self, peer_id: &PeerId, connection_id: u64) {
+        let mut peers = self.inner.peers.lock().unwrap();
+        if peers.get(peer_id).is_some_and(|peer| peer.connection_id == connection_id) {
+            peers.remove(peer_id);
+        }
+    }
+}
+
+/// A message of the `n42p` protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PrivateTxMessage {
+    /// First message on a connection, with the signature of the signer key of the sender if it
+    /// is a signer.
+    Hello { signature: Option<Signature> },
+    /// A private transaction to include up to block `expires_at`.
+    Transaction { expires_at: BlockNumber, tx: TransactionSigned },
+}
+
+impl PrivateTxMessage {
+    const HELLO: u8 = 0x00;
+    const TRANSACTION: u8 = 0x01;
+
+    /// Returns the protocol, with its two message ids.
+    const fn protocol() -> Protocol {
+        Protocol::new(Capability::new_static("n42p", 1), 2)
+    }
+
+    fn encoded(&self) -> BytesMut {
+        let mut buf = BytesMut::new();
+        match self {
+            Self::Hello { signature } => {
+                buf.put_u8(Self::HELLO);
+                if let Some(signature) = signature {
+                    buf.put_slice(&signature.as_bytes());
+                }
+            }
+            Self::Transaction { expires_at, tx } => {
+                buf.put_u8(Self::TRANSACTION);
+                buf.put_u64(*expires_at);
+                tx.encode_2718(&mut buf);
+            }
+        }
+        buf
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        if buf.is_empty() {
+            return None
+        }
+        let id = buf.get_u8();
+        match id {
+            Self::HELLO if buf.is_empty() => Some(Self::Hello { signature: None }),
+            Self::HELLO => {
+                let signature = Signature::from_raw(buf).ok()?;
+                Some(Self::Hello { signature: Some(signature) })
+            }
+            Self::TRANSACTION if buf.len() > 8 => {
+                let expires_at = buf.get_u64();
+                let tx = TransactionSigned::decode_2718(buf).ok()?;
+                buf.is_empty().then_some(Self::Transaction { expires_at, tx })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Hash signed in the hello of `sender` to `receiver`, binding the proof to the connection.
+fn hello_hash(sender: &PeerId, receiver: &PeerId) -> B256 {
+    keccak256([HELLO_DOMAIN, sender.as_slice(), receiver.as_slice()].concat())
+}
+
+/// Handler of the `n42p` protocol.
+#[derive(Debug)]
+pub struct PrivateTxProtocolHandler {
+    local_peer_id: PeerId,
+    signer_key: Option<PrivateKeySigner>,
+    signers: SignerSet,
+    private_txs: PrivateTransactions,
+}
+
+impl PrivateTxProtocolHandler {
+    /// Creates the handler of the node `local_peer_id`, proving its signer with `signer_key` if
+    /// it is one and taking private transactions from the peers proven to be one of `signers`.
+    pub fn new(
+        local_peer_id: PeerId,
+        signer_key: Option<PrivateKeySigner>,
+        signers: SignerSet,
+        private_txs: PrivateTransactions,
+    ) -> Self {
+        if let Some(key) = &signer_key {
+            private_txs.inner.local_signer.set(key.address()).ok();
+        }
+        Self { local_peer_id, signer_key, signers, private_txs }
+    }
+
+    fn connection_handler(&self) -> PrivateTxConnectionHandler {
+        PrivateTxConnectionHandler {
+            local_peer_id: self.local_peer_id,
+            signer_key: self.signer_key.clone(),
+            signers: self.signers.clone(),
+            private_txs: self.private_txs.clone(),
+        }
+    }
+}
+
+impl ProtocolHandler for PrivateTxProtocolHandler {
+    type ConnectionHandler = PrivateTxConnectionHandler;
+
+    fn on_incoming(&self, _socket_addr: SocketAddr) -> Option<Self::ConnectionHandler> {
+        Some(self.connection_handler())
+    }
+
+    fn on_outgoing(
+        &self,
+        _socket_addr: SocketAddr,
+        _peer_id: PeerId,
+    ) -> Option<Self::ConnectionHandler> {
+        Some(self.connection_handler())
+    }
+}
+
+/// Sets up `n42p` connections.
+#[derive(Debug)]
+pub struct PrivateTxConnectionHandler {
+    local_peer_id: PeerId,
+    signer_key: Option<PrivateKeySigner>,
+    signers: SignerSet,
+    private_txs: PrivateTransactions,
+}
+
+impl ConnectionHandler for PrivateTxConnectionHandler {
+    type Connection = PrivateTxConnection;
+
+    fn protocol(&self) -> Protocol {
+        PrivateTxMessage::protocol()
+    }
+
+    fn on_unsupported_by_peer(
+        self,
+        _supported: &SharedCapabilities,
+        _direction: Direction,
+        _peer_id: PeerId,
+    ) -> OnNotSupported {
+        OnNotSupported::KeepAlive
+    }
+
+    fn into_connection(
+        self,
+        _direction: Direction,
+        peer_id: PeerId,
+        conn: ProtocolConnection,
+    ) -> Self::Connection {
+        let signature = self
+            .signer_key
+            .as_ref()
+            .and_then(|key| key.sign_hash_sync(&hello_hash(&self.local_peer_id, &peer_id)).ok());
+        let (tx, rx) = mpsc::unbounded_channel();
+        let connection_id = self.private_txs.register(peer_id, tx);
+        PrivateTxConnection {
+            conn,
+            peer_id,
+            local_peer_id: self.local_peer_id,
+            connection_id,
+            hello: Some(PrivateTxMessage::Hello { signature }),
+            peer_signer: None,
+            outgoing: rx,
+            signers: self.signers,
+            private_txs: self.private_txs,
+        }
+    }
+}
+
+/// A `n42p` connection to a peer.
+#[derive(Debug)]
+pub struct PrivateTxConnection {
+    conn: ProtocolConnection,
+    peer_id: PeerId,
+    local_peer_id: PeerId,
+    connection_id: u64,
+    /// Hello to send first.
+    hello: Option<PrivateTxMessage>,
+    /// The signer the peer proved to hold the key of.
+    peer_signer: Option<Address>,
+    /// Messages to send to the peer.
+    outgoing: mpsc::UnboundedReceiver<BytesMut>,
+    /// The authorized signers at the head.
+    signers: SignerSet,
+    private_txs: PrivateTransactions,
+}
+
+impl PrivateTxConnection {
+    /// Handles a message of the peer, returns `false` if the peer misbehaved.
+    fn on_message(&mut self, message: PrivateTxMessage) -> bool {
+        match message {
+            PrivateTxMessage::Hello { signature: None } => true,
+            PrivateTxMessage::Hello { signature: Some(signature) } => {
+                let hash = hello_hash(&self.peer_id, &self.local_peer_id);
+                let Ok(signer) = signature.recover_address_from_prehash(&hash) else {
+                    return false
+                };
+                debug!(target: "n42::private_tx", peer_id=%self.peer_id, %signer, "peer proved signer");
+                self.peer_signer = Some(signer);
+                self.private_txs.set_peer_signer(&self.peer_id, self.connection_id, signer);
+                true
+            }
+            PrivateTxMessage::Transaction { expires_at, tx } => {
+                // only signers build blocks, other nodes have no use for the transaction
+                if self.private_txs.local_signer().is_none() || tx.is_eip4844() {
+                    return true
+                }
+                // private transactions are only handed from signer to signer
+                let Some(signer) = self.peer_signer.filter(|signer| self.signers.is_signer(signer))
+                else {
+                    trace!(target: "n42::private_tx", peer_id=%self.peer_id, "private transaction from a peer that isn't a proven signer");
+                    return true
+                };
+                let Ok(tx) = tx.try_into_recovered() else { return false };
+                let head = self.private_txs.head();
+                let expires_at = expires_at.min(head + MAX_PRIVATE_TX_LIFETIME);
+                if expires_at <= head {
+                    return true
+                }
+                trace!(target: "n42::private_tx", peer_id=%self.peer_id, %signer, hash=%tx.tx_hash(), expires_at, "received private transaction");
+                self.private_txs.insert_valid(tx, expires_at);
+                true
+            }
+        }
+    }
+}
+
+impl Stream for PrivateTxConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(hello) = this.hello.take() {
+            return Poll::Ready(Some(hello.encoded()))
+        }
+
+        loop {
+            if let Poll::Ready(Some(message)) = this.outgoing.poll_recv(cx) {
+                return Poll::Ready(Some(message))
+            }
+
+            let Poll::Ready(message) = this.conn.poll_next_unpin(cx) else { return Poll::Pending };
+            let Some(message) = message else { return Poll::Ready(None) };
+            let Some(message) = PrivateTxMessage::decode(&mut &message[..]) else {
+                debug!(target: "n42::private_tx", peer_id=%this.peer_id, "invalid message, disconnecting");
+                return Poll::Ready(None)
+            };
+            if !this.on_message(message) {
+                debug!(target: "n42::private_tx", peer_id=%this.peer_id, "bad message, disconnecting");
+                return Poll::Ready(None)
+            }
+        }
+    }
+}
+
+impl Drop for PrivateTxConnection {
+    fn drop(&mut self) {
+        self.private_txs.unregister(&self.peer_id, self.connection_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{SignableTransaction, TxLegacy};
+    use alloy_primitives::TxKind;
+
+    fn signed_tx(key: &PrivateKeySigner, nonce: u64) -> Recovered<TransactionSigned> {
+        let tx = TxLegacy {
+            nonce,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::with_last_byte(1)),
+            ..Default::default()
+        };
+        let signature = key.sign_hash_sync(&tx.signature_hash()).unwrap();
+        let signed: TransactionSigned = tx.into_signed(signature).into();
+        Recovered::new_unchecked(signed, key.address())
+    }
+
+    #[test]
+    fn encodes_messages() {
+        let key = PrivateKeySigner::random();
+        let hash = hello_hash(&PeerId::random(), &PeerId::random());
+        let messages = [
+            PrivateTxMessage::Hello { signature: None },
+            PrivateTxMessage::Hello { signature: Some(key.sign_hash_sync(&hash).unwrap()) },
+            PrivateTxMessage::Transaction { expires_at: 42, tx: signed_tx(&key, 0).into_inner() },
+        ];
+        for message in messages {
+            let encoded = message.encoded();
+            assert_eq!(PrivateTxMessage::decode(&mut &encoded[..]), Some(message));
+        }
+        assert_eq!(PrivateTxMessage::decode(&mut &[0x02][..]), None);
+    }
+
+    #[test]
+    fn expires_private_transactions() {
+        let private_txs = PrivateTransactions::default();
+        let key = PrivateKeySigner::random();
+        assert!(private_txs.insert(signed_tx(&key, 1), 10));
+        assert!(private_txs.insert(signed_tx(&key, 0), 12));
+        assert!(!private_txs.insert(signed_tx(&key, 0), 12));
+
+        let pending = private_txs.pending(10);
+        assert_eq!(pending.iter().map(|tx| tx.nonce()).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(private_txs.pending(11).len(), 1);
+        assert_eq!(private_txs.len(), 1);
+
+        private_txs.on_new_head(12);
+        assert!(private_txs.is_empty());
+        assert_eq!(private_txs.head(), 12);
+    }
+
+    #[test]
+    fn submits_to_proven_signers() {
+        let local = PrivateKeySigner::random();
+        let private_txs = PrivateTransactions::default();
+        let _handler = PrivateTxProtocolHandler::new(
+            PeerId::random(),
+            Some(local.clone()),
+            SignerSet::new(HashSet::new()).1,
+            private_txs.clone(),
+        );
+
+        let (signer, other) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let peer_id = PeerId::random();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let connection_id = private_txs.register(peer_id, tx);
+        private_txs.set_peer_signer(&peer_id, connection_id, signer);
+
+        let sender = PrivateKeySigner::random();
+        let reached = private_txs.submit(signed_tx(&sender, 0), 5, &HashSet::from([other]));
+        assert!(reached.is_empty());
+        assert!(rx.try_recv().is_err());
+
+        let targets = HashSet::from([signer, local.address()]);
+        let reached = private_txs.submit(signed_tx(&sender, 0), 5, &targets);
+        assert_eq!(reached, targets);
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(private_txs.len(), 1);
+
+        private_txs.unregister(&peer_id, connection_id);
+        assert_eq!(private_txs.submit(signed_tx(&sender, 1), 5, &targets).len(), 1);
+    }
+}
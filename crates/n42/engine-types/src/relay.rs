@@ -0,0 +1,439 @@
+//! Client of the block builder relays a signer takes its blocks from.
+//!
+//! N42 relays serve the builder API of the beacon chain (`/eth/v1/builder/*`) with the
+//! [relay types](alloy_rpc_types_beacon::relay), except that a proposer is known by its signer
+//! address instead of a BLS key: the address fills the first 20 bytes of the public key fields,
+//! and a secp256k1 signature the first 65 bytes of the signature fields. The slot of a block is
+//! its number.
+//!
+//! A relay is configured as `http://<relay address>@<host>`, and its bids must be signed by the
+//! key of that address.
+//!
+//! A bid is requested once per payload job, in the background, so a slow relay never holds up
+//! the local build; the delivered block is picked up by the next build attempt of the job.
+
+use crate::policy::PolicyViolation;
+use alloy_primitives::{keccak256, Address, Signature, B256};
+use alloy_rpc_types_beacon::{
+    relay::{
+        BidTrace, SignedBidTrace, SubmitBlockRequest, ValidatorRegistration,
+        ValidatorRegistrationMessage,
+    },
+    BlsPublicKey, BlsSignature,
+};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use futures_util::future::join_all;
+use reqwest::{Response, StatusCode, Url};
+use reth_node_core::args::PayloadBuilderArgs;
+use reth_payload_primitives::PayloadId;
+use reth_tasks::TaskExecutor;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+use tracing::{debug, warn};
+
+/// Endpoint registering the fee recipient of a proposer.
+const REGISTER_VALIDATOR_PATH: &str = "/eth/v1/builder/validators";
+/// Endpoint returning the best bid, followed by `/{slot}/{parent_hash}/{pubkey}`.
+const GET_HEADER_PATH: &str = "/eth/v1/builder/header";
+/// Endpoint trading a signed bid for its block.
+const GET_PAYLOAD_PATH: &str = "/eth/v1/builder/blinded_blocks";
+/// Number of payload jobs whose bids are kept.
+const MAX_TRACKED_JOBS: usize = 8;
+/// Length of a secp256k1 signature with its recovery id.
+const SIGNATURE_LENGTH: usize = 65;
+
+/// Errors talking to a relay.
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    /// The request failed.
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// The relay answered with an error status.
+    #[error("relay {relay} answered with status {status}")]
+    Status {
+        /// URL of the relay
+        relay: String,
+        /// Status of the response
+        status: StatusCode,
+    },
+    /// The relay URL does not name the relay address.
+    #[error("relay URL must be http(s)://<relay address>@<host>, got {0}")]
+    InvalidUrl(String),
+    /// The bid is not for the requested block.
+    #[error("bid with a different {0} than requested")]
+    BidMismatch(&'static str),
+    /// The bid is not signed by the key of the relay.
+    #[error("bid not signed by relay {0}")]
+    BidSignature(Address),
+    /// The block of a bid was rejected.
+    #[error("invalid relay block: {0}")]
+    InvalidPayload(&'static str),
+    /// A transaction of the block of a bid is not admitted by the transaction policy.
+    #[error("relay block transaction rejected: {0}")]
+    Policy(#[from] PolicyViolation),
+    /// Signing a relay message failed.
+    #[error(transparent)]
+    Signer(#[from] alloy_signer::Error),
+}
+
+/// A relay configured with `--builder.relays`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relay {
+    /// URL of the relay API
+    pub url: String,
+    /// Address of the key the relay signs its bids with
+    pub address: Address,
+}
+
+impl FromStr for Relay {
+    type Err = RelayError;
+
+    /// Parses `http(s)://<relay address>@<host>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RelayError::InvalidUrl(s.to_string());
+        let mut url = Url::parse(s).map_err(|_| invalid())?;
+        let address = url.username().parse().map_err(|_| invalid())?;
+        url.set_username("").map_err(|_| invalid())?;
+        Ok(Self { url: url.as_str().trim_end_matches('/').to_string(), address })
+    }
+}
+
+/// Block a signer asks the relays to bid for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BidRequest {
+    /// Payload job building the block
+    pub id: PayloadId,
+    /// Number of the block
+    pub slot: u64,
+    /// Hash of the parent block
+    pub parent_hash: B256,
+    /// Recipient of the block fees
+    pub fee_recipient: Address,
+    /// Gas limit of the block
+    pub gas_limit: u64,
+}
+
+/// Bid of the relays for a payload job.
+#[derive(Debug)]
+enum RelayBid {
+    /// The relays are still asked.
+    Pending(oneshot::Receiver<Option<SubmitBlockRequest>>),
+    /// The block of the best bid, if any was delivered and not rejected.
+    Ready(Option<Arc<SubmitBlockRequest>>),
+}
+
+/// Client of the relays configured with `--builder.relays`.
+#[derive(Debug, Clone)]
+pub struct RelayClient {
+    inner: Arc<RelayClientInner>,
+}
+
+#[derive(Debug)]
+struct RelayClientInner {
+    relays: Vec<Relay>,
+    http: reqwest::Client,
+    signer: PrivateKeySigner,
+    timeout: Duration,
+    executor: TaskExecutor,
+    /// Fee recipient and gas limit last registered with all relays
+    registered: Mutex<Option<(Address, u64)>>,
+    bids: Mutex<HashMap<PayloadId, RelayBid>>,
+}
+
+impl RelayClient {
+    /// Creates a client of `relays` signing its messages with `signer`.
+    pub fn new(
+        relays: Vec<Relay>,
+        signer: PrivateKeySigner,
+        timeout: Duration,
+        executor: TaskExecutor,
+    ) -> Self {
+        Self {
+            inner: Arc::new(RelayClientInner {
+                relays,
+                http: reqwest::Client::new(),
+                signer,
+                timeout,
+                executor,
+                registered: Mutex::new(None),
+                bids: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Creates the client configured by `args`, if any relay is.
+    ///
+    /// Relays are only used by a node holding its signer key.
+    pub fn from_args(
+        args: &PayloadBuilderArgs,
+        signer_key: Option<B256>,
+        executor: TaskExecutor,
+    ) -> eyre::Result<Option<Self>> {
+        if args.relays.is_empty() {
+            return Ok(None)
+        }
+        let Some(signer_key) = signer_key else {
+            warn!(target: "payload_builder::relay", "relays are ignored without a signer key");
+            return Ok(None)
+        };
+        let relays = args.relays.iter().map(|relay| relay.parse()).collect::<Result<_, _>>()?;
+        let signer = PrivateKeySigner::from_bytes(&signer_key)?;
+        Ok(Some(Self::new(relays, signer, args.relay_timeout, executor)))
+    }
+
+    /// Public key field identifying this signer to the relays.
+    pub fn pubkey(&self) -> BlsPublicKey {
+        signer_pubkey(self.inner.signer.address())
+    }
+
+    /// Returns the block of the best bid for `request`, once the relays delivered it.
+    ///
+    /// The first call for a payload job asks the relays in the background and returns `None`.
+    pub fn poll_bid(&self, request: BidRequest) -> Option<Arc<SubmitBlockRequest>> {
+        let mut bids = self.inner.bids.lock().unwrap();
+        if !bids.contains_key(&request.id) && bids.len() >= MAX_TRACKED_JOBS {
+            bids.clear();
+        }
+        let bid = bids.entry(request.id).or_insert_with(|| self.spawn_fetch(request));
+        if let RelayBid::Pending(rx) = bid {
+            match rx.try_recv() {
+                Ok(block) => *bid = RelayBid::Ready(block.map(Arc::new)),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Closed) => *bid = RelayBid::Ready(None),
+            }
+        }
+        match bid {
+            RelayBid::Ready(block) => block.clone(),
+            RelayBid::Pending(_) => None,
+        }
+    }
+
+    /// Drops the block delivered for the payload job `id`, so the local blocks are used.
+    pub fn reject(&self, id: PayloadId) {
+        self.inner.bids.lock().unwrap().insert(id, RelayBid::Ready(None));
+    }
+
+    fn spawn_fetch(&self, request: BidRequest) -> RelayBid {
+        let (tx, rx) = oneshot::channel();
+        let client = self.clone();
+        self.inner.executor.spawn(Box::pin(async move {
+            let block = match tokio::time::timeout(client.inner.timeout, client.fetch(request)).await
+            {
+                Ok(Ok(block)) => block,
+                Ok(Err(err)) => {
+                    warn!(target: "payload_builder::relay", slot = request.slot, %err, "failed to get a relay block");
+                    None
+                }
+                Err(_) => {
+                    debug!(target: "payload_builder::relay", slot = request.slot, "relays did not deliver a block in time");
+                    None
+                }
+            };
+            let _ = tx.send(block);
+        }));
+        RelayBid::Pending(rx)
+    }
+
+    /// Asks every relay for a bid and unblinds the most valuable one.
+    async fn fetch(&self, request: BidRequest) -> Result<Option<SubmitBlockRequest>, RelayError> {
+        self.register(request.fee_recipient, request.gas_limit).await;
+
+        let bids = join_all(
+            self.inner.relays.iter().map(|relay| async move { (relay, self.get_bid(relay, &request).await) }),
+        )
+        .await;
+        let best = bids
+            .into_iter()
+            .filter_map(|(relay, bid)| match bid {
+                Ok(bid) => bid.map(|bid| (relay, bid)),
+                Err(err) => {
+                    debug!(target: "payload_builder::relay", relay = %relay.url, %err, "no bid from relay");
+                    None
+                }
+            })
+            .max_by_key(|(_, bid)| bid.message.value);
+        let Some((relay, bid)) = best else { return Ok(None) };
+        debug!(target: "payload_builder::relay", relay = %relay.url, slot = request.slot, value = %bid.message.value, "unblinding the best bid");
+        self.get_payload(&relay.url, bid).await.map(Some)
+    }
+
+    /// Registers the fee recipient with the relays it is not registered with yet.
+    async fn register(&self, fee_recipient: Address, gas_limit: u64) {
+        if *self.inner.registered.lock().unwrap() == Some((fee_recipient, gas_limit)) {
+            return
+        }
+
+        let message = ValidatorRegistrationMessage {
+            fee_recipient,
+            gas_limit,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            pubkey: self.pubkey(),
+        };
+        let registration = match self.sign(registration_signing_hash(&message)) {
+            Ok(signature) => ValidatorRegistration { message, signature },
+            Err(err) => {
+                warn!(target: "payload_builder::relay", %err, "failed to sign the registration");
+                return
+            }
+        };
+
+        let results = join_all(self.inner.relays.iter().map(|relay| {
+            let request = self
+                .inner
+                .http
+                .post(format!("{}{REGISTER_VALIDATOR_PATH}", relay.url))
+                .json(std::slice::from_ref(&registration));
+            async move { (&relay.url, request.send().await.map_err(RelayError::from)) }
+        }))
+        .await;
+        let mut registered = true;
+        for (relay, response) in results {
+            if let Err(err) = response.and_then(|response| error_for_status(relay, response)) {
+                warn!(target: "payload_builder::relay", %relay, %err, "failed to register the fee recipient");
+                registered = false;
+            }
+        }
+        if registered {
+            *self.inner.registered.lock().unwrap() = Some((fee_recipient, gas_limit));
+        }
+    }
+
+    /// Returns the bid of `relay` for `request`, if it has one.
+    async fn get_bid(
+        &self,
+        relay: &Relay,
+        request: &BidRequest,
+    ) -> Result<Option<SignedBidTrace>, RelayError> {
+        let url = format!(
+            "{}{GET_HEADER_PATH}/{}/{}/{}",
+            relay.url,
+            request.slot,
+            request.parent_hash,
+            self.pubkey()
+        );
+        let response = self.inner.http.get(url).send().await?;
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None)
+        }
+        let bid: SignedBidTrace = error_for_status(&relay.url, response)?.json().await?;
+
+        let trace = &bid.message;
+        if trace.slot != request.slot {
+            return Err(RelayError::BidMismatch("slot"))
+        }
+        if trace.parent_hash != request.parent_hash {
+            return Err(RelayError::BidMismatch("parent hash"))
+        }
+        if trace.proposer_fee_recipient != request.fee_recipient {
+            return Err(RelayError::BidMismatch("fee recipient"))
+        }
+        if trace.proposer_pubkey != self.pubkey() {
+            return Err(RelayError::BidMismatch("proposer"))
+        }
+        if trace.gas_limit != request.gas_limit {
+            return Err(RelayError::BidMismatch("gas limit"))
+        }
+
+        let hash = relay_bid_signing_hash(trace);
+        let signer = Signature::from_raw(&bid.signature.0[..SIGNATURE_LENGTH])
+            .ok()
+            .and_then(|signature| signature.recover_address_from_prehash(&hash).ok());
+        if signer != Some(relay.address) {
+            return Err(RelayError::BidSignature(relay.address))
+        }
+        Ok(Some(bid))
+    }
+
+    /// Signs `bid` and trades it for its block.
+    async fn get_payload(
+        &self,
+        relay: &str,
+        bid: SignedBidTrace,
+    ) -> Result<SubmitBlockRequest, RelayError> {
+        let signed =
+            SignedBidTrace { signature: self.sign(bid_signing_hash(&bid.message))?, message: bid.message };
+        let response =
+            self.inner.http.post(format!("{relay}{GET_PAYLOAD_PATH}")).json(&signed).send().await?;
+        let block: SubmitBlockRequest = error_for_status(relay, response)?.json().await?;
+        if *block.bid_trace() != signed.message {
+            return Err(RelayError::BidMismatch("block"))
+        }
+        Ok(block)
+    }
+
+    fn sign(&self, hash: B256) -> Result<BlsSignature, RelayError> {
+        let signature = self.inner.signer.sign_hash_sync(&hash)?;
+        let mut padded = BlsSignature::ZERO;
+        padded.0[..SIGNATURE_LENGTH].copy_from_slice(&signature.as_bytes());
+        Ok(padded)
+    }
+}
+
+fn error_for_status(relay: &str, response: Response) -> Result<Response, RelayError> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(RelayError::Status { relay: relay.to_string(), status })
+    }
+    Ok(response)
+}
+
+/// Public key field identifying the signer `address` to a relay.
+pub fn signer_pubkey(address: Address) -> BlsPublicKey {
+    let mut pubkey = BlsPublicKey::ZERO;
+    pubkey.0[..Address::len_bytes()].copy_from_slice(address.as_slice());
+    pubkey
+}
+
+/// Hash a signer signs to register `message` with a relay.
+pub fn registration_signing_hash(message: &ValidatorRegistrationMessage) -> B256 {
+    keccak256(
+        [
+            message.fee_recipient.as_slice(),
+            &message.gas_limit.to_be_bytes(),
+            &message.timestamp.to_be_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+/// Hash a relay signs to offer the block of `bid`.
+pub fn relay_bid_signing_hash(bid: &BidTrace) -> B256 {
+    keccak256(
+        [
+            &bid.slot.to_be_bytes()[..],
+            bid.parent_hash.as_slice(),
+            bid.block_hash.as_slice(),
+            bid.proposer_fee_recipient.as_slice(),
+            &bid.gas_limit.to_be_bytes(),
+            &bid.gas_used.to_be_bytes(),
+            &bid.value.to_be_bytes::<32>(),
+        ]
+        .concat(),
+    )
+}
+
+/// Hash a signer signs to commit to the block of `bid`.
+pub fn bid_signing_hash(bid: &BidTrace) -> B256 {
+    keccak256([&bid.slot.to_be_bytes()[..], bid.block_hash.as_slice()].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relay_address() {
+        let address = Address::with_last_byte(1);
+        let relay: Relay = format!("http://{address}@127.0.0.1:18550/").parse().unwrap();
+        assert_eq!(relay, Relay { url: "http://127.0.0.1:18550".to_string(), address });
+
+        assert!("http://127.0.0.1:18550".parse::<Relay>().is_err());
+        assert!("http://relay@127.0.0.1:18550".parse::<Relay>().is_err());
+    }
+}
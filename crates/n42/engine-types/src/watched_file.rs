@@ -0,0 +1,89 @@
+//! Files read again when their modification time changes.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// A file whose contents are read again by [`Self::reload_if_changed`] once its modification time
+/// changes.
+#[derive(Debug, Clone)]
+pub(crate) struct WatchedFile {
+    path: PathBuf,
+    /// Modification time of the file when it was last read successfully
+    modified: Arc<Mutex<Option<SystemTime>>>,
+}
+
+impl WatchedFile {
+    /// Watches the file at `path`, about to be read for the first time.
+    pub(crate) fn new(path: PathBuf) -> Self {
+        let modified = std::fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+        Self { path, modified: Arc::new(Mutex::new(modified)) }
+    }
+
+    /// The path of the file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Calls `reload` with the path of the file if it was modified since it was last read, returns
+    /// whether it did.
+    ///
+    /// The modification time is only remembered if `reload` succeeds, so that a file that can't
+    /// be read or parsed is tried again on the next call. Concurrent calls are serialized.
+    pub(crate) fn reload_if_changed(
+        &self,
+        reload: impl FnOnce(&Path) -> eyre::Result<()>,
+    ) -> eyre::Result<bool> {
+        let modified = std::fs::metadata(&self.path).and_then(|meta| meta.modified())?;
+        let mut last_modified = self.modified.lock().unwrap();
+        if *last_modified == Some(modified) {
+            return Ok(false)
+        }
+        reload(&self.path)?;
+        *last_modified = Some(modified);
+        Ok(true)
+    }
+}
+
+/// Moves the modification time of the file at `path` a second past the current time, so that
+/// the change is noticed on coarse grained filesystems too.
+#[cfg(test)]
+pub(crate) fn touch(path: &Path) {
+    let modified = std::fs::metadata(path).unwrap().modified().unwrap().max(SystemTime::now());
+    let modified = modified + std::time::Duration::from_secs(1);
+    std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reloads_modified_files_until_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watched");
+        std::fs::write(&path, "a").unwrap();
+        let file = WatchedFile::new(path.clone());
+        let read = |expected: &'static str| {
+            move |path: &Path| {
+                let content = std::fs::read_to_string(path)?;
+                eyre::ensure!(content == expected, "unexpected content {content:?}");
+                Ok(())
+            }
+        };
+
+        assert!(!file.reload_if_changed(read("a")).unwrap());
+
+        std::fs::write(&path, "b").unwrap();
+        touch(&path);
+        // failed reloads are tried again
+        assert!(file.reload_if_changed(read("a")).is_err());
+        assert!(file.reload_if_changed(read("b")).unwrap());
+        assert!(!file.reload_if_changed(read("b")).unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(file.reload_if_changed(read("b")).is_err());
+    }
+}
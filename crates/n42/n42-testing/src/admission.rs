@@ -0,0 +1,80 @@
+//! Transactions submitted to a node with `--txpool.admission-rules`.
+#![cfg(test)]
+
+use crate::{
+    policy::{funded_chainspec, pooled_transfer, GWEI},
+    snapshot_test_utils::TesterAccountPool,
+    utils::{launch_node, node_config},
+};
+use alloy_eips::eip2718::Encodable2718;
+use alloy_primitives::{Address, Bytes, B256};
+use jsonrpsee::{
+    core::{client::ClientT, ClientError},
+    rpc_params,
+};
+use n42_engine_types::ADMISSION_RULES_RELOAD_INTERVAL;
+use reth::args::TxPoolArgs;
+use reth_tasks::TaskManager;
+use reth_transaction_pool::PoolTransaction;
+use std::time::SystemTime;
+
+#[tokio::test]
+async fn test_admission_rules() -> eyre::Result<()> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+    let mut accounts = TesterAccountPool::new();
+    let (a, b) = (accounts.address("A"), accounts.address("B"));
+    let denied_target = Address::with_last_byte(0xde);
+    let target = Address::with_last_byte(0xff);
+
+    let dir = tempfile::tempdir()?;
+    let rules = dir.path().join("admission.toml");
+    std::fs::write(
+        &rules,
+        format!(
+            "allowed_senders = [\"{a}\"]\ndenied_targets = [\"{denied_target}\"]\n\n\
+             [rate_limit]\nmax_txs = 2\nperiod_secs = 3600\n"
+        ),
+    )?;
+
+    let chainspec = funded_chainspec(&mut accounts, &["A", "B"]);
+    let chain_id = chainspec.chain.id();
+    let config = node_config(chainspec)
+        .with_txpool(TxPoolArgs { admission_rules: Some(rules.clone()), ..Default::default() });
+    let node = launch_node(&tasks, config).await?;
+    let client = node.rpc_server_handle().http_client().expect("HTTP RPC is enabled");
+
+    // submits the transfer over `eth_sendRawTransaction`, returning the message of the error
+    let mut submit = |sender: &str, nonce: u64, to: Address| {
+        let tx = pooled_transfer(&mut accounts, chain_id, sender, nonce, GWEI, to).unwrap();
+        let raw = Bytes::from(tx.clone_into_consensus().into_inner().encoded_2718());
+        let client = client.clone();
+        async move {
+            match client.request::<B256, _>("eth_sendRawTransaction", rpc_params![raw]).await {
+                Ok(hash) => Ok(hash),
+                Err(ClientError::Call(err)) => Err(err.message().to_string()),
+                Err(err) => panic!("unexpected RPC error: {err}"),
+            }
+        }
+    };
+
+    let err = submit("B", 0, target).await.unwrap_err();
+    assert!(err.contains(&format!("sender {b} is not allowed")), "{err}");
+    let err = submit("A", 0, denied_target).await.unwrap_err();
+    assert!(err.contains(&format!("calls to {denied_target} are denied")), "{err}");
+    submit("A", 0, target).await.unwrap();
+    submit("A", 1, target).await.unwrap();
+    let err = submit("A", 2, target).await.unwrap_err();
+    assert!(err.contains("exceeded its limit of 2 transactions per 3600s"), "{err}");
+
+    // the rules are reloaded without a restart
+    std::fs::write(&rules, format!("denied_senders = [\"{a}\"]\n"))?;
+    let modified = SystemTime::now() + ADMISSION_RULES_RELOAD_INTERVAL;
+    std::fs::File::options().write(true).open(&rules)?.set_modified(modified)?;
+    tokio::time::sleep(ADMISSION_RULES_RELOAD_INTERVAL * 3).await;
+    submit("B", 0, target).await.unwrap();
+    let err = submit("A", 2, target).await.unwrap_err();
+    assert!(err.contains(&format!("sender {a} is denied")), "{err}");
+
+    Ok(())
+}
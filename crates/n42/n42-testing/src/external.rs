@@ -0,0 +1,76 @@
+//! Blocks sealed by the standalone consensus client over the Engine API of a node started with
+//! `--consensus.external`.
+#![cfg(test)]
+
+use crate::{
+    policy::{funded_chainspec, pooled_transfer, GWEI},
+    snapshot_test_utils::TesterAccountPool,
+    utils::node_config,
+};
+use alloy_consensus::BlockHeader;
+use alloy_primitives::{Address, B256};
+use alloy_signer_local::PrivateKeySigner;
+use consensus_client::remote::RemoteSealer;
+use n42::consensus_ext::{ConsensusExt, ConsensusExtApiServer};
+use n42_clique::DIFF_IN_TURN;
+use n42_engine_types::N42Node;
+use reth::args::DevArgs;
+use reth_node_builder::{Node, NodeBuilder, NodeHandle};
+use reth_primitives_traits::header::clique_utils::recover_address_generic;
+use reth_provider::{BlockReader, BlockReaderIdExt};
+use reth_tasks::TaskManager;
+use reth_transaction_pool::{TransactionOrigin, TransactionPool};
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_external_consensus_client_on_loopback() -> eyre::Result<()> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = funded_chainspec(&mut accounts, &["A"]);
+    let chain_id = chainspec.chain.id();
+    let signer = accounts.address("signer");
+
+    // the node holds no signer key, and serves `consensusExt` next to the Engine API
+    let config =
+        node_config(chainspec).with_dev(DevArgs { external_consensus: true, ..Default::default() });
+    let NodeHandle { node, .. } = NodeBuilder::new(config)
+        .testing_node(tasks.executor())
+        .with_types::<N42Node>()
+        .with_components(N42Node::default().components_builder())
+        .with_add_ons(N42Node::default().add_ons())
+        .extend_rpc_modules(|ctx| {
+            let ext = ConsensusExt::new(ctx.consensus().clone(), ctx.provider().clone());
+            ctx.auth_module.merge_auth_methods(ext.into_rpc())?;
+            Ok(())
+        })
+        .launch()
+        .await?;
+
+    let transfer =
+        pooled_transfer(&mut accounts, chain_id, "A", 0, GWEI, Address::with_last_byte(0xff))?;
+    node.pool.add_transaction(TransactionOrigin::Local, transfer).await?;
+
+    let key =
+        PrivateKeySigner::from_bytes(&B256::from(accounts.secret_key("signer").secret_bytes()))?;
+    let sealer =
+        RemoteSealer::new(node.auth_server_handle().http_client(), key, Duration::from_millis(100));
+    let hash = tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            if let Some(hash) = sealer.seal_next().await? {
+                return eyre::Ok(hash)
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await??;
+
+    let head = node.provider.latest_header()?.expect("head");
+    assert_eq!(head.hash(), hash);
+    assert_eq!(head.number(), 1);
+    assert_eq!(head.difficulty(), DIFF_IN_TURN);
+    assert_eq!(recover_address_generic(head.header())?, signer);
+    let block = node.provider.block_by_number(1)?.expect("sealed block");
+    assert_eq!(block.body.transactions.len(), 1);
+    Ok(())
+}
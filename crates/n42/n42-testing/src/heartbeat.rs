@@ -0,0 +1,109 @@
+//! Empty blocks held back until the heartbeat interval of the chain elapsed.
+#![cfg(test)]
+
+use crate::{
+    policy::funded_chainspec,
+    snapshot_test_utils::TesterAccountPool,
+    utils::{idle_signer, launch_node, n42_payload_attributes, node_config},
+};
+use alloy_consensus::BlockHeader;
+use alloy_primitives::B256;
+use n42_clique::AposError;
+use reth::args::DevArgs;
+use reth_chainspec::{make_genesis_header, ChainSpec};
+use reth_consensus::{ConsensusError, HeaderValidator};
+use reth_payload_primitives::{BuiltPayload, PayloadKind};
+use reth_primitives_traits::SealedHeader;
+use reth_provider::{BlockReader, BlockReaderIdExt};
+use reth_tasks::TaskManager;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HEARTBEAT_INTERVAL: u64 = 5;
+
+/// Returns the chain spec of a single `signer` chain with a one second period, whose genesis is
+/// sealed now so the heartbeat of the first block is still ahead.
+fn heartbeat_chainspec(accounts: &mut TesterAccountPool) -> ChainSpec {
+    let mut chainspec = funded_chainspec(accounts, &[]);
+    chainspec.apos_config.period = 1;
+    chainspec.apos_config.heartbeat_interval = HEARTBEAT_INTERVAL;
+    chainspec.genesis.timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    chainspec.genesis_header =
+        SealedHeader::new_unhashed(make_genesis_header(&chainspec.genesis, &chainspec.hardforks));
+    chainspec
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_empty_block_before_heartbeat() -> eyre::Result<()> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = heartbeat_chainspec(&mut accounts);
+    let genesis = chainspec.genesis_header.clone();
+    let signer = accounts.address("signer");
+
+    let node =
+        launch_node(&tasks, node_config(chainspec).with_dev(idle_signer(&mut accounts, "signer")))
+            .await?;
+    let build = |timestamp: u64| {
+        let attributes = n42_payload_attributes(timestamp, genesis.hash(), signer);
+        let payload_builder = node.payload_builder_handle.clone();
+        async move {
+            let payload_id = payload_builder.send_new_payload(attributes.into()).await.unwrap()?;
+            let payload = payload_builder.resolve_kind(payload_id, PayloadKind::WaitForPending);
+            eyre::Ok(payload.await.expect("payload job"))
+        }
+    };
+
+    // the builder aborts every attempt at an empty block within the interval
+    let early = build(genesis.timestamp + 1).await?;
+    assert!(early.is_err(), "built {early:?}");
+
+    let payload = build(genesis.timestamp + HEARTBEAT_INTERVAL).await??;
+    let heartbeat = payload.block().sealed_header().clone();
+    assert!(payload.block().body().transactions.is_empty());
+    node.consensus.validate_header_against_parent(&heartbeat, &genesis)?;
+
+    // the same block sealed a period after its parent is rejected by the other signers
+    let mut header = heartbeat.clone_header();
+    header.timestamp = genesis.timestamp + 1;
+    let err = node
+        .consensus
+        .validate_header_against_parent(&SealedHeader::seal_slow(header), &genesis)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ConsensusError::AposErrorDetail { detail: AposError::EarlyEmptyBlock.to_string() }
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_miner_seals_heartbeat_block() -> eyre::Result<()> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = heartbeat_chainspec(&mut accounts);
+    let genesis_timestamp = chainspec.genesis.timestamp;
+
+    let signer_key = B256::from(accounts.secret_key("signer").secret_bytes());
+    let dev = DevArgs { consensus_signer_private_key: Some(signer_key), ..Default::default() };
+    let node = launch_node(&tasks, node_config(chainspec).with_dev(dev)).await?;
+
+    // without transactions, the miner skips the slots of the interval and seals its heartbeat
+    let head = tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            let head = node.provider.latest_header()?.expect("head");
+            if head.number() > 0 {
+                return eyre::Ok(head)
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    })
+    .await??;
+
+    assert_eq!(head.number(), 1);
+    assert!(head.timestamp() >= genesis_timestamp + HEARTBEAT_INTERVAL, "{head:?}");
+    let block = node.provider.block_by_number(1)?.expect("heartbeat block");
+    assert!(block.body.transactions.is_empty());
+    Ok(())
+}
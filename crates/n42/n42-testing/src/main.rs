@@ -1,6 +1,12 @@
 mod dev;
 mod utils;
 mod snapshot_test_utils;
+mod policy;
+mod admission;
+mod relay;
+mod external;
+mod heartbeat;
+mod private_tx;
 
 #[tokio::main]
 async fn main() {
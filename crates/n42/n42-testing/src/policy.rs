@@ -0,0 +1,215 @@
+//! Blocks built from a seeded pool under the `--builder.*` transaction policies.
+#![cfg(test)]
+
+use crate::{
+    dev::CliqueTest,
+    snapshot_test_utils::TesterAccountPool,
+    utils::{idle_signer, launch_node, n42_payload_attributes, node_config},
+};
+use alloy_consensus::{SignableTransaction, Transaction as _, TxEip1559};
+use alloy_eips::Encodable2718;
+use alloy_genesis::GenesisAccount;
+use alloy_primitives::{Address, TxKind, B256, U256};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use reth::args::{PayloadBuilderArgs, PayloadTxOrdering};
+use reth_chainspec::{make_genesis_header, ChainSpec};
+use reth_ethereum_primitives::TransactionSigned;
+use reth_payload_primitives::{BuiltPayload, PayloadKind};
+use reth_primitives_traits::{Recovered, SealedHeader, SignedTransaction};
+use reth_provider::BlockReaderIdExt;
+use reth_tasks::TaskManager;
+use reth_transaction_pool::{EthPooledTransaction, TransactionOrigin, TransactionPool};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const GAS_LIMIT: u64 = 30_000_000;
+const TRANSFER_GAS: u64 = 21_000;
+pub(crate) const GWEI: u128 = 1_000_000_000;
+
+/// A transfer to seed the pool with.
+struct SeededTx {
+    sender: &'static str,
+    nonce: u64,
+    priority_fee: u128,
+    to: Address,
+}
+
+impl SeededTx {
+    fn new(sender: &'static str, nonce: u64, priority_fee: u128) -> Self {
+        Self { sender, nonce, priority_fee, to: Address::with_last_byte(0xff) }
+    }
+
+    fn with_recipient(mut self, to: Address) -> Self {
+        self.to = to;
+        self
+    }
+}
+
+/// Returns the chain spec of a single `signer` chain, funding `senders` and raising the gas
+/// limit of the blocks to [`GAS_LIMIT`].
+pub(crate) fn funded_chainspec(accounts: &mut TesterAccountPool, senders: &[&str]) -> ChainSpec {
+    signers_chainspec(accounts, &["signer"], senders)
+}
+
+/// Returns the chain spec of a chain of `signers`, in this order, funding `senders` and raising
+/// the gas limit of the blocks to [`GAS_LIMIT`].
+pub(crate) fn signers_chainspec(
+    accounts: &mut TesterAccountPool,
+    signers: &[&str],
+    senders: &[&str],
+) -> ChainSpec {
+    let signers = signers.iter().map(|signer| signer.to_string()).collect();
+    let mut chainspec = CliqueTest { signers, ..Default::default() }.gen_chainspec(accounts);
+    for sender in senders {
+        chainspec.genesis.alloc.insert(
+            accounts.address(sender),
+            GenesisAccount::default().with_balance(U256::from(1_000_000_000_000_000_000u128)),
+        );
+    }
+    chainspec.genesis.gas_limit = GAS_LIMIT;
+    chainspec.genesis_header =
+        SealedHeader::new_unhashed(make_genesis_header(&chainspec.genesis, &chainspec.hardforks));
+    chainspec
+}
+
+/// Signs a transfer of one wei from `sender`, ready for the pool.
+pub(crate) fn pooled_transfer(
+    accounts: &mut TesterAccountPool,
+    chain_id: u64,
+    sender: &str,
+    nonce: u64,
+    priority_fee: u128,
+    to: Address,
+) -> eyre::Result<EthPooledTransaction> {
+    let key = PrivateKeySigner::from_bytes(&B256::from(accounts.secret_key(sender).secret_bytes()))?;
+    let transfer = TxEip1559 {
+        chain_id,
+        nonce,
+        gas_limit: TRANSFER_GAS,
+        max_fee_per_gas: 100 * GWEI,
+        max_priority_fee_per_gas: priority_fee,
+        to: TxKind::Call(to),
+        value: U256::from(1),
+        ..Default::default()
+    };
+    let signature = key.sign_hash_sync(&transfer.signature_hash())?;
+    let signed: TransactionSigned = transfer.into_signed(signature).into();
+    let len = signed.encode_2718_len();
+    Ok(EthPooledTransaction::new(Recovered::new_unchecked(signed, key.address()), len))
+}
+
+/// Seeds the pool of a fresh node with `txs`, in order, and returns the `(sender, nonce)` of the
+/// transactions of the block it builds on genesis.
+async fn build_block(
+    accounts: &mut TesterAccountPool,
+    builder: PayloadBuilderArgs,
+    txs: Vec<SeededTx>,
+) -> eyre::Result<Vec<(Address, u64)>> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+
+    let senders: Vec<_> = txs.iter().map(|tx| tx.sender).collect();
+    let chainspec = funded_chainspec(accounts, &senders);
+    let chain_id = chainspec.chain.id();
+
+    let config = node_config(chainspec)
+        .with_payload_builder(PayloadBuilderArgs { gas_limit: Some(GAS_LIMIT), ..builder })
+        .with_dev(idle_signer(accounts, "signer"));
+    let node = launch_node(&tasks, config).await?;
+
+    for tx in txs {
+        let pooled = pooled_transfer(accounts, chain_id, tx.sender, tx.nonce, tx.priority_fee, tx.to)?;
+        node.pool.add_transaction(TransactionOrigin::Local, pooled).await?;
+    }
+
+    let parent_hash = node.provider.latest_header()?.expect("genesis header").hash();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let attributes = n42_payload_attributes(timestamp, parent_hash, accounts.address("signer"));
+    let payload_id = node.payload_builder_handle.send_new_payload(attributes.into()).await.unwrap()?;
+    let payload = node
+        .payload_builder_handle
+        .resolve_kind(payload_id, PayloadKind::WaitForPending)
+        .await
+        .unwrap()?;
+
+    payload
+        .block()
+        .body()
+        .transactions
+        .iter()
+        .map(|tx| Ok((tx.recover_signer()?, tx.nonce())))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_fee_and_fifo_ordering() -> eyre::Result<()> {
+    let mut accounts = TesterAccountPool::new();
+    let (a, b) = (accounts.address("A"), accounts.address("B"));
+    // B's cheap transaction arrives first
+    let txs = || vec![SeededTx::new("B", 0, GWEI), SeededTx::new("A", 0, 10 * GWEI)];
+
+    let included = build_block(&mut accounts, PayloadBuilderArgs::default(), txs()).await?;
+    assert_eq!(included, vec![(a, 0), (b, 0)]);
+
+    let fifo = PayloadBuilderArgs { tx_ordering: PayloadTxOrdering::Fifo, ..Default::default() };
+    let included = build_block(&mut accounts, fifo, txs()).await?;
+    assert_eq!(included, vec![(b, 0), (a, 0)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sender_cap_and_deny_list() -> eyre::Result<()> {
+    let mut accounts = TesterAccountPool::new();
+    let (a, b) = (accounts.address("A"), accounts.address("B"));
+    let denied_recipient = Address::with_last_byte(0xde);
+
+    let dir = tempfile::tempdir()?;
+    let deny_list = dir.path().join("deny.txt");
+    std::fs::write(&deny_list, format!("{}\n{denied_recipient}\n", accounts.address("C")))?;
+
+    let builder = PayloadBuilderArgs {
+        max_txs_per_sender: Some(2),
+        deny_list: Some(deny_list),
+        ..Default::default()
+    };
+    let txs = vec![
+        SeededTx::new("A", 0, GWEI),
+        SeededTx::new("A", 1, GWEI),
+        SeededTx::new("A", 2, GWEI),
+        SeededTx::new("C", 0, 10 * GWEI),
+        SeededTx::new("B", 0, 5 * GWEI),
+        SeededTx::new("B", 1, 5 * GWEI).with_recipient(denied_recipient),
+    ];
+    let included = build_block(&mut accounts, builder, txs).await?;
+
+    assert_eq!(included, vec![(b, 0), (a, 0), (a, 1)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reserved_system_gas() -> eyre::Result<()> {
+    let mut accounts = TesterAccountPool::new();
+    let (a, system) = (accounts.address("A"), accounts.address("system"));
+
+    // non-system senders are left room for two transfers
+    let builder = PayloadBuilderArgs {
+        system_senders: vec![system],
+        reserved_system_gas: GAS_LIMIT - 2 * TRANSFER_GAS - TRANSFER_GAS / 2,
+        ..Default::default()
+    };
+    let txs = vec![
+        SeededTx::new("A", 0, 10 * GWEI),
+        SeededTx::new("A", 1, 10 * GWEI),
+        SeededTx::new("A", 2, 10 * GWEI),
+        SeededTx::new("system", 0, GWEI),
+        SeededTx::new("system", 1, GWEI),
+        SeededTx::new("system", 2, GWEI),
+    ];
+    let included = build_block(&mut accounts, builder, txs).await?;
+
+    assert_eq!(
+        included,
+        vec![(a, 0), (a, 1), (system, 0), (system, 1), (system, 2)]
+    );
+    Ok(())
+}
@@ -0,0 +1,186 @@
+//! Private transactions handed from signer to signer over the `n42p` subprotocol.
+#![cfg(test)]
+
+use crate::{
+    policy::{pooled_transfer, signers_chainspec, GWEI},
+    snapshot_test_utils::TesterAccountPool,
+    utils::{idle_signer, n42_payload_attributes, node_config, TestNode},
+};
+use alloy_eips::eip2718::Encodable2718;
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_rpc_types_engine::ExecutionPayloadV3;
+use jsonrpsee::{
+    core::{client::ClientT, ClientError},
+    rpc_params,
+};
+use n42::private_tx_ext::{N42PrivateTx, N42PrivateTxApiServer};
+use n42_engine_types::{N42Node, N42TransactionPolicy, PrivateTransactions};
+use reth::rpc::types::engine::ForkchoiceState;
+use reth_chainspec::ChainSpec;
+use reth_consensus::Consensus;
+use reth_network::{Peers, PeersInfo};
+use reth_node_builder::{Node, NodeBuilder, NodeConfig, NodeHandle};
+use reth_node_ethereum::EthEngineTypes;
+use reth_payload_primitives::{BuiltPayload, PayloadKind};
+use reth_provider::BlockReaderIdExt;
+use reth_rpc_api::EngineApiClient;
+use reth_tasks::TaskManager;
+use reth_transaction_pool::{EthPooledTransaction, PoolTransaction, TransactionPool};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Launches a node serving `n42_sendPrivateTransaction`, with the private transactions it keeps.
+async fn launch_private_tx_node(
+    tasks: &TaskManager,
+    config: NodeConfig<ChainSpec>,
+) -> eyre::Result<(TestNode, PrivateTransactions)> {
+    let n42_node = N42Node::default();
+    let private_txs = n42_node.private_transactions().clone();
+    let rpc_private_txs = private_txs.clone();
+    let NodeHandle { node, .. } = NodeBuilder::new(config)
+        .testing_node(tasks.executor())
+        .with_types::<N42Node>()
+        .with_components(n42_node.components_builder())
+        .with_add_ons(n42_node.add_ons())
+        .extend_rpc_modules(move |ctx| {
+            let private = N42PrivateTx {
+                consensus: ctx.consensus().clone(),
+                provider: ctx.provider().clone(),
+                private_txs: rpc_private_txs,
+                policy: N42TransactionPolicy::from_args(&ctx.config().builder)?,
+            };
+            ctx.modules.merge_configured(private.into_rpc())?;
+            Ok(())
+        })
+        .launch()
+        .await?;
+    Ok((node, private_txs))
+}
+
+fn raw(tx: &EthPooledTransaction) -> Bytes {
+    tx.clone_into_consensus().into_inner().encoded_2718().into()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_private_transaction_to_inturn_signer() -> eyre::Result<()> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+    let mut accounts = TesterAccountPool::new();
+
+    // the first signer is in turn for block 1, the second for block 2
+    let chainspec = signers_chainspec(&mut accounts, &["S1", "S2", "S3"], &["A"]);
+    let chain_id = chainspec.chain.id();
+    let (inturn, outturn) = (accounts.address("S1"), accounts.address("S3"));
+    let (node, private_txs) = launch_private_tx_node(
+        &tasks,
+        node_config(chainspec.clone()).with_dev(idle_signer(&mut accounts, "S1")),
+    )
+    .await?;
+    let (other, other_private_txs) = launch_private_tx_node(
+        &tasks,
+        node_config(chainspec).with_dev(idle_signer(&mut accounts, "S3")),
+    )
+    .await?;
+
+    let genesis = node.provider.latest_header()?.expect("genesis header");
+    let snapshot = node.consensus.snapshot(0, genesis.hash(), None)?;
+    assert!(snapshot.inturn(1, &inturn));
+    assert!(!(1..=2).any(|number| snapshot.inturn(number, &outturn)));
+
+    other.network.add_peer(*node.network.peer_id(), node.network.local_node_record().tcp_addr());
+
+    // a transfer to include, and one whose nonce gap keeps it out of the blocks until it expires
+    let to = Address::with_last_byte(0xff);
+    let transfer = pooled_transfer(&mut accounts, chain_id, "A", 0, GWEI, to)?;
+    let stuck = pooled_transfer(&mut accounts, chain_id, "A", 5, GWEI, to)?;
+    let hashes = [*transfer.hash(), *stuck.hash()];
+
+    // sent through the signer out of turn, which hands them to the in-turn signer it reaches
+    let client = other.rpc_server_handle().http_client().expect("HTTP RPC is enabled");
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while !hashes.iter().all(|hash| private_txs.contains(hash)) {
+            let _ = client
+                .request::<B256, _>("n42_sendPrivateTransaction", rpc_params![raw(&transfer), 10])
+                .await;
+            let _ = client
+                .request::<B256, _>("n42_sendPrivateTransaction", rpc_params![raw(&stuck), 1])
+                .await;
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    })
+    .await?;
+    for hash in &hashes {
+        assert!(!other_private_txs.contains(hash));
+        assert!(node.pool.get(hash).is_none());
+        assert!(other.pool.get(hash).is_none());
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let attributes = n42_payload_attributes(timestamp, genesis.hash(), inturn);
+    let payload_id =
+        node.payload_builder_handle.send_new_payload(attributes.into()).await.unwrap()?;
+    let payload = node
+        .payload_builder_handle
+        .resolve_kind(payload_id, PayloadKind::WaitForPending)
+        .await
+        .unwrap()?;
+    let included: Vec<_> =
+        payload.block().body().transactions.iter().map(|tx| *tx.tx_hash()).collect();
+    assert_eq!(included, vec![hashes[0]]);
+    assert!(private_txs.contains(&hashes[1]));
+
+    // the transaction kept for one block is dropped once the block is added
+    let client = node.engine_http_client();
+    let block = payload.block().clone();
+    let status = EngineApiClient::<EthEngineTypes>::new_payload_v3(
+        &client,
+        ExecutionPayloadV3::from_block_slow(&block.clone().into_block()),
+        vec![],
+        B256::ZERO,
+    )
+    .await?;
+    assert!(status.status.is_valid(), "{status:?}");
+    let state = ForkchoiceState {
+        head_block_hash: block.hash(),
+        safe_block_hash: genesis.hash(),
+        finalized_block_hash: genesis.hash(),
+    };
+    EngineApiClient::<EthEngineTypes>::fork_choice_updated_v3(&client, state, None).await?;
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while private_txs.contains(&hashes[1]) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await?;
+    assert!(node.pool.get(&hashes[1]).is_none());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_private_transaction_from_non_signer() -> eyre::Result<()> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = signers_chainspec(&mut accounts, &["S1", "S2"], &["A"]);
+    let chain_id = chainspec.chain.id();
+    let transfer =
+        pooled_transfer(&mut accounts, chain_id, "A", 0, GWEI, Address::with_last_byte(0xff))?;
+
+    // a node without a signer key, and one whose key isn't among the signers
+    let (follower, _) = launch_private_tx_node(&tasks, node_config(chainspec.clone())).await?;
+    let (unauthorized, _) = launch_private_tx_node(
+        &tasks,
+        node_config(chainspec).with_dev(idle_signer(&mut accounts, "A")),
+    )
+    .await?;
+
+    for node in [&follower, &unauthorized] {
+        let client = node.rpc_server_handle().http_client().expect("HTTP RPC is enabled");
+        let err = client
+            .request::<B256, _>("n42_sendPrivateTransaction", rpc_params![raw(&transfer)])
+            .await
+            .unwrap_err();
+        let ClientError::Call(err) = err else { panic!("unexpected RPC error: {err}") };
+        assert!(err.message().contains("authorized signer"), "{}", err.message());
+    }
+    Ok(())
+}
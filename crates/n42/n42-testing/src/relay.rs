@@ -0,0 +1,324 @@
+//! Blocks taken from a mock block builder relay.
+#![cfg(test)]
+
+use crate::{
+    policy::{funded_chainspec, pooled_transfer, GAS_LIMIT, GWEI},
+    snapshot_test_utils::TesterAccountPool,
+    utils::{idle_signer, launch_node, n42_payload_attributes, node_config},
+};
+use alloy_primitives::{Address, B256};
+use alloy_rpc_types_beacon::{
+    relay::{BidTrace, SignedBidSubmissionV3, SignedBidTrace, SubmitBlockRequest, ValidatorRegistration},
+    BlsSignature,
+};
+use alloy_rpc_types_engine::ExecutionPayloadV3;
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use n42_engine_types::{relay_bid_signing_hash, signer_pubkey};
+use reth::args::PayloadBuilderArgs;
+use reth_chainspec::ChainSpec;
+use reth_ethereum_engine_primitives::EthBuiltPayload;
+use reth_payload_primitives::{BuiltPayload, PayloadKind};
+use reth_primitives_traits::header::clique_utils::recover_address_generic;
+use reth_provider::BlockReaderIdExt;
+use reth_tasks::TaskManager;
+use reth_transaction_pool::{EthPooledTransaction, TransactionOrigin, TransactionPool};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Relay answering bid requests with one block after `delay`, signed with `key`, recording the
+/// registrations it receives.
+#[derive(Debug, Clone)]
+struct MockRelay {
+    block: Option<SubmitBlockRequest>,
+    delay: Duration,
+    key: PrivateKeySigner,
+    registrations: Arc<Mutex<Vec<ValidatorRegistration>>>,
+}
+
+impl MockRelay {
+    fn new(
+        accounts: &mut TesterAccountPool,
+        key: &str,
+        block: Option<SubmitBlockRequest>,
+        delay: Duration,
+    ) -> Self {
+        let key = B256::from(accounts.secret_key(key).secret_bytes());
+        let key = PrivateKeySigner::from_bytes(&key).expect("relay key");
+        Self { block, delay, key, registrations: Default::default() }
+    }
+
+    /// Serves the relay API on a local port and returns its URL, naming `address` as the relay
+    /// address.
+    async fn serve(&self, address: Address) -> eyre::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{address}@{}", listener.local_addr()?);
+        let relay = self.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(relay.clone().handle(stream));
+            }
+        });
+        Ok(url)
+    }
+
+    async fn handle(self, mut stream: TcpStream) {
+        let Some((head, body)) = read_request(&mut stream).await else { return };
+        let mut request_line = head.split_whitespace();
+        let (method, path) = (request_line.next().unwrap_or_default(), request_line.next().unwrap_or_default());
+
+        let (status, response) = match (method, path) {
+            ("POST", "/eth/v1/builder/validators") => {
+                let registrations: Vec<ValidatorRegistration> =
+                    serde_json::from_slice(&body).expect("registrations");
+                self.registrations.lock().unwrap().extend(registrations);
+                ("200 OK", String::new())
+            }
+            ("GET", path) if path.starts_with("/eth/v1/builder/header/") => {
+                tokio::time::sleep(self.delay).await;
+                match &self.block {
+                    Some(block) => {
+                        let message = block.bid_trace().clone();
+                        let signature =
+                            self.key.sign_hash_sync(&relay_bid_signing_hash(&message)).unwrap();
+                        let mut bid = SignedBidTrace { message, signature: BlsSignature::ZERO };
+                        bid.signature.0[..65].copy_from_slice(&signature.as_bytes());
+                        ("200 OK", serde_json::to_string(&bid).unwrap())
+                    }
+                    None => ("204 No Content", String::new()),
+                }
+            }
+            ("POST", "/eth/v1/builder/blinded_blocks") => match &self.block {
+                Some(block) => ("200 OK", serde_json::to_string(block).unwrap()),
+                None => ("400 Bad Request", String::new()),
+            },
+            _ => ("404 Not Found", String::new()),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{response}",
+            response.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+}
+
+/// Reads the head and the body of an HTTP request.
+async fn read_request(stream: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end
+        }
+        let read = stream.read(&mut chunk).await.ok().filter(|read| *read > 0)?;
+        buf.extend_from_slice(&chunk[..read]);
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+
+    let body_start = head_end + 4;
+    while buf.len() < body_start + content_length {
+        let read = stream.read(&mut chunk).await.ok().filter(|read| *read > 0)?;
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    Some((head, buf[body_start..body_start + content_length].to_vec()))
+}
+
+/// Launches a node signing with the key of `signer`, seeds its pool with `txs` and returns the
+/// payload it has for the block on genesis after `wait`, or once built if `wait` is `None`.
+async fn build_payload(
+    accounts: &mut TesterAccountPool,
+    chainspec: ChainSpec,
+    builder: PayloadBuilderArgs,
+    txs: Vec<EthPooledTransaction>,
+    timestamp: u64,
+    wait: Option<Duration>,
+) -> eyre::Result<EthBuiltPayload> {
+    reth_tracing::init_test_tracing();
+    let tasks = TaskManager::current();
+
+    let config = node_config(chainspec)
+        .with_payload_builder(PayloadBuilderArgs {
+            gas_limit: Some(GAS_LIMIT),
+            interval: Duration::from_millis(100),
+            ..builder
+        })
+        .with_dev(idle_signer(accounts, "signer"));
+    let node = launch_node(&tasks, config).await?;
+
+    for tx in txs {
+        node.pool.add_transaction(TransactionOrigin::Local, tx).await?;
+    }
+
+    let parent_hash = node.provider.latest_header()?.expect("genesis header").hash();
+    let attributes = n42_payload_attributes(timestamp, parent_hash, accounts.address("signer"));
+    let payload_id = node.payload_builder_handle.send_new_payload(attributes.into()).await.unwrap()?;
+    let kind = match wait {
+        Some(wait) => {
+            tokio::time::sleep(wait).await;
+            PayloadKind::Earliest
+        }
+        None => PayloadKind::WaitForPending,
+    };
+    Ok(node.payload_builder_handle.resolve_kind(payload_id, kind).await.unwrap()?)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Returns the block of an external builder transferring from `A`, built here by a node without
+/// relays, and its submission to a relay.
+async fn relay_block(
+    accounts: &mut TesterAccountPool,
+    chainspec: ChainSpec,
+    timestamp: u64,
+) -> eyre::Result<(EthBuiltPayload, SubmitBlockRequest)> {
+    let chain_id = chainspec.chain.id();
+    let signer = accounts.address("signer");
+    let transfer =
+        pooled_transfer(accounts, chain_id, "A", 0, GWEI, Address::with_last_byte(0xff))?;
+    let built = build_payload(
+        accounts,
+        chainspec,
+        PayloadBuilderArgs::default(),
+        vec![transfer],
+        timestamp,
+        None,
+    )
+    .await?;
+    let header = built.block().header().clone();
+    let mut execution_payload =
+        ExecutionPayloadV3::from_block_unchecked(header.hash_slow(), &built.block().clone().into_block());
+    execution_payload.payload_inner.payload_inner.fee_recipient = signer;
+    let bid = BidTrace {
+        slot: header.number,
+        parent_hash: header.parent_hash,
+        block_hash: header.hash_slow(),
+        proposer_pubkey: signer_pubkey(signer),
+        proposer_fee_recipient: signer,
+        gas_limit: header.gas_limit,
+        gas_used: header.gas_used,
+        value: built.fees(),
+        ..Default::default()
+    };
+    let block = SignedBidSubmissionV3 {
+        message: bid,
+        execution_payload,
+        blobs_bundle: Default::default(),
+        signature: BlsSignature::ZERO,
+    };
+    Ok((built, block.into()))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_relay_block_sealed_by_signer() -> eyre::Result<()> {
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = funded_chainspec(&mut accounts, &["A"]);
+    let signer = accounts.address("signer");
+    let timestamp = now();
+
+    let (built, block) = relay_block(&mut accounts, chainspec.clone(), timestamp).await?;
+    let header = built.block().header().clone();
+    let relay = MockRelay::new(&mut accounts, "relay", Some(block), Duration::ZERO);
+
+    let url = relay.serve(accounts.address("relay")).await?;
+    let builder = PayloadBuilderArgs { relays: vec![url], ..Default::default() };
+    let payload =
+        build_payload(&mut accounts, chainspec, builder, Vec::new(), timestamp, Some(Duration::from_secs(1)))
+            .await?;
+
+    let registrations = relay.registrations.lock().unwrap().clone();
+    assert!(registrations
+        .iter()
+        .any(|r| r.message.fee_recipient == signer && r.message.pubkey == signer_pubkey(signer)));
+    assert_eq!(payload.fees(), built.fees());
+    assert_eq!(payload.block().body().transactions, built.block().body().transactions);
+    assert_eq!(payload.block().header().state_root, header.state_root);
+    assert_eq!(recover_address_generic(payload.block().header())?, signer);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_local_block_on_relay_timeout() -> eyre::Result<()> {
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = funded_chainspec(&mut accounts, &["A"]);
+    let chain_id = chainspec.chain.id();
+    let signer = accounts.address("signer");
+
+    let relay = MockRelay::new(&mut accounts, "relay", None, Duration::from_secs(5));
+    let builder = PayloadBuilderArgs {
+        relays: vec![relay.serve(accounts.address("relay")).await?],
+        relay_timeout: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let transfer = pooled_transfer(&mut accounts, chain_id, "A", 0, GWEI, Address::with_last_byte(0xff))?;
+    let payload = build_payload(
+        &mut accounts,
+        chainspec,
+        builder,
+        vec![transfer],
+        now(),
+        Some(Duration::from_secs(1)),
+    )
+    .await?;
+
+    assert!(!relay.registrations.lock().unwrap().is_empty());
+    assert_eq!(payload.block().body().transactions.len(), 1);
+    assert_eq!(recover_address_generic(payload.block().header())?, signer);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_relay_block_rejected() -> eyre::Result<()> {
+    let mut accounts = TesterAccountPool::new();
+    let chainspec = funded_chainspec(&mut accounts, &["A"]);
+    let timestamp = now();
+    let (_, block) = relay_block(&mut accounts, chainspec.clone(), timestamp).await?;
+    let dir = tempfile::tempdir()?;
+    let deny_list = dir.path().join("deny.txt");
+    std::fs::write(&deny_list, format!("{}\n", accounts.address("A")))?;
+
+    // a bid signed by another key than the relay's, and a block with a denied sender
+    let forged = MockRelay::new(&mut accounts, "mallory", Some(block.clone()), Duration::ZERO);
+    let denied = MockRelay::new(&mut accounts, "relay", Some(block), Duration::ZERO);
+    let relay_address = accounts.address("relay");
+    let cases = [
+        PayloadBuilderArgs {
+            relays: vec![forged.serve(relay_address).await?],
+            ..Default::default()
+        },
+        PayloadBuilderArgs {
+            relays: vec![denied.serve(relay_address).await?],
+            deny_list: Some(deny_list),
+            ..Default::default()
+        },
+    ];
+
+    for builder in cases {
+        let payload = build_payload(
+            &mut accounts,
+            chainspec.clone(),
+            builder,
+            Vec::new(),
+            timestamp,
+            Some(Duration::from_secs(1)),
+        )
+        .await?;
+        assert!(payload.block().body().transactions.is_empty());
+        assert_eq!(recover_address_generic(payload.block().header())?, accounts.address("signer"));
+    }
+    Ok(())
+}
@@ -1,6 +1,77 @@
 use alloy_primitives::{Address, B256};
 use reth::rpc::types::engine::PayloadAttributes;
 use reth_payload_builder::EthPayloadBuilderAttributes;
+#[cfg(test)]
+use {
+    crate::snapshot_test_utils::TesterAccountPool,
+    n42_engine_types::N42Node,
+    reth::args::{DevArgs, DiscoveryArgs, NetworkArgs, RpcServerArgs},
+    reth_chainspec::ChainSpec,
+    reth_db::{test_utils::TempDatabase, DatabaseEnv},
+    reth_node_builder::{
+        FullNode, Node, NodeAdapter, NodeBuilder, NodeComponentsBuilder, NodeConfig, NodeHandle,
+        RethFullAdapter,
+    },
+    reth_tasks::TaskManager,
+    std::{sync::Arc, time::Duration},
+};
+
+/// Node types of the nodes launched on a temporary database.
+#[cfg(test)]
+type TmpNodeAdapter = RethFullAdapter<Arc<TempDatabase<DatabaseEnv>>, N42Node>;
+
+/// An [`N42Node`] launched by [`launch_node`].
+#[cfg(test)]
+pub(crate) type TestNode = FullNode<
+    NodeAdapter<
+        TmpNodeAdapter,
+        <<N42Node as Node<TmpNodeAdapter>>::ComponentsBuilder as NodeComponentsBuilder<
+            TmpNodeAdapter,
+        >>::Components,
+    >,
+    <N42Node as Node<TmpNodeAdapter>>::AddOns,
+>;
+
+/// Returns the config of a node on `chainspec` without discovery, serving HTTP RPC, all on
+/// unused ports.
+#[cfg(test)]
+pub(crate) fn node_config(chainspec: ChainSpec) -> NodeConfig<ChainSpec> {
+    NodeConfig::new(Arc::new(chainspec))
+        .with_network(NetworkArgs {
+            discovery: DiscoveryArgs { disable_discovery: true, ..DiscoveryArgs::default() },
+            ..NetworkArgs::default()
+        })
+        .with_unused_ports()
+        .with_rpc(RpcServerArgs::default().with_unused_ports().with_http())
+}
+
+/// Returns the dev args of a node sealing with the key of `signer` whose miner doesn't tick
+/// within a test, so payloads are only built when the test asks the payload builder.
+#[cfg(test)]
+pub(crate) fn idle_signer(accounts: &mut TesterAccountPool, signer: &str) -> DevArgs {
+    DevArgs {
+        consensus_signer_private_key: Some(B256::from(accounts.secret_key(signer).secret_bytes())),
+        // the first tick of the miner, it follows the chain period only after that one
+        block_time: Some(Duration::from_secs(24 * 60 * 60)),
+        ..Default::default()
+    }
+}
+
+/// Launches an [`N42Node`] with `config` on a temporary database.
+#[cfg(test)]
+pub(crate) async fn launch_node(
+    tasks: &TaskManager,
+    config: NodeConfig<ChainSpec>,
+) -> eyre::Result<TestNode> {
+    let NodeHandle { node, .. } = NodeBuilder::new(config)
+        .testing_node(tasks.executor())
+        .with_types::<N42Node>()
+        .with_components(N42Node::default().components_builder())
+        .with_add_ons(N42Node::default().add_ons())
+        .launch()
+        .await?;
+    Ok(node)
+}
 
 #[cfg(test)]
 pub(crate) fn n42_payload_attributes(timestamp: u64, parent_hash: B256, eth_signer_address: Address) -> EthPayloadBuilderAttributes {
@@ -0,0 +1,68 @@
+#![allow(missing_docs)]
+//! Replays of 100k headers onto a voting snapshot.
+
+use alloy_primitives::{Address, B256, B64, U256};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use n42_primitives::{APosConfig, Snapshot};
+use reth_primitives::Header;
+use reth_primitives_traits::SealedHeader;
+use std::error::Error;
+
+const HEADERS: u64 = 100_000;
+const SIGNERS: usize = 21;
+/// Accounts each signer keeps voting on, in turn.
+const CANDIDATES_PER_SIGNER: usize = 100;
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = replay
+}
+criterion_main!(benches);
+
+fn signers() -> Vec<Address> {
+    (0..SIGNERS).map(|i| Address::with_last_byte(i as u8 + 1)).collect()
+}
+
+/// Headers sealed in turn by the signers, each voting to add one of its own candidates when
+/// `votes` is set, so that no vote ever passes but every signer keeps a vote on each of its
+/// candidates.
+fn headers(votes: bool) -> Vec<SealedHeader> {
+    (1..=HEADERS)
+        .map(|number| {
+            let turn = (number - 1) as usize;
+            let (beneficiary, nonce) = if votes {
+                let signer = turn % SIGNERS;
+                let candidate = signer * CANDIDATES_PER_SIGNER + (turn / SIGNERS) % CANDIDATES_PER_SIGNER;
+                (Address::from_word(B256::from(U256::from(1_000 + candidate))), B64::repeat_byte(0xff))
+            } else {
+                (Address::ZERO, B64::ZERO)
+            };
+            let header = Header { number, beneficiary, nonce, ..Default::default() };
+            SealedHeader::new(header, B256::from(U256::from(number)))
+        })
+        .collect()
+}
+
+fn sealer(header: &SealedHeader) -> Result<Address, Box<dyn Error>> {
+    Ok(Address::with_last_byte(((header.number - 1) as usize % SIGNERS) as u8 + 1))
+}
+
+fn replay(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_apply");
+    let config = APosConfig { epoch: 30_000, ..Default::default() };
+    let genesis = Snapshot::new_snapshot(config, 0, B256::ZERO, signers());
+
+    for (name, votes) in [("replay_100k_without_votes", false), ("replay_100k_with_votes", true)] {
+        let headers = headers(votes);
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || (genesis.clone(), headers.clone()),
+                |(snap, headers)| snap.apply(headers, sealer).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
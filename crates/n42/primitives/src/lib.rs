@@ -1,5 +1,8 @@
 //! n42 primitives
 
+mod liveness;
 mod snapshot;
-pub use snapshot::Snapshot;
-pub use snapshot::APosConfig;
+pub use liveness::{SignerLiveness, SignerLivenessTracker};
+pub use snapshot::{Snapshot, SnapshotApplier};
+pub use snapshot::{APosConfig, DEFAULT_CHECKPOINT_INTERVAL};
+pub use snapshot::APosConfigUpdate;
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+/// Activity of a snapshot signer, as seen in the canonical headers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignerLiveness {
+    /// Last block sealed by the signer
+    pub last_sealed: Option<u64>,
+    /// In-turn slots the signer left to an out-of-turn signer
+    pub missed_in_turn: u64,
+    /// In-turn slots missed since the signer last sealed a block; a signer has one in-turn slot
+    /// per round, so this is also the number of consecutive rounds it missed
+    pub consecutive_missed: u64,
+}
+
+/// Tracks the [`SignerLiveness`] of the current signers block by block.
+#[derive(Clone, Debug, Default)]
+pub struct SignerLivenessTracker {
+    signers: BTreeMap<Address, SignerLiveness>,
+    /// Highest block accounted for, lower or repeated blocks were replaced by a reorg
+    head: u64,
+}
+
+impl SignerLivenessTracker {
+    /// Accounts for block `number` sealed by `sealer`, with `signers` the snapshot signers of its
+    /// parent.
+    ///
+    /// Returns false if the block was not accounted for because it is not above the last one.
+    pub fn on_block(&mut self, signers: &[Address], number: u64, sealer: Address) -> bool {
+        if number <= self.head || signers.is_empty() {
+            return false
+        }
+        self.head = number;
+
+        // forget dropped signers and start tracking new ones
+        self.signers.retain(|signer, _| signers.contains(signer));
+        for signer in signers {
+            self.signers.entry(*signer).or_default();
+        }
+
+        let in_turn = signers[((number - 1) % signers.len() as u64) as usize];
+        if in_turn != sealer {
+            if let Some(liveness) = self.signers.get_mut(&in_turn) {
+                liveness.missed_in_turn += 1;
+                liveness.consecutive_missed += 1;
+            }
+        }
+        if let Some(liveness) = self.signers.get_mut(&sealer) {
+            liveness.last_sealed = Some(number);
+            liveness.consecutive_missed = 0;
+        }
+        true
+    }
+
+    /// Returns the liveness of the tracked signers.
+    pub const fn signers(&self) -> &BTreeMap<Address, SignerLiveness> {
+        &self.signers
+    }
+
+    /// Returns the signers that missed more than `rounds` consecutive rounds.
+    pub fn inactive(&self, rounds: u64) -> impl Iterator<Item = Address> + '_ {
+        self.signers
+            .iter()
+            .filter(move |(_, liveness)| liveness.consecutive_missed > rounds)
+            .map(|(signer, _)| *signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_missed_in_turn_slots() {
+        let (a, b, c) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let signers = [a, b, c];
+        let mut tracker = SignerLivenessTracker::default();
+
+        // c is offline, its slots (3 and 6) are sealed out of turn by a
+        for (number, sealer) in [(1, a), (2, b), (3, a), (4, a), (5, b), (6, a)] {
+            assert!(tracker.on_block(&signers, number, sealer));
+        }
+        assert!(!tracker.on_block(&signers, 6, b));
+
+        let c_liveness = &tracker.signers()[&c];
+        assert_eq!(c_liveness.last_sealed, None);
+        assert_eq!(c_liveness.missed_in_turn, 2);
+        assert_eq!(c_liveness.consecutive_missed, 2);
+        assert_eq!(tracker.signers()[&a].last_sealed, Some(6));
+        assert_eq!(tracker.inactive(1).collect::<Vec<_>>(), vec![c]);
+
+        // c comes back, and a is dropped
+        assert!(tracker.on_block(&[b, c], 7, c));
+        assert_eq!(tracker.signers()[&c].consecutive_missed, 0);
+        assert_eq!(tracker.signers()[&c].missed_in_turn, 2);
+        assert!(!tracker.signers().contains_key(&a));
+    }
+}
@@ -1,24 +1,23 @@
 
 // use ethcore::snapshot::{ManifestData, SnapshotService};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use reth_primitives::{arbitrary, Header};
 
 use std::time::{Duration, Instant};
 
-use reth_primitives_traits::{BlockHeader as BlockHeaderTrait};
+use reth_primitives_traits::{BlockHeader as BlockHeaderTrait, SealedHeader};
 use reth_primitives_traits::AlloyBlockHeader;
-use alloy_primitives::Sealable;
 
-use alloy_primitives::{Address, B256, U256, hex};
+use alloy_primitives::{Address, B256, B64, U256, hex};
 use alloy_rlp::{RlpDecodable, RlpEncodable};
 use arbitrary::Arbitrary;
 use serde::{Deserialize, Serialize};
 
 use tracing::info;
 
-const NONCE_AUTH_VOTE: [u8; 8] = hex!("ffffffffffffffff"); // Magic nonce number to vote on adding a new signer
-const NONCE_DROP_VOTE: [u8; 8] = hex!("0000000000000000"); // Magic nonce number to vote on removing a signer
+const NONCE_AUTH_VOTE: B64 = B64::new(hex!("ffffffffffffffff")); // Magic nonce number to vote on adding a new signer
+const NONCE_DROP_VOTE: B64 = B64::new(hex!("0000000000000000")); // Magic nonce number to vote on removing a signer
 
 
 #[derive(Debug)]
@@ -76,6 +75,20 @@ pub struct APosConfig {
     pub reward_limit: U256,
     /// Deposit contract
     pub deposit_contract: Address,
+    /// Number of blocks after which the vote snapshot is saved to the database
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: u64,
+    /// Minimum number of seconds between an empty block and its parent, 0 to seal empty blocks
+    /// every period
+    #[serde(default)]
+    pub heartbeat_interval: u64,
+}
+
+/// Default number of blocks between two snapshots persisted to the database.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 2048;
+
+const fn default_checkpoint_interval() -> u64 {
+    DEFAULT_CHECKPOINT_INTERVAL
 }
 
 impl Default for APosConfig {
@@ -86,10 +99,74 @@ impl Default for APosConfig {
             reward_epoch: 10800,
             reward_limit: U256::from(0x6F05B59D3B20000_u64),
             deposit_contract: Address::ZERO,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            heartbeat_interval: 0,
+        }
+    }
+}
+
+impl APosConfig {
+    /// Applies the fields set in `update` on top of this config.
+    pub fn apply_update(&mut self, update: &APosConfigUpdate) {
+        if let Some(period) = update.period {
+            self.period = period;
+        }
+        if let Some(epoch) = update.epoch {
+            self.epoch = epoch;
         }
+        if let Some(reward_epoch) = update.reward_epoch {
+            self.reward_epoch = reward_epoch;
+        }
+        if let Some(reward_limit) = update.reward_limit {
+            self.reward_limit = reward_limit;
+        }
+        if let Some(deposit_contract) = update.deposit_contract {
+            self.deposit_contract = deposit_contract;
+        }
+        if let Some(checkpoint_interval) = update.checkpoint_interval {
+            self.checkpoint_interval = checkpoint_interval;
+        }
+        if let Some(heartbeat_interval) = update.heartbeat_interval {
+            self.heartbeat_interval = heartbeat_interval;
+        }
+    }
+
+    /// Whether a block without transactions may be sealed at `timestamp` on top of a parent sealed
+    /// at `parent_timestamp`.
+    pub const fn allows_empty_block(&self, parent_timestamp: u64, timestamp: u64) -> bool {
+        self.heartbeat_interval == 0 || timestamp >= parent_timestamp + self.heartbeat_interval
     }
 }
 
+/// Changes to [`APosConfig`] that take effect once a hardfork activates.
+///
+/// Fields left as `None` keep the value that was in effect before the fork.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct APosConfigUpdate {
+    /// New number of seconds between blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<u64>,
+    /// New epoch length
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<u64>,
+    /// New reward epoch duration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward_epoch: Option<u64>,
+    /// New maximum reward limit per epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward_limit: Option<U256>,
+    /// New deposit contract
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deposit_contract: Option<Address>,
+    /// New snapshot persistence interval, only valid in the genesis `apos` section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_interval: Option<u64>,
+    /// New minimum number of seconds between an empty block and its parent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heartbeat_interval: Option<u64>,
+}
+
 /// snapshot
 #[derive(Clone, Debug, PartialEq, Eq, Serialize,Deserialize,Arbitrary,Default)]
 pub struct  Snapshot
@@ -203,137 +280,50 @@ impl Snapshot
         }
     }
 
-	 /// Create a new authorization snapshot using the given header information
-	 pub fn apply<F, H>(&self, headers: Vec<H>,func:F) -> Result<Self, VotingError>
-     where
-         F: Fn(H) -> Result<Address, Box<dyn Error>>,
-         H: BlockHeaderTrait,
-     {
+    /// Create a new authorization snapshot using the given header information
+    ///
+    /// `func` recovers the signer of a header.
+    pub fn apply<F, H>(self, headers: Vec<SealedHeader<H>>, mut func: F) -> Result<Self, VotingError>
+    where
+        F: FnMut(&SealedHeader<H>) -> Result<Address, Box<dyn Error>>,
+        H: BlockHeaderTrait,
+    {
         //If there is no header information, return the current snapshot directly
         if headers.is_empty() {
-            return Ok(self.clone());
-        }
-
-        //Check the validity of header information
-        for i in 0..headers.len() - 1 {
-            if headers[i + 1].number() != headers[i].number() + 1 {
-                return Err(VotingError::InvalidVotingChain);
-            }
-        }
-        if headers[0].number() != self.number + 1 {
-            return Err(VotingError::InvalidVotingChain);
+            return Ok(self);
         }
 
-        //Create a new snapshot
-        let mut snap = self.copy();
+        let mut applier = SnapshotApplier::new(self);
         let start = Instant::now();
-        let logged = Instant::now();
-
-        for (i, i_header) in headers.iter().enumerate() {
-            let header = i_header;
-            let number = header.number();
-
-            //If it is a checkpoint block, remove all votes
-            if number % self.config.epoch == 0 {
-                snap.votes.clear();
-                snap.tally.clear();
-            }
-
-            //Remove the oldest signer from the recent signer collection to allow them to sign again
-            let limit = snap.signers.len() as u64 / 2 + 1;
-            if number >= limit {
-                snap.recents.remove(&(number - limit));
-            }
-
-            //Verify the signer and check if they are in the signer list
-            let signer = func(header.clone()).map_err(|e| VotingError::RecoverError(e.to_string()))?;
-            if !snap.signers.contains(&signer) {
-                return Err(VotingError::UnauthorizedSigner);
-            }
+        let mut logged = Instant::now();
 
-            if snap.recents.values().any(|&recent| recent == signer) {
-                return Err(VotingError::SignerRecentlySigned);
-            }
-            snap.recents.insert(number, signer);
-
-            //Discard any previous votes of the signer
-            while let Some(i) = snap.votes.iter().position(|vote| vote.signer == signer && vote.address == header.beneficiary()) {
-                snap.uncast(snap.votes[i].address, snap.votes[i].authorize);
-                snap.votes.remove(i);
-            }
-
-            //Count new votes
-            let authorize = match header.nonce().unwrap() {
-                nonce if hex::encode(nonce) == hex::encode(NONCE_AUTH_VOTE) => true,
-                nonce if hex::encode(nonce) == hex::encode(NONCE_DROP_VOTE) => false,
-                _ => return Err(VotingError::InvalidVote),
-            };
-
-            if snap.cast(header.beneficiary(), authorize) {
-                snap.votes.push(Vote {
-                    signer,
-                    block: number,
-                    address: header.beneficiary(),
-                    authorize,
-                });
-            }
-
-            //If the vote is passed, update the list of signatories
-            if let Some(tally) = snap.tally.get(&header.beneficiary()) {
-                if tally.votes > (snap.signers.len() / 2).try_into().unwrap() {
-                    if tally.authorize {
-                        snap.signers.push(header.beneficiary());
-                    } else {
-                        if let Some(pos) = snap.signers.iter().position(|x| *x == header.beneficiary()) {
-                            snap.signers.remove(pos);
-                        }
-                        // snap.signers.remove(header.beneficiary);
-
-                        //Reduce the signer list and delete any remaining recent cache
-                        let limit = snap.signers.len() as u64 / 2 + 1;
-                        if number >= limit {
-                            snap.recents.remove(&(number - limit));
-                        }
-
-                       //Discard any previous votes of the revoked authorized signatory
-                        while let Some(i) = snap.votes.iter().position(|vote| vote.signer == header.beneficiary()) {
-                            snap.uncast(snap.votes[i].address, snap.votes[i].authorize);
-                            snap.votes.remove(i);
-                        }
-                    }
-
-                    //Discard any previous votes that have just changed the account
-                    snap.votes.retain(|vote| vote.address != header.beneficiary());
-                    snap.tally.remove(&header.beneficiary());
-                }
-            }
+        for (i, header) in headers.iter().enumerate() {
+            let signer = func(header).map_err(|e| VotingError::RecoverError(e.to_string()))?;
+            applier.apply_header(header, signer)?;
 
             //If the operation takes too long, notify the user regularly
             if logged.elapsed() > Duration::from_secs(8) {
-                
                 info!(
                     target: "Apos",
-					"Reconstructing voting history: i={}, headers.len()={}, elapsed={:?}",
-					i,
-					headers.len(),
-					start.elapsed()
-				);
+                    "Reconstructing voting history: i={}, headers.len()={}, elapsed={:?}",
+                    i,
+                    headers.len(),
+                    start.elapsed()
+                );
+                logged = Instant::now();
             }
         }
 
         if start.elapsed() > Duration::from_secs(8) {
             info!(
                 target: "Apos",
-				"Reconstructed voting history: headers.len()={}, elapsed={:?}",
-				headers.len(),
-				start.elapsed()
-			);
+                "Reconstructed voting history: headers.len()={}, elapsed={:?}",
+                headers.len(),
+                start.elapsed()
+            );
         }
 
-        snap.number = headers.last().unwrap().number();
-        snap.hash = headers.last().unwrap().hash_slow();
-
-        Ok(snap)
+        Ok(applier.into_snapshot())
     }
 
 	 /// signers retrieves the list of authorized signers in ascending order.
@@ -356,4 +346,265 @@ impl Snapshot
         //Determine whether the signer of a given block height is an in turn signer
         ((number - 1) % signers.len() as u64) == offset as u64
     }
+
+    /// Whether `signer` sealed one of the recent blocks, and must wait for others before
+    /// sealing block `number`.
+    pub fn recently_signed(&self, number: u64, signer: &Address) -> bool {
+        let limit = (self.signers.len() as u64 / 2) + 1;
+        self.recents
+            .iter()
+            .any(|(seen, recent)| recent == signer && (number < limit || *seen > number - limit))
+    }
+}
+
+/// Applies headers one by one to a [`Snapshot`].
+///
+/// Indexes of the signers, the recent signers and the votes, built once when the applier is
+/// created, make each header cost constant time, amortized over the votes it later discards.
+#[derive(Debug)]
+pub struct SnapshotApplier {
+    snap: Snapshot,
+    /// The authorized signers
+    signers: HashSet<Address>,
+    /// Number of blocks of each signer among the recent ones
+    recent_signers: HashMap<Address, usize>,
+    /// Votes in chronological order, discarded ones left as `None` until the next compaction
+    votes: Vec<Option<Vote>>,
+    /// Position in `votes` of the vote of each signer on each account
+    vote_positions: HashMap<(Address, Address), usize>,
+    /// Accounts each signer has a vote on
+    votes_by_signer: HashMap<Address, HashSet<Address>>,
+    /// Signers having a vote on each account
+    votes_by_address: HashMap<Address, HashSet<Address>>,
+}
+
+impl SnapshotApplier {
+    /// Creates an applier continuing from `snap`.
+    pub fn new(mut snap: Snapshot) -> Self {
+        let mut applier = Self {
+            signers: snap.signers.iter().copied().collect(),
+            recent_signers: HashMap::new(),
+            votes: Vec::new(),
+            vote_positions: HashMap::new(),
+            votes_by_signer: HashMap::new(),
+            votes_by_address: HashMap::new(),
+            snap: Snapshot::default(),
+        };
+        for signer in snap.recents.values() {
+            *applier.recent_signers.entry(*signer).or_default() += 1;
+        }
+        for vote in std::mem::take(&mut snap.votes) {
+            applier.push_vote(vote);
+        }
+        applier.snap = snap;
+        applier
+    }
+
+    /// The snapshot at the last applied header, without the votes.
+    pub const fn snapshot(&self) -> &Snapshot {
+        &self.snap
+    }
+
+    /// Returns the snapshot at the last applied header.
+    pub fn into_snapshot(mut self) -> Snapshot {
+        self.snap.votes = self.votes.into_iter().flatten().collect();
+        self.snap
+    }
+
+    /// Applies the next header, sealed by `signer`.
+    pub fn apply_header<H: BlockHeaderTrait>(
+        &mut self,
+        header: &SealedHeader<H>,
+        signer: Address,
+    ) -> Result<(), VotingError> {
+        let number = header.header().number();
+        if number != self.snap.number + 1 {
+            return Err(VotingError::InvalidVotingChain);
+        }
+
+        //If it is a checkpoint block, remove all votes
+        if number % self.snap.config.epoch == 0 {
+            self.clear_votes();
+        }
+
+        //Remove the oldest signer from the recent signer collection to allow them to sign again
+        self.forget_recent(number);
+
+        //Verify the signer and check if they are in the signer list
+        if !self.signers.contains(&signer) {
+            return Err(VotingError::UnauthorizedSigner);
+        }
+        if self.recent_signers.contains_key(&signer) {
+            return Err(VotingError::SignerRecentlySigned);
+        }
+        self.snap.recents.insert(number, signer);
+        *self.recent_signers.entry(signer).or_default() += 1;
+
+        //Discard any previous vote of the signer on the same account
+        let address = header.header().beneficiary();
+        if let Some(vote) = self.remove_vote(signer, address) {
+            self.snap.uncast(vote.address, vote.authorize);
+        }
+
+        //Count new votes
+        let authorize = match header.header().nonce() {
+            Some(nonce) if nonce == NONCE_AUTH_VOTE => true,
+            Some(nonce) if nonce == NONCE_DROP_VOTE => false,
+            _ => return Err(VotingError::InvalidVote),
+        };
+        if self.cast(address, authorize) {
+            self.push_vote(Vote { signer, block: number, address, authorize });
+        }
+
+        //If the vote is passed, update the list of signatories
+        let passed =
+            self.snap.tally.get(&address).filter(|tally| tally.votes as usize > self.snap.signers.len() / 2).cloned();
+        if let Some(tally) = passed {
+            if tally.authorize {
+                self.snap.signers.push(address);
+                self.signers.insert(address);
+            } else {
+                if let Some(pos) = self.snap.signers.iter().position(|x| *x == address) {
+                    self.snap.signers.remove(pos);
+                }
+                self.signers.remove(&address);
+
+                //Reduce the signer list and delete any remaining recent cache
+                self.forget_recent(number);
+
+                //Discard any previous votes of the revoked authorized signatory
+                for voted in self.votes_by_signer.get(&address).cloned().unwrap_or_default() {
+                    if let Some(vote) = self.remove_vote(address, voted) {
+                        self.snap.uncast(vote.address, vote.authorize);
+                    }
+                }
+            }
+
+            //Discard any previous votes that have just changed the account
+            for voter in self.votes_by_address.get(&address).cloned().unwrap_or_default() {
+                self.remove_vote(voter, address);
+            }
+            self.snap.tally.remove(&address);
+        }
+
+        self.snap.number = number;
+        self.snap.hash = header.hash();
+        Ok(())
+    }
+
+    /// [`Snapshot::cast`] with the signer set index.
+    fn cast(&mut self, address: Address, authorize: bool) -> bool {
+        if self.signers.contains(&address) == authorize {
+            return false;
+        }
+        self.snap.tally.entry(address).or_insert(Tally { authorize, votes: 0 }).votes += 1;
+        true
+    }
+
+    /// Drops the recent signer of the block that leaves the recent window at `number`.
+    fn forget_recent(&mut self, number: u64) {
+        let limit = self.snap.signers.len() as u64 / 2 + 1;
+        if number < limit {
+            return;
+        }
+        if let Some(signer) = self.snap.recents.remove(&(number - limit)) {
+            if let Some(count) = self.recent_signers.get_mut(&signer) {
+                *count -= 1;
+                if *count == 0 {
+                    self.recent_signers.remove(&signer);
+                }
+            }
+        }
+    }
+
+    fn push_vote(&mut self, vote: Vote) {
+        let key = (vote.signer, vote.address);
+        if let Some(previous) = self.vote_positions.insert(key, self.votes.len()) {
+            self.votes[previous] = None;
+        }
+        self.votes_by_signer.entry(vote.signer).or_default().insert(vote.address);
+        self.votes_by_address.entry(vote.address).or_default().insert(vote.signer);
+        self.votes.push(Some(vote));
+    }
+
+    fn remove_vote(&mut self, signer: Address, address: Address) -> Option<Vote> {
+        let position = self.vote_positions.remove(&(signer, address))?;
+        if let Some(addresses) = self.votes_by_signer.get_mut(&signer) {
+            addresses.remove(&address);
+            if addresses.is_empty() {
+                self.votes_by_signer.remove(&signer);
+            }
+        }
+        if let Some(signers) = self.votes_by_address.get_mut(&address) {
+            signers.remove(&signer);
+            if signers.is_empty() {
+                self.votes_by_address.remove(&address);
+            }
+        }
+        let vote = self.votes[position].take();
+        self.compact_votes();
+        vote
+    }
+
+    /// Drops the discarded votes once they outnumber the live ones.
+    fn compact_votes(&mut self) {
+        if self.votes.len() < 64 || self.votes.len() < 2 * self.vote_positions.len() {
+            return;
+        }
+        self.votes.retain(Option::is_some);
+        for (position, vote) in self.votes.iter().enumerate() {
+            if let Some(vote) = vote {
+                self.vote_positions.insert((vote.signer, vote.address), position);
+            }
+        }
+    }
+
+    fn clear_votes(&mut self) {
+        self.votes.clear();
+        self.vote_positions.clear();
+        self.votes_by_signer.clear();
+        self.votes_by_address.clear();
+        self.snap.tally.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(number: u64, beneficiary: Address, nonce: B64) -> SealedHeader {
+        let header = Header { number, beneficiary, nonce, ..Default::default() };
+        SealedHeader::new(header, B256::with_last_byte(number as u8))
+    }
+
+    #[test]
+    fn applies_votes_and_recent_signers() {
+        let [a, b, c, d] = [1, 2, 3, 4].map(Address::with_last_byte);
+        let snap = Snapshot::new_snapshot(APosConfig::default(), 0, B256::ZERO, vec![a, b, c]);
+        let mut applier = SnapshotApplier::new(snap);
+
+        applier.apply_header(&header(1, d, NONCE_AUTH_VOTE), a).unwrap();
+        assert_eq!(applier.snapshot().tally.get(&d).map(|tally| tally.votes), Some(1));
+        // the second vote of three signers passes
+        applier.apply_header(&header(2, d, NONCE_AUTH_VOTE), b).unwrap();
+        applier.apply_header(&header(3, Address::ZERO, NONCE_DROP_VOTE), c).unwrap();
+        applier.apply_header(&header(4, Address::ZERO, NONCE_DROP_VOTE), d).unwrap();
+
+        // four signers keep the last three blocks
+        assert!(matches!(
+            applier.apply_header(&header(5, Address::ZERO, NONCE_DROP_VOTE), c),
+            Err(VotingError::SignerRecentlySigned)
+        ));
+        applier.apply_header(&header(5, Address::ZERO, NONCE_DROP_VOTE), a).unwrap();
+        assert!(matches!(
+            applier.apply_header(&header(7, Address::ZERO, NONCE_DROP_VOTE), b),
+            Err(VotingError::InvalidVotingChain)
+        ));
+
+        let snap = applier.into_snapshot();
+        assert_eq!(snap.signers, vec![a, b, c, d]);
+        assert!(snap.votes.is_empty() && snap.tally.is_empty());
+        assert_eq!(snap.recents, HashMap::from([(3, c), (4, d), (5, a)]));
+        assert_eq!((snap.number, snap.hash), (5, B256::with_last_byte(5)));
+    }
 }
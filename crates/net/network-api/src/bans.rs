@@ -0,0 +1,222 @@
+//! Bans of the peer set.
+//!
+//! Peers are banned by the network when their reputation drops too low or they break the
+//! protocol, and by operators through [`Peers::ban`](crate::Peers::ban). Bans are listed as
+//! [`BanEntry`]s, which also survive restarts.
+
+use crate::PeerId;
+use reth_network_peers::NodeRecord;
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+/// An IP subnet in CIDR notation, e.g. `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpSubnet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpSubnet {
+    /// Returns the subnet of the first `prefix_len` bits of `addr`, or `None` if the prefix is
+    /// longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let addr = match addr {
+            IpAddr::V4(addr) if prefix_len <= 32 => IpAddr::V4(mask_v4(addr, prefix_len)),
+            IpAddr::V6(addr) if prefix_len <= 128 => IpAddr::V6(mask_v6(addr, prefix_len)),
+            _ => return None,
+        };
+        Some(Self { addr, prefix_len })
+    }
+
+    /// Returns the first address of the subnet.
+    pub const fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the number of leading bits shared by the addresses of the subnet.
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns whether `ip` belongs to the subnet.
+    ///
+    /// IPv4 addresses mapped to IPv6 belong to the IPv4 subnets.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => mask_v4(*ip, self.prefix_len) == net,
+            (IpAddr::V4(net), IpAddr::V6(ip)) => {
+                ip.to_ipv4_mapped().is_some_and(|ip| mask_v4(ip, self.prefix_len) == net)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => mask_v6(*ip, self.prefix_len) == net,
+            (IpAddr::V6(_), IpAddr::V4(_)) => false,
+        }
+    }
+}
+
+fn mask_v4(addr: Ipv4Addr, prefix_len: u8) -> Ipv4Addr {
+    let mask = u32::MAX.checked_shl(32 - u32::from(prefix_len)).unwrap_or(0);
+    Ipv4Addr::from(u32::from(addr) & mask)
+}
+
+fn mask_v6(addr: Ipv6Addr, prefix_len: u8) -> Ipv6Addr {
+    let mask = u128::MAX.checked_shl(128 - u32::from(prefix_len)).unwrap_or(0);
+    Ipv6Addr::from(u128::from(addr) & mask)
+}
+
+impl fmt::Display for IpSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpSubnet {
+    type Err = ParseBanTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseBanTargetError(s.to_string());
+        let (addr, prefix_len) = s.split_once('/').ok_or_else(err)?;
+        let addr = addr.parse().map_err(|_| err())?;
+        let prefix_len = prefix_len.parse().map_err(|_| err())?;
+        Self::new(addr, prefix_len).ok_or_else(err)
+    }
+}
+
+/// What a ban applies to.
+///
+/// Parsed from a peer id, an enode URL, an IP address or a subnet in CIDR notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BanTarget {
+    /// A single peer, whatever its address.
+    Peer(PeerId),
+    /// All peers connecting from or listening on the IP address.
+    Ip(IpAddr),
+    /// All peers with an IP address in the subnet.
+    Subnet(IpSubnet),
+}
+
+impl BanTarget {
+    /// Returns whether the peer `peer_id` reached at `ip` is banned by this target.
+    pub fn matches(&self, peer_id: &PeerId, ip: &IpAddr) -> bool {
+        match self {
+            Self::Peer(banned) => banned == peer_id,
+            Self::Ip(banned) => banned == ip,
+            Self::Subnet(subnet) => subnet.contains(ip),
+        }
+    }
+
+    /// Returns whether connections from `ip` are banned by this target.
+    pub fn matches_ip(&self, ip: &IpAddr) -> bool {
+        match self {
+            Self::Peer(_) => false,
+            Self::Ip(banned) => banned == ip,
+            Self::Subnet(subnet) => subnet.contains(ip),
+        }
+    }
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Peer(peer_id) => write!(f, "{peer_id:x}"),
+            Self::Ip(ip) => ip.fmt(f),
+            Self::Subnet(subnet) => subnet.fmt(f),
+        }
+    }
+}
+
+impl FromStr for BanTarget {
+    type Err = ParseBanTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains('/') && !s.starts_with("enode://") {
+            return s.parse().map(Self::Subnet)
+        }
+        if let Ok(ip) = s.parse() {
+            return Ok(Self::Ip(ip))
+        }
+        if let Ok(record) = s.parse::<NodeRecord>() {
+            return Ok(Self::Peer(record.id))
+        }
+        s.parse().map(Self::Peer).map_err(|_| ParseBanTargetError(s.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BanTarget {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BanTarget {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Error parsing a [`BanTarget`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("expected a peer id, enode URL, IP address or CIDR subnet, got {0:?}")]
+pub struct ParseBanTargetError(String);
+
+/// A ban of the peer set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct BanEntry {
+    /// The banned peer, IP or subnet.
+    pub target: BanTarget,
+    /// Why it was banned.
+    pub reason: String,
+    /// Unix timestamp in seconds at which the ban is lifted, `None` if it is permanent.
+    pub expires_at: Option<u64>,
+}
+
+impl BanEntry {
+    /// Returns whether the ban is lifted at the unix timestamp `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ban_targets() {
+        let peer_id = PeerId::random();
+        assert_eq!(format!("{peer_id:x}").parse(), Ok(BanTarget::Peer(peer_id)));
+        assert_eq!(format!("{peer_id}").parse(), Ok(BanTarget::Peer(peer_id)));
+        assert_eq!(
+            format!("enode://{peer_id:x}@10.0.0.1:30303").parse(),
+            Ok(BanTarget::Peer(peer_id))
+        );
+        assert_eq!("10.0.0.1".parse(), Ok(BanTarget::Ip("10.0.0.1".parse().unwrap())));
+
+        let target: BanTarget = "10.1.2.3/16".parse().unwrap();
+        assert_eq!(target.to_string(), "10.1.0.0/16");
+        assert!("10.0.0.0/33".parse::<BanTarget>().is_err());
+        assert!("not a peer".parse::<BanTarget>().is_err());
+    }
+
+    #[test]
+    fn subnet_contains() {
+        let subnet: IpSubnet = "192.168.0.0/16".parse().unwrap();
+        assert!(subnet.contains(&"192.168.10.1".parse().unwrap()));
+        assert!(subnet.contains(&"::ffff:192.168.10.1".parse().unwrap()));
+        assert!(!subnet.contains(&"192.169.0.1".parse().unwrap()));
+
+        let all: IpSubnet = "0.0.0.0/0".parse().unwrap();
+        assert!(all.contains(&"8.8.8.8".parse().unwrap()));
+
+        let subnet: IpSubnet = "2001:db8::/32".parse().unwrap();
+        assert!(subnet.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(!subnet.contains(&"2001:db9::1".parse().unwrap()));
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashSet;
-use alloy_primitives::{BlockHash, TxHash, B256};
+use std::future::Future;
+use alloy_primitives::{BlockHash, BlockNumber, TxHash, B256};
 use derive_more::Error;
 use reth_errors::BlockExecutionError;
 use reth_eth_wire_types::NewBlock;
 use reth_tokio_util::EventStream;
 use reth_consensus::ConsensusError;
+use crate::{NetworkError, PeerId};
 
 /// block import error.
 #[derive(Debug, Clone)]
@@ -41,4 +43,57 @@ pub trait BlockAnnounceProvider {
 
     fn validated_block(&self, result: N42BlockImportOutcome<Self::Block>);
 
+    /// Returns how the `limit` most recently seen blocks travelled through the node, newest
+    /// first.
+    fn block_propagation(
+        &self,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<BlockPropagation>, NetworkError>> + Send;
+}
+
+/// Where the local node got a block from first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", tag = "kind"))]
+pub enum BlockOrigin {
+    /// Sealed by the node, or handed over by its consensus client.
+    Local,
+    /// Received from a peer.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Peer {
+        /// The peer that sent the block or announced its hash.
+        peer_id: PeerId,
+    },
+}
+
+/// A block sent to or received from a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct BlockHop {
+    /// The other end.
+    pub peer_id: PeerId,
+    /// Milliseconds since the block was first seen by the node.
+    pub delay_ms: u64,
+    /// Whether the whole block was sent, rather than its hash.
+    pub full: bool,
+}
+
+/// The propagation of a block through the local node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct BlockPropagation {
+    /// Hash of the block.
+    pub hash: B256,
+    /// Number of the block.
+    pub number: BlockNumber,
+    /// Unix timestamp in milliseconds at which the node first saw the block.
+    pub first_seen_ms: u64,
+    /// Where the block, or its hash, was first seen.
+    pub origin: BlockOrigin,
+    /// The copies of the block and the announcements of its hash received after the first one.
+    pub received: Vec<BlockHop>,
+    /// The block and hash announcements sent to peers.
+    pub relayed: Vec<BlockHop>,
 }
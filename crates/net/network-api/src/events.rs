@@ -1,5 +1,6 @@
 //! API related to listening for network events.
 
+use alloy_primitives::Address;
 use reth_eth_wire_types::{
     message::RequestPair, BlockBodies, BlockHeaders, Capabilities, DisconnectReason, EthMessage,
     EthNetworkPrimitives, EthVersion, GetBlockBodies, GetBlockHeaders, GetNodeData,
@@ -154,6 +155,15 @@ pub enum DiscoveryEvent {
     NewNode(DiscoveredEvent),
     /// Retrieved a [`ForkId`] from the peer via ENR request, See <https://eips.ethereum.org/EIPS/eip-868>
     EnrForkId(PeerId, ForkId),
+    /// The `APos` signer advertised in the record of a peer was verified, or is no longer valid.
+    EnrSigner {
+        /// The peer advertising the signer.
+        peer_id: PeerId,
+        /// The address of the peer.
+        addr: PeerAddr,
+        /// The verified signer, `None` if the peer stopped being one.
+        signer: Option<Address>,
+    },
 }
 
 /// Represents events related to peer discovery in the network.
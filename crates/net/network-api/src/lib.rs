@@ -13,6 +13,7 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+pub mod bans;
 pub mod downloaders;
 /// Network Error
 pub mod error;
@@ -27,9 +28,12 @@ pub use alloy_rpc_types_admin::EthProtocolInfo;
 pub use reth_network_p2p::{BlockClient, HeadersClient};
 pub use reth_network_types::{PeerKind, Reputation, ReputationChangeKind};
 
+pub use bans::{BanEntry, BanTarget, IpSubnet};
 pub use downloaders::BlockDownloaderProvider;
 pub use block::BlockAnnounceProvider;
-pub use block::{N42BlockImportError, N42BlockImportOutcome};
+pub use block::{
+    BlockHop, BlockOrigin, BlockPropagation, N42BlockImportError, N42BlockImportOutcome,
+};
 pub use error::NetworkError;
 pub use events::{
     DiscoveredEvent, DiscoveryEvent, NetworkEvent, NetworkEventListenerProvider, PeerRequest,
@@ -41,7 +45,12 @@ use reth_eth_wire_types::{
 };
 use reth_network_p2p::sync::NetworkSyncUpdater;
 use reth_network_peers::NodeRecord;
-use std::{future::Future, net::SocketAddr, sync::Arc, time::Instant};
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// The `PeerId` type.
 pub type PeerId = alloy_primitives::B512;
@@ -216,6 +225,17 @@ pub trait Peers: PeersInfo {
         &self,
         peer_id: PeerId,
     ) -> impl Future<Output = Result<Option<Reputation>, NetworkError>> + Send;
+
+    /// Bans the peer, IP or subnet for `duration`, or until it is unbanned if `None`.
+    ///
+    /// Matching peers are disconnected and dropped from the peer set.
+    fn ban(&self, target: BanTarget, duration: Option<Duration>, reason: String);
+
+    /// Lifts the ban of the target, returns `false` if it wasn't banned.
+    fn unban(&self, target: BanTarget) -> impl Future<Output = Result<bool, NetworkError>> + Send;
+
+    /// Returns the current bans.
+    fn bans(&self) -> impl Future<Output = Result<Vec<BanEntry>, NetworkError>> + Send;
 }
 
 /// Info about an active peer session.
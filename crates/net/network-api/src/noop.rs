@@ -4,7 +4,10 @@
 //! generic over it.
 
 use core::{fmt, marker::PhantomData};
-use std::net::{IpAddr, SocketAddr};
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
 
 use alloy_rpc_types_admin::EthProtocolInfo;
 use enr::{secp256k1::SecretKey, Enr};
@@ -21,7 +24,7 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 use crate::{
     events::{NetworkPeersEvents, PeerEventStream},
     test_utils::{PeersHandle, PeersHandleProvider},
-    BlockDownloaderProvider, DiscoveryEvent, NetworkError, NetworkEvent,
+    BanEntry, BanTarget, BlockDownloaderProvider, DiscoveryEvent, NetworkError, NetworkEvent,
     NetworkEventListenerProvider, NetworkInfo, NetworkStatus, PeerId, PeerInfo, PeerRequest, Peers,
     PeersInfo,
 };
@@ -159,6 +162,16 @@ where
     async fn reputation_by_id(&self, _peer_id: PeerId) -> Result<Option<Reputation>, NetworkError> {
         Ok(None)
     }
+
+    fn ban(&self, _target: BanTarget, _duration: Option<Duration>, _reason: String) {}
+
+    async fn unban(&self, _target: BanTarget) -> Result<bool, NetworkError> {
+        Ok(false)
+    }
+
+    async fn bans(&self) -> Result<Vec<BanEntry>, NetworkError> {
+        Ok(Vec::new())
+    }
 }
 
 impl<Net> BlockDownloaderProvider for NoopNetwork<Net>
@@ -0,0 +1,79 @@
+//! Reasons and expiry of the bans of the [`PeersManager`](crate::peers::PeersManager).
+
+use reth_network_api::{BanEntry, BanTarget};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the file the bans are persisted to, next to the known peers file.
+pub const BANNED_PEERS_FILE_NAME: &str = "banned-peers.json";
+
+/// The bans of the peer set.
+///
+/// Peer bans are enforced by the [`BanList`](reth_net_banlist::BanList), which has no notion of
+/// subnets, so IP and subnet bans are checked against these entries.
+#[derive(Debug, Default)]
+pub(crate) struct Bans {
+    entries: HashMap<BanTarget, BanEntry>,
+    /// Whether the entries changed since [`Self::take_changed`] was last called.
+    changed: bool,
+}
+
+impl Bans {
+    /// Records a ban lasting `duration`, or until it is lifted if `None`.
+    pub(crate) fn insert(&mut self, target: BanTarget, duration: Option<Duration>, reason: String) {
+        let expires_at = duration.map(|duration| unix_now().saturating_add(duration.as_secs()));
+        self.restore(BanEntry { target, reason, expires_at });
+    }
+
+    /// Records a ban as it was persisted.
+    pub(crate) fn restore(&mut self, entry: BanEntry) {
+        self.entries.insert(entry.target, entry);
+        self.changed = true;
+    }
+
+    /// Removes the ban of `target`.
+    pub(crate) fn remove(&mut self, target: &BanTarget) -> Option<BanEntry> {
+        let entry = self.entries.remove(target);
+        self.changed |= entry.is_some();
+        entry
+    }
+
+    /// Returns whether connections from `ip` are banned.
+    pub(crate) fn is_banned_ip(&self, ip: &IpAddr) -> bool {
+        let now = unix_now();
+        self.entries.values().any(|entry| !entry.is_expired(now) && entry.target.matches_ip(ip))
+    }
+
+    /// Drops the bans that expired.
+    pub(crate) fn evict_expired(&mut self) {
+        let now = unix_now();
+        let len = self.entries.len();
+        self.entries.retain(|_, entry| !entry.is_expired(now));
+        self.changed |= self.entries.len() != len;
+    }
+
+    /// Returns whether a ban was added, lifted or expired since the last call.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Returns an iterator over all bans.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &BanEntry> + '_ {
+        self.entries.values()
+    }
+}
+
+/// Returns the current unix timestamp in seconds.
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Reads the bans persisted with
+/// [`NetworkManager::write_bans_to_file`](crate::NetworkManager::write_bans_to_file).
+#[cfg(feature = "serde")]
+pub fn read_bans_file(path: &std::path::Path) -> Result<Vec<BanEntry>, reth_fs_util::FsPathError> {
+    reth_fs_util::read_json_file(path)
+}
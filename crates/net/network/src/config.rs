@@ -3,6 +3,8 @@
 use crate::{
     error::NetworkError,
     import::{BlockImport, ProofOfStakeBlockImport},
+    session::BandwidthLimits,
+    signer_enr::{SignerEnrEntry, SignerSet, SIGNER_ENR_KEY},
     transactions::TransactionsManagerConfig,
     NetworkHandle, NetworkManager,
 };
@@ -15,17 +17,25 @@ use reth_eth_wire::{
     EthNetworkPrimitives, HelloMessage, HelloMessageWithProtocols, NetworkPrimitives, Status,
 };
 use reth_ethereum_forks::{ForkFilter, Head};
+use reth_network_api::BanEntry;
 use reth_network_peers::{mainnet_nodes, pk2id, sepolia_nodes, PeerId, TrustedPeer};
 use reth_network_types::{PeersConfig, SessionsConfig};
 use reth_storage_api::{noop::NoopProvider, BlockNumReader, BlockReader, HeaderProvider};
 use reth_tasks::{TaskSpawner, TokioTaskExecutor};
 use secp256k1::SECP256K1;
-use std::{collections::HashSet, net::SocketAddr, sync::Arc};
+use std::{collections::HashSet, net::SocketAddr, path::PathBuf, sync::Arc};
+use tracing::warn;
 
 // re-export for convenience
 use crate::protocol::{IntoRlpxSubProtocol, RlpxSubProtocols};
 pub use secp256k1::SecretKey;
 
+/// Key of the ENR entry holding the fork id of N42 chains.
+///
+/// The entry is only published and required by nodes configured with
+/// [`NetworkConfigBuilder::n42_fork_id_filter`].
+pub const N42_FORK_ID_ENR_KEY: &str = "n42";
+
 /// Convenience function to create a new random [`SecretKey`]
 pub fn rng_secret_key() -> SecretKey {
     SecretKey::new(&mut rand_08::thread_rng())
@@ -45,6 +55,8 @@ pub struct NetworkConfig<C, N: NetworkPrimitives = EthNetworkPrimitives> {
     pub boot_nodes: HashSet<TrustedPeer>,
     /// How to set up discovery over DNS.
     pub dns_discovery_config: Option<DnsDiscoveryConfig>,
+    /// DNS server to resolve the EIP-1459 trees with, instead of the system resolver.
+    pub dns_server: Option<SocketAddr>,
     /// Address to use for discovery v4.
     pub discovery_v4_addr: SocketAddr,
     /// How to set up discovery.
@@ -55,8 +67,27 @@ pub struct NetworkConfig<C, N: NetworkPrimitives = EthNetworkPrimitives> {
     pub listener_addr: SocketAddr,
     /// How to instantiate peer manager.
     pub peers_config: PeersConfig,
+    /// `APos` signer nodes to keep connected to, see [`PeersManager::with_signer_peers`].
+    ///
+    /// [`PeersManager::with_signer_peers`]: crate::peers::PeersManager::with_signer_peers
+    pub signer_peers: Vec<TrustedPeer>,
+    /// The `APos` signer entry published in the local discovery records.
+    pub signer_enr: Option<SignerEnrEntry>,
+    /// The signers against which the signer entries of discovered nodes are verified.
+    pub signer_set: Option<SignerSet>,
+    /// Whether to publish the fork id under [`N42_FORK_ID_ENR_KEY`] and only dial the discovered
+    /// nodes announcing a fork id.
+    pub n42_fork_id_filter: bool,
     /// How to configure the [`SessionManager`](crate::session::SessionManager).
     pub sessions_config: SessionsConfig,
+    /// Byte rate limits of the sessions.
+    pub bandwidth_limits: BandwidthLimits,
+    /// The bans restored at startup.
+    pub bans: Vec<BanEntry>,
+    /// The file the bans are written to whenever they change.
+    pub bans_file: Option<PathBuf>,
+    /// The file the records of the known peers are written to periodically.
+    pub enrs_file: Option<PathBuf>,
     /// The chain id
     pub chain_id: u64,
     /// The [`ForkFilter`] to use at launch for authenticating sessions.
@@ -181,6 +212,8 @@ pub struct NetworkConfigBuilder<N: NetworkPrimitives = EthNetworkPrimitives> {
     secret_key: SecretKey,
     /// How to configure discovery over DNS.
     dns_discovery_config: Option<DnsDiscoveryConfig>,
+    /// DNS server to resolve the EIP-1459 trees with.
+    dns_server: Option<SocketAddr>,
     /// How to set up discovery version 4.
     discovery_v4_builder: Option<Discv4ConfigBuilder>,
     /// How to set up discovery version 5.
@@ -193,8 +226,24 @@ pub struct NetworkConfigBuilder<N: NetworkPrimitives = EthNetworkPrimitives> {
     listener_addr: Option<SocketAddr>,
     /// How to instantiate peer manager.
     peers_config: Option<PeersConfig>,
+    /// `APos` signer nodes to keep connected to.
+    signer_peers: Vec<TrustedPeer>,
+    /// Key of the `APos` signer running this node.
+    apos_signer_key: Option<SecretKey>,
+    /// The current `APos` signers.
+    signer_set: Option<SignerSet>,
+    /// Whether to publish and require the N42 fork id entry.
+    n42_fork_id_filter: bool,
     /// How to configure the sessions manager
     sessions_config: Option<SessionsConfig>,
+    /// Byte rate limits of the sessions.
+    bandwidth_limits: BandwidthLimits,
+    /// The bans restored at startup.
+    bans: Vec<BanEntry>,
+    /// The file the bans are written to whenever they change.
+    bans_file: Option<PathBuf>,
+    /// The file the records of the known peers are written to periodically.
+    enrs_file: Option<PathBuf>,
     /// The default mode of the network.
     network_mode: NetworkMode,
     /// The executor to use for spawning tasks.
@@ -239,13 +288,22 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
         Self {
             secret_key,
             dns_discovery_config: Some(Default::default()),
+            dns_server: None,
             discovery_v4_builder: Some(Default::default()),
             discovery_v5_builder: None,
             boot_nodes: Default::default(),
             discovery_addr: None,
             listener_addr: None,
             peers_config: None,
+            signer_peers: Vec::new(),
+            apos_signer_key: None,
+            signer_set: None,
+            n42_fork_id_filter: false,
             sessions_config: None,
+            bandwidth_limits: Default::default(),
+            bans: Vec::new(),
+            bans_file: None,
+            enrs_file: None,
             network_mode: Default::default(),
             executor: None,
             hello_message: None,
@@ -325,6 +383,49 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
         self
     }
 
+    /// Sets the `APos` signer nodes to keep connected to.
+    ///
+    /// Signer peers get connection slots on top of the configured limits and are retried shortly
+    /// after failed connection attempts, so that sealed blocks reach the other signers directly.
+    pub fn signer_peers<T: Into<TrustedPeer>>(
+        mut self,
+        nodes: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.signer_peers = nodes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the key of the `APos` signer running this node.
+    ///
+    /// The node publishes a [`SignerEnrEntry`] signed by this key in its discovery records, so
+    /// that other nodes can tell it is run by the signer.
+    pub const fn apos_signer_key(mut self, signer_key: SecretKey) -> Self {
+        self.apos_signer_key = Some(signer_key);
+        self
+    }
+
+    /// Sets the current `APos` signers.
+    ///
+    /// Discovered nodes whose records carry a valid [`SignerEnrEntry`] of one of them are kept
+    /// connected like the configured [signer peers](Self::signer_peers).
+    pub fn signer_set(mut self, signer_set: SignerSet) -> Self {
+        self.signer_set = Some(signer_set);
+        self
+    }
+
+    /// Publishes the fork id of the chain under [`N42_FORK_ID_ENR_KEY`] and only dials the
+    /// discovered nodes announcing a fork id.
+    ///
+    /// Chains without an `eth` network stack id don't publish their fork id over discv5, so nodes
+    /// of unrelated chains are only told apart at the `Status` handshake. With the filter, the
+    /// fork id is read from the [`N42_FORK_ID_ENR_KEY`] entry for discv5 and DNS records, and from
+    /// the `eth` entry for discv4 records, which are held back until their ENR is received. Nodes
+    /// with an incompatible fork id are never added to the peer set.
+    pub const fn n42_fork_id_filter(mut self, enabled: bool) -> Self {
+        self.n42_fork_id_filter = enabled;
+        self
+    }
+
     /// Sets the executor to use for spawning tasks.
     ///
     /// If `None`, then [`tokio::spawn`] is used for spawning tasks.
@@ -436,6 +537,13 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
         self
     }
 
+    /// Sets the DNS server to resolve the EIP-1459 trees with, e.g. a local server standing in for
+    /// the public DNS in tests.
+    pub const fn dns_server(mut self, server: Option<SocketAddr>) -> Self {
+        self.dns_server = server;
+        self
+    }
+
     /// Convenience function for setting [`Self::boot_nodes`] to the mainnet boot nodes.
     pub fn mainnet_boot_nodes(self) -> Self {
         self.boot_nodes(mainnet_nodes())
@@ -564,6 +672,36 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
         self
     }
 
+    /// Sets the global and per-peer byte rate limits of the sessions.
+    ///
+    /// Blocks and the messages of other sub-protocols are sent ahead of the transaction gossip and
+    /// the requests, which are held back while a limit is exceeded. The transaction gossip received
+    /// while a limit is exceeded is dropped.
+    pub const fn bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.bandwidth_limits = limits;
+        self
+    }
+
+    /// Sets the bans to restore, usually read from the
+    /// [`BANNED_PEERS_FILE_NAME`](crate::BANNED_PEERS_FILE_NAME) file.
+    pub fn bans(mut self, bans: Vec<BanEntry>) -> Self {
+        self.bans = bans;
+        self
+    }
+
+    /// Sets the file the bans are written to whenever a peer, IP or subnet is banned or unbanned.
+    pub fn bans_file(mut self, bans_file: Option<PathBuf>) -> Self {
+        self.bans_file = bans_file;
+        self
+    }
+
+    /// Sets the file the records of the known peers are written to periodically, see
+    /// [`KNOWN_ENRS_FILE_NAME`](crate::KNOWN_ENRS_FILE_NAME).
+    pub fn enrs_file(mut self, enrs_file: Option<PathBuf>) -> Self {
+        self.enrs_file = enrs_file;
+        self
+    }
+
     /// Consumes the type and creates the actual [`NetworkConfig`]
     /// for the given client type that can interact with the chain.
     ///
@@ -579,13 +717,22 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
         let Self {
             secret_key,
             mut dns_discovery_config,
+            dns_server,
             discovery_v4_builder,
             mut discovery_v5_builder,
             boot_nodes,
             discovery_addr,
             listener_addr,
             peers_config,
+            signer_peers,
+            apos_signer_key,
+            signer_set,
+            n42_fork_id_filter,
             sessions_config,
+            bandwidth_limits,
+            bans,
+            bans_file,
+            enrs_file,
             network_mode,
             executor,
             hello_message,
@@ -607,7 +754,10 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
         });
 
         discovery_v5_builder = discovery_v5_builder.map(|mut builder| {
-            if let Some(network_stack_id) = NetworkStackId::id(&chain_spec) {
+            if n42_fork_id_filter {
+                let fork_id = chain_spec.fork_id(&head);
+                builder = builder.fork(N42_FORK_ID_ENR_KEY.as_bytes(), fork_id)
+            } else if let Some(network_stack_id) = NetworkStackId::id(&chain_spec) {
                 let fork_id = chain_spec.fork_id(&head);
                 builder = builder.fork(network_stack_id, fork_id)
             }
@@ -615,6 +765,14 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
             builder
         });
 
+        let signer_enr =
+            apos_signer_key.map(|signer_key| SignerEnrEntry::new(peer_id, &signer_key));
+        if let Some(entry) = &signer_enr {
+            discovery_v5_builder = discovery_v5_builder.map(|builder| {
+                builder.add_enr_kv_pair(SIGNER_ENR_KEY.as_bytes(), alloy_rlp::encode(entry).into())
+            });
+        }
+
         let listener_addr = listener_addr.unwrap_or(DEFAULT_DISCOVERY_ADDRESS);
 
         let mut hello_message =
@@ -635,7 +793,17 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
             dns_discovery_config.as_mut().and_then(|c| c.bootstrap_dns_networks.as_mut())
         {
             if dns_networks.is_empty() {
-                if let Some(link) = chain_spec.chain().public_dns_network_protocol() {
+                if let Some(link) = chain_spec.dns_discovery() {
+                    // validated when the chain spec was read from a genesis file
+                    match link.parse() {
+                        Ok(link) => {
+                            dns_networks.insert(link);
+                        }
+                        Err(err) => {
+                            warn!(target: "net", %err, link, "Invalid DNS tree link in chain spec")
+                        }
+                    }
+                } else if let Some(link) = chain_spec.chain().public_dns_network_protocol() {
                     dns_networks.insert(link.parse().expect("is valid DNS link entry"));
                 }
             }
@@ -646,12 +814,21 @@ impl<N: NetworkPrimitives> NetworkConfigBuilder<N> {
             secret_key,
             boot_nodes,
             dns_discovery_config,
+            dns_server,
             discovery_v4_config: discovery_v4_builder.map(|builder| builder.build()),
             discovery_v5_config: discovery_v5_builder.map(|builder| builder.build()),
             discovery_v4_addr: discovery_addr.unwrap_or(DEFAULT_DISCOVERY_ADDRESS),
             listener_addr,
             peers_config: peers_config.unwrap_or_default(),
+            signer_peers,
+            signer_enr,
+            signer_set,
+            n42_fork_id_filter,
             sessions_config: sessions_config.unwrap_or_default(),
+            bandwidth_limits,
+            bans,
+            bans_file,
+            enrs_file,
             chain_id,
             block_import: block_import.unwrap_or_else(|| Box::<ProofOfStakeBlockImport>::default()),
             network_mode,
@@ -2,10 +2,20 @@
 
 use crate::{
     cache::LruMap,
+    config::N42_FORK_ID_ENR_KEY,
+    enr_request::EnrRequester,
     error::{NetworkError, ServiceKind},
+    metrics::{DiscoveryFilterMetrics, SignerEnrMetrics},
+    signer_enr::{SignerEnrEntry, SignerSet, SIGNER_ENR_KEY},
 };
-use enr::Enr;
+use alloy_primitives::Address;
+use enr::{Enr, EnrKey};
 use futures::StreamExt;
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    TokioResolver,
+};
 use reth_discv4::{DiscoveryUpdate, Discv4, Discv4Config};
 use reth_discv5::{DiscoveredPeer, Discv5};
 use reth_dns_discovery::{
@@ -17,21 +27,28 @@ use reth_network_peers::{NodeRecord, PeerId};
 use reth_network_types::PeerAddr;
 use secp256k1::SecretKey;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
     net::{IpAddr, SocketAddr},
     pin::Pin,
     sync::Arc,
     task::{ready, Context, Poll},
 };
 use tokio::{sync::mpsc, task::JoinHandle};
-use tokio_stream::{wrappers::ReceiverStream, Stream};
-use tracing::trace;
+use tokio_stream::{
+    wrappers::{ReceiverStream, WatchStream},
+    Stream,
+};
+use tracing::{debug, info, trace};
 
 /// Default max capacity for cache of discovered peers.
 ///
 /// Default is 10 000 peers.
 pub const DEFAULT_MAX_CAPACITY_DISCOVERED_PEERS_CACHE: u32 = 10_000;
 
+/// Name of the file the records of the known peers are persisted to, next to the known peers
+/// file. They are the leaves of the EIP-1459 trees published for the network.
+pub const KNOWN_ENRS_FILE_NAME: &str = "known-enrs.json";
+
 /// An abstraction over the configured discovery protocol.
 ///
 /// Listens for new discovered nodes and emits events for discovered nodes and their
@@ -60,6 +77,26 @@ pub struct Discovery {
     dns_discovery_updates: Option<ReceiverStream<DnsNodeRecordUpdate>>,
     /// The handle to the spawned DNS discovery service
     _dns_disc_service: Option<JoinHandle<()>>,
+    /// Whether only nodes announcing a fork id are reported.
+    n42_fork_id_filter: bool,
+    /// Nodes found over discv4, held back until their ENR tells their fork id.
+    awaiting_fork_id: LruMap<PeerId, NodeRecord>,
+    /// Discovered nodes rejected for their fork id.
+    filter_metrics: DiscoveryFilterMetrics,
+    /// The `APos` signers against which the signer entries of discovered records are verified.
+    signer_set: Option<SignerSet>,
+    /// Yields whenever the signer set changes.
+    signer_set_changes: Option<WatchStream<HashSet<Address>>>,
+    /// The latest signer entries of discovered nodes, verified again when the signer set changes.
+    signer_entries: LruMap<PeerId, (NodeRecord, SignerEnrEntry)>,
+    /// The verified signers of discovered nodes.
+    enr_signers: HashMap<PeerId, Address>,
+    /// Verified and rejected signer entries.
+    signer_metrics: SignerEnrMetrics,
+    /// The latest signed records of discovered nodes, base64 encoded.
+    enrs: LruMap<PeerId, String>,
+    /// Requests the records of the nodes found over discv4, if it has EIP-868 enabled.
+    enr_requester: Option<EnrRequester>,
     /// Events buffered until polled.
     queued_events: VecDeque<DiscoveryEvent>,
     /// List of listeners subscribed to discovery events.
@@ -78,10 +115,12 @@ impl Discovery {
         discv4_config: Option<Discv4Config>,
         discv5_config: Option<reth_discv5::Config>, // contains discv5 listen address
         dns_discovery_config: Option<DnsDiscoveryConfig>,
+        dns_server: Option<SocketAddr>,
     ) -> Result<Self, NetworkError> {
         // setup discv4 with the discovery address and tcp port
         let local_enr =
             NodeRecord::from_secret_key(discovery_v4_addr, &sk).with_tcp_port(tcp_addr.port());
+        let request_enrs = discv4_config.as_ref().is_some_and(|config| config.enable_eip868);
 
         let discv4_future = async {
             let Some(disc_config) = discv4_config else { return Ok((None, None, None)) };
@@ -107,13 +146,26 @@ impl Discovery {
         let ((discv4, discv4_updates, _discv4_service), (discv5, discv5_updates)) =
             tokio::try_join!(discv4_future, discv5_future)?;
 
+        // request the records of the discv4 nodes from the IP address they bonded with
+        let enr_requester =
+            if request_enrs {
+                let addr = SocketAddr::new(discovery_v4_addr.ip(), 0);
+                Some(EnrRequester::spawn(addr, sk).await.map_err(|err| {
+                    NetworkError::from_io_error(err, ServiceKind::Discovery(addr))
+                })?)
+            } else {
+                None
+            };
+
         // setup DNS discovery
         let (_dns_discovery, dns_discovery_updates, _dns_disc_service) =
             if let Some(dns_config) = dns_discovery_config {
-                let (mut service, dns_disc) = DnsDiscoveryService::new_pair(
-                    Arc::new(DnsResolver::from_system_conf()?),
-                    dns_config,
-                );
+                let resolver = match dns_server {
+                    Some(server) => dns_server_resolver(server),
+                    None => DnsResolver::from_system_conf()?,
+                };
+                let (mut service, dns_disc) =
+                    DnsDiscoveryService::new_pair(Arc::new(resolver), dns_config);
                 let dns_discovery_updates = service.node_record_stream();
                 let dns_disc_service = service.spawn();
                 (Some(dns_disc), Some(dns_discovery_updates), Some(dns_disc_service))
@@ -130,6 +182,16 @@ impl Discovery {
             discv5,
             discv5_updates,
             discovered_nodes: LruMap::new(DEFAULT_MAX_CAPACITY_DISCOVERED_PEERS_CACHE),
+            n42_fork_id_filter: false,
+            awaiting_fork_id: LruMap::new(DEFAULT_MAX_CAPACITY_DISCOVERED_PEERS_CACHE),
+            filter_metrics: Default::default(),
+            signer_set: None,
+            signer_set_changes: None,
+            signer_entries: LruMap::new(DEFAULT_MAX_CAPACITY_DISCOVERED_PEERS_CACHE),
+            enr_signers: Default::default(),
+            signer_metrics: Default::default(),
+            enrs: LruMap::new(DEFAULT_MAX_CAPACITY_DISCOVERED_PEERS_CACHE),
+            enr_requester,
             queued_events: Default::default(),
             _dns_disc_service,
             _dns_discovery,
@@ -137,6 +199,29 @@ impl Discovery {
         })
     }
 
+    /// Only reports the discovered nodes announcing a fork id, read from the
+    /// [`N42_FORK_ID_ENR_KEY`] entry of discv5 and DNS records and from the `eth` entry of discv4
+    /// records.
+    pub const fn with_n42_fork_id_filter(mut self, enabled: bool) -> Self {
+        self.n42_fork_id_filter = enabled;
+        self
+    }
+
+    /// Sets the `APos` signers to verify the signer entries of discovered records against.
+    ///
+    /// Without a signer set, signer entries are ignored. The entries found are verified again
+    /// whenever the set changes.
+    pub fn with_signer_set(mut self, signer_set: Option<SignerSet>) -> Self {
+        self.signer_set_changes = signer_set.as_ref().map(SignerSet::changes);
+        self.signer_set = signer_set;
+        self
+    }
+
+    /// Returns the latest signed record of the node `peer_id`, if discovery found or requested it.
+    pub fn enr(&self, peer_id: &PeerId) -> Option<&str> {
+        self.enrs.peek(peer_id).map(String::as_str)
+    }
+
     /// Registers a listener for receiving [`DiscoveryEvent`] updates.
     pub(crate) fn add_listener(&mut self, tx: mpsc::UnboundedSender<DiscoveryEvent>) {
         self.discovery_listeners.push(tx);
@@ -148,11 +233,17 @@ impl Discovery {
         self.discovery_listeners.retain_mut(|listener| listener.send(event.clone()).is_ok());
     }
 
-    /// Updates the `eth:ForkId` field in discv4.
+    /// Updates the `eth:ForkId` field in discv4, and the N42 fork id if it is published.
     pub(crate) fn update_fork_id(&self, fork_id: ForkId) {
         if let Some(discv4) = &self.discv4 {
             // use forward-compatible forkid entry
-            discv4.set_eip868_rlp(b"eth".to_vec(), EnrForkIdEntry::from(fork_id))
+            discv4.set_eip868_rlp(b"eth".to_vec(), EnrForkIdEntry::from(fork_id));
+            if self.n42_fork_id_filter {
+                discv4.set_eip868_rlp(
+                    N42_FORK_ID_ENR_KEY.as_bytes().to_vec(),
+                    EnrForkIdEntry::from(fork_id),
+                )
+            }
         }
         // todo: update discv5 enr
     }
@@ -212,6 +303,11 @@ impl Discovery {
     /// Processes an incoming [`NodeRecord`] update from a discovery service
     fn on_node_record_update(&mut self, record: NodeRecord, fork_id: Option<ForkId>) {
         let peer_id = record.id;
+        if self.n42_fork_id_filter && fork_id.is_none() {
+            trace!(target: "net::discovery", ?peer_id, "discovered node without fork id");
+            self.filter_metrics.rejected_missing_fork_id.increment(1);
+            return
+        }
         let tcp_addr = record.tcp_addr();
         if tcp_addr.port() == 0 {
             // useless peer for p2p
@@ -229,16 +325,97 @@ impl Discovery {
             })
     }
 
+    /// Processes the `APos` signer entry found in the ENR of a discovered node.
+    ///
+    /// Emits [`DiscoveryEvent::EnrSigner`] when the verified signer of the node changes.
+    fn on_signer_entry(&mut self, record: NodeRecord, entry: Option<SignerEnrEntry>) {
+        let Some(signer_set) = &self.signer_set else { return };
+        let peer_id = record.id;
+        if record.tcp_port == 0 {
+            return
+        }
+
+        let signer = entry.as_ref().and_then(|entry| match entry.verify(peer_id) {
+            Ok(signer) if signer_set.is_signer(&signer) => Some(signer),
+            Ok(signer) => {
+                debug!(target: "net::discovery", %peer_id, %signer, "unauthorized signer entry");
+                self.signer_metrics.rejected_signer_entries.increment(1);
+                None
+            }
+            Err(err) => {
+                debug!(target: "net::discovery", %peer_id, %err, "invalid signer entry");
+                self.signer_metrics.rejected_signer_entries.increment(1);
+                None
+            }
+        });
+        match entry {
+            Some(entry) => {
+                self.signer_entries.insert(peer_id, (record, entry));
+            }
+            None => {
+                self.signer_entries.remove(&peer_id);
+            }
+        }
+        if self.enr_signers.get(&peer_id).copied() == signer {
+            return
+        }
+
+        match signer {
+            Some(signer) => {
+                info!(target: "net::discovery", enode=%record, %signer, "Discovered signer node");
+                self.enr_signers.insert(peer_id, signer);
+            }
+            None => {
+                self.enr_signers.remove(&peer_id);
+            }
+        }
+        self.signer_metrics.verified_signer_nodes.set(self.enr_signers.len() as f64);
+        let addr = PeerAddr::new(record.tcp_addr(), Some(record.udp_addr()));
+        self.queued_events.push_back(DiscoveryEvent::EnrSigner { peer_id, addr, signer });
+    }
+
+    /// Verifies the signer entries found so far against the changed signer set.
+    fn on_signer_set_change(&mut self) {
+        let entries: Vec<_> = self
+            .signer_entries
+            .iter()
+            .map(|(_, (record, entry))| (*record, entry.clone()))
+            .collect();
+        for (record, entry) in entries {
+            self.on_signer_entry(record, Some(entry));
+        }
+    }
+
     fn on_discv4_update(&mut self, update: DiscoveryUpdate) {
         match update {
             DiscoveryUpdate::Added(record) | DiscoveryUpdate::DiscoveredAtCapacity(record) => {
-                self.on_node_record_update(record, None);
+                if self.n42_fork_id_filter {
+                    // the fork id is only known once the ENR of the node is received
+                    if self.discovered_nodes.peek(&record.id).is_none() {
+                        self.awaiting_fork_id.insert(record.id, record);
+                    }
+                } else {
+                    self.on_node_record_update(record, None);
+                }
             }
             DiscoveryUpdate::EnrForkId(node, fork_id) => {
-                self.queued_events.push_back(DiscoveryEvent::EnrForkId(node.id, fork_id))
+                // the node answers ENR requests, ask for the record discv4 keeps to itself
+                if let Some(requester) = &self.enr_requester {
+                    requester.request(node);
+                }
+                if self.awaiting_fork_id.remove(&node.id).is_some() {
+                    self.on_node_record_update(node, Some(fork_id));
+                } else {
+                    self.queued_events.push_back(DiscoveryEvent::EnrForkId(node.id, fork_id))
+                }
             }
             DiscoveryUpdate::Removed(peer_id) => {
                 self.discovered_nodes.remove(&peer_id);
+                self.awaiting_fork_id.remove(&peer_id);
+                self.signer_entries.remove(&peer_id);
+                if self.enr_signers.remove(&peer_id).is_some() {
+                    self.signer_metrics.verified_signer_nodes.set(self.enr_signers.len() as f64);
+                }
             }
             DiscoveryUpdate::Batch(updates) => {
                 for update in updates {
@@ -256,6 +433,13 @@ impl Discovery {
                 return Poll::Ready(event)
             }
 
+            // verify the signer entries found so far again once the signers change
+            while let Some(Poll::Ready(Some(_))) =
+                self.signer_set_changes.as_mut().map(|changes| changes.poll_next_unpin(cx))
+            {
+                self.on_signer_set_change()
+            }
+
             // drain the discv4 update stream
             while let Some(Poll::Ready(Some(update))) =
                 self.discv4_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
@@ -263,15 +447,35 @@ impl Discovery {
                 self.on_discv4_update(update)
             }
 
+            // drain the records requested from discv4 nodes
+            while let Some(Poll::Ready(Some((node, enr)))) =
+                self.enr_requester.as_mut().map(|requester| requester.poll_response(cx))
+            {
+                self.enrs.insert(node.id, enr.to_base64());
+                self.on_signer_entry(node, signer_entry(&enr));
+            }
+
             // drain the discv5 update stream
             while let Some(Poll::Ready(Some(update))) =
                 self.discv5_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
             {
+                let (signer_entry, enr) = match &update {
+                    discv5::Event::Discovered(enr) | discv5::Event::SessionEstablished(enr, _) => {
+                        (Some(signer_entry(enr)), Some(enr.to_base64()))
+                    }
+                    _ => (None, None),
+                };
                 if let Some(discv5) = self.discv5.as_mut() {
                     if let Some(DiscoveredPeer { node_record, fork_id }) =
                         discv5.on_discv5_update(update)
                     {
+                        if let Some(enr) = enr {
+                            self.enrs.insert(node_record.id, enr);
+                        }
                         self.on_node_record_update(node_record, fork_id);
+                        if let Some(entry) = signer_entry {
+                            self.on_signer_entry(node_record, entry);
+                        }
                     }
                 }
             }
@@ -281,13 +485,29 @@ impl Discovery {
                 self.dns_discovery_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
             {
                 self.add_discv4_node(update.node_record);
+                self.enrs.insert(update.node_record.id, update.enr.to_base64());
+                let signer_entry = signer_entry(&update.enr);
+                let fork_id = if self.n42_fork_id_filter {
+                    match update.enr.get_decodable::<EnrForkIdEntry>(N42_FORK_ID_ENR_KEY) {
+                        Some(Ok(entry)) => Some(entry.into()),
+                        Some(Err(err)) => {
+                            trace!(target: "net::discovery", %err, "malformed fork id entry");
+                            self.filter_metrics.rejected_malformed_fork_id.increment(1);
+                            continue
+                        }
+                        None => None,
+                    }
+                } else {
+                    update.fork_id
+                };
                 if let Err(err) = self.add_discv5_node(update.enr) {
                     trace!(target: "net::discovery",
                         %err,
                         "failed adding node discovered by dns to discv5"
                     );
                 }
-                self.on_node_record_update(update.node_record, update.fork_id);
+                self.on_node_record_update(update.node_record, fork_id);
+                self.on_signer_entry(update.node_record, signer_entry);
             }
 
             if self.queued_events.is_empty() {
@@ -297,6 +517,30 @@ impl Discovery {
     }
 }
 
+/// Returns a resolver querying the DNS server at `server` only, e.g. a local server standing in
+/// for the public DNS.
+fn dns_server_resolver(server: SocketAddr) -> DnsResolver {
+    let name_servers = NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true);
+    let config = ResolverConfig::from_parts(None, Vec::new(), name_servers);
+    DnsResolver::new(
+        TokioResolver::builder_with_config(config, TokioConnectionProvider::default()).build(),
+    )
+}
+
+/// Returns the `APos` signer entry of `enr`, if it has a well formed one.
+///
+/// Discv4 only exposes the fork id of the records it finds, so the signer entries of discv4 nodes
+/// are read from the records requested by the [`EnrRequester`].
+fn signer_entry<K: EnrKey>(enr: &Enr<K>) -> Option<SignerEnrEntry> {
+    match enr.get_decodable::<SignerEnrEntry>(SIGNER_ENR_KEY)? {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            trace!(target: "net::discovery", %err, "malformed signer entry");
+            None
+        }
+    }
+}
+
 impl Stream for Discovery {
     type Item = DiscoveryEvent;
 
@@ -326,6 +570,16 @@ impl Discovery {
             discv4_updates: Default::default(),
             discv5: None,
             discv5_updates: None,
+            n42_fork_id_filter: false,
+            awaiting_fork_id: LruMap::new(0),
+            filter_metrics: Default::default(),
+            signer_set: None,
+            signer_set_changes: None,
+            signer_entries: LruMap::new(0),
+            enr_signers: Default::default(),
+            signer_metrics: Default::default(),
+            enrs: LruMap::new(0),
+            enr_requester: None,
             queued_events: Default::default(),
             _discv4_service: Default::default(),
             _dns_discovery: None,
@@ -339,6 +593,7 @@ impl Discovery {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reth_ethereum_forks::ForkHash;
     use secp256k1::SECP256K1;
     use std::net::{Ipv4Addr, SocketAddrV4};
 
@@ -353,6 +608,7 @@ mod tests {
             Default::default(),
             None,
             Default::default(),
+            None,
         )
         .await
         .unwrap();
@@ -362,6 +618,35 @@ mod tests {
     use reth_discv5::{enr::EnrCombinedKeyWrapper, enr_to_discv4_id};
     use tracing::trace;
 
+    #[test]
+    fn n42_fork_id_filter_holds_back_discv4_nodes() {
+        let mut discovery = Discovery::noop().with_n42_fork_id_filter(true);
+        discovery.awaiting_fork_id = LruMap::new(10);
+        discovery.discovered_nodes = LruMap::new(10);
+        let record = NodeRecord::new("127.0.0.1:30303".parse().unwrap(), PeerId::random());
+        let fork_id = ForkId { hash: ForkHash([1, 2, 3, 4]), next: 0 };
+
+        discovery.on_discv4_update(DiscoveryUpdate::Added(record));
+        assert!(discovery.queued_events.is_empty());
+
+        discovery.on_discv4_update(DiscoveryUpdate::EnrForkId(record, fork_id));
+        assert_eq!(
+            discovery.queued_events.pop_front(),
+            Some(DiscoveryEvent::NewNode(DiscoveredEvent::EventQueued {
+                peer_id: record.id,
+                addr: PeerAddr::new(record.tcp_addr(), Some(record.udp_addr())),
+                fork_id: Some(fork_id),
+            }))
+        );
+
+        // nodes without fork id are never reported
+        discovery.on_node_record_update(
+            NodeRecord::new("127.0.0.1:30304".parse().unwrap(), PeerId::random()),
+            None,
+        );
+        assert!(discovery.queued_events.is_empty());
+    }
+
     async fn start_discovery_node(udp_port_discv4: u16, udp_port_discv5: u16) -> Discovery {
         let secret_key = SecretKey::new(&mut rand_08::thread_rng());
 
@@ -383,6 +668,7 @@ mod tests {
             Some(discv4_config),
             Some(discv5_config),
             None,
+            None,
         )
         .await
         .expect("should build discv5 with discv4 downgrade")
@@ -469,4 +755,149 @@ mod tests {
         assert_eq!(1, node_1.discovered_nodes.len());
         assert_eq!(1, node_2.discovered_nodes.len());
     }
+
+    #[test]
+    fn signer_entries_verified_again_when_signers_change() {
+        let signer_key = SecretKey::new(&mut rand_08::thread_rng());
+        let record = NodeRecord::new("127.0.0.1:30303".parse().unwrap(), PeerId::random());
+        let entry = SignerEnrEntry::new(record.id, &signer_key);
+        let addr = PeerAddr::new(record.tcp_addr(), Some(record.udp_addr()));
+
+        let (signers, signer_set) = SignerSet::new(HashSet::from([entry.signer]));
+        let mut discovery = Discovery::noop().with_signer_set(Some(signer_set));
+        discovery.signer_entries = LruMap::new(10);
+        discovery.on_signer_entry(record, Some(entry.clone()));
+        assert_eq!(
+            discovery.queued_events.pop_front(),
+            Some(DiscoveryEvent::EnrSigner {
+                peer_id: record.id,
+                addr,
+                signer: Some(entry.signer)
+            })
+        );
+
+        // the signer is voted out, then back in
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        signers.send_replace(HashSet::new());
+        assert_eq!(
+            discovery.poll(&mut cx),
+            Poll::Ready(DiscoveryEvent::EnrSigner { peer_id: record.id, addr, signer: None })
+        );
+        signers.send_replace(HashSet::from([entry.signer]));
+        assert_eq!(
+            discovery.poll(&mut cx),
+            Poll::Ready(DiscoveryEvent::EnrSigner {
+                peer_id: record.id,
+                addr,
+                signer: Some(entry.signer)
+            })
+        );
+        assert!(discovery.poll(&mut cx).is_pending());
+    }
+
+    /// Serves the TXT `records` over UDP, standing in for the public DNS.
+    async fn serve_txt_records(records: HashMap<String, String>) -> SocketAddr {
+        let socket = tokio::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            while let Ok((len, remote_addr)) = socket.recv_from(&mut buf).await {
+                let query = &buf[..len];
+                // the question follows the 12 byte header: the labels of the name, type and class
+                let mut pos = 12;
+                let mut labels = Vec::new();
+                while query[pos] != 0 {
+                    let label = &query[pos + 1..pos + 1 + query[pos] as usize];
+                    labels.push(String::from_utf8_lossy(label).to_lowercase());
+                    pos += 1 + label.len();
+                }
+                let txt = records.get(&labels.join("."));
+
+                // same id, a recursive response, NXDOMAIN for unknown names
+                let mut response = query[..2].to_vec();
+                response.extend_from_slice(&[0x81, if txt.is_some() { 0x80 } else { 0x83 }]);
+                response.extend_from_slice(&[0, 1, 0, u8::from(txt.is_some()), 0, 0, 0, 0]);
+                response.extend_from_slice(&query[12..pos + 5]);
+                if let Some(txt) = txt {
+                    let rdata: Vec<u8> = txt
+                        .as_bytes()
+                        .chunks(255)
+                        .flat_map(|chunk| std::iter::once(chunk.len() as u8).chain(chunk.to_vec()))
+                        .collect();
+                    // pointer to the question name, TXT, IN, TTL
+                    response.extend_from_slice(&[0xc0, 12, 0, 16, 0, 1, 0, 0, 0, 60]);
+                    response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+                    response.extend_from_slice(&rdata);
+                }
+                let _ = socket.send_to(&response, remote_addr).await;
+            }
+        });
+        addr
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dns_discovery_resolves_with_configured_server() {
+        use alloy_primitives::keccak256;
+        use data_encoding::{BASE32_NOPAD, BASE64URL_NOPAD};
+        use reth_network_peers::pk2id;
+        use secp256k1::Message;
+        use std::time::Duration;
+
+        let tree_key = SecretKey::new(&mut rand_08::thread_rng());
+        let node_key = SecretKey::new(&mut rand_08::thread_rng());
+        let enr = Enr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .tcp4(30303)
+            .udp4(30303)
+            .build(&node_key)
+            .unwrap();
+
+        // a tree with a single node record and no links
+        let subdomain = |record: &str| BASE32_NOPAD.encode(&keccak256(record.as_bytes())[..16]);
+        let leaf = enr.to_base64();
+        let links = "enrtree-branch:".to_string();
+        let root = format!("enrtree-root:v1 e={} l={} seq=1", subdomain(&leaf), subdomain(&links));
+        let message = Message::from_digest(keccak256(root.as_bytes()).0);
+        let (recovery_id, signature) =
+            SECP256K1.sign_ecdsa_recoverable(&message, &tree_key).serialize_compact();
+        let mut sig = signature.to_vec();
+        sig.push(i32::from(recovery_id) as u8);
+
+        let domain = "nodes.n42.test";
+        let dns_server = serve_txt_records(HashMap::from([
+            (domain.to_string(), format!("{root} sig={}", BASE64URL_NOPAD.encode(&sig))),
+            (format!("{}.{domain}", subdomain(&leaf)), leaf.clone()),
+            (format!("{}.{domain}", subdomain(&links)), links),
+        ]))
+        .await;
+        let tree_public_key = tree_key.public_key(SECP256K1).serialize();
+        let link = format!("enrtree://{}@{domain}", BASE32_NOPAD.encode(&tree_public_key));
+        let dns_config = DnsDiscoveryConfig {
+            bootstrap_dns_networks: Some(HashSet::from([link.parse().unwrap()])),
+            ..Default::default()
+        };
+
+        let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+        let mut discovery = Discovery::new(
+            addr,
+            addr,
+            SecretKey::new(&mut rand_08::thread_rng()),
+            None,
+            None,
+            Some(dns_config),
+            Some(dns_server),
+        )
+        .await
+        .unwrap();
+
+        let peer_id = pk2id(&node_key.public_key(SECP256K1));
+        let event =
+            tokio::time::timeout(Duration::from_secs(10), discovery.next()).await.unwrap().unwrap();
+        assert!(matches!(
+            event,
+            DiscoveryEvent::NewNode(DiscoveredEvent::EventQueued { peer_id: id, .. }) if id == peer_id
+        ));
+        assert_eq!(discovery.enr(&peer_id), Some(leaf.as_str()));
+    }
 }
@@ -0,0 +1,256 @@
+//! Requests for the signed records of discv4 nodes, see [EIP-868](https://eips.ethereum.org/EIPS/eip-868).
+//!
+//! Discv4 asks the nodes it bonds with for their record but only reports the fork id it holds, so
+//! the [`EnrRequester`] asks them again from a socket of its own, signing with the node key. Nodes
+//! answer an `ENRRequest` of a node they are bonded with whatever the port it comes from.
+
+use alloy_primitives::{keccak256, B256};
+use alloy_rlp::{Decodable, Encodable, RlpDecodable, RlpEncodable};
+use enr::Enr;
+use reth_network_peers::{pk2id, NodeRecord, PeerId};
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message, SecretKey, SECP256K1,
+};
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{net::UdpSocket, sync::mpsc, task::JoinHandle};
+use tracing::trace;
+
+/// Packet type of an `ENRRequest`.
+const ENR_REQUEST_PACKET: u8 = 0x05;
+
+/// Packet type of an `ENRResponse`.
+const ENR_RESPONSE_PACKET: u8 = 0x06;
+
+/// How long a request is valid and waits for its response.
+const ENR_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Length of the hash and signature heading every packet.
+const PACKET_HEADER_LENGTH: usize = 32 + 65;
+
+/// Maximum size of a discv4 packet.
+const MAX_PACKET_SIZE: usize = 1280;
+
+/// Payload of an `ENRRequest`.
+#[derive(Debug, RlpEncodable, RlpDecodable)]
+struct EnrRequest {
+    /// Unix timestamp after which the request is dropped.
+    expire: u64,
+}
+
+/// Payload of an `ENRResponse`.
+#[derive(Debug, RlpEncodable, RlpDecodable)]
+struct EnrResponse {
+    /// Hash of the request packet answered.
+    request_hash: B256,
+    /// The record of the node.
+    enr: Enr<SecretKey>,
+}
+
+/// Errors decoding a discv4 packet.
+#[derive(Debug, thiserror::Error)]
+enum PacketError {
+    /// The packet is shorter than its header.
+    #[error("packet too short")]
+    TooShort,
+    /// The hash doesn't match the packet.
+    #[error("packet hash mismatch")]
+    HashMismatch,
+    /// The signature is malformed or doesn't recover a key.
+    #[error("invalid packet signature: {0}")]
+    InvalidSignature(#[from] secp256k1::Error),
+}
+
+/// Handle to the task requesting the records of discv4 nodes.
+#[derive(Debug)]
+pub(crate) struct EnrRequester {
+    /// Sends the nodes to request the record of to the task.
+    to_service: mpsc::UnboundedSender<NodeRecord>,
+    /// The records received, with the node they were requested from.
+    responses: mpsc::UnboundedReceiver<(NodeRecord, Enr<SecretKey>)>,
+    /// The handle to the spawned task.
+    _service: JoinHandle<()>,
+}
+
+impl EnrRequester {
+    /// Binds the socket the requests are sent from to `addr` and spawns the task sending them.
+    ///
+    /// The IP address of `addr` must be the one of the discv4 socket, which nodes bonded with.
+    pub(crate) async fn spawn(addr: SocketAddr, secret_key: SecretKey) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr).await?;
+        let (to_service, requests) = mpsc::unbounded_channel();
+        let (responses_tx, responses) = mpsc::unbounded_channel();
+        let service = tokio::spawn(run(socket, secret_key, requests, responses_tx));
+        Ok(Self { to_service, responses, _service: service })
+    }
+
+    /// Requests the record of `node`, which is yielded by [`Self::poll_response`] if it answers
+    /// within [`ENR_REQUEST_TIMEOUT`].
+    pub(crate) fn request(&self, node: NodeRecord) {
+        let _ = self.to_service.send(node);
+    }
+
+    /// Polls for the next record received, whose public key is the id of the node it was
+    /// requested from.
+    pub(crate) fn poll_response(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<(NodeRecord, Enr<SecretKey>)>> {
+        self.responses.poll_recv(cx)
+    }
+}
+
+/// Sends the requests for the nodes of `requests` and forwards the records they answer with.
+async fn run(
+    socket: UdpSocket,
+    secret_key: SecretKey,
+    mut requests: mpsc::UnboundedReceiver<NodeRecord>,
+    responses: mpsc::UnboundedSender<(NodeRecord, Enr<SecretKey>)>,
+) {
+    // requests sent within the same second are identical, so they're told apart by the node
+    let mut pending = HashMap::<(B256, PeerId), (NodeRecord, Instant)>::new();
+    let mut evict_interval = tokio::time::interval(ENR_REQUEST_TIMEOUT);
+    let mut buf = [0; MAX_PACKET_SIZE];
+    loop {
+        tokio::select! {
+            node = requests.recv() => {
+                let Some(node) = node else { return };
+                let expire = unix_now().saturating_add(ENR_REQUEST_TIMEOUT.as_secs());
+                let (packet, hash) =
+                    encode_packet(&secret_key, ENR_REQUEST_PACKET, &EnrRequest { expire });
+                if let Err(err) = socket.send_to(&packet, node.udp_addr()).await {
+                    trace!(target: "net::discovery", %err, ?node, "failed to send ENR request");
+                    continue
+                }
+                pending.insert((hash, node.id), (node, Instant::now()));
+            }
+            received = socket.recv_from(&mut buf) => {
+                let Ok((len, remote_addr)) = received else { continue };
+                let response = decode_packet(&buf[..len]).ok().and_then(|(peer_id, packet)| {
+                    let (&packet_type, mut payload) = packet.split_first()?;
+                    if packet_type != ENR_RESPONSE_PACKET {
+                        return None
+                    }
+                    Some((peer_id, EnrResponse::decode(&mut payload).ok()?))
+                });
+                let Some((peer_id, response)) = response else {
+                    trace!(target: "net::discovery", %remote_addr, "invalid ENR response");
+                    continue
+                };
+                let Some((node, _)) = pending.remove(&(response.request_hash, peer_id)) else {
+                    continue
+                };
+                if pk2id(&response.enr.public_key()) != node.id {
+                    trace!(target: "net::discovery", ?node, "ENR of another node");
+                    continue
+                }
+                if responses.send((node, response.enr)).is_err() {
+                    return
+                }
+            }
+            _ = evict_interval.tick() => {
+                pending.retain(|_, (_, sent_at)| sent_at.elapsed() < ENR_REQUEST_TIMEOUT);
+            }
+        }
+    }
+}
+
+/// Returns the packet of type `packet_type` carrying `payload` signed with `secret_key`, with its
+/// hash.
+fn encode_packet<T: Encodable>(
+    secret_key: &SecretKey,
+    packet_type: u8,
+    payload: &T,
+) -> (Vec<u8>, B256) {
+    let mut packet = vec![0; PACKET_HEADER_LENGTH];
+    packet.push(packet_type);
+    payload.encode(&mut packet);
+
+    let message = Message::from_digest(keccak256(&packet[PACKET_HEADER_LENGTH..]).0);
+    let (recovery_id, signature) =
+        SECP256K1.sign_ecdsa_recoverable(&message, secret_key).serialize_compact();
+    packet[32..96].copy_from_slice(&signature);
+    packet[96] = i32::from(recovery_id) as u8;
+
+    let hash = keccak256(&packet[32..]);
+    packet[..32].copy_from_slice(hash.as_slice());
+    (packet, hash)
+}
+
+/// Returns the id of the node that signed `packet`, with its type and payload.
+fn decode_packet(packet: &[u8]) -> Result<(PeerId, &[u8]), PacketError> {
+    if packet.len() <= PACKET_HEADER_LENGTH {
+        return Err(PacketError::TooShort)
+    }
+    if keccak256(&packet[32..]).as_slice() != &packet[..32] {
+        return Err(PacketError::HashMismatch)
+    }
+    let signature = RecoverableSignature::from_compact(
+        &packet[32..96],
+        RecoveryId::try_from(i32::from(packet[96]))?,
+    )?;
+    let message = Message::from_digest(keccak256(&packet[PACKET_HEADER_LENGTH..]).0);
+    let public_key = SECP256K1.recover_ecdsa(&message, &signature)?;
+    Ok((pk2id(&public_key), &packet[PACKET_HEADER_LENGTH..]))
+}
+
+/// Returns the current unix timestamp in seconds.
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn requests_enr_of_node() {
+        let local = (Ipv4Addr::LOCALHOST, 0).into();
+        let mut requester =
+            EnrRequester::spawn(local, SecretKey::new(&mut rand_08::thread_rng())).await.unwrap();
+
+        let node_key = SecretKey::new(&mut rand_08::thread_rng());
+        let node_socket = UdpSocket::bind(local).await.unwrap();
+        let node_addr = node_socket.local_addr().unwrap();
+        let enr = Enr::builder()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(node_addr.port())
+            .build(&node_key)
+            .unwrap();
+        let node = NodeRecord::from_secret_key(node_addr, &node_key);
+        requester.request(node);
+
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let (len, requester_addr) = node_socket.recv_from(&mut buf).await.unwrap();
+        let (_, packet) = decode_packet(&buf[..len]).unwrap();
+        assert_eq!(packet[0], ENR_REQUEST_PACKET);
+        assert!(EnrRequest::decode(&mut &packet[1..]).unwrap().expire > unix_now());
+        let request_hash = B256::from_slice(&buf[..32]);
+
+        // a response signed by another node is ignored
+        let other_key = SecretKey::new(&mut rand_08::thread_rng());
+        let response = EnrResponse { request_hash, enr: enr.clone() };
+        let (packet, _) = encode_packet(&other_key, ENR_RESPONSE_PACKET, &response);
+        node_socket.send_to(&packet, requester_addr).await.unwrap();
+
+        let (packet, _) = encode_packet(&node_key, ENR_RESPONSE_PACKET, &response);
+        node_socket.send_to(&packet, requester_addr).await.unwrap();
+
+        let (responder, received) = tokio::time::timeout(
+            Duration::from_secs(5),
+            std::future::poll_fn(|cx| requester.poll_response(cx)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(responder, node);
+        assert_eq!(received, enr);
+    }
+}
@@ -129,17 +129,21 @@ pub mod import;
 pub mod message;
 pub mod peers;
 pub mod protocol;
+pub mod signer_enr;
 pub mod transactions;
 
+mod bans;
 mod budget;
 mod builder;
 mod discovery;
+mod enr_request;
 mod fetch;
 mod flattened_response;
 mod listener;
 mod manager;
 mod metrics;
 mod network;
+mod propagation;
 mod session;
 mod state;
 mod swarm;
@@ -148,20 +152,24 @@ mod trusted_peers_resolver;
 pub use reth_eth_wire::{DisconnectReason, HelloMessageWithProtocols};
 pub use reth_eth_wire_types::{primitives, EthNetworkPrimitives, NetworkPrimitives};
 pub use reth_network_api::{
-    events, BlockDownloaderProvider, DiscoveredEvent, DiscoveryEvent, NetworkEvent,
-    NetworkEventListenerProvider, NetworkInfo, PeerRequest, PeerRequestSender, Peers, PeersInfo,
+    events, BanEntry, BanTarget, BlockDownloaderProvider, DiscoveredEvent, DiscoveryEvent,
+    NetworkEvent, NetworkEventListenerProvider, NetworkInfo, PeerRequest, PeerRequestSender, Peers,
+    PeersInfo,
 };
 pub use reth_network_p2p::sync::{NetworkSyncUpdater, SyncState};
 pub use reth_network_types::{PeersConfig, SessionsConfig};
 pub use session::{
-    ActiveSessionHandle, ActiveSessionMessage, Direction, EthRlpxConnection, PeerInfo,
-    PendingSessionEvent, PendingSessionHandle, PendingSessionHandshakeError, SessionCommand,
-    SessionEvent, SessionId, SessionManager,
+    ActiveSessionHandle, ActiveSessionMessage, BandwidthLimits, Direction, EthRlpxConnection,
+    PeerInfo, PendingSessionEvent, PendingSessionHandle, PendingSessionHandshakeError,
+    SessionCommand, SessionEvent, SessionId, SessionManager,
 };
 
+#[cfg(feature = "serde")]
+pub use bans::read_bans_file;
+pub use bans::BANNED_PEERS_FILE_NAME;
 pub use builder::NetworkBuilder;
 pub use config::{NetworkConfig, NetworkConfigBuilder};
-pub use discovery::Discovery;
+pub use discovery::{Discovery, KNOWN_ENRS_FILE_NAME};
 pub use fetch::FetchClient;
 pub use flattened_response::FlattenedResponse;
 pub use manager::NetworkManager;
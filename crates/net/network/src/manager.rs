@@ -18,7 +18,7 @@
 use std::collections::HashSet;
 use crate::{
     budget::{DEFAULT_BUDGET_TRY_DRAIN_NETWORK_HANDLE_CHANNEL, DEFAULT_BUDGET_TRY_DRAIN_SWARM},
-    config::NetworkConfig,
+    config::{NetworkConfig, N42_FORK_ID_ENR_KEY},
     discovery::Discovery,
     error::{NetworkError, ServiceKind},
     eth_requests::IncomingEthRequest,
@@ -31,6 +31,7 @@ use crate::{
     poll_nested_stream_with_budget,
     protocol::IntoRlpxSubProtocol,
     session::SessionManager,
+    signer_enr::SIGNER_ENR_KEY,
     state::NetworkState,
     swarm::{Swarm, SwarmEvent},
     transactions::NetworkTransactionEvent,
@@ -39,13 +40,14 @@ use crate::{
 use futures::{Future, StreamExt};
 use parking_lot::Mutex;
 use reth_eth_wire::{DisconnectReason, EthNetworkPrimitives, NetworkPrimitives, NewBlock};
+use reth_ethereum_forks::EnrForkIdEntry;
 use reth_fs_util::{self as fs, FsPathError};
 use reth_metrics::common::mpsc::UnboundedMeteredSender;
 use reth_network_api::{
     events::{PeerEvent, SessionInfo},
     test_utils::PeersHandle,
     EthProtocolInfo, NetworkEvent, NetworkStatus, PeerInfo, PeerRequest,
-    BlockAnnounceProvider,
+    BlockAnnounceProvider, BlockOrigin,
     N42BlockImportError, N42BlockImportOutcome, 
 };
 use reth_network_peers::{NodeRecord, PeerId};
@@ -58,7 +60,7 @@ use alloy_consensus::BlockHeader;
 use secp256k1::SecretKey;
 use std::{
     net::SocketAddr,
-    path::Path,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::{
         atomic::{AtomicU64, AtomicUsize, Ordering},
@@ -67,10 +69,17 @@ use std::{
     task::{Context, Poll},
     time::{Duration, Instant},
 };
-use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time::Interval,
+};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, trace, warn};
 
+/// How often the records of the known peers are written to the
+/// [`KNOWN_ENRS_FILE_NAME`](crate::KNOWN_ENRS_FILE_NAME) file.
+const PERSIST_ENRS_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
 #[cfg_attr(doc, aquamarine::aquamarine)]
 // TODO: Inlined diagram due to a bug in aquamarine library, should become an include when it's
 // fixed. See https://github.com/mersinvald/aquamarine/issues/50
@@ -142,6 +151,12 @@ pub struct NetworkManager<N: NetworkPrimitives = EthNetworkPrimitives> {
     metrics: NetworkMetrics,
     /// Disconnect metrics for the Network
     disconnect_metrics: DisconnectMetrics,
+    /// The file the bans are written to whenever they change.
+    bans_file: Option<PathBuf>,
+    /// The file the records of the known peers are written to every [`PERSIST_ENRS_INTERVAL`].
+    enrs_file: Option<PathBuf>,
+    /// Interval at which the records of the known peers are written to the `enrs_file`.
+    persist_enrs_interval: Interval,
 }
 
 impl NetworkManager {
@@ -239,7 +254,15 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             mut discovery_v5_config,
             listener_addr,
             peers_config,
+            signer_peers,
+            signer_enr,
+            signer_set,
+            n42_fork_id_filter,
             sessions_config,
+            bandwidth_limits,
+            bans,
+            bans_file,
+            enrs_file,
             chain_id,
             block_import,
             network_mode,
@@ -249,6 +272,7 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             status,
             fork_filter,
             dns_discovery_config,
+            dns_server,
             extra_protocols,
             tx_gossip_disabled,
             transactions_manager_config: _,
@@ -256,7 +280,8 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             handshake,
         } = config;
 
-        let peers_manager = PeersManager::new(peers_config);
+        let peers_manager =
+            PeersManager::new(peers_config).with_signer_peers(signer_peers).with_bans(bans);
         let peers_handle = peers_manager.handle();
 
         let incoming = ConnectionListener::bind(listener_addr).await.map_err(|err| {
@@ -274,6 +299,13 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             // merge configured boot nodes
             disc_config.bootstrap_nodes.extend(resolved_boot_nodes.clone());
             disc_config.add_eip868_pair("eth", status.forkid);
+            if let Some(entry) = &signer_enr {
+                disc_config.add_eip868_pair(SIGNER_ENR_KEY, entry.clone());
+            }
+            if n42_fork_id_filter {
+                let entry = EnrForkIdEntry::from(status.forkid);
+                disc_config.add_eip868_pair(N42_FORK_ID_ENR_KEY, entry);
+            }
         }
 
         if let Some(discv5) = discovery_v5_config.as_mut() {
@@ -288,8 +320,11 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             discovery_v4_config,
             discovery_v5_config,
             dns_discovery_config,
+            dns_server,
         )
-        .await?;
+        .await?
+        .with_signer_set(signer_set)
+        .with_n42_fork_id_filter(n42_fork_id_filter);
         // need to retrieve the addr here since provided port could be `0`
         let local_peer_id = discovery.local_id();
         let discv4 = discovery.discv4();
@@ -306,7 +341,8 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             fork_filter,
             extra_protocols,
             handshake,
-        );
+        )
+        .with_bandwidth_limits(bandwidth_limits);
 
         let state = NetworkState::new(
             crate::state::BlockNumReader::new(client),
@@ -350,6 +386,12 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             num_active_peers,
             metrics: Default::default(),
             disconnect_metrics: Default::default(),
+            bans_file,
+            enrs_file,
+            persist_enrs_interval: tokio::time::interval_at(
+                tokio::time::Instant::now() + PERSIST_ENRS_INTERVAL,
+                PERSIST_ENRS_INTERVAL,
+            ),
         })
     }
 
@@ -437,6 +479,46 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
         Ok(())
     }
 
+    /// Write the current bans of the peer set to the given `persistent_bans_file`.
+    #[cfg(feature = "serde")]
+    pub fn write_bans_to_file(&self, persistent_bans_file: &Path) -> Result<(), FsPathError> {
+        let bans = self.swarm.state().peers().bans().cloned().collect::<Vec<_>>();
+        persistent_bans_file.parent().map(fs::create_dir_all).transpose()?;
+        reth_fs_util::write_json_file(persistent_bans_file, &bans)?;
+        Ok(())
+    }
+
+    /// Writes the bans to the configured bans file if they changed since the last write.
+    ///
+    /// Called for every ban and unban of the [`NetworkHandle`], and after each poll for the bans
+    /// of misbehaving peers and the expired bans.
+    fn persist_bans(&mut self) {
+        if !self.swarm.state_mut().peers_mut().take_bans_changed() {
+            return
+        }
+        let Some(bans_file) = self.bans_file.as_deref() else { return };
+        #[cfg(feature = "serde")]
+        if let Err(err) = self.write_bans_to_file(bans_file) {
+            warn!(target: "net", %err, "Failed to write banned peers to file");
+        }
+        #[cfg(not(feature = "serde"))]
+        let _ = bans_file;
+    }
+
+    /// Write the signed records of the peers in the peer set found or requested by discovery to the
+    /// given `persistent_enrs_file`.
+    pub fn write_enrs_to_file(&self, persistent_enrs_file: &Path) -> Result<(), FsPathError> {
+        let discovery = self.swarm.state().discovery();
+        let enrs = self
+            .all_peers()
+            .filter_map(|record| discovery.enr(&record.id))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        persistent_enrs_file.parent().map(fs::create_dir_all).transpose()?;
+        reth_fs_util::write_json_file(persistent_enrs_file, &enrs)?;
+        Ok(())
+    }
+
     /// Returns a new [`FetchClient`] that can be cloned and shared.
     ///
     /// The [`FetchClient`] is the entrypoint for sending requests to the network.
@@ -613,6 +695,13 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
                 self.within_pow_or_disconnect(peer_id, |this| {
                     // update peer's state, to track what blocks this peer has seen
                     this.swarm.state_mut().on_new_block_hashes(peer_id, hashes.0.clone());
+                    for announced in &hashes.0 {
+                        this.swarm.state_mut().block_propagation_mut().on_announcement(
+                            announced.hash,
+                            announced.number,
+                            peer_id,
+                        );
+                    }
                     // start block import process for the hashes
                     this.block_import.on_new_block(peer_id, NewBlockEvent::Hashes(hashes));
                 })
@@ -620,6 +709,13 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             PeerMessage::NewBlock(block) => {
                 self.within_pow_or_disconnect(peer_id, move |this| {
                     this.swarm.state_mut().on_new_block(peer_id, block.hash);
+                    let header = block.block.block.header();
+                    this.swarm.state_mut().block_propagation_mut().on_block(
+                        block.hash,
+                        header.number(),
+                        header.timestamp(),
+                        BlockOrigin::Peer { peer_id },
+                    );
                     // start block import process
                     this.block_import.on_new_block(peer_id, NewBlockEvent::Block(block.clone()));
                     // n42
@@ -662,6 +758,13 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
                     warn!(target: "net", "Peer performed block propagation, but it is not supported in proof of stake (EIP-3675)");
                     return
                 }
+                let header = block.block.header();
+                self.swarm.state_mut().block_propagation_mut().on_block(
+                    hash,
+                    header.number(),
+                    header.timestamp(),
+                    BlockOrigin::Local,
+                );
                 let msg = NewBlockMessage { hash, block: Arc::new(block) };
                 self.swarm.state_mut().announce_new_block(msg);
             }
@@ -711,6 +814,21 @@ impl<N: NetworkPrimitives> NetworkManager<N> {
             NetworkHandleMessage::GetReputationById(peer_id, tx) => {
                 let _ = tx.send(self.swarm.state_mut().peers().get_reputation(&peer_id));
             }
+            NetworkHandleMessage::Ban { target, duration, reason } => {
+                self.swarm.state_mut().peers_mut().ban(target, duration, reason);
+                self.persist_bans();
+            }
+            NetworkHandleMessage::Unban(target, tx) => {
+                let was_banned = self.swarm.state_mut().peers_mut().unban(&target);
+                self.persist_bans();
+                let _ = tx.send(was_banned);
+            }
+            NetworkHandleMessage::GetBans(tx) => {
+                let _ = tx.send(self.swarm.state().peers().bans().cloned().collect());
+            }
+            NetworkHandleMessage::GetBlockPropagation(limit, tx) => {
+                let _ = tx.send(self.swarm.state().block_propagation().traces(limit));
+            }
             NetworkHandleMessage::FetchClient(tx) => {
                 let _ = tx.send(self.fetch_client());
             }
@@ -1155,6 +1273,17 @@ impl<N: NetworkPrimitives> Future for NetworkManager<N> {
         poll_durations.acc_swarm =
             start_network_handle.elapsed() - poll_durations.acc_network_handle;
 
+        this.persist_bans();
+
+        // periodically write the records of the known peers, so they outlive an unclean shutdown
+        if this.persist_enrs_interval.poll_tick(cx).is_ready() {
+            if let Some(enrs_file) = this.enrs_file.as_deref() {
+                if let Err(err) = this.write_enrs_to_file(enrs_file) {
+                    warn!(target: "net", %err, "Failed to write peer records to file");
+                }
+            }
+        }
+
         // all streams are fully drained and import futures pending
         if maybe_more_handle_messages || maybe_more_swarm_events {
             // make sure we're woken up again
@@ -77,6 +77,59 @@ pub struct NetworkMetrics {
     pub(crate) acc_duration_poll_swarm: Gauge,
 }
 
+/// Metrics for the discovered nodes rejected before dialing them.
+#[derive(Metrics)]
+#[metrics(scope = "network.discovery")]
+pub struct DiscoveryFilterMetrics {
+    /// Number of discovered nodes rejected because their record has no fork id
+    pub(crate) rejected_missing_fork_id: Counter,
+    /// Number of discovered nodes rejected because the fork id in their record is malformed
+    pub(crate) rejected_malformed_fork_id: Counter,
+    /// Number of discovered nodes rejected because their fork id is incompatible with ours
+    pub(crate) rejected_incompatible_fork_id: Counter,
+}
+
+/// Metrics for the `APos` signer entries of discovered records.
+#[derive(Metrics)]
+#[metrics(scope = "network.discovery")]
+pub struct SignerEnrMetrics {
+    /// Number of discovered nodes whose record advertises a verified signer of the current set
+    pub(crate) verified_signer_nodes: Gauge,
+    /// Number of signer entries rejected as forged or advertising an unauthorized signer
+    pub(crate) rejected_signer_entries: Counter,
+}
+
+/// Metrics for the byte rate limits of the sessions.
+#[derive(Metrics)]
+#[metrics(scope = "network.bandwidth")]
+pub struct BandwidthMetrics {
+    /// Total number of message bytes read from peers, before decompression
+    pub(crate) ingress_bytes: Counter,
+    /// Total number of message bytes sent to peers, before compression
+    pub(crate) egress_bytes: Counter,
+    /// Number of transaction gossip messages dropped to stay within the limits
+    pub(crate) dropped_ingress: Counter,
+    /// Number of times a session held back messages to its peer to stay within the limits
+    pub(crate) throttled_egress: Counter,
+}
+
+/// Metrics for the propagation of blocks through the node
+#[derive(Metrics)]
+#[metrics(scope = "network.block_propagation")]
+pub struct BlockPropagationMetrics {
+    /// Seconds between the timestamp of a block and its first reception in full from a peer.
+    ///
+    /// Header timestamps have a resolution of one second and are set before the out-of-turn
+    /// sealers wait their wiggle, so the delay includes both.
+    pub(crate) receive_delay: Histogram,
+    /// Seconds between the first and every later reception of a block
+    pub(crate) duplicate_delay: Histogram,
+    /// Seconds between the first sighting of a block and its relay to a peer
+    pub(crate) relay_delay: Histogram,
+    /// Number of copies of already seen blocks received from peers
+    pub(crate) duplicate_blocks: Counter,
+}
+
 /// Metrics for `SessionManager`
 #[derive(Metrics)]
 #[metrics(scope = "network")]
@@ -21,10 +21,10 @@ use reth_ethereum_forks::Head;
 use reth_network_api::{
     events::{NetworkPeersEvents, PeerEvent, PeerEventStream},
     test_utils::{PeersHandle, PeersHandleProvider},
-    BlockDownloaderProvider, DiscoveryEvent, NetworkError, NetworkEvent,
+    BanEntry, BanTarget, BlockDownloaderProvider, DiscoveryEvent, NetworkError, NetworkEvent,
     NetworkEventListenerProvider, NetworkInfo, NetworkStatus, PeerInfo, PeerRequest, Peers,
     PeersInfo,
-    BlockAnnounceProvider,
+    BlockAnnounceProvider, BlockPropagation,
     N42BlockImportError, N42BlockImportOutcome, 
 };
 use reth_network_p2p::sync::{NetworkSyncUpdater, SyncState, SyncStateProvider};
@@ -40,6 +40,7 @@ use std::{
         atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
+    time::Duration,
 };
 use tokio::sync::{
     mpsc::{self, UnboundedSender},
@@ -420,6 +421,23 @@ impl<N: NetworkPrimitives> Peers for NetworkHandle<N> {
         let _ = self.manager().send(NetworkHandleMessage::GetReputationById(peer_id, tx));
         Ok(rx.await?)
     }
+
+    /// Sends a message to the [`NetworkManager`](crate::NetworkManager) to ban the target.
+    fn ban(&self, target: BanTarget, duration: Option<Duration>, reason: String) {
+        self.send_message(NetworkHandleMessage::Ban { target, duration, reason })
+    }
+
+    async fn unban(&self, target: BanTarget) -> Result<bool, NetworkError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.manager().send(NetworkHandleMessage::Unban(target, tx));
+        Ok(rx.await?)
+    }
+
+    async fn bans(&self) -> Result<Vec<BanEntry>, NetworkError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.manager().send(NetworkHandleMessage::GetBans(tx));
+        Ok(rx.await?)
+    }
 }
 
 impl<N: NetworkPrimitives> PeersHandleProvider for NetworkHandle<N> {
@@ -507,6 +525,12 @@ impl<N: NetworkPrimitives> BlockAnnounceProvider for NetworkHandle<N> {
         let mut queue = validated_block.write().unwrap();
         queue.push_back(result);
     }
+
+    async fn block_propagation(&self, limit: usize) -> Result<Vec<BlockPropagation>, NetworkError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.manager().send(NetworkHandleMessage::GetBlockPropagation(limit, tx));
+        Ok(rx.await?)
+    }
 }
 
 #[derive(Debug)]
@@ -623,6 +647,21 @@ pub(crate) enum NetworkHandleMessage<N: NetworkPrimitives = EthNetworkPrimitives
     GetPeerInfosByPeerKind(PeerKind, oneshot::Sender<Vec<PeerInfo>>),
     /// Gets the reputation for a specific peer via a oneshot sender.
     GetReputationById(PeerId, oneshot::Sender<Option<Reputation>>),
+    /// Bans a peer, IP or subnet.
+    Ban {
+        /// The banned peer, IP or subnet.
+        target: BanTarget,
+        /// How long the ban lasts, `None` if it is permanent.
+        duration: Option<Duration>,
+        /// Why it is banned.
+        reason: String,
+    },
+    /// Lifts a ban, replying whether the target was banned.
+    Unban(BanTarget, oneshot::Sender<bool>),
+    /// Gets the current bans via a oneshot sender.
+    GetBans(oneshot::Sender<Vec<BanEntry>>),
+    /// Retrieves the propagation of the given number of most recently seen blocks.
+    GetBlockPropagation(usize, oneshot::Sender<Vec<BlockPropagation>>),
     /// Retrieves the `TransactionsHandle` via a oneshot sender.
     GetTransactionsHandle(oneshot::Sender<Option<TransactionsHandle<N>>>),
     /// Initiates a graceful shutdown of the network via a oneshot sender.
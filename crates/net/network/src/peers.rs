@@ -1,6 +1,7 @@
 //! Peer related implementations
 
 use crate::{
+    bans::{unix_now, Bans},
     error::SessionError,
     session::{Direction, PendingSessionHandshakeError},
     swarm::NetworkConnectionState,
@@ -11,8 +12,11 @@ use futures::StreamExt;
 use reth_eth_wire::{errors::EthStreamError, DisconnectReason};
 use reth_ethereum_forks::ForkId;
 use reth_net_banlist::BanList;
-use reth_network_api::test_utils::{PeerCommand, PeersHandle};
-use reth_network_peers::{NodeRecord, PeerId};
+use reth_network_api::{
+    test_utils::{PeerCommand, PeersHandle},
+    BanEntry, BanTarget,
+};
+use reth_network_peers::{NodeRecord, PeerId, TrustedPeer};
 use reth_network_types::{
     peers::{
         config::PeerBackoffDurations,
@@ -37,6 +41,12 @@ use tokio::{
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{trace, warn};
 
+/// How long signer peers are put in time out after a failed connection attempt.
+///
+/// Connections between signers carry the sealed blocks, so they are retried about every refill
+/// interval instead of backing off.
+const SIGNER_PEER_BACKOFF: Duration = Duration::from_secs(1);
+
 /// Maintains the state of _all_ the peers known to the network.
 ///
 /// This is supposed to be owned by the network itself, but can be reached via the [`PeersHandle`].
@@ -52,6 +62,14 @@ pub struct PeersManager {
     /// This tracks peer ids that are considered trusted, but for which we don't necessarily have
     /// an address: [`Self::add_trusted_peer_id`]
     trusted_peer_ids: HashSet<PeerId>,
+    /// The set of `APos` signer peer ids.
+    ///
+    /// Signer peers are dialed and accepted on top of the connection limits, and are only
+    /// penalized for bad blocks: [`Self::add_signer_peer`]
+    signer_peer_ids: HashSet<PeerId>,
+    /// The signer peers found by discovery rather than configured, with the kind they had before,
+    /// see [`Self::add_discovered_signer_peer`].
+    discovered_signer_peer_ids: HashMap<PeerId, PeerKind>,
     /// A resolver used to periodically resolve DNS names for trusted peers. This updates the
     /// peer's address when the DNS records change.
     trusted_peers_resolver: TrustedPeersResolver,
@@ -69,6 +87,12 @@ pub struct PeersManager {
     connection_info: ConnectionInfo,
     /// Tracks unwanted ips/peer ids.
     ban_list: BanList,
+    /// Reasons and expiry of the bans, and the bans of IPs and subnets set by
+    /// [`Self::ban`].
+    bans: Bans,
+    /// The trusted, static and signer peers dropped from the set by [`Self::ban`], added back
+    /// once they are no longer banned.
+    banned_reserved_peers: HashMap<PeerId, BannedReservedPeer>,
     /// Tracks currently backed off peers.
     backed_off_peers: HashMap<PeerId, std::time::Instant>,
     /// Interval at which to check for peers to unban and release from the backoff map.
@@ -139,6 +163,8 @@ impl PeersManager {
         Self {
             peers,
             trusted_peer_ids,
+            signer_peer_ids: Default::default(),
+            discovered_signer_peer_ids: Default::default(),
             trusted_peers_resolver: TrustedPeersResolver::new(
                 trusted_nodes,
                 tokio::time::interval(Duration::from_secs(60 * 60)), // 1 hour
@@ -151,6 +177,8 @@ impl PeersManager {
             release_interval: tokio::time::interval_at(now + unban_interval, unban_interval),
             connection_info: ConnectionInfo::new(connection_info),
             ban_list,
+            bans: Default::default(),
+            banned_reserved_peers: Default::default(),
             backed_off_peers: Default::default(),
             ban_duration,
             backoff_durations,
@@ -162,6 +190,41 @@ impl PeersManager {
         }
     }
 
+    /// Adds the configured `APos` signer peers, see [`Self::add_signer_peer`].
+    pub fn with_signer_peers(mut self, signer_nodes: Vec<TrustedPeer>) -> Self {
+        for signer_peer in signer_nodes {
+            match signer_peer.resolve_blocking() {
+                Ok(NodeRecord { address, tcp_port, udp_port, id }) => {
+                    let addr = PeerAddr::new_with_ports(address, tcp_port, Some(udp_port));
+                    self.add_signer_peer(id, addr);
+                }
+                Err(err) => {
+                    warn!(target: "net::peers", ?err, "Failed to resolve signer peer");
+                }
+            }
+        }
+        self
+    }
+
+    /// Restores the persisted bans, the expired ones are dropped.
+    pub fn with_bans(mut self, bans: Vec<BanEntry>) -> Self {
+        let now = unix_now();
+        for entry in bans.into_iter().filter(|entry| !entry.is_expired(now)) {
+            if let BanTarget::Peer(peer_id) = entry.target {
+                match entry.expires_at {
+                    Some(expires_at) => {
+                        let remaining = Duration::from_secs(expires_at - now);
+                        self.ban_list.ban_peer_until(peer_id, std::time::Instant::now() + remaining)
+                    }
+                    None => self.ban_list.ban_peer(peer_id),
+                }
+            }
+            self.drop_banned_peers(entry.target);
+            self.bans.restore(entry);
+        }
+        self
+    }
+
     /// Returns a new [`PeersHandle`] that can send commands to this type.
     pub(crate) fn handle(&self) -> PeersHandle {
         PeersHandle::new(self.manager_tx.clone())
@@ -229,9 +292,15 @@ impl PeersManager {
         self.backed_off_peers.len()
     }
 
-    /// Returns the number of idle trusted peers.
-    fn num_idle_trusted_peers(&self) -> usize {
-        self.peers.iter().filter(|(_, peer)| peer.kind.is_trusted() && peer.state.is_idle()).count()
+    /// Returns the number of idle trusted and signer peers.
+    fn num_idle_reserved_peers(&self) -> usize {
+        self.peers
+            .iter()
+            .filter(|(peer_id, peer)| {
+                (peer.kind.is_trusted() || self.signer_peer_ids.contains(*peer_id)) &&
+                    peer.state.is_idle()
+            })
+            .count()
     }
 
     /// Invoked when a new _incoming_ tcp connection is accepted.
@@ -241,13 +310,15 @@ impl PeersManager {
         &mut self,
         addr: IpAddr,
     ) -> Result<(), InboundConnectionError> {
-        if self.ban_list.is_banned_ip(&addr) {
+        if self.ban_list.is_banned_ip(&addr) || self.bans.is_banned_ip(&addr) {
             return Err(InboundConnectionError::IpBanned)
         }
 
         // check if we even have slots for a new incoming connection
         if !self.connection_info.has_in_capacity() {
-            if self.trusted_peer_ids.is_empty() {
+            // signer peers have reserved slots, like trusted peers
+            let num_reserved_peers = self.trusted_peer_ids.len() + self.signer_peer_ids.len();
+            if num_reserved_peers == 0 {
                 // if we don't have any incoming slots and no trusted peers, we don't accept any new
                 // connections
                 return Err(InboundConnectionError::ExceedsCapacity)
@@ -256,11 +327,10 @@ impl PeersManager {
             // there's an edge case here where no incoming connections besides from trusted peers
             // are allowed (max_inbound == 0), in which case we still need to allow new pending
             // incoming connections until all trusted peers are connected.
-            let num_idle_trusted_peers = self.num_idle_trusted_peers();
-            if num_idle_trusted_peers <= self.trusted_peer_ids.len() {
+            let num_idle_reserved_peers = self.num_idle_reserved_peers();
+            if num_idle_reserved_peers <= num_reserved_peers {
                 // we still want to limit concurrent pending connections
-                let max_inbound =
-                    self.trusted_peer_ids.len().max(self.connection_info.config.max_inbound);
+                let max_inbound = num_reserved_peers.max(self.connection_info.config.max_inbound);
                 if self.connection_info.num_pending_in < max_inbound {
                     self.connection_info.inc_pending_in();
                     return Ok(())
@@ -301,7 +371,7 @@ impl PeersManager {
         err: &PendingSessionHandshakeError,
     ) {
         if err.is_fatal_protocol_error() {
-            self.ban_ip(remote_addr.ip());
+            self.ban_ip(remote_addr.ip(), format!("fatal handshake error: {err}"));
 
             if err.merits_discovery_ban() {
                 self.queued_actions
@@ -370,7 +440,7 @@ impl PeersManager {
         self.connection_info.inc_in();
 
         // disconnect the peer if we don't have capacity for more inbound connections
-        if !is_trusted && !has_in_capacity {
+        if !is_trusted && !self.signer_peer_ids.contains(&peer_id) && !has_in_capacity {
             self.queued_actions.push_back(PeerAction::Disconnect {
                 peer_id,
                 reason: Some(DisconnectReason::TooManyPeers),
@@ -379,23 +449,134 @@ impl PeersManager {
     }
 
     /// Bans the peer temporarily with the configured ban timeout
-    fn ban_peer(&mut self, peer_id: PeerId) {
+    fn ban_peer(&mut self, peer_id: PeerId, reason: String) {
         let mut ban_duration = self.ban_duration;
         if let Some(peer) = self.peers.get(&peer_id) {
-            if peer.is_trusted() || peer.is_static() {
-                // For misbehaving trusted or static peers, we provide a bit more leeway when
-                // penalizing them.
+            if peer.is_trusted() || peer.is_static() || self.signer_peer_ids.contains(&peer_id) {
+                // For misbehaving trusted, static or signer peers, we provide a bit more leeway
+                // when penalizing them.
                 ban_duration = self.backoff_durations.low / 2;
             }
         }
 
         self.ban_list.ban_peer_until(peer_id, std::time::Instant::now() + ban_duration);
+        self.bans.insert(BanTarget::Peer(peer_id), Some(ban_duration), reason);
         self.queued_actions.push_back(PeerAction::BanPeer { peer_id });
     }
 
     /// Bans the IP temporarily with the configured ban timeout
-    fn ban_ip(&mut self, ip: IpAddr) {
+    fn ban_ip(&mut self, ip: IpAddr, reason: String) {
         self.ban_list.ban_ip_until(ip, std::time::Instant::now() + self.ban_duration);
+        self.bans.insert(BanTarget::Ip(ip), Some(self.ban_duration), reason);
+    }
+
+    /// Returns whether the peer or its IP address is banned.
+    fn is_banned(&self, peer_id: &PeerId, ip: &IpAddr) -> bool {
+        self.ban_list.is_banned(peer_id, ip) || self.bans.is_banned_ip(ip)
+    }
+
+    /// Bans the peer, IP or subnet for `duration`, or until [`Self::unban`] if `None`.
+    ///
+    /// Matching peers are disconnected and dropped from the set, even trusted and signer peers.
+    pub(crate) fn ban(&mut self, target: BanTarget, duration: Option<Duration>, reason: String) {
+        trace!(target: "net::peers", %target, ?duration, %reason, "banning");
+
+        if let BanTarget::Peer(peer_id) = target {
+            match duration {
+                Some(duration) => {
+                    self.ban_list.ban_peer_until(peer_id, std::time::Instant::now() + duration)
+                }
+                None => self.ban_list.ban_peer(peer_id),
+            }
+            self.queued_actions.push_back(PeerAction::BanPeer { peer_id });
+        }
+        self.bans.insert(target, duration, reason);
+        self.drop_banned_peers(target);
+    }
+
+    /// Removes the peers matching `target` from the set, even trusted and signer peers, whose
+    /// status is kept until [`Self::restore_unbanned_peers`].
+    fn drop_banned_peers(&mut self, target: BanTarget) {
+        let banned_peers = self
+            .peers
+            .iter()
+            .filter(|(peer_id, peer)| target.matches(peer_id, &peer.addr.tcp().ip()))
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        for peer_id in banned_peers {
+            let signer = self.signer_peer_ids.remove(&peer_id);
+            let discovered_signer = self.discovered_signer_peer_ids.remove(&peer_id);
+            self.trusted_peer_ids.remove(&peer_id);
+            if let Some(peer) = self.peers.get_mut(&peer_id) {
+                if peer.kind != PeerKind::Basic || signer {
+                    self.banned_reserved_peers.insert(
+                        peer_id,
+                        BannedReservedPeer {
+                            kind: peer.kind,
+                            addr: peer.addr,
+                            signer,
+                            discovered_signer,
+                        },
+                    );
+                }
+                peer.kind = PeerKind::Basic;
+            }
+            self.remove_peer(peer_id);
+        }
+    }
+
+    /// Adds the trusted, static and signer peers dropped by [`Self::ban`] back to the set once
+    /// neither they nor their IP address are banned anymore.
+    fn restore_unbanned_peers(&mut self) {
+        let unbanned_peers = self
+            .banned_reserved_peers
+            .iter()
+            .filter(|(peer_id, peer)| !self.is_banned(peer_id, &peer.addr.tcp().ip()))
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        for peer_id in unbanned_peers {
+            let Some(peer) = self.banned_reserved_peers.remove(&peer_id) else { continue };
+            trace!(target: "net::peers", ?peer_id, kind=?peer.kind, signer=peer.signer, "restoring unbanned peer");
+            if peer.signer {
+                self.signer_peer_ids.insert(peer_id);
+            }
+            if let Some(kind) = peer.discovered_signer {
+                self.discovered_signer_peer_ids.insert(peer_id, kind);
+            }
+            self.add_peer_kind(peer_id, peer.kind, peer.addr, None);
+        }
+    }
+
+    /// Lifts the ban of `target`, returns `false` if it wasn't banned.
+    pub(crate) fn unban(&mut self, target: &BanTarget) -> bool {
+        let mut was_banned = self.bans.remove(target).is_some();
+        match target {
+            BanTarget::Peer(peer_id) => {
+                was_banned |= self.ban_list.is_banned_peer(peer_id);
+                self.ban_list.unban_peer(peer_id);
+                if let Some(peer) = self.peers.get_mut(peer_id) {
+                    peer.unban();
+                }
+                self.queued_actions.push_back(PeerAction::UnBanPeer { peer_id: *peer_id });
+            }
+            BanTarget::Ip(ip) => {
+                was_banned |= self.ban_list.is_banned_ip(ip);
+                self.ban_list.unban_ip(ip);
+            }
+            BanTarget::Subnet(_) => {}
+        }
+        self.restore_unbanned_peers();
+        was_banned
+    }
+
+    /// Returns an iterator over the current bans.
+    pub(crate) fn bans(&self) -> impl Iterator<Item = &BanEntry> + '_ {
+        self.bans.iter()
+    }
+
+    /// Returns whether a ban was added, lifted or expired since the last call.
+    pub(crate) fn take_bans_changed(&mut self) -> bool {
+        self.bans.take_changed()
     }
 
     /// Bans the IP temporarily to rate limit inbound connection attempts per IP.
@@ -417,6 +598,7 @@ impl PeersManager {
     /// Unbans the peer
     fn unban_peer(&mut self, peer_id: PeerId) {
         self.ban_list.unban_peer(&peer_id);
+        self.bans.remove(&BanTarget::Peer(peer_id));
         self.queued_actions.push_back(PeerAction::UnBanPeer { peer_id });
     }
 
@@ -458,6 +640,12 @@ impl PeersManager {
             // First check if we should reset the reputation
             if rep.is_reset() {
                 peer.reset_reputation()
+            } else if self.signer_peer_ids.contains(peer_id) &&
+                !matches!(rep, ReputationChangeKind::BadBlock)
+            {
+                // signer peers are only penalized for the blocks they propagate, so that they stay
+                // connected through slow or flaky periods
+                return
             } else {
                 let mut reputation_change = self.reputation_weights.change(rep).as_i32();
                 if peer.is_trusted() || peer.is_static() {
@@ -487,7 +675,7 @@ impl PeersManager {
         match outcome {
             ReputationChangeOutcome::None => {}
             ReputationChangeOutcome::Ban => {
-                self.ban_peer(*peer_id);
+                self.ban_peer(*peer_id, format!("reputation dropped on {rep:?}"));
             }
             ReputationChangeOutcome::Unban => self.unban_peer(*peer_id),
             ReputationChangeOutcome::DisconnectAndBan => {
@@ -495,7 +683,7 @@ impl PeersManager {
                     peer_id: *peer_id,
                     reason: Some(DisconnectReason::DisconnectRequested),
                 });
-                self.ban_peer(*peer_id);
+                self.ban_peer(*peer_id, format!("reputation dropped on {rep:?}"));
             }
         }
     }
@@ -525,7 +713,10 @@ impl PeersManager {
             Entry::Occupied(mut entry) => {
                 self.connection_info.decr_state(entry.get().state);
 
-                if entry.get().remove_after_disconnect && !entry.get().is_trusted() {
+                if entry.get().remove_after_disconnect &&
+                    !entry.get().is_trusted() &&
+                    !self.signer_peer_ids.contains(&peer_id)
+                {
                     // this peer should be removed from the set
                     entry.remove();
                     self.queued_actions.push_back(PeerAction::PeerRemoved(peer_id));
@@ -603,7 +794,7 @@ impl PeersManager {
             if let Entry::Occupied(mut entry) = self.peers.entry(*peer_id) {
                 self.connection_info.decr_state(entry.get().state);
                 // only remove if the peer is not trusted
-                if entry.get().is_trusted() {
+                if entry.get().is_trusted() || self.signer_peer_ids.contains(peer_id) {
                     entry.get_mut().state = PeerConnectionState::Idle;
                 } else {
                     entry.remove();
@@ -619,14 +810,17 @@ impl PeersManager {
             }
 
             // ban the peer
-            self.ban_peer(*peer_id);
+            self.ban_peer(*peer_id, format!("fatal protocol error: {err}"));
         } else {
             let mut backoff_until = None;
             let mut remove_peer = false;
 
+            let is_signer = self.signer_peer_ids.contains(peer_id);
             if let Some(peer) = self.peers.get_mut(peer_id) {
                 if let Some(kind) = err.should_backoff() {
-                    if peer.is_trusted() || peer.is_static() {
+                    if is_signer {
+                        backoff_until = Some(std::time::Instant::now() + SIGNER_PEER_BACKOFF);
+                    } else if peer.is_trusted() || peer.is_static() {
                         // provide a bit more leeway for trusted peers and use a lower backoff so
                         // that we keep re-trying them after backing off shortly
                         let backoff = self.backoff_durations.low / 2;
@@ -646,7 +840,7 @@ impl PeersManager {
                         // while
                         backoff_until = Some(backoff_time);
                     }
-                } else {
+                } else if !is_signer {
                     // If the error was not a backoff error, we reduce the peer's reputation
                     let reputation_change = self.reputation_weights.change(reputation_change);
                     peer.reputation = peer.reputation.saturating_add(reputation_change.as_i32());
@@ -657,7 +851,8 @@ impl PeersManager {
 
                 if peer.severe_backoff_counter > self.max_backoff_count &&
                     !peer.is_trusted() &&
-                    !peer.is_static()
+                    !peer.is_static() &&
+                    !is_signer
                 {
                     // mark peer for removal if it has been backoff too many times and is _not_
                     // trusted or static
@@ -719,6 +914,45 @@ impl PeersManager {
         self.trusted_peer_ids.insert(peer_id);
     }
 
+    /// Called for a configured or discovered `APos` signer peer.
+    ///
+    /// Signer peers are dialed first and on top of the outbound limit, accepted on top of the
+    /// inbound limit, retried after [`SIGNER_PEER_BACKOFF`] and kept in the set whatever their
+    /// connection failures. Only bad blocks change their reputation.
+    pub(crate) fn add_signer_peer(&mut self, peer_id: PeerId, addr: PeerAddr) {
+        self.signer_peer_ids.insert(peer_id);
+        let kind = self.peers.get(&peer_id).map(|peer| peer.kind).unwrap_or(PeerKind::Static);
+        let fork_id = self.peers.get(&peer_id).and_then(|peer| peer.fork_id);
+        self.add_peer_kind(peer_id, kind, addr, fork_id);
+    }
+
+    /// Called for a peer whose record advertises a verified `APos` signer.
+    ///
+    /// Configured signer peers are left as they are.
+    pub(crate) fn add_discovered_signer_peer(&mut self, peer_id: PeerId, addr: PeerAddr) {
+        if self.signer_peer_ids.contains(&peer_id) &&
+            !self.discovered_signer_peer_ids.contains_key(&peer_id)
+        {
+            return
+        }
+        // peers unknown until now are added as static peers, and are basic peers once no longer
+        // signers
+        let kind = self.peers.get(&peer_id).map(|peer| peer.kind).unwrap_or(PeerKind::Basic);
+        self.discovered_signer_peer_ids.entry(peer_id).or_insert(kind);
+        self.add_signer_peer(peer_id, addr);
+    }
+
+    /// Called when the signer advertised by a discovered signer peer is no longer authorized.
+    ///
+    /// The peer stays in the set with the kind it had before it was a signer peer.
+    pub(crate) fn remove_discovered_signer_peer(&mut self, peer_id: &PeerId) {
+        let Some(kind) = self.discovered_signer_peer_ids.remove(peer_id) else { return };
+        self.signer_peer_ids.remove(peer_id);
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.kind = kind;
+        }
+    }
+
     /// Called for a newly discovered trusted peer.
     ///
     /// If the peer already exists, then the address and kind will be updated.
@@ -737,7 +971,7 @@ impl PeersManager {
         addr: PeerAddr,
         fork_id: Option<ForkId>,
     ) {
-        if self.ban_list.is_banned(&peer_id, &addr.tcp().ip()) {
+        if self.is_banned(&peer_id, &addr.tcp().ip()) {
             return
         }
 
@@ -772,7 +1006,7 @@ impl PeersManager {
     /// Removes the tracked node from the set.
     pub(crate) fn remove_peer(&mut self, peer_id: PeerId) {
         let Entry::Occupied(entry) = self.peers.entry(peer_id) else { return };
-        if entry.get().is_trusted() {
+        if entry.get().is_trusted() || self.signer_peer_ids.contains(&peer_id) {
             return
         }
         let mut peer = entry.remove();
@@ -818,7 +1052,7 @@ impl PeersManager {
         addr: PeerAddr,
         fork_id: Option<ForkId>,
     ) {
-        if self.ban_list.is_banned(&peer_id, &addr.tcp().ip()) {
+        if self.is_banned(&peer_id, &addr.tcp().ip()) {
             return
         }
 
@@ -870,7 +1104,8 @@ impl PeersManager {
     /// Returns the idle peer with the highest reputation.
     ///
     /// Peers that are `trusted` or `static`, see [`PeerKind`], are prioritized as long as they're
-    /// not currently marked as banned or backed off.
+    /// not currently marked as banned or backed off. Signer peers are dialed before, by
+    /// [`Self::fill_outbound_slots`].
     ///
     /// If `trusted_nodes_only` is enabled, see [`PeersConfig`], then this will only consider
     /// `trusted` peers.
@@ -918,6 +1153,25 @@ impl PeersManager {
             return
         }
 
+        // signer peers have reserved slots, so they are dialed regardless of the capacity
+        for peer_id in &self.signer_peer_ids {
+            let Some(peer) = self.peers.get_mut(peer_id) else { continue };
+            if peer.is_backed_off() ||
+                peer.is_banned() ||
+                !peer.state.is_unconnected() ||
+                (self.trusted_nodes_only && !peer.is_trusted())
+            {
+                continue
+            }
+
+            trace!(target: "net::peers", ?peer_id, addr=?peer.addr, "schedule outbound signer connection");
+
+            peer.state = PeerConnectionState::PendingOut;
+            self.connection_info.inc_pending_out();
+            self.queued_actions
+                .push_back(PeerAction::Connect { peer_id: *peer_id, remote_addr: peer.addr.tcp() });
+        }
+
         // as long as there are slots available fill them with the best peers
         while self.connection_info.has_out_capacity() {
             let action = {
@@ -1000,6 +1254,7 @@ impl PeersManager {
             if self.release_interval.poll_tick(cx).is_ready() {
                 let now = std::time::Instant::now();
                 let (_, unbanned_peers) = self.ban_list.evict(now);
+                self.bans.evict_expired();
 
                 for peer_id in unbanned_peers {
                     if let Some(peer) = self.peers.get_mut(&peer_id) {
@@ -1007,6 +1262,7 @@ impl PeersManager {
                         self.queued_actions.push_back(PeerAction::UnBanPeer { peer_id });
                     }
                 }
+                self.restore_unbanned_peers();
 
                 // clear the backoff list of expired backoffs, and mark the relevant peers as
                 // ready to be dialed
@@ -1042,6 +1298,19 @@ impl Default for PeersManager {
     }
 }
 
+/// What made a peer dropped by [`PeersManager::ban`] more than a regular peer.
+#[derive(Debug, Clone, Copy)]
+struct BannedReservedPeer {
+    /// The kind of the peer when it was banned.
+    kind: PeerKind,
+    /// The address of the peer when it was banned.
+    addr: PeerAddr,
+    /// Whether the peer was a signer peer.
+    signer: bool,
+    /// The kind the peer had before it was a signer peer found by discovery, if it was one.
+    discovered_signer: Option<PeerKind>,
+}
+
 /// Tracks stats about connected nodes
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct ConnectionInfo {
@@ -1202,7 +1471,7 @@ mod tests {
         DisconnectReason,
     };
     use reth_net_banlist::BanList;
-    use reth_network_api::Direction;
+    use reth_network_api::{BanEntry, BanTarget, Direction};
     use reth_network_peers::{PeerId, TrustedPeer};
     use reth_network_types::{
         peers::reputation::DEFAULT_REPUTATION, BackoffKind, Peer, ReputationChangeKind,
@@ -1217,7 +1486,7 @@ mod tests {
     };
     use url::Host;
 
-    use super::PeersManager;
+    use super::{PeersManager, SIGNER_PEER_BACKOFF};
     use crate::{
         error::SessionError,
         peers::{
@@ -1304,7 +1573,7 @@ mod tests {
         let peer = PeerId::random();
         let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
         let mut peers = PeersManager::default();
-        peers.ban_peer(peer);
+        peers.ban_peer(peer, "test".to_string());
         peers.add_peer(peer, PeerAddr::from_tcp(socket_addr), None);
 
         match event!(peers) {
@@ -1326,7 +1595,7 @@ mod tests {
         let peer = PeerId::random();
         let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
         let mut peers = PeersManager::default();
-        peers.ban_peer(peer);
+        peers.ban_peer(peer, "test".to_string());
         peers.add_peer(peer, PeerAddr::from_tcp(socket_addr), None);
 
         match event!(peers) {
@@ -1358,6 +1627,115 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_subnet_ban() {
+        let peer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 1, 2)), 8008);
+        let mut peers = PeersManager::default();
+        peers.add_trusted_peer(peer, PeerAddr::from_tcp(socket_addr));
+        assert!(matches!(event!(peers), PeerAction::PeerAdded(_)));
+
+        let subnet: BanTarget = "10.0.0.0/16".parse().unwrap();
+        peers.ban(subnet, None, "spam".to_string());
+        match event!(peers) {
+            PeerAction::PeerRemoved(peer_id) => assert_eq!(peer_id, peer),
+            _ => unreachable!(),
+        }
+        assert!(peers.peers.is_empty());
+        assert_eq!(
+            peers.on_incoming_pending_session(IpAddr::V4(Ipv4Addr::new(10, 0, 200, 1))),
+            Err(InboundConnectionError::IpBanned)
+        );
+
+        // banned addresses aren't added back
+        peers.add_peer(PeerId::random(), PeerAddr::from_tcp(socket_addr), None);
+        assert!(peers.peers.is_empty());
+
+        assert!(peers.unban(&subnet));
+        assert!(!peers.unban(&subnet));
+        assert!(peers.on_incoming_pending_session(socket_addr.ip()).is_ok());
+
+        // the trusted peer is added back
+        assert!(matches!(event!(peers), PeerAction::PeerAdded(peer_id) if peer_id == peer));
+        assert!(peers.peers.get(&peer).unwrap().is_trusted());
+        assert!(peers.trusted_peer_ids.contains(&peer));
+    }
+
+    #[tokio::test]
+    async fn test_signer_peer_restored_on_unban() {
+        let signer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let mut peers = PeersManager::default();
+        peers.add_discovered_signer_peer(signer, PeerAddr::from_tcp(socket_addr));
+        assert!(matches!(event!(peers), PeerAction::PeerAdded(_)));
+
+        let target = BanTarget::Peer(signer);
+        peers.ban(target, None, "spam".to_string());
+        assert!(!peers.peers.contains_key(&signer));
+        assert!(!peers.signer_peer_ids.contains(&signer));
+        assert!(peers.take_bans_changed());
+        assert!(!peers.take_bans_changed());
+
+        assert!(peers.unban(&target));
+        assert!(peers.take_bans_changed());
+        let peer = peers.peers.get(&signer).unwrap();
+        assert!(peer.is_static());
+        assert_eq!(peer.addr, PeerAddr::from_tcp(socket_addr));
+        assert!(peers.signer_peer_ids.contains(&signer));
+        assert!(peers.discovered_signer_peer_ids.contains_key(&signer));
+    }
+
+    #[tokio::test]
+    async fn test_discovered_signer_peer_restores_kind() {
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let mut peers = PeersManager::default();
+
+        // a peer unknown until found as a signer is a basic peer once no longer one, also when
+        // found again in between
+        let unknown = PeerId::random();
+        peers.add_discovered_signer_peer(unknown, PeerAddr::from_tcp(socket_addr));
+        peers.add_discovered_signer_peer(unknown, PeerAddr::from_tcp(socket_addr));
+        assert!(peers.peers.get(&unknown).unwrap().is_static());
+        peers.remove_discovered_signer_peer(&unknown);
+        assert_eq!(peers.peers.get(&unknown).unwrap().kind, PeerKind::Basic);
+        assert!(!peers.signer_peer_ids.contains(&unknown));
+
+        // a known peer gets back the kind it had
+        let trusted = PeerId::random();
+        peers.add_trusted_peer(trusted, PeerAddr::from_tcp(socket_addr));
+        peers.add_discovered_signer_peer(trusted, PeerAddr::from_tcp(socket_addr));
+        assert!(peers.signer_peer_ids.contains(&trusted));
+        peers.remove_discovered_signer_peer(&trusted);
+        assert!(peers.peers.get(&trusted).unwrap().is_trusted());
+        assert!(!peers.signer_peer_ids.contains(&trusted));
+    }
+
+    #[tokio::test]
+    async fn test_restore_bans() {
+        let banned = PeerId::random();
+        let expired = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let bans = vec![
+            BanEntry {
+                target: BanTarget::Peer(banned),
+                reason: "bad block".to_string(),
+                expires_at: None,
+            },
+            BanEntry {
+                target: BanTarget::Peer(expired),
+                reason: "bad block".to_string(),
+                expires_at: Some(1),
+            },
+        ];
+        let mut peers = PeersManager::default().with_bans(bans);
+        assert_eq!(peers.bans().count(), 1);
+
+        peers.add_peer(banned, PeerAddr::from_tcp(socket_addr), None);
+        assert!(peers.peers.is_empty());
+        peers.add_peer(expired, PeerAddr::from_tcp(socket_addr), None);
+        assert!(peers.peers.contains_key(&expired));
+    }
+
     #[tokio::test]
     async fn test_backoff_on_busy() {
         let peer = PeerId::random();
@@ -2887,4 +3265,73 @@ mod tests {
         let updated_peer = manager.peers.get(&peer_id).unwrap();
         assert_eq!(updated_peer.addr.tcp().ip(), updated_ip);
     }
+
+    #[tokio::test]
+    async fn test_signer_peer_dialed_on_top_of_outbound_limit() {
+        let mut peers = PeersManager::new(PeersConfig::test().with_max_outbound(1));
+        let ip = IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2));
+        let basic = PeerId::random();
+        peers.add_peer(basic, PeerAddr::from_tcp(SocketAddr::new(ip, 8008)), None);
+        peers.fill_outbound_slots();
+        peers.on_active_outgoing_established(basic);
+        assert!(!peers.connection_info.has_out_capacity());
+
+        let signer = PeerId::random();
+        peers.add_signer_peer(signer, PeerAddr::from_tcp(SocketAddr::new(ip, 8009)));
+        peers.add_peer(PeerId::random(), PeerAddr::from_tcp(SocketAddr::new(ip, 8010)), None);
+        peers.queued_actions.clear();
+        peers.fill_outbound_slots();
+
+        let dials = peers
+            .queued_actions
+            .iter()
+            .filter_map(|action| match action {
+                PeerAction::Connect { peer_id, .. } => Some(*peer_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(dials, vec![signer]);
+    }
+
+    #[tokio::test]
+    async fn test_signer_peer_only_penalized_for_bad_blocks() {
+        let mut peers = PeersManager::new(PeersConfig::test());
+        let signer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        peers.add_signer_peer(signer, PeerAddr::from_tcp(socket_addr));
+
+        for _ in 0..100 {
+            peers.apply_reputation_change(&signer, ReputationChangeKind::BadMessage);
+            peers.apply_reputation_change(&signer, ReputationChangeKind::Timeout);
+        }
+        assert_eq!(peers.get_reputation(&signer), Some(DEFAULT_REPUTATION));
+
+        // dropped sessions are retried shortly and the peer is kept
+        peers.fill_outbound_slots();
+        peers.on_active_outgoing_established(signer);
+        peers.on_active_session_dropped(
+            &socket_addr,
+            &signer,
+            &EthStreamError::P2PStreamError(P2PStreamError::Disconnected(
+                DisconnectReason::TooManyPeers,
+            )),
+        );
+        let backoff_until = peers.backed_off_peers[&signer];
+        assert!(backoff_until <= std::time::Instant::now() + SIGNER_PEER_BACKOFF);
+        assert_eq!(peers.get_reputation(&signer), Some(DEFAULT_REPUTATION));
+        peers.remove_peer(signer);
+        assert!(peers.peers.contains_key(&signer));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                peers.apply_reputation_change(&signer, ReputationChangeKind::BadBlock);
+                if peers.peers.get(&signer).unwrap().is_banned() {
+                    break
+                }
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("signer peer banned for bad blocks");
+    }
 }
@@ -0,0 +1,178 @@
+//! Tracks how blocks propagate through the node.
+//!
+//! Every block is recorded when it is first seen, sealed locally, received from a peer or announced
+//! by one, along with the later copies and announcements received from other peers and the
+//! announcements relayed to peers. The
+//! delays are exported as histograms and the most recent traces can be queried as
+//! [`BlockPropagation`]s.
+
+use crate::{cache::LruMap, metrics::BlockPropagationMetrics};
+use alloy_primitives::{BlockNumber, B256};
+use reth_network_api::{BlockHop, BlockOrigin, BlockPropagation};
+use reth_network_peers::PeerId;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Number of blocks the tracker keeps the propagation of.
+const TRACKED_BLOCKS_LIMIT: u32 = 128;
+
+/// Records when blocks were first seen and where they were sent afterwards.
+#[derive(Debug)]
+pub(crate) struct BlockPropagationTracker {
+    /// Traces of the recently seen blocks, most recent first.
+    blocks: LruMap<B256, TrackedBlock>,
+    metrics: BlockPropagationMetrics,
+}
+
+impl BlockPropagationTracker {
+    /// Records that the block was seen, returns `false` if it was already tracked.
+    ///
+    /// `timestamp` is the timestamp of the block header, used to measure how long blocks of peers
+    /// took to reach the node. It only has a resolution of one second and is set before an
+    /// out-of-turn sealer waits its wiggle, so the measured delay includes both.
+    pub(crate) fn on_block(
+        &mut self,
+        hash: B256,
+        number: BlockNumber,
+        timestamp: u64,
+        origin: BlockOrigin,
+    ) -> bool {
+        if let Some(tracked) = self.blocks.peek_mut(&hash) {
+            if let BlockOrigin::Peer { peer_id } = origin {
+                let delay = tracked.first_seen.elapsed();
+                if tracked.full {
+                    self.metrics.duplicate_delay.record(delay);
+                    self.metrics.duplicate_blocks.increment(1);
+                } else {
+                    // only its hash was announced so far
+                    self.metrics.receive_delay.record(since_timestamp(timestamp));
+                    tracked.full = true;
+                }
+                tracked.trace.received.push(hop(peer_id, delay, true));
+            }
+            return false
+        }
+
+        if origin != BlockOrigin::Local {
+            self.metrics.receive_delay.record(since_timestamp(timestamp));
+        }
+        self.track(hash, number, origin, true);
+        true
+    }
+
+    /// Records that the peer announced the hash of the block.
+    pub(crate) fn on_announcement(&mut self, hash: B256, number: BlockNumber, peer_id: PeerId) {
+        if let Some(tracked) = self.blocks.peek_mut(&hash) {
+            let delay = tracked.first_seen.elapsed();
+            tracked.trace.received.push(hop(peer_id, delay, false));
+            return
+        }
+        self.track(hash, number, BlockOrigin::Peer { peer_id }, false);
+    }
+
+    /// Starts the trace of a block seen for the first time.
+    fn track(&mut self, hash: B256, number: BlockNumber, origin: BlockOrigin, full: bool) {
+        let trace = BlockPropagation {
+            hash,
+            number,
+            first_seen_ms: unix_now_ms(),
+            origin,
+            received: Vec::new(),
+            relayed: Vec::new(),
+        };
+        self.blocks.insert(hash, TrackedBlock { first_seen: Instant::now(), full, trace });
+    }
+
+    /// Records that the block, or only its hash if `full` is `false`, was sent to the peer.
+    pub(crate) fn on_relay(&mut self, hash: &B256, peer_id: PeerId, full: bool) {
+        if let Some(tracked) = self.blocks.peek_mut(hash) {
+            let delay = tracked.first_seen.elapsed();
+            self.metrics.relay_delay.record(delay);
+            tracked.trace.relayed.push(hop(peer_id, delay, full));
+        }
+    }
+
+    /// Returns the traces of the `limit` most recently seen blocks, most recent first.
+    pub(crate) fn traces(&self, limit: usize) -> Vec<BlockPropagation> {
+        self.blocks.iter().take(limit).map(|(_, tracked)| tracked.trace.clone()).collect()
+    }
+}
+
+impl Default for BlockPropagationTracker {
+    fn default() -> Self {
+        Self { blocks: LruMap::new(TRACKED_BLOCKS_LIMIT), metrics: Default::default() }
+    }
+}
+
+/// A tracked block.
+#[derive(Debug)]
+struct TrackedBlock {
+    /// When the block was first seen, delays are measured from it.
+    first_seen: Instant,
+    /// Whether the block was seen in full, rather than only announced.
+    full: bool,
+    trace: BlockPropagation,
+}
+
+fn hop(peer_id: PeerId, delay: Duration, full: bool) -> BlockHop {
+    BlockHop { peer_id, delay_ms: delay.as_millis() as u64, full }
+}
+
+/// Returns the time elapsed since the header `timestamp`, in seconds.
+fn since_timestamp(timestamp: u64) -> Duration {
+    Duration::from_millis(unix_now_ms().saturating_sub(timestamp.saturating_mul(1000)))
+}
+
+fn unix_now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_block_propagation() {
+        let mut tracker = BlockPropagationTracker::default();
+        let (first, second) = (PeerId::random(), PeerId::random());
+        let origin = BlockOrigin::Peer { peer_id: first };
+
+        assert!(tracker.on_block(B256::with_last_byte(1), 1, 0, origin));
+        let duplicate = BlockOrigin::Peer { peer_id: second };
+        assert!(!tracker.on_block(B256::with_last_byte(1), 1, 0, duplicate));
+        tracker.on_relay(&B256::with_last_byte(1), second, false);
+        assert!(tracker.on_block(B256::with_last_byte(2), 2, 0, BlockOrigin::Local));
+        // untracked blocks are ignored
+        tracker.on_relay(&B256::with_last_byte(3), first, true);
+
+        let traces = tracker.traces(10);
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[0].origin, BlockOrigin::Local);
+        assert_eq!(traces[1].origin, origin);
+        assert_eq!(traces[1].received.len(), 1);
+        assert_eq!(traces[1].received[0].peer_id, second);
+        assert_eq!(traces[1].relayed.len(), 1);
+        assert!(!traces[1].relayed[0].full);
+
+        assert_eq!(tracker.traces(1).len(), 1);
+    }
+
+    #[test]
+    fn test_track_block_announcements() {
+        let mut tracker = BlockPropagationTracker::default();
+        let (first, second) = (PeerId::random(), PeerId::random());
+        let hash = B256::with_last_byte(1);
+
+        tracker.on_announcement(hash, 1, first);
+        tracker.on_announcement(hash, 1, second);
+        // the first full copy isn't a duplicate
+        assert!(!tracker.on_block(hash, 1, 0, BlockOrigin::Peer { peer_id: second }));
+        assert!(tracker.blocks.peek_mut(&hash).unwrap().full);
+
+        let traces = tracker.traces(10);
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].origin, BlockOrigin::Peer { peer_id: first });
+        assert_eq!(traces[0].received.len(), 2);
+        assert!(!traces[0].received[0].full);
+        assert!(traces[0].received[1].full);
+    }
+}
@@ -14,12 +14,14 @@ use std::{
 use crate::{
     message::{NewBlockMessage, PeerMessage, PeerResponse, PeerResponseResult},
     session::{
+        bandwidth::SessionBandwidth,
         conn::EthRlpxConnection,
         handle::{ActiveSessionMessage, SessionCommand},
         SessionId,
     },
 };
 use alloy_primitives::Sealable;
+use alloy_rlp::Encodable;
 use futures::{stream::Fuse, SinkExt, StreamExt};
 use metrics::Gauge;
 use reth_eth_wire::{
@@ -114,6 +116,8 @@ pub(crate) struct ActiveSession<N: NetworkPrimitives> {
     /// Used to reserve a slot to guarantee that the termination message is delivered
     pub(crate) terminate_message:
         Option<(PollSender<ActiveSessionMessage<N>>, ActiveSessionMessage<N>)>,
+    /// Byte rate limits of the session.
+    pub(crate) bandwidth: SessionBandwidth,
 }
 
 impl<N: NetworkPrimitives> ActiveSession<N> {
@@ -574,8 +578,12 @@ impl<N: NetworkPrimitives> Future for ActiveSession<N> {
 
             // Send messages by advancing the sink and queuing in buffered messages
             while this.conn.poll_ready_unpin(cx).is_ready() {
-                if let Some(msg) = this.queued_outgoing.pop_front() {
+                let egress = &mut this.bandwidth.egress;
+                if let Some(msg) =
+                    this.queued_outgoing.pop_front(|| egress.poll_ready(cx).is_ready())
+                {
                     progress = true;
+                    egress.consume(msg.length());
                     let res = match msg {
                         OutgoingMessage::Eth(msg) => this.conn.start_send_unpin(msg),
                         OutgoingMessage::Broadcast(msg) => this.conn.start_send_broadcast(msg),
@@ -654,6 +662,17 @@ impl<N: NetworkPrimitives> Future for ActiveSession<N> {
                         match res {
                             Ok(msg) => {
                                 trace!(target: "net::session", msg_id=?msg.message_id(), remote_peer_id=?this.remote_peer_id, "received eth message");
+                                // blocks are never throttled, transaction gossip is dropped while
+                                // over the ingress limits, the other messages are always read
+                                if is_block_message(&msg) {
+                                    this.bandwidth.ingress.record(msg.length());
+                                } else if !is_gossip_message(&msg) {
+                                    this.bandwidth.ingress.consume(msg.length());
+                                } else if !this.bandwidth.ingress.try_consume(msg.length()) {
+                                    trace!(target: "net::session", msg_id=?msg.message_id(), remote_peer_id=?this.remote_peer_id, "dropped transaction gossip over the ingress limits");
+                                    progress = true;
+                                    continue 'receive
+                                }
                                 // decode and handle message
                                 match this.on_incoming_message(msg) {
                                     OnIncomingMessageOutcome::Ok => {
@@ -793,6 +812,42 @@ impl<N: NetworkPrimitives> OutgoingMessage<N> {
             _ => false,
         }
     }
+
+    /// Returns true if the message is sent ahead of the others, whatever the bandwidth limits.
+    ///
+    /// These are blocks and the messages of other sub-protocols, which carry consensus messages.
+    const fn is_priority(&self) -> bool {
+        match self {
+            Self::Eth(msg) => is_block_message(msg),
+            Self::Broadcast(msg) => matches!(msg, EthBroadcastMessage::NewBlock(_)),
+            Self::Raw(_) => true,
+        }
+    }
+
+    /// Returns the size of the encoded message.
+    fn length(&self) -> usize {
+        match self {
+            Self::Eth(msg) => msg.length(),
+            Self::Broadcast(msg) => msg.length(),
+            Self::Raw(msg) => msg.payload.len(),
+        }
+    }
+}
+
+/// Returns true if `msg` announces a block.
+const fn is_block_message<N: NetworkPrimitives>(msg: &EthMessage<N>) -> bool {
+    matches!(msg, EthMessage::NewBlock(_) | EthMessage::NewBlockHashes(_))
+}
+
+/// Returns true if the message is transaction gossip, which is dropped while over the ingress
+/// limits.
+const fn is_gossip_message<N: NetworkPrimitives>(msg: &EthMessage<N>) -> bool {
+    matches!(
+        msg,
+        EthMessage::Transactions(_) |
+            EthMessage::NewPooledTransactionHashes66(_) |
+            EthMessage::NewPooledTransactionHashes68(_)
+    )
 }
 
 impl<N: NetworkPrimitives> From<EthMessage<N>> for OutgoingMessage<N> {
@@ -819,26 +874,42 @@ fn calculate_new_timeout(current_timeout: Duration, estimated_rtt: Duration) ->
 }
 
 /// A helper struct that wraps the queue of outgoing messages and a metric to track their count
+///
+/// Priority messages, see [`OutgoingMessage::is_priority`], are queued separately and sent first.
 pub(crate) struct QueuedOutgoingMessages<N: NetworkPrimitives> {
+    priority: VecDeque<OutgoingMessage<N>>,
     messages: VecDeque<OutgoingMessage<N>>,
     count: Gauge,
 }
 
 impl<N: NetworkPrimitives> QueuedOutgoingMessages<N> {
     pub(crate) const fn new(metric: Gauge) -> Self {
-        Self { messages: VecDeque::new(), count: metric }
+        Self { priority: VecDeque::new(), messages: VecDeque::new(), count: metric }
     }
 
     pub(crate) fn push_back(&mut self, message: OutgoingMessage<N>) {
-        self.messages.push_back(message);
+        if message.is_priority() {
+            self.priority.push_back(message);
+        } else {
+            self.messages.push_back(message);
+        }
         self.count.increment(1);
     }
 
-    pub(crate) fn pop_front(&mut self) -> Option<OutgoingMessage<N>> {
-        self.messages.pop_front().inspect(|_| self.count.decrement(1))
+    /// Returns the next message to send, priority messages first.
+    ///
+    /// The other messages are only returned if `ready` says they can be sent now.
+    pub(crate) fn pop_front(&mut self, ready: impl FnOnce() -> bool) -> Option<OutgoingMessage<N>> {
+        let message = match self.priority.pop_front() {
+            Some(message) => Some(message),
+            None if !self.messages.is_empty() && ready() => self.messages.pop_front(),
+            None => None,
+        };
+        message.inspect(|_| self.count.decrement(1))
     }
 
     pub(crate) fn shrink_to_fit(&mut self) {
+        self.priority.shrink_to_fit();
         self.messages.shrink_to_fit();
     }
 }
@@ -846,8 +917,13 @@ impl<N: NetworkPrimitives> QueuedOutgoingMessages<N> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::session::{handle::PendingSessionEvent, start_pending_incoming_session};
+    use crate::session::{
+        bandwidth::{BandwidthLimits, SharedBandwidth},
+        handle::PendingSessionEvent,
+        start_pending_incoming_session,
+    };
     use alloy_eips::eip2124::ForkFilter;
+    use alloy_primitives::B256;
     use reth_chainspec::MAINNET;
     use reth_ecies::stream::ECIESStream;
     use reth_eth_wire::{
@@ -981,6 +1057,7 @@ mod tests {
                         )),
                         protocol_breach_request_timeout: PROTOCOL_BREACH_REQUEST_TIMEOUT,
                         terminate_message: None,
+                        bandwidth: SharedBandwidth::default().session(),
                     }
                 }
                 ev => {
@@ -1226,6 +1303,61 @@ mod tests {
         }
     }
 
+    // This tests that only transaction gossip gives way to the ingress limits.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ingress_limit_drops_gossip_only() {
+        let mut builder = SessionBuilder::default();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let fut = builder.with_client_stream(local_addr, move |mut client_stream| async move {
+            // the first announcement puts the session in debt for minutes
+            for _ in 0..2 {
+                client_stream
+                    .send(EthMessage::NewPooledTransactionHashes66(vec![B256::ZERO; 8].into()))
+                    .await
+                    .unwrap();
+            }
+            client_stream
+                .send(EthMessage::GetBlockBodies(RequestPair {
+                    request_id: 0,
+                    message: GetBlockBodies(vec![]),
+                }))
+                .await
+                .unwrap();
+            let _ = tokio::time::timeout(Duration::from_secs(100), client_stream.next()).await;
+        });
+        tokio::task::spawn(fut);
+
+        let (incoming, _) = listener.accept().await.unwrap();
+        let mut session = builder.connect_incoming(incoming).await;
+        session.bandwidth =
+            SharedBandwidth::new(BandwidthLimits { peer_ingress: Some(1), ..Default::default() })
+                .session();
+        tokio::task::spawn(session);
+
+        let message = builder.active_session_rx.next().await.unwrap();
+        assert!(matches!(
+            message,
+            ActiveSessionMessage::ValidMessage { message: PeerMessage::PooledTransactions(_), .. }
+        ));
+
+        // the second announcement is dropped and the request is still read
+        let message =
+            tokio::time::timeout(Duration::from_secs(5), builder.active_session_rx.next())
+                .await
+                .unwrap()
+                .unwrap();
+        match message {
+            ActiveSessionMessage::ValidMessage {
+                message: PeerMessage::EthRequest(PeerRequest::GetBlockBodies { .. }),
+                ..
+            } => {}
+            ev => unreachable!("{ev:?}"),
+        }
+    }
+
     #[test]
     fn timeout_calculation_sanity_tests() {
         let rtt = Duration::from_secs(5);
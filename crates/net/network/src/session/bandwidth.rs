@@ -0,0 +1,279 @@
+//! Byte rate limits of the `RLPx` sessions.
+//!
+//! Each direction is limited by a token bucket per session and by a token bucket shared by all
+//! sessions. Buckets may go into debt: a message is always accounted in full, and the session
+//! then waits until the debt is repaid before it sends the next throttled message.
+//!
+//! Blocks and the messages of other sub-protocols, like consensus messages, are never held back.
+//! Sent ones still drain the buckets so that the transaction gossip and the requests queued behind
+//! them make room. Received messages are never held back either, the peer isn't slowed down:
+//! requests and responses drain the ingress buckets, and the transaction gossip received while
+//! they are in debt is dropped. Received blocks are only counted.
+
+use crate::metrics::BandwidthMetrics;
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+
+/// Byte rate limits of the `RLPx` sessions, in bytes per second.
+///
+/// Sizes are those of the encoded messages, before compression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+    /// Limit of the bytes received from all peers.
+    pub ingress: Option<u64>,
+    /// Limit of the bytes sent to all peers.
+    pub egress: Option<u64>,
+    /// Limit of the bytes received from a single peer.
+    pub peer_ingress: Option<u64>,
+    /// Limit of the bytes sent to a single peer.
+    pub peer_egress: Option<u64>,
+}
+
+impl BandwidthLimits {
+    /// Whether no limit is set.
+    pub const fn is_unlimited(&self) -> bool {
+        self.ingress.is_none() &&
+            self.egress.is_none() &&
+            self.peer_ingress.is_none() &&
+            self.peer_egress.is_none()
+    }
+}
+
+/// Token bucket holding up to one second of its rate.
+#[derive(Debug)]
+struct TokenBucket {
+    /// Bytes per second.
+    rate: f64,
+    /// Available bytes, negative while in debt.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        let rate = rate.max(1) as f64;
+        Self { rate, tokens: rate, updated: Instant::now() }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.updated = now;
+    }
+
+    fn consume(&mut self, bytes: usize, now: Instant) {
+        self.refill(now);
+        self.tokens -= bytes as f64;
+    }
+
+    /// Returns how long to wait until the debt is repaid.
+    fn wait_time(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        (self.tokens < 0.0).then(|| Duration::from_secs_f64(-self.tokens / self.rate))
+    }
+}
+
+/// The buckets shared by all sessions.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedBandwidth {
+    limits: BandwidthLimits,
+    ingress: Option<Arc<Mutex<TokenBucket>>>,
+    egress: Option<Arc<Mutex<TokenBucket>>>,
+    metrics: Arc<BandwidthMetrics>,
+}
+
+impl SharedBandwidth {
+    /// Creates the shared buckets of `limits`.
+    pub(crate) fn new(limits: BandwidthLimits) -> Self {
+        let bucket =
+            |rate: Option<u64>| rate.map(|rate| Arc::new(Mutex::new(TokenBucket::new(rate))));
+        Self {
+            limits,
+            ingress: bucket(limits.ingress),
+            egress: bucket(limits.egress),
+            metrics: Default::default(),
+        }
+    }
+
+    /// Returns the limiters of a new session.
+    pub(crate) fn session(&self) -> SessionBandwidth {
+        SessionBandwidth {
+            ingress: Limiter::new(
+                self.limits.peer_ingress,
+                self.ingress.clone(),
+                self.metrics.clone(),
+                Direction::Ingress,
+            ),
+            egress: Limiter::new(
+                self.limits.peer_egress,
+                self.egress.clone(),
+                self.metrics.clone(),
+                Direction::Egress,
+            ),
+        }
+    }
+}
+
+/// The limiters of a session.
+#[derive(Debug)]
+pub(crate) struct SessionBandwidth {
+    /// Limits the messages read from the peer.
+    pub(crate) ingress: Limiter,
+    /// Limits the messages sent to the peer.
+    pub(crate) egress: Limiter,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Ingress,
+    Egress,
+}
+
+/// Limits one direction of a session.
+#[derive(Debug)]
+pub(crate) struct Limiter {
+    peer: Option<TokenBucket>,
+    global: Option<Arc<Mutex<TokenBucket>>>,
+    /// Fires once the debt is repaid, set while throttled.
+    delay: Option<Pin<Box<Sleep>>>,
+    metrics: Arc<BandwidthMetrics>,
+    direction: Direction,
+}
+
+impl Limiter {
+    fn new(
+        peer_rate: Option<u64>,
+        global: Option<Arc<Mutex<TokenBucket>>>,
+        metrics: Arc<BandwidthMetrics>,
+        direction: Direction,
+    ) -> Self {
+        Self { peer: peer_rate.map(TokenBucket::new), global, delay: None, metrics, direction }
+    }
+
+    /// Accounts a message of `bytes` that isn't throttled, it only shows in the metrics.
+    pub(crate) fn record(&self, bytes: usize) {
+        match self.direction {
+            Direction::Ingress => self.metrics.ingress_bytes.increment(bytes as u64),
+            Direction::Egress => self.metrics.egress_bytes.increment(bytes as u64),
+        }
+    }
+
+    /// Accounts a message of `bytes` against the limits.
+    pub(crate) fn consume(&mut self, bytes: usize) {
+        let now = Instant::now();
+        if let Some(peer) = &mut self.peer {
+            peer.consume(bytes, now);
+        }
+        if let Some(global) = &self.global {
+            global.lock().consume(bytes, now);
+        }
+        self.record(bytes);
+    }
+
+    /// Accounts a message of `bytes` that is dropped while over the limits, returns `false` if it
+    /// is.
+    pub(crate) fn try_consume(&mut self, bytes: usize) -> bool {
+        let now = Instant::now();
+        let peer_debt = self.peer.as_mut().is_some_and(|peer| peer.wait_time(now).is_some());
+        let global_debt =
+            self.global.as_ref().is_some_and(|global| global.lock().wait_time(now).is_some());
+        if peer_debt || global_debt {
+            self.metrics.dropped_ingress.increment(1);
+            self.record(bytes);
+            return false
+        }
+        self.consume(bytes);
+        true
+    }
+
+    /// Returns whether the next throttled message can be sent.
+    ///
+    /// If not, the task is woken up once the buckets are out of debt.
+    pub(crate) fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            let now = Instant::now();
+            let peer_wait = self.peer.as_mut().and_then(|peer| peer.wait_time(now));
+            let global_wait = self.global.as_ref().and_then(|global| global.lock().wait_time(now));
+            let Some(wait) = peer_wait.max(global_wait) else {
+                self.delay = None;
+                return Poll::Ready(())
+            };
+
+            let deadline = now + wait;
+            match &mut self.delay {
+                Some(delay) => delay.as_mut().reset(deadline),
+                None => {
+                    self.metrics.throttled_egress.increment(1);
+                    self.delay = Some(Box::pin(tokio::time::sleep_until(deadline)));
+                }
+            }
+            if let Some(delay) = &mut self.delay {
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_goes_into_debt() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(1000);
+        bucket.updated = now;
+
+        bucket.consume(500, now);
+        assert_eq!(bucket.wait_time(now), None);
+
+        bucket.consume(1500, now);
+        assert_eq!(bucket.wait_time(now), Some(Duration::from_secs(1)));
+        assert_eq!(bucket.wait_time(now + Duration::from_secs(1)), None);
+    }
+
+    #[tokio::test]
+    async fn drops_while_in_debt() {
+        let shared =
+            SharedBandwidth::new(BandwidthLimits { ingress: Some(1000), ..Default::default() });
+        let mut first = shared.session();
+        let mut second = shared.session();
+
+        assert!(first.ingress.try_consume(1500));
+        assert!(!second.ingress.try_consume(10));
+        // other messages are still accounted
+        second.ingress.consume(500);
+        assert!(!first.ingress.try_consume(10));
+
+        shared.ingress.as_ref().unwrap().lock().tokens = 0.0;
+        assert!(first.ingress.try_consume(10));
+    }
+
+    #[tokio::test]
+    async fn shared_bucket_throttles_all_sessions() {
+        let shared =
+            SharedBandwidth::new(BandwidthLimits { egress: Some(1000), ..Default::default() });
+        let mut first = shared.session();
+        let mut second = shared.session();
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        first.egress.consume(3000);
+        assert!(second.egress.poll_ready(&mut cx).is_pending());
+        assert!(second.ingress.poll_ready(&mut cx).is_ready());
+
+        // the debt is repaid
+        shared.egress.as_ref().unwrap().lock().tokens = 0.0;
+        assert!(second.egress.poll_ready(&mut cx).is_ready());
+        assert!(first.egress.poll_ready(&mut cx).is_ready());
+    }
+}
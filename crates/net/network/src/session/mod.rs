@@ -1,11 +1,14 @@
 //! Support for handling peer sessions.
 
 mod active;
+mod bandwidth;
 mod conn;
 mod counter;
 mod handle;
 
 use active::QueuedOutgoingMessages;
+pub use bandwidth::BandwidthLimits;
+use bandwidth::SharedBandwidth;
 pub use conn::EthRlpxConnection;
 pub use handle::{
     ActiveSessionHandle, ActiveSessionMessage, PendingSessionEvent, PendingSessionHandle,
@@ -115,6 +118,8 @@ pub struct SessionManager<N: NetworkPrimitives> {
     metrics: SessionManagerMetrics,
     /// The [`EthRlpxHandshake`] is used to perform the initial handshake with the peer.
     handshake: Arc<dyn EthRlpxHandshake>,
+    /// Byte rate limits shared by the active sessions.
+    bandwidth: SharedBandwidth,
 }
 
 // === impl SessionManager ===
@@ -158,9 +163,20 @@ impl<N: NetworkPrimitives> SessionManager<N> {
             disconnections_counter: Default::default(),
             metrics: Default::default(),
             handshake,
+            bandwidth: Default::default(),
         }
     }
 
+    /// Limits the byte rates of the active sessions.
+    ///
+    /// Blocks and the messages of other sub-protocols are always sent right away, the transaction
+    /// gossip and the requests and responses wait while a limit is exceeded. Received messages are
+    /// always read, the transaction gossip received while a limit is exceeded is dropped.
+    pub fn with_bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.bandwidth = SharedBandwidth::new(limits);
+        self
+    }
+
     /// Check whether the provided [`ForkId`] is compatible based on the validation rules in
     /// `EIP-2124`.
     pub fn is_valid_fork_id(&self, fork_id: ForkId) -> bool {
@@ -540,6 +556,7 @@ impl<N: NetworkPrimitives> SessionManager<N> {
                     internal_request_timeout: Arc::clone(&timeout),
                     protocol_breach_request_timeout: self.protocol_breach_request_timeout,
                     terminate_message: None,
+                    bandwidth: self.bandwidth.session(),
                 };
 
                 self.spawn(session);
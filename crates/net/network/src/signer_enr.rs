@@ -0,0 +1,161 @@
+//! `APos` signer identity advertised in discovery records.
+//!
+//! A node run by an authorized signer publishes a [`SignerEnrEntry`] under [`SIGNER_ENR_KEY`]: its
+//! signer address and a signature by the signer key over the node id. Other nodes recover the
+//! address from the signature and only accept the entry if it is a signer of their current
+//! snapshot, see [`SignerSet`]. A copied entry doesn't verify for another node id.
+
+use alloy_primitives::{keccak256, Address, Bytes, B256};
+use alloy_rlp::{RlpDecodable, RlpEncodable};
+use reth_network_peers::PeerId;
+use reth_primitives_traits::header::clique_utils::public_key_to_address;
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message, SecretKey, SECP256K1,
+};
+use std::collections::HashSet;
+use tokio::sync::watch;
+use tokio_stream::wrappers::WatchStream;
+
+/// Key of the signer entry in the ENR of `APos` signer nodes.
+pub const SIGNER_ENR_KEY: &str = "apos";
+
+/// Domain separating the signed node ids from any other message signed by the signer key.
+const SIGNER_ENR_DOMAIN: &[u8] = b"n42-apos-signer-enr";
+
+/// Length of a recoverable signature.
+const SIGNATURE_LENGTH: usize = 65;
+
+/// The set of authorized `APos` signers.
+///
+/// The node keeps it up to date on top of its consensus through the sender returned by
+/// [`SignerSet::new`]. Discovery reads it whenever it finds a record with a [`SignerEnrEntry`], and
+/// verifies the entries it found again once it changes.
+#[derive(Debug, Clone)]
+pub struct SignerSet {
+    signers: watch::Receiver<HashSet<Address>>,
+}
+
+impl SignerSet {
+    /// Creates the set holding `signers`, with the sender replacing them.
+    pub fn new(signers: HashSet<Address>) -> (watch::Sender<HashSet<Address>>, Self) {
+        let (tx, signers) = watch::channel(signers);
+        (tx, Self { signers })
+    }
+
+    /// Returns whether `signer` is an authorized signer at the current head.
+    pub fn is_signer(&self, signer: &Address) -> bool {
+        self.signers.borrow().contains(signer)
+    }
+
+    /// Returns a stream yielding the signers whenever they change.
+    pub(crate) fn changes(&self) -> WatchStream<HashSet<Address>> {
+        WatchStream::from_changes(self.signers.clone())
+    }
+}
+
+/// Errors verifying a [`SignerEnrEntry`].
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SignerEnrError {
+    /// The signature is malformed or doesn't recover a key.
+    #[error("invalid signer entry signature: {0}")]
+    InvalidSignature(#[from] secp256k1::Error),
+    /// The signature was made by another key than the one of the advertised signer.
+    #[error("signer entry signed by {recovered}, not by {signer}")]
+    SignerMismatch {
+        /// The advertised signer.
+        signer: Address,
+        /// The signer recovered from the signature.
+        recovered: Address,
+    },
+}
+
+/// Signer address of a node and the proof that the signer key runs the node.
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct SignerEnrEntry {
+    /// The advertised signer address.
+    pub signer: Address,
+    /// Recoverable signature of the node id by the signer key.
+    pub signature: Bytes,
+}
+
+impl SignerEnrEntry {
+    /// Signs the node id `node_id` with the signer key `signer_key`.
+    pub fn new(node_id: PeerId, signer_key: &SecretKey) -> Self {
+        let message = Message::from_digest(signing_hash(node_id).0);
+        let (recovery_id, signature) =
+            SECP256K1.sign_ecdsa_recoverable(&message, signer_key).serialize_compact();
+
+        let mut bytes = Vec::with_capacity(SIGNATURE_LENGTH);
+        bytes.extend_from_slice(&signature);
+        bytes.push(i32::from(recovery_id) as u8);
+
+        Self {
+            signer: public_key_to_address(signer_key.public_key(SECP256K1)),
+            signature: bytes.into(),
+        }
+    }
+
+    /// Returns the signer if the entry was signed by its key for the node id `node_id`.
+    pub fn verify(&self, node_id: PeerId) -> Result<Address, SignerEnrError> {
+        if self.signature.len() != SIGNATURE_LENGTH {
+            return Err(secp256k1::Error::InvalidSignature.into())
+        }
+        let signature = RecoverableSignature::from_compact(
+            &self.signature[..64],
+            RecoveryId::try_from(i32::from(self.signature[64]))?,
+        )?;
+        let message = Message::from_digest(signing_hash(node_id).0);
+        let recovered = public_key_to_address(SECP256K1.recover_ecdsa(&message, &signature)?);
+
+        if recovered != self.signer {
+            return Err(SignerEnrError::SignerMismatch { signer: self.signer, recovered })
+        }
+        Ok(recovered)
+    }
+}
+
+/// Hash signed by the signer key for the node `node_id`.
+fn signing_hash(node_id: PeerId) -> B256 {
+    let mut message = Vec::with_capacity(SIGNER_ENR_DOMAIN.len() + node_id.len());
+    message.extend_from_slice(SIGNER_ENR_DOMAIN);
+    message.extend_from_slice(node_id.as_slice());
+    keccak256(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_rlp::{Decodable, Encodable};
+
+    #[test]
+    fn signer_entry_roundtrip() {
+        let signer_key = SecretKey::new(&mut rand_08::thread_rng());
+        let node_id = PeerId::random();
+        let entry = SignerEnrEntry::new(node_id, &signer_key);
+
+        let mut buf = Vec::new();
+        entry.encode(&mut buf);
+        let decoded = SignerEnrEntry::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, entry);
+
+        let signer = public_key_to_address(signer_key.public_key(SECP256K1));
+        assert_eq!(decoded.verify(node_id), Ok(signer));
+    }
+
+    #[test]
+    fn signer_entry_bound_to_node_id() {
+        let signer_key = SecretKey::new(&mut rand_08::thread_rng());
+        let entry = SignerEnrEntry::new(PeerId::random(), &signer_key);
+        assert!(matches!(
+            entry.verify(PeerId::random()),
+            Err(SignerEnrError::SignerMismatch { .. })
+        ));
+
+        let other_key = SecretKey::new(&mut rand_08::thread_rng());
+        let node_id = PeerId::random();
+        let forged =
+            SignerEnrEntry { signer: entry.signer, ..SignerEnrEntry::new(node_id, &other_key) };
+        assert!(matches!(forged.verify(node_id), Err(SignerEnrError::SignerMismatch { .. })));
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
     fetch::{BlockResponseOutcome, FetchAction, StateFetcher},
     message::{BlockRequest, NewBlockMessage, PeerResponse, PeerResponseResult},
     peers::{PeerAction, PeersManager},
+    propagation::BlockPropagationTracker,
     FetchClient,
 };
 use alloy_consensus::BlockHeader;
@@ -92,6 +93,8 @@ pub struct NetworkState<N: NetworkPrimitives = EthNetworkPrimitives> {
     /// will then queue in the request and notify the fetcher once the result has been
     /// received.
     state_fetcher: StateFetcher<N>,
+    /// Traces the blocks seen and relayed by the node.
+    block_propagation: BlockPropagationTracker,
 }
 
 impl<N: NetworkPrimitives> NetworkState<N> {
@@ -110,6 +113,7 @@ impl<N: NetworkPrimitives> NetworkState<N> {
             client,
             discovery,
             state_fetcher,
+            block_propagation: Default::default(),
         }
     }
 
@@ -123,11 +127,26 @@ impl<N: NetworkPrimitives> NetworkState<N> {
         &mut self.discovery
     }
 
+    /// Returns access to the [`Discovery`]
+    pub(crate) const fn discovery(&self) -> &Discovery {
+        &self.discovery
+    }
+
     /// Returns access to the [`PeersManager`]
     pub(crate) const fn peers(&self) -> &PeersManager {
         &self.peers_manager
     }
 
+    /// Returns access to the [`BlockPropagationTracker`]
+    pub(crate) const fn block_propagation(&self) -> &BlockPropagationTracker {
+        &self.block_propagation
+    }
+
+    /// Returns mutable access to the [`BlockPropagationTracker`]
+    pub(crate) const fn block_propagation_mut(&mut self) -> &mut BlockPropagationTracker {
+        &mut self.block_propagation
+    }
+
     /// Returns a new [`FetchClient`]
     pub(crate) fn fetch_client(&self) -> FetchClient<N> {
         self.state_fetcher.client()
@@ -208,6 +227,7 @@ impl<N: NetworkPrimitives> NetworkState<N> {
             if count < num_propagate {
                 self.queued_messages
                     .push_back(StateAction::NewBlock { peer_id: *peer_id, block: msg.clone() });
+                self.block_propagation.on_relay(&msg.hash, *peer_id, true);
 
                 // update peer block info
                 if self.state_fetcher.update_peer_block(peer_id, msg.hash, number) {
@@ -245,6 +265,7 @@ impl<N: NetworkPrimitives> NetworkState<N> {
                 peer_id: *peer_id,
                 hashes: hashes.clone(),
             });
+            self.block_propagation.on_relay(&msg.hash, *peer_id, false);
         }
     }
 
@@ -328,6 +349,13 @@ impl<N: NetworkPrimitives> NetworkState<N> {
                 self.queued_messages
                     .push_back(StateAction::DiscoveredEnrForkId { peer_id, fork_id });
             }
+            DiscoveryEvent::EnrSigner { peer_id, addr, signer } => {
+                if signer.is_some() {
+                    self.peers_manager.add_discovered_signer_peer(peer_id, addr);
+                } else {
+                    self.peers_manager.remove_discovered_signer_peer(&peer_id);
+                }
+            }
         }
     }
 
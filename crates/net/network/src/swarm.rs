@@ -1,6 +1,7 @@
 use crate::{
     listener::{ConnectionListener, ListenerEvent},
     message::PeerMessage,
+    metrics::DiscoveryFilterMetrics,
     peers::InboundConnectionError,
     protocol::IntoRlpxSubProtocol,
     session::{Direction, PendingSessionHandshakeError, SessionEvent, SessionId, SessionManager},
@@ -55,18 +56,20 @@ pub(crate) struct Swarm<N: NetworkPrimitives = EthNetworkPrimitives> {
     sessions: SessionManager<N>,
     /// Tracks the entire state of the network and handles events received from the sessions.
     state: NetworkState<N>,
+    /// Discovered nodes rejected for their fork id.
+    discovery_filter_metrics: DiscoveryFilterMetrics,
 }
 
 // === impl Swarm ===
 
 impl<N: NetworkPrimitives> Swarm<N> {
     /// Configures a new swarm instance.
-    pub(crate) const fn new(
+    pub(crate) fn new(
         incoming: ConnectionListener,
         sessions: SessionManager<N>,
         state: NetworkState<N>,
     ) -> Self {
-        Self { incoming, sessions, state }
+        Self { incoming, sessions, state, discovery_filter_metrics: Default::default() }
     }
 
     /// Adds a protocol handler to the `RLPx` sub-protocol list.
@@ -251,12 +254,16 @@ impl<N: NetworkPrimitives> Swarm<N> {
                 // Insert peer only if no fork id or a valid fork id
                 if fork_id.map_or_else(|| true, |f| self.sessions.is_valid_fork_id(f)) {
                     self.state_mut().peers_mut().add_peer(peer_id, addr, fork_id);
+                } else {
+                    trace!(target: "net", ?peer_id, ?fork_id, "discovered node on another fork");
+                    self.discovery_filter_metrics.rejected_incompatible_fork_id.increment(1);
                 }
             }
             StateAction::DiscoveredEnrForkId { peer_id, fork_id } => {
                 if self.sessions.is_valid_fork_id(fork_id) {
                     self.state_mut().peers_mut().set_discovered_fork_id(peer_id, fork_id);
                 } else {
+                    self.discovery_filter_metrics.rejected_incompatible_fork_id.increment(1);
                     self.state_mut().peers_mut().remove_peer(peer_id);
                 }
             }
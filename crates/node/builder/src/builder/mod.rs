@@ -720,7 +720,10 @@ impl<Node: FullNodeTypes> BuilderContext<Node> {
         self.executor.spawn_critical("p2p eth request handler", eth);
 
         let default_peers_path = self.config().datadir().known_peers();
-        let known_peers_file = self.config().network.persistent_peers_file(default_peers_path);
+        let known_peers_file =
+            self.config().network.persistent_peers_file(default_peers_path.clone());
+        let bans_file = self.config().network.persistent_bans_file(default_peers_path.clone());
+        let enrs_file = self.config().network.persistent_enrs_file(default_peers_path);
         self.executor.spawn_critical_with_graceful_shutdown_signal(
             "p2p network task",
             |shutdown| {
@@ -737,6 +740,16 @@ impl<Node: FullNodeTypes> BuilderContext<Node> {
                             }
                         }
                     }
+                    if let Some(bans_file) = bans_file {
+                        if let Err(err) = network.write_bans_to_file(bans_file.as_path()) {
+                            warn!(target: "reth::cli", %err, "Failed to write banned peers to file");
+                        }
+                    }
+                    if let Some(enrs_file) = enrs_file {
+                        if let Err(err) = network.write_enrs_to_file(enrs_file.as_path()) {
+                            warn!(target: "reth::cli", %err, "Failed to write peer records to file");
+                        }
+                    }
                 })
             },
         );
@@ -449,6 +449,20 @@ where
             })?;
         }
 
+        // `APos` snapshots and signers used to be written apart from their blocks, drop the ones
+        // of blocks that were never persisted or are not canonical anymore.
+        let provider_rw = factory.provider_rw()?;
+        let removed = provider_rw.remove_stale_consensus_state()?;
+        // databases created before the `APos` signer index existed are indexed once here
+        let indexed = provider_rw.update_block_signers()?;
+        provider_rw.commit()?;
+        if removed > 0 {
+            info!(target: "reth::cli", removed, "Removed APos consensus state of non-canonical blocks");
+        }
+        if indexed > 0 {
+            info!(target: "reth::cli", indexed, "Indexed the APos signers of canonical blocks");
+        }
+
         Ok(factory)
     }
 
@@ -8,7 +8,7 @@ use alloy_signer_local::PrivateKeySigner;
 use consensus_client::miner::N42Miner;
 use n42_engine_primitives::N42PayloadAttributesBuilder;
 use reth_provider::BlockReaderIdExt;
-use reth_chainspec::{EthChainSpec, EthereumHardforks};
+use reth_chainspec::{AposChainSpec, EthChainSpec, EthereumHardforks};
 use reth_db_api::{database_metrics::DatabaseMetrics, Database};
 use reth_engine_local::{LocalEngineService, LocalPayloadAttributesBuilder};
 use reth_engine_service::service::{ChainEvent, EngineService};
@@ -48,8 +48,6 @@ use crate::{
     NodeBuilderWithComponents, NodeComponents, NodeComponentsBuilder, NodeHandle, NodeTypesAdapter,
 };
 
-const DEFAULT_BLOCK_TIME_SECS: u64 = 8;
-
 /// The engine node launcher.
 #[derive(Debug)]
 pub struct EngineNodeLauncher {
@@ -74,7 +72,7 @@ impl EngineNodeLauncher {
 
 impl<Types, DB, T, CB, AO> LaunchNode<NodeBuilderWithComponents<T, CB, AO>> for EngineNodeLauncher
 where
-    Types: NodeTypesForProvider + NodeTypes,
+    Types: NodeTypesForProvider + NodeTypes<ChainSpec: AposChainSpec>,
     DB: Database + DatabaseMetrics + Clone + Unpin + 'static,
     T: FullNodeTypes<
         Types = Types,
@@ -380,8 +378,14 @@ where
         });
 
         let mining_mode = if let Some(_) = ctx.node_config().dev.consensus_signer_private_key {
-            let block_time = ctx.node_config().dev.block_time.unwrap_or_else(|| Duration::from_secs(DEFAULT_BLOCK_TIME_SECS));
+            // the miner follows the period of the chain spec, this only sets the first tick
+            let block_time = ctx.node_config().dev.block_time.unwrap_or_else(|| {
+                Duration::from_secs(ctx.chain_spec().apos_genesis_config().period.max(1))
+            });
             consensus_client::miner::MiningMode::interval(block_time)
+        } else if ctx.node_config().dev.external_consensus {
+            info!(target: "reth::cli", "blocks are sealed by an external consensus client");
+            consensus_client::miner::MiningMode::External
         } else {
             consensus_client::miner::MiningMode::NoMining
         };
@@ -401,6 +405,7 @@ where
             ctx.components().payload_builder_handle().clone(),
             ctx.components().network().clone(),
             consensus,
+            ctx.node_config().builder.deadline,
         );
 
         let full_node = FullNode {
@@ -49,6 +49,24 @@ pub struct DevArgs {
         verbatim_doc_comment,
     )]
     pub consensus_signer_private_key: Option<B256>,
+
+    /// Leave block sealing to an external consensus client driving the node over the
+    /// authenticated Engine API.
+    ///
+    /// The node builds the payloads and announces the blocks the client seals, the signer key
+    /// stays with the client.
+    #[arg(
+        long = "consensus.external",
+        conflicts_with = "consensus_signer_private_key",
+        verbatim_doc_comment
+    )]
+    pub external_consensus: bool,
+
+    /// Propose to drop signers that missed more than this many consecutive in-turn rounds.
+    ///
+    /// The drop vote is withdrawn once the signer seals a block again.
+    #[arg(long = "dev.drop-inactive-signers-after", value_name = "ROUNDS", verbatim_doc_comment)]
+    pub drop_inactive_signers_after: Option<u64>,
 }
 
 #[cfg(test)]
@@ -67,13 +85,13 @@ mod tests {
     #[test]
     fn test_parse_dev_args() {
         let args = CommandParser::<DevArgs>::parse_from(["reth"]).args;
-        assert_eq!(args, DevArgs { dev: false, block_max_transactions: None, block_time: None, consensus_signer_private_key: None });
+        assert_eq!(args, DevArgs { dev: false, block_max_transactions: None, block_time: None, consensus_signer_private_key: None, external_consensus: false, drop_inactive_signers_after: None });
 
         let args = CommandParser::<DevArgs>::parse_from(["reth", "--dev"]).args;
-        assert_eq!(args, DevArgs { dev: true, block_max_transactions: None, block_time: None, consensus_signer_private_key: None });
+        assert_eq!(args, DevArgs { dev: true, block_max_transactions: None, block_time: None, consensus_signer_private_key: None, external_consensus: false, drop_inactive_signers_after: None });
 
         let args = CommandParser::<DevArgs>::parse_from(["reth", "--auto-mine"]).args;
-        assert_eq!(args, DevArgs { dev: true, block_max_transactions: None, block_time: None, consensus_signer_private_key: None });
+        assert_eq!(args, DevArgs { dev: true, block_max_transactions: None, block_time: None, consensus_signer_private_key: None, external_consensus: false, drop_inactive_signers_after: None });
 
         let args = CommandParser::<DevArgs>::parse_from([
             "reth",
@@ -82,7 +100,7 @@ mod tests {
             "2",
         ])
         .args;
-        assert_eq!(args, DevArgs { dev: true, block_max_transactions: Some(2), block_time: None, consensus_signer_private_key: None });
+        assert_eq!(args, DevArgs { dev: true, block_max_transactions: Some(2), block_time: None, consensus_signer_private_key: None, external_consensus: false, drop_inactive_signers_after: None });
 
         let args =
             CommandParser::<DevArgs>::parse_from(["reth", "--dev", "--dev.block-time", "1s"]).args;
@@ -90,6 +108,8 @@ mod tests {
             args,
             DevArgs {
                 consensus_signer_private_key: None,
+                external_consensus: false,
+                drop_inactive_signers_after: None,
                 dev: true,
                 block_max_transactions: None,
                 block_time: Some(std::time::Duration::from_secs(1))
@@ -124,6 +144,27 @@ mod tests {
         assert_eq!(cmd.args.consensus_signer_private_key.unwrap().to_string(), signer_private_key);
     }
 
+    #[test]
+    fn test_parse_external_consensus() {
+        let cmd = CommandParser::<DevArgs>::parse_from(["reth", "--consensus.external"]);
+        assert!(cmd.args.external_consensus);
+
+        let signer_private_key = SIGNER_PRIVATE_KEY_ALL_ZERO.to_string();
+        let cmd = CommandParser::<DevArgs>::try_parse_from([
+            "reth",
+            "--consensus.external",
+            "--dev.consensus-signer-private-key",
+            &signer_private_key,
+        ]);
+        assert!(cmd.is_err());
+    }
+
+    #[test]
+    fn test_parse_drop_inactive_signers_after() {
+        let cmd = CommandParser::<DevArgs>::parse_from(["reth", "--dev.drop-inactive-signers-after", "3"]);
+        assert_eq!(cmd.args.drop_inactive_signers_after, Some(3));
+    }
+
     #[ignore]
     #[test]
     fn test_parse_arg_signer_private_key_from_env() {
@@ -26,7 +26,7 @@ pub use log::{ColorMode, LogArgs, Verbosity};
 
 /// `PayloadBuilderArgs` struct for configuring the payload builder
 mod payload_builder;
-pub use payload_builder::PayloadBuilderArgs;
+pub use payload_builder::{PayloadBuilderArgs, PayloadTxOrdering};
 
 /// Stage related arguments
 mod stage;
@@ -31,11 +31,12 @@ use reth_network::{
         DEFAULT_SOFT_LIMIT_BYTE_SIZE_POOLED_TRANSACTIONS_RESP_ON_PACK_GET_POOLED_TRANSACTIONS_REQ,
         SOFT_LIMIT_BYTE_SIZE_POOLED_TRANSACTIONS_RESPONSE,
     },
-    HelloMessageWithProtocols, NetworkConfigBuilder, NetworkPrimitives, SessionsConfig,
+    BanEntry, BandwidthLimits, HelloMessageWithProtocols, NetworkConfigBuilder, NetworkPrimitives,
+    SessionsConfig, BANNED_PEERS_FILE_NAME, KNOWN_ENRS_FILE_NAME,
 };
 use reth_network_peers::{mainnet_nodes, TrustedPeer};
 use secp256k1::SecretKey;
-use tracing::{error, debug};
+use tracing::{debug, error, warn};
 
 use crate::version::P2P_CLIENT_VERSION;
 
@@ -58,6 +59,16 @@ pub struct NetworkArgs {
     #[arg(long)]
     pub trusted_only: bool,
 
+    #[expect(clippy::doc_markdown)]
+    /// Comma separated enode URLs of APos signer nodes.
+    ///
+    /// Signer peers get connection slots on top of the peer limits, are redialed quickly and only
+    /// lose reputation for bad blocks.
+    ///
+    /// --signer-peers enode://abcd@192.168.0.1:30303
+    #[arg(long, value_delimiter = ',')]
+    pub signer_peers: Vec<TrustedPeer>,
+
     /// Comma separated enode URLs for P2P discovery bootstrap.
     ///
     /// Will fall back to a network-specific default if not specified.
@@ -161,6 +172,30 @@ pub struct NetworkArgs {
     /// The policy determines which peers transactions are gossiped to.
     #[arg(long = "tx-propagation-policy", default_value_t = TransactionPropagationKind::All)]
     pub tx_propagation_policy: TransactionPropagationKind,
+
+    /// Limit of the bytes received from all peers, in KiB per second.
+    ///
+    /// Transaction gossip received once the limit is reached is dropped, blocks, requests and
+    /// responses are always read.
+    #[arg(long = "bandwidth.ingress", value_name = "KIB_PER_SEC")]
+    pub bandwidth_ingress: Option<u64>,
+
+    /// Limit of the bytes sent to all peers, in KiB per second.
+    ///
+    /// Blocks and consensus messages are sent ahead of transactions and responses, which are held
+    /// back once the limit is reached.
+    #[arg(long = "bandwidth.egress", value_name = "KIB_PER_SEC")]
+    pub bandwidth_egress: Option<u64>,
+
+    /// Limit of the bytes received from a single peer, in KiB per second.
+    ///
+    /// Transaction gossip received from the peer once the limit is reached is dropped.
+    #[arg(long = "bandwidth.peer-ingress", value_name = "KIB_PER_SEC")]
+    pub bandwidth_peer_ingress: Option<u64>,
+
+    /// Limit of the bytes sent to a single peer, in KiB per second.
+    #[arg(long = "bandwidth.peer-egress", value_name = "KIB_PER_SEC")]
+    pub bandwidth_peer_egress: Option<u64>,
 }
 
 impl NetworkArgs {
@@ -206,6 +241,17 @@ impl NetworkArgs {
         }
     }
 
+    /// Returns the byte rate limits of the sessions, converted to bytes per second.
+    pub fn bandwidth_limits(&self) -> BandwidthLimits {
+        let bytes = |kib: Option<u64>| kib.map(|kib| kib.saturating_mul(1024));
+        BandwidthLimits {
+            ingress: bytes(self.bandwidth_ingress),
+            egress: bytes(self.bandwidth_egress),
+            peer_ingress: bytes(self.bandwidth_peer_ingress),
+            peer_egress: bytes(self.bandwidth_peer_egress),
+        }
+    }
+
     /// Build a [`NetworkConfigBuilder`] from a [`Config`] and a [`EthChainSpec`], in addition to
     /// the values in this option struct.
     ///
@@ -232,6 +278,9 @@ impl NetworkArgs {
                         ?chain_bootnodes,
                     );
         let peers_file = self.peers_file.clone().unwrap_or(default_peers_file);
+        let bans_file = self.persistent_bans_file(peers_file.clone());
+        let bans = bans_file.clone().map(read_bans).unwrap_or_default();
+        let enrs_file = self.persistent_enrs_file(peers_file.clone());
 
         // Configure peer connections
         let peers_config = config
@@ -250,8 +299,13 @@ impl NetworkArgs {
                 SessionsConfig::default().with_upscaled_event_buffer(peers_config.max_peers()),
             )
             .peer_config(peers_config)
+            .signer_peers(self.signer_peers.clone())
             .boot_nodes(chain_bootnodes.clone())
             .transactions_manager_config(self.transactions_manager_config())
+            .bandwidth_limits(self.bandwidth_limits())
+            .bans(bans)
+            .bans_file(bans_file)
+            .enrs_file(enrs_file)
             // Configure node identity
             .apply(|builder| {
                 let peer_id = builder.get_peer_id();
@@ -282,6 +336,19 @@ impl NetworkArgs {
         self.no_persist_peers.not().then_some(peers_file)
     }
 
+    /// If `no_persist_peers` is false then this returns the path to the persistent bans file, next
+    /// to the persistent peers file.
+    pub fn persistent_bans_file(&self, peers_file: PathBuf) -> Option<PathBuf> {
+        self.persistent_peers_file(peers_file)
+            .map(|file| file.with_file_name(BANNED_PEERS_FILE_NAME))
+    }
+
+    /// If `no_persist_peers` is false then this returns the path to the persistent file of the
+    /// signed records of the known peers, next to the persistent peers file.
+    pub fn persistent_enrs_file(&self, peers_file: PathBuf) -> Option<PathBuf> {
+        self.persistent_peers_file(peers_file).map(|file| file.with_file_name(KNOWN_ENRS_FILE_NAME))
+    }
+
     /// Sets the p2p port to zero, to allow the OS to assign a random unused port when
     /// the network components bind to a socket.
     pub const fn with_unused_p2p_port(mut self) -> Self {
@@ -319,12 +386,24 @@ impl NetworkArgs {
     }
 }
 
+/// Reads the persisted bans, a missing or unreadable file restores none.
+fn read_bans(bans_file: PathBuf) -> Vec<BanEntry> {
+    if !bans_file.exists() {
+        return Vec::new()
+    }
+    reth_network::read_bans_file(&bans_file).unwrap_or_else(|err| {
+        warn!(target: "reth::cli", %err, "Failed to read banned peers file");
+        Vec::new()
+    })
+}
+
 impl Default for NetworkArgs {
     fn default() -> Self {
         Self {
             discovery: DiscoveryArgs::default(),
             trusted_peers: vec![],
             trusted_only: false,
+            signer_peers: vec![],
             bootnodes: None,
             dns_retries: 0,
             peers_file: None,
@@ -345,7 +424,11 @@ impl Default for NetworkArgs {
             max_seen_tx_history: DEFAULT_MAX_COUNT_TRANSACTIONS_SEEN_BY_PEER,
             max_capacity_cache_txns_pending_fetch: DEFAULT_MAX_CAPACITY_CACHE_PENDING_FETCH,
             net_if: None,
-            tx_propagation_policy: TransactionPropagationKind::default()
+            tx_propagation_policy: TransactionPropagationKind::default(),
+            bandwidth_ingress: None,
+            bandwidth_egress: None,
+            bandwidth_peer_ingress: None,
+            bandwidth_peer_egress: None,
         }
     }
 }
@@ -361,6 +444,19 @@ pub struct DiscoveryArgs {
     #[arg(long, conflicts_with = "disable_discovery")]
     pub disable_dns_discovery: bool,
 
+    /// DNS server to resolve the EIP-1459 node trees with instead of the system resolver, e.g. a
+    /// local server serving a test tree.
+    #[arg(id = "dns.server", long = "dns.server", value_name = "DNS_SERVER", conflicts_with = "disable_dns_discovery")]
+    pub dns_server: Option<SocketAddr>,
+
+    /// Publish the fork id of the chain in an `n42` discovery entry, and only dial the discovered
+    /// nodes whose record announces a compatible one.
+    ///
+    /// Nodes only publish the entry with this flag, so a network enables it on all its nodes,
+    /// bootnodes included.
+    #[arg(id = "n42.fork-id-filter", long = "n42.fork-id-filter", conflicts_with = "disable_discovery")]
+    pub n42_fork_id_filter: bool,
+
     /// Disable Discv4 discovery.
     #[arg(long, conflicts_with = "disable_discovery")]
     pub disable_discv4_discovery: bool,
@@ -434,6 +530,9 @@ impl DiscoveryArgs {
         if self.disable_discovery || self.disable_dns_discovery {
             network_config_builder = network_config_builder.disable_dns_discovery();
         }
+        network_config_builder = network_config_builder
+            .dns_server(self.dns_server)
+            .n42_fork_id_filter(self.n42_fork_id_filter);
 
         if self.disable_discovery || self.disable_discv4_discovery {
             network_config_builder = network_config_builder.disable_discv4_discovery();
@@ -518,6 +617,8 @@ impl Default for DiscoveryArgs {
         Self {
             disable_discovery: false,
             disable_dns_discovery: false,
+            dns_server: None,
+            n42_fork_id_filter: false,
             disable_discv4_discovery: false,
             enable_discv5_discovery: false,
             disable_nat: false,
@@ -609,6 +710,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_bandwidth_args() {
+        let args = CommandParser::<NetworkArgs>::parse_from([
+            "reth",
+            "--bandwidth.egress",
+            "1024",
+            "--bandwidth.peer-ingress",
+            "64",
+        ])
+        .args;
+        assert_eq!(
+            args.bandwidth_limits(),
+            BandwidthLimits {
+                ingress: None,
+                egress: Some(1024 * 1024),
+                peer_ingress: Some(64 * 1024),
+                peer_egress: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_dns_server_args() {
+        let args =
+            CommandParser::<NetworkArgs>::parse_from(["reth", "--dns.server", "127.0.0.1:5353"])
+                .args;
+        assert_eq!(args.discovery.dns_server, Some("127.0.0.1:5353".parse().unwrap()));
+
+        let result = CommandParser::<NetworkArgs>::try_parse_from([
+            "reth",
+            "--disable-dns-discovery",
+            "--dns.server",
+            "127.0.0.1:5353",
+        ]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn network_args_default_sanity_test() {
         let default_args = NetworkArgs::default();
@@ -616,4 +754,13 @@ mod tests {
 
         assert_eq!(args, default_args);
     }
+
+    #[test]
+    fn parse_n42_fork_id_filter_args() {
+        let args = CommandParser::<NetworkArgs>::parse_from(["reth"]).args;
+        assert!(!args.discovery.n42_fork_id_filter);
+
+        let args = CommandParser::<NetworkArgs>::parse_from(["reth", "--n42.fork-id-filter"]).args;
+        assert!(args.discovery.n42_fork_id_filter);
+    }
 }
@@ -1,12 +1,13 @@
 use crate::{cli::config::PayloadBuilderConfig, version::default_extra_data};
 use alloy_consensus::constants::MAXIMUM_EXTRA_DATA_SIZE;
 use alloy_eips::merge::SLOT_DURATION;
+use alloy_primitives::Address;
 use clap::{
     builder::{RangedU64ValueParser, TypedValueParser},
-    Arg, Args, Command,
+    Arg, Args, Command, ValueEnum,
 };
 use reth_cli_util::{parse_duration_from_secs, parse_duration_from_secs_or_ms};
-use std::{borrow::Cow, ffi::OsStr, time::Duration};
+use std::{borrow::Cow, ffi::OsStr, path::PathBuf, time::Duration};
 
 /// Parameters for configuring the Payload Builder
 #[derive(Debug, Clone, Args, PartialEq, Eq)]
@@ -35,6 +36,54 @@ pub struct PayloadBuilderArgs {
     /// Maximum number of tasks to spawn for building a payload.
     #[arg(long = "builder.max-tasks", default_value = "3", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
     pub max_payload_tasks: usize,
+
+    /// Order in which the pending transactions are considered for a block.
+    #[arg(long = "builder.tx-ordering", value_enum, default_value_t = PayloadTxOrdering::Fee)]
+    pub tx_ordering: PayloadTxOrdering,
+
+    /// Maximum number of transactions of a single sender in a block.
+    #[arg(long = "builder.max-txs-per-sender", value_name = "COUNT")]
+    pub max_txs_per_sender: Option<usize>,
+
+    /// Senders allowed to use the gas reserved with `--builder.reserved-system-gas`.
+    #[arg(long = "builder.system-senders", value_name = "ADDRESS", value_delimiter = ',')]
+    pub system_senders: Vec<Address>,
+
+    /// Gas of each block that only the system senders may use.
+    #[arg(long = "builder.reserved-system-gas", value_name = "GAS", default_value_t = 0)]
+    pub reserved_system_gas: u64,
+
+    /// File listing the addresses, one per line, whose transactions are never included, either
+    /// as sender or as recipient.
+    ///
+    /// The file is reloaded when it changes.
+    #[arg(long = "builder.deny-list", value_name = "PATH")]
+    pub deny_list: Option<PathBuf>,
+
+    /// URLs of the block builder relays asked for a bid on each block this node signs, as
+    /// `http(s)://<relay address>@<host>`.
+    ///
+    /// A bid is only taken when it is signed by the key of the relay address. The fee recipient
+    /// is registered with each relay. A block built by a relay is only used when it is delivered
+    /// within `--builder.relay-timeout`, otherwise the locally built block is sealed.
+    #[arg(long = "builder.relays", value_name = "URL", value_delimiter = ',')]
+    pub relays: Vec<String>,
+
+    /// How long to wait for the relays to deliver a block.
+    ///
+    /// Timeout is specified in seconds or in milliseconds if the value ends with `ms`.
+    #[arg(long = "builder.relay-timeout", value_parser = parse_duration_from_secs_or_ms, default_value = "500ms", value_name = "DURATION")]
+    pub relay_timeout: Duration,
+}
+
+/// Order in which the payload builder considers the pending transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PayloadTxOrdering {
+    /// Highest paying transactions first.
+    #[default]
+    Fee,
+    /// Transactions in the order they arrived in the pool, keeping each sender's nonce order.
+    Fifo,
 }
 
 impl Default for PayloadBuilderArgs {
@@ -45,6 +94,13 @@ impl Default for PayloadBuilderArgs {
             gas_limit: None,
             deadline: SLOT_DURATION,
             max_payload_tasks: 3,
+            tx_ordering: PayloadTxOrdering::Fee,
+            max_txs_per_sender: None,
+            system_senders: Vec::new(),
+            reserved_system_gas: 0,
+            deny_list: None,
+            relays: Vec::new(),
+            relay_timeout: Duration::from_millis(500),
         }
     }
 }
@@ -166,6 +222,50 @@ mod tests {
         assert_eq!(args.interval, Duration::from_secs(50));
     }
 
+    #[test]
+    fn test_args_with_tx_policies() {
+        let sender = Address::with_last_byte(1);
+        let args = CommandParser::<PayloadBuilderArgs>::parse_from([
+            "reth",
+            "--builder.tx-ordering",
+            "fifo",
+            "--builder.max-txs-per-sender",
+            "4",
+            "--builder.system-senders",
+            &format!("{sender},{}", Address::with_last_byte(2)),
+            "--builder.reserved-system-gas",
+            "1000000",
+            "--builder.deny-list",
+            "deny.txt",
+        ])
+        .args;
+        assert_eq!(args.tx_ordering, PayloadTxOrdering::Fifo);
+        assert_eq!(args.max_txs_per_sender, Some(4));
+        assert_eq!(args.system_senders, vec![sender, Address::with_last_byte(2)]);
+        assert_eq!(args.reserved_system_gas, 1_000_000);
+        assert_eq!(args.deny_list, Some(PathBuf::from("deny.txt")));
+    }
+
+    #[test]
+    fn test_args_with_relays() {
+        let args = CommandParser::<PayloadBuilderArgs>::parse_from([
+            "reth",
+            "--builder.relays",
+            "http://0x0000000000000000000000000000000000000001@127.0.0.1:18550,https://0x0000000000000000000000000000000000000002@relay.example.org",
+            "--builder.relay-timeout",
+            "250ms",
+        ])
+        .args;
+        assert_eq!(
+            args.relays,
+            vec![
+                "http://0x0000000000000000000000000000000000000001@127.0.0.1:18550",
+                "https://0x0000000000000000000000000000000000000002@relay.example.org"
+            ]
+        );
+        assert_eq!(args.relay_timeout, Duration::from_millis(250));
+    }
+
     #[test]
     fn test_args_with_ms_interval() {
         let args =
@@ -122,6 +122,13 @@ pub struct TxPoolArgs {
         conflicts_with = "transactions_backup_path"
     )]
     pub disable_transactions_backup: bool,
+
+    /// Path to a TOML file with the rules deciding which transactions may enter the pool: allowed
+    /// and denied senders and targets, approved deployers and a per-sender rate limit.
+    ///
+    /// The file is reloaded when it changes.
+    #[arg(long = "txpool.admission-rules", value_name = "PATH")]
+    pub admission_rules: Option<std::path::PathBuf>,
 }
 
 impl Default for TxPoolArgs {
@@ -153,6 +160,7 @@ impl Default for TxPoolArgs {
             max_queued_lifetime: MAX_QUEUED_TRANSACTION_LIFETIME,
             transactions_backup_path: None,
             disable_transactions_backup: false,
+            admission_rules: None,
         }
     }
 }
@@ -228,6 +236,17 @@ mod tests {
         assert_eq!(args.locals, vec![Address::ZERO]);
     }
 
+    #[test]
+    fn txpool_parse_admission_rules() {
+        let args = CommandParser::<TxPoolArgs>::parse_from([
+            "reth",
+            "--txpool.admission-rules",
+            "admission.toml",
+        ])
+        .args;
+        assert_eq!(args.admission_rules, Some("admission.toml".into()));
+    }
+
     #[test]
     fn txpool_parse_max_tx_lifetime() {
         // Test with a custom duration
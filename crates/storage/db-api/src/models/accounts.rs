@@ -102,7 +102,57 @@ impl Decode for AddressStorageKey {
     }
 }
 
-impl_fixed_arbitrary!((BlockNumberAddress, 28), (AddressStorageKey, 52));
+/// [`Address`] concatenated with [`BlockNumber`]. Used by the `APos` signer index.
+///
+/// Since it's used as a key, it isn't compressed when encoding it.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Ord, PartialOrd, Hash,
+)]
+pub struct AddressBlockNumber(pub (Address, BlockNumber));
+
+impl AddressBlockNumber {
+    /// Create a new Range over the blocks of `address` from `start` to `end`
+    ///
+    /// Note: End is inclusive
+    pub fn range(address: Address, range: RangeInclusive<BlockNumber>) -> RangeInclusive<Self> {
+        Self((address, *range.start()))..=Self((address, *range.end()))
+    }
+
+    /// Return the address
+    pub const fn address(&self) -> Address {
+        self.0 .0
+    }
+
+    /// Return the block number
+    pub const fn block_number(&self) -> BlockNumber {
+        self.0 .1
+    }
+}
+
+impl Encode for AddressBlockNumber {
+    type Encoded = [u8; 28];
+
+    fn encode(self) -> Self::Encoded {
+        let address = self.0 .0;
+        let block_number = self.0 .1;
+
+        let mut buf = [0u8; 28];
+
+        buf[..20].copy_from_slice(address.as_slice());
+        buf[20..].copy_from_slice(&block_number.to_be_bytes());
+        buf
+    }
+}
+
+impl Decode for AddressBlockNumber {
+    fn decode(value: &[u8]) -> Result<Self, DatabaseError> {
+        let address = Address::from_slice(value.get(..20).ok_or(DatabaseError::Decode)?);
+        let num = u64::from_be_bytes(value[20..].try_into().map_err(|_| DatabaseError::Decode)?);
+        Ok(Self((address, num)))
+    }
+}
+
+impl_fixed_arbitrary!((BlockNumberAddress, 28), (AddressStorageKey, 52), (AddressBlockNumber, 28));
 
 #[cfg(test)]
 mod tests {
@@ -152,6 +202,23 @@ mod tests {
         assert_eq!(decoded, key);
     }
 
+    #[test]
+    fn test_address_block_number() {
+        let num = 1u64;
+        let address = Address::from_str("ba5e000000000000000000000000000000000000").unwrap();
+        let key = AddressBlockNumber((address, num));
+
+        let mut bytes = [0u8; 28];
+        bytes[..20].copy_from_slice(address.as_slice());
+        bytes[20..].copy_from_slice(&num.to_be_bytes());
+
+        let encoded = Encode::encode(key);
+        assert_eq!(encoded, bytes);
+
+        let decoded: AddressBlockNumber = Decode::decode(&encoded).unwrap();
+        assert_eq!(decoded, key);
+    }
+
     #[test]
     fn test_address_storage_key_rand() {
         let mut bytes = [0u8; 52];
@@ -18,7 +18,7 @@ pub use raw::{RawDupSort, RawKey, RawTable, RawValue, TableRawRow};
 
 use crate::{
     models::{
-        accounts::BlockNumberAddress,
+        accounts::{AddressBlockNumber, BlockNumberAddress},
         blocks::{HeaderHash, StoredBlockOmmers},
         storage_sharded_key::StorageShardedKey,
         AccountBeforeTx, ClientVersion, CompactU256, IntegerList, ShardedKey,
@@ -318,6 +318,18 @@ tables! {
         type Key = HeaderHash;
         type Value = Address;
     }
+
+    /// Stores the `APos` sealer of each canonical block.
+    table CanonicalSigners {
+        type Key = BlockNumber;
+        type Value = Address;
+    }
+
+    /// Stores the hashes of the canonical blocks sealed by each `APos` signer.
+    table SignerBlocks {
+        type Key = AddressBlockNumber;
+        type Value = BlockHash;
+    }
     
     /// Stores the header hashes belonging to the canonical chain.
     table CanonicalHeaders {
@@ -31,14 +31,15 @@ use reth_evm::{ConfigureEvm, EvmEnv};
 use reth_execution_types::ExecutionOutcome;
 use reth_node_types::{BlockTy, HeaderTy, NodeTypesWithDB, ReceiptTy, TxTy};
 use reth_primitives_traits::{
-    Account, BlockBody, NodePrimitives, RecoveredBlock, SealedBlock, SealedHeader, StorageEntry,
+    header::clique_utils::recover_address_generic, Account, BlockBody, NodePrimitives,
+    RecoveredBlock, SealedBlock, SealedHeader, StorageEntry,
 };
 use reth_prune_types::{PruneCheckpoint, PruneSegment};
 use reth_stages_types::{StageCheckpoint, StageId};
 use reth_storage_api::{
     BlockBodyIndicesProvider, DBProvider, NodePrimitivesProvider, OmmersProvider,
     StateCommitmentProvider, StorageChangeSetReader,
-    SnapshotProvider, SnapshotProviderWriter,
+    SignerBlocksProvider, SnapshotProvider, SnapshotProviderWriter,
 };
 use reth_storage_errors::provider::ProviderResult;
 use reth_trie::HashedPostState;
@@ -713,6 +714,59 @@ impl<N: ProviderNodeTypes> SnapshotProvider for BlockchainProvider<N> {
     }
 }
 
+impl<N: ProviderNodeTypes> BlockchainProvider<N> {
+    /// Returns the `APos` sealers of the canonical blocks in memory within `range`, oldest first.
+    fn in_memory_block_signers(
+        &self,
+        range: &RangeInclusive<BlockNumber>,
+    ) -> Vec<(BlockNumHash, Address)> {
+        let mut signers = self
+            .canonical_in_memory_state
+            .canonical_chain()
+            .filter(|block_state| range.contains(&block_state.number()))
+            .filter_map(|block_state| {
+                let block = block_state.block_ref().recovered_block();
+                let signer = recover_address_generic(block.header()).ok()?;
+                Some((BlockNumHash::new(block_state.number(), block_state.hash()), signer))
+            })
+            .collect::<Vec<_>>();
+        signers.reverse();
+        signers
+    }
+}
+
+impl<N: ProviderNodeTypes> SignerBlocksProvider for BlockchainProvider<N> {
+    fn signer_blocks(
+        &self,
+        signer: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> ProviderResult<Vec<BlockNumHash>> {
+        // the in-memory blocks are read first, so blocks persisted in between are not missed
+        let in_memory = self.in_memory_block_signers(&range);
+        let mut blocks = self.database_provider_ro()?.signer_blocks(signer, range)?;
+        if let Some((first, _)) = in_memory.first() {
+            blocks.retain(|block| block.number < first.number);
+        }
+        blocks.extend(
+            in_memory.into_iter().filter(|(_, sealer)| *sealer == signer).map(|(block, _)| block),
+        );
+        Ok(blocks)
+    }
+
+    fn block_signers(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> ProviderResult<Vec<(BlockNumber, Address)>> {
+        let in_memory = self.in_memory_block_signers(&range);
+        let mut signers = self.database_provider_ro()?.block_signers(range)?;
+        if let Some((first, _)) = in_memory.first() {
+            signers.retain(|(number, _)| *number < first.number);
+        }
+        signers.extend(in_memory.into_iter().map(|(block, signer)| (block.number, signer)));
+        Ok(signers)
+    }
+}
+
 impl<N: ProviderNodeTypes> SnapshotProviderWriter for BlockchainProvider<N> {
     fn save_snapshot(&self, id: BlockNumber, snapshot: Snapshot) -> ProviderResult<bool> {
         let provider_rw = self.database_provider_rw()?;
@@ -721,15 +775,13 @@ impl<N: ProviderNodeTypes> SnapshotProviderWriter for BlockchainProvider<N> {
     }
 
     fn save_snapshot_by_hash(&self, block_hash: &BlockHash,  snapshot: Snapshot) -> ProviderResult<()> {
-        let provider_rw = self.database_provider_rw()?;
-        provider_rw.save_snapshot_by_hash(block_hash, snapshot)?;
-        provider_rw.commit().map(|_|())
+        self.database.consensus_state().insert_snapshot(*block_hash, snapshot);
+        Ok(())
     }
 
-    fn save_signer_by_hash(&self, block_hash: &BlockHash,  signer: Address) -> ProviderResult<()> {
-        let provider_rw = self.database.database_provider_rw()?;
-        provider_rw.save_signer_by_hash(block_hash, signer)?;
-        provider_rw.commit().map(|_|())
+    fn save_signer_by_hash(&self, block_hash: &BlockHash, block_number: BlockNumber, signer: Address) -> ProviderResult<()> {
+        self.database.consensus_state().insert_signer(*block_hash, block_number, signer);
+        Ok(())
     }
 }
 impl<N: ProviderNodeTypes> ForkChoiceSubscriptions for BlockchainProvider<N> {
@@ -0,0 +1,120 @@
+//! `APos` consensus state of blocks that are not persisted yet.
+
+use alloy_primitives::{Address, BlockHash, BlockNumber};
+use n42_primitives::Snapshot;
+use parking_lot::RwLock;
+use reth_storage_errors::provider::ProviderResult;
+use std::{collections::HashMap, sync::Arc};
+
+/// Vote snapshots and block signers computed by consensus, waiting for their blocks to be
+/// persisted.
+///
+/// Consensus validates a block before the engine persists it, so what it computes for the block
+/// is kept here and written by the commit of the transaction inserting the block, see
+/// [`DatabaseProvider::commit`](super::DatabaseProvider::commit). After a crash the database never
+/// holds state for blocks it doesn't have. The state of side chain blocks that are never
+/// persisted is dropped once a block at or above their height is.
+#[derive(Debug, Clone, Default)]
+pub struct PendingConsensusState {
+    inner: Arc<RwLock<PendingInner>>,
+}
+
+#[derive(Debug, Default)]
+struct PendingInner {
+    snapshots: HashMap<BlockHash, Snapshot>,
+    signers: HashMap<BlockHash, (BlockNumber, Address)>,
+}
+
+/// Entries of a [`PendingConsensusState`] to write in a transaction.
+#[derive(Debug, Default)]
+pub(crate) struct ConsensusStateWrite {
+    /// Snapshots of blocks in the database.
+    pub(crate) snapshots: Vec<(BlockHash, Snapshot)>,
+    /// Signers of blocks in the database.
+    pub(crate) signers: Vec<(BlockHash, Address)>,
+    /// Entries of side chain blocks, below a block in the database.
+    pub(crate) stale: Vec<BlockHash>,
+}
+
+impl PendingConsensusState {
+    /// Buffers the vote snapshot at the block `hash`.
+    pub fn insert_snapshot(&self, hash: BlockHash, snapshot: Snapshot) {
+        self.inner.write().snapshots.insert(hash, snapshot);
+    }
+
+    /// Buffers the signer of the block `hash`.
+    pub fn insert_signer(&self, hash: BlockHash, number: BlockNumber, signer: Address) {
+        self.inner.write().signers.insert(hash, (number, signer));
+    }
+
+    /// Returns the buffered vote snapshot at the block `hash`.
+    pub fn snapshot(&self, hash: &BlockHash) -> Option<Snapshot> {
+        self.inner.read().snapshots.get(hash).cloned()
+    }
+
+    /// Returns the buffered signer of the block `hash`.
+    pub fn signer(&self, hash: &BlockHash) -> Option<Address> {
+        self.inner.read().signers.get(hash).map(|(_, signer)| *signer)
+    }
+
+    /// Number of buffered snapshots and signers.
+    pub fn len(&self) -> usize {
+        let inner = self.inner.read();
+        inner.snapshots.len() + inner.signers.len()
+    }
+
+    /// Whether nothing is buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits the buffered entries by whether `block_number` finds their block in the database.
+    pub(crate) fn to_write(
+        &self,
+        mut block_number: impl FnMut(&BlockHash) -> ProviderResult<Option<BlockNumber>>,
+    ) -> ProviderResult<ConsensusStateWrite> {
+        let inner = self.inner.read();
+        let mut write = ConsensusStateWrite::default();
+        let mut missing = Vec::new();
+        let mut tip = None;
+
+        for (hash, snapshot) in &inner.snapshots {
+            if block_number(hash)?.is_some() {
+                tip = tip.max(Some(snapshot.number));
+                write.snapshots.push((*hash, snapshot.clone()));
+            } else {
+                missing.push((*hash, snapshot.number));
+            }
+        }
+        for (hash, (number, signer)) in &inner.signers {
+            if block_number(hash)?.is_some() {
+                tip = tip.max(Some(*number));
+                write.signers.push((*hash, *signer));
+            } else {
+                missing.push((*hash, *number));
+            }
+        }
+
+        write.stale = missing
+            .into_iter()
+            .filter(|(_, number)| tip.is_some_and(|tip| *number <= tip))
+            .map(|(hash, _)| hash)
+            .collect();
+        Ok(write)
+    }
+
+    /// Drops the entries written or found stale, once their transaction is committed.
+    pub(crate) fn remove_written(&self, write: &ConsensusStateWrite) {
+        let mut inner = self.inner.write();
+        for (hash, _) in &write.snapshots {
+            inner.snapshots.remove(hash);
+        }
+        for (hash, _) in &write.signers {
+            inner.signers.remove(hash);
+        }
+        for hash in &write.stale {
+            inner.snapshots.remove(hash);
+            inner.signers.remove(hash);
+        }
+    }
+}
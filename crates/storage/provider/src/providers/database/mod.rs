@@ -41,13 +41,16 @@ use std::{
 use tracing::trace;
 
 mod provider;
-pub use provider::{DatabaseProvider, DatabaseProviderRO, DatabaseProviderRW};
+pub use provider::{DatabaseProvider, DatabaseProviderRO, DatabaseProviderRW, SIGNER_INDEX_STAGE};
 
 use super::ProviderNodeTypes;
 
 mod builder;
 pub use builder::{ProviderFactoryBuilder, ReadOnlyConfig};
 
+mod consensus_state;
+pub use consensus_state::PendingConsensusState;
+
 mod metrics;
 
 mod chain;
@@ -67,6 +70,8 @@ pub struct ProviderFactory<N: NodeTypesWithDB> {
     prune_modes: PruneModes,
     /// The node storage handler.
     storage: Arc<N::Storage>,
+    /// `APos` consensus state of the blocks not persisted yet.
+    consensus_state: PendingConsensusState,
 }
 
 impl<N: NodeTypes> ProviderFactory<NodeTypesWithDBAdapter<N, Arc<DatabaseEnv>>> {
@@ -89,6 +94,7 @@ impl<N: NodeTypesWithDB> ProviderFactory<N> {
             static_file_provider,
             prune_modes: PruneModes::none(),
             storage: Default::default(),
+            consensus_state: Default::default(),
         }
     }
 
@@ -104,6 +110,11 @@ impl<N: NodeTypesWithDB> ProviderFactory<N> {
         self
     }
 
+    /// Returns the buffer of `APos` consensus state shared by the providers of this factory.
+    pub const fn consensus_state(&self) -> &PendingConsensusState {
+        &self.consensus_state
+    }
+
     /// Returns reference to the underlying database.
     pub const fn db_ref(&self) -> &N::DB {
         &self.db
@@ -131,6 +142,7 @@ impl<N: NodeTypesWithDB<DB = Arc<DatabaseEnv>>> ProviderFactory<N> {
             static_file_provider,
             prune_modes: PruneModes::none(),
             storage: Default::default(),
+            consensus_state: Default::default(),
         })
     }
 }
@@ -150,7 +162,8 @@ impl<N: ProviderNodeTypes> ProviderFactory<N> {
             self.static_file_provider.clone(),
             self.prune_modes.clone(),
             self.storage.clone(),
-        ))
+        )
+        .with_consensus_state(self.consensus_state.clone()))
     }
 
     /// Returns a provider with a created `DbTxMut` inside, which allows fetching and updating
@@ -159,13 +172,17 @@ impl<N: ProviderNodeTypes> ProviderFactory<N> {
     /// open.
     #[track_caller]
     pub fn provider_rw(&self) -> ProviderResult<DatabaseProviderRW<N::DB, N>> {
-        Ok(DatabaseProviderRW(DatabaseProvider::new_rw(
-            self.db.tx_mut()?,
-            self.chain_spec.clone(),
-            self.static_file_provider.clone(),
-            self.prune_modes.clone(),
-            self.storage.clone(),
-        )))
+        Ok(DatabaseProviderRW(
+            DatabaseProvider::new_rw(
+                self.db.tx_mut()?,
+                self.chain_spec.clone(),
+                self.static_file_provider.clone(),
+                self.prune_modes.clone(),
+                self.storage.clone(),
+            )
+            .with_consensus_state(self.consensus_state.clone())
+            .with_consensus_writes(),
+        ))
     }
 
     /// State provider for latest block
@@ -622,13 +639,15 @@ where
     N: NodeTypesWithDB<DB: fmt::Debug, ChainSpec: fmt::Debug, Storage: fmt::Debug>,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Self { db, chain_spec, static_file_provider, prune_modes, storage } = self;
+        let Self { db, chain_spec, static_file_provider, prune_modes, storage, consensus_state } =
+            self;
         f.debug_struct("ProviderFactory")
             .field("db", &db)
             .field("chain_spec", &chain_spec)
             .field("static_file_provider", &static_file_provider)
             .field("prune_modes", &prune_modes)
             .field("storage", &storage)
+            .field("consensus_state", &consensus_state)
             .finish()
     }
 }
@@ -641,6 +660,7 @@ impl<N: NodeTypesWithDB> Clone for ProviderFactory<N> {
             static_file_provider: self.static_file_provider.clone(),
             prune_modes: self.prune_modes.clone(),
             storage: self.storage.clone(),
+            consensus_state: self.consensus_state.clone(),
         }
     }
 }
@@ -661,8 +681,15 @@ mod tests {
         mdbx::DatabaseArguments,
         test_utils::{create_test_static_files_dir, ERROR_TEMPDIR},
     };
-    use reth_db_api::tables;
-    use reth_primitives_traits::SignerRecoverable;
+    use reth_db_api::{
+        tables,
+        transaction::{DbTx, DbTxMut},
+    };
+    use reth_primitives_traits::{
+        header::clique_utils::{public_key_to_address, seal_hash_generic},
+        SignerRecoverable,
+    };
+    use reth_storage_api::SignerBlocksProvider;
     use reth_prune_types::{PruneMode, PruneModes};
     use reth_storage_errors::provider::ProviderError;
     use reth_testing_utils::generators::{self, random_block, random_header, BlockParams};
@@ -831,6 +858,8 @@ mod tests {
             reward_epoch: 1000, 
             reward_limit: U256::from(1000), 
             deposit_contract: "0x0000000000000000000000000000000000000000".parse::<Address>().unwrap(),
+            checkpoint_interval: 2048,
+            heartbeat_interval: 0,
         };
         let number=1;
         let hash: B256 = "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".parse().unwrap();
@@ -858,4 +887,187 @@ mod tests {
         println!("{:#?}", loaded_snapshot);
         
     }
+
+    /// Returns an empty block sealed by `key` the way `APos` signers seal them.
+    fn signed_block(
+        key: &secp256k1::SecretKey,
+        number: u64,
+        parent_hash: B256,
+    ) -> RecoveredBlock<reth_ethereum_primitives::Block> {
+        let mut header = alloy_consensus::Header {
+            number,
+            parent_hash,
+            extra_data: vec![0; 32 + 65].into(),
+            ..Default::default()
+        };
+        let message = secp256k1::Message::from_digest(seal_hash_generic(&header).0);
+        let (recovery_id, signature) =
+            secp256k1::SECP256K1.sign_ecdsa_recoverable(&message, key).serialize_compact();
+        let mut extra_data = vec![0; 32];
+        extra_data.extend_from_slice(&signature);
+        extra_data.push(i32::from(recovery_id) as u8);
+        header.extra_data = extra_data.into();
+        RecoveredBlock::new_unhashed(
+            reth_ethereum_primitives::Block { header, body: Default::default() },
+            vec![],
+        )
+    }
+
+    #[test]
+    fn signer_blocks_index() {
+        let factory = create_test_provider_factory();
+        let key_a = secp256k1::SecretKey::from_slice(&[0xa; 32]).unwrap();
+        let key_b = secp256k1::SecretKey::from_slice(&[0xb; 32]).unwrap();
+        let signer_a = public_key_to_address(key_a.public_key(secp256k1::SECP256K1));
+        let signer_b = public_key_to_address(key_b.public_key(secp256k1::SECP256K1));
+
+        let provider_rw = factory.provider_rw().unwrap();
+        let mut hashes = vec![B256::ZERO];
+        for number in 1..=6u64 {
+            let key = if number % 2 == 0 { &key_a } else { &key_b };
+            let block = signed_block(key, number, hashes[number as usize - 1]);
+            hashes.push(block.hash());
+            provider_rw.insert_block(block, StorageLocation::Database).unwrap();
+        }
+        provider_rw.commit().unwrap();
+
+        // the blocks are indexed when the transaction is committed
+        let provider = factory.provider().unwrap();
+        let blocks = provider.signer_blocks(signer_a, 1..=5).unwrap();
+        assert_eq!(blocks.iter().map(|b| b.number).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(blocks[0].hash, hashes[2]);
+        assert_eq!(provider.block_signers(5..=6).unwrap(), vec![(5, signer_b), (6, signer_a)]);
+        drop(provider);
+
+        // unwinding removes both sides of the index
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw.remove_blocks_above(3, StorageLocation::Database).unwrap();
+        provider_rw.commit().unwrap();
+        let provider = factory.provider().unwrap();
+        assert_eq!(provider.block_signers(1..=6).unwrap().len(), 3);
+        assert_eq!(provider.signer_blocks(signer_a, 0..=u64::MAX).unwrap().len(), 1);
+        assert_eq!(provider.signer_blocks(signer_b, 0..=u64::MAX).unwrap().len(), 2);
+        assert_eq!(
+            provider.get_stage_checkpoint(SIGNER_INDEX_STAGE).unwrap(),
+            Some(StageCheckpoint::new(3))
+        );
+        drop(provider);
+
+        // a database written before the index existed is indexed on the next update
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw.tx_ref().clear::<tables::CanonicalSigners>().unwrap();
+        provider_rw.tx_ref().clear::<tables::SignerBlocks>().unwrap();
+        provider_rw
+            .tx_ref()
+            .delete::<tables::StageCheckpoints>(SIGNER_INDEX_STAGE.to_string(), None)
+            .unwrap();
+        assert_eq!(provider_rw.update_block_signers().unwrap(), 3);
+        assert_eq!(provider_rw.block_signers(1..=6).unwrap().len(), 3);
+        assert_eq!(provider_rw.update_block_signers().unwrap(), 0);
+    }
+
+    #[test]
+    fn signer_index_reinserted_blocks() {
+        let factory = create_test_provider_factory();
+        let key_a = secp256k1::SecretKey::from_slice(&[0xa; 32]).unwrap();
+        let key_b = secp256k1::SecretKey::from_slice(&[0xb; 32]).unwrap();
+        let signer_a = public_key_to_address(key_a.public_key(secp256k1::SECP256K1));
+        let signer_b = public_key_to_address(key_b.public_key(secp256k1::SECP256K1));
+
+        let provider_rw = factory.provider_rw().unwrap();
+        let mut hashes = vec![B256::ZERO];
+        for number in 1..=4u64 {
+            let block = signed_block(&key_a, number, hashes[number as usize - 1]);
+            hashes.push(block.hash());
+            provider_rw.insert_block(block, StorageLocation::Database).unwrap();
+        }
+        provider_rw.commit().unwrap();
+
+        // a reorg unwinds and inserts the new blocks within the same transaction
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw.remove_blocks_above(2, StorageLocation::Database).unwrap();
+        hashes.truncate(3);
+        for number in 3..=4u64 {
+            let block = signed_block(&key_b, number, hashes[number as usize - 1]);
+            hashes.push(block.hash());
+            provider_rw.insert_block(block, StorageLocation::Database).unwrap();
+        }
+        provider_rw.commit().unwrap();
+
+        let provider = factory.provider().unwrap();
+        assert_eq!(
+            provider.block_signers(1..=4).unwrap(),
+            vec![(1, signer_a), (2, signer_a), (3, signer_b), (4, signer_b)]
+        );
+        let blocks = provider.signer_blocks(signer_b, 0..=u64::MAX).unwrap();
+        assert_eq!(blocks.iter().map(|b| b.hash).collect::<Vec<_>>(), hashes[3..].to_vec());
+        assert_eq!(provider.signer_blocks(signer_a, 0..=u64::MAX).unwrap().len(), 2);
+        assert_eq!(
+            provider.get_stage_checkpoint(SIGNER_INDEX_STAGE).unwrap(),
+            Some(StageCheckpoint::new(4))
+        );
+    }
+
+    #[test]
+    fn consensus_state_persisted_with_block() {
+        let factory = create_test_provider_factory();
+        let signers = vec![Address::with_last_byte(0xa)];
+        let (canonical, side) = (B256::with_last_byte(1), B256::with_last_byte(2));
+        let snapshot = Snapshot::new_snapshot(APosConfig::default(), 1, canonical, signers.clone());
+        factory.consensus_state().insert_snapshot(canonical, snapshot.clone());
+        factory.consensus_state().insert_signer(canonical, 1, signers[0]);
+        factory.consensus_state().insert_snapshot(
+            side,
+            Snapshot::new_snapshot(APosConfig::default(), 1, side, signers.clone()),
+        );
+
+        // the blocks are not persisted, the state is read from the buffer only
+        factory.provider_rw().unwrap().commit().unwrap();
+        let provider = factory.provider().unwrap();
+        assert_eq!(provider.load_snapshot_by_hash(&canonical).unwrap(), Some(snapshot.clone()));
+        assert_eq!(provider.tx_ref().get::<tables::SnapshotsByHash>(canonical).unwrap(), None);
+        drop(provider);
+
+        // persisting the block writes its state and drops the side chain one
+        let provider_rw = factory.provider_rw().unwrap();
+        provider_rw.tx_ref().put::<tables::HeaderNumbers>(canonical, 1).unwrap();
+        provider_rw.commit().unwrap();
+        assert!(factory.consensus_state().is_empty());
+        let provider = factory.provider().unwrap();
+        assert_eq!(
+            provider.tx_ref().get::<tables::SnapshotsByHash>(canonical).unwrap(),
+            Some(snapshot)
+        );
+        assert_eq!(
+            provider.tx_ref().get::<tables::SignersByHash>(canonical).unwrap(),
+            Some(signers[0])
+        );
+        assert_eq!(provider.load_snapshot_by_hash(&side).unwrap(), None);
+    }
+
+    #[test]
+    fn remove_stale_consensus_state() {
+        let factory = create_test_provider_factory();
+        let signers = vec![Address::with_last_byte(0xa)];
+        let provider_rw = factory.provider_rw().unwrap();
+        for number in 1..=3u64 {
+            let hash = B256::with_last_byte(number as u8);
+            let snapshot =
+                Snapshot::new_snapshot(APosConfig::default(), number, hash, signers.clone());
+            provider_rw.save_snapshot_by_hash(&hash, snapshot).unwrap();
+            provider_rw.save_signer_by_hash(&hash, number, signers[0]).unwrap();
+        }
+        // block 3 was never persisted, block 2 is persisted at another height
+        provider_rw.tx_ref().put::<tables::HeaderNumbers>(B256::with_last_byte(1), 1).unwrap();
+        provider_rw.tx_ref().put::<tables::HeaderNumbers>(B256::with_last_byte(2), 5).unwrap();
+
+        assert_eq!(provider_rw.remove_stale_consensus_state().unwrap(), 3);
+        assert!(provider_rw.load_snapshot_by_hash(&B256::with_last_byte(1)).unwrap().is_some());
+        assert!(provider_rw.load_snapshot_by_hash(&B256::with_last_byte(2)).unwrap().is_none());
+        assert!(provider_rw
+            .tx_ref()
+            .get::<tables::SignersByHash>(B256::with_last_byte(3))
+            .unwrap()
+            .is_none());
+    }
 }
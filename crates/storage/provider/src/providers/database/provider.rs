@@ -1,7 +1,7 @@
 use crate::{
     bundle_state::StorageRevertsIter,
     providers::{
-        database::{chain::ChainStorage, metrics},
+        database::{chain::ChainStorage, metrics, PendingConsensusState},
         static_file::StaticFileWriter,
         NodeTypesForProvider, StaticFileProvider,
     },
@@ -23,7 +23,7 @@ use alloy_consensus::{
     transaction::{SignerRecoverable, TransactionMeta},
     BlockHeader, Header, TxReceipt,
 };
-use alloy_eips::{eip2718::Encodable2718, eip4895::Withdrawals, BlockHashOrNumber};
+use alloy_eips::{eip2718::Encodable2718, eip4895::Withdrawals, BlockHashOrNumber, BlockNumHash};
 use alloy_primitives::{
     keccak256,
     map::{hash_map, B256Map, HashMap, HashSet},
@@ -36,8 +36,8 @@ use reth_db_api::{
     cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW},
     database::Database,
     models::{
-        sharded_key, storage_sharded_key::StorageShardedKey, AccountBeforeTx, BlockNumberAddress,
-        ShardedKey, StoredBlockBodyIndices,
+        sharded_key, storage_sharded_key::StorageShardedKey, AccountBeforeTx, AddressBlockNumber,
+        BlockNumberAddress, ShardedKey, StoredBlockBodyIndices,
     },
     table::Table,
     tables,
@@ -47,7 +47,7 @@ use reth_db_api::{
 use reth_execution_types::{Chain, ExecutionOutcome};
 use reth_node_types::{BlockTy, BodyTy, HeaderTy, NodeTypes, ReceiptTy, TxTy};
 use reth_primitives_traits::{
-    Account, Block as _, BlockBody as _, Bytecode, GotExpected, NodePrimitives, RecoveredBlock,
+    header::clique_utils::recover_address_generic, Account, Block as _, BlockBody as _, Bytecode, GotExpected, NodePrimitives, RecoveredBlock,
     SealedBlock, SealedHeader, SignedTransaction, StorageEntry,
 };
 use reth_prune_types::{
@@ -58,7 +58,7 @@ use reth_static_file_types::StaticFileSegment;
 use reth_storage_api::{
     BlockBodyIndicesProvider, BlockBodyReader, NodePrimitivesProvider, OmmersProvider,
     StateProvider, StorageChangeSetReader, TryIntoHistoricalStateProvider,
-    SnapshotProvider, SnapshotProviderWriter
+    SignerBlocksProvider, SnapshotProvider, SnapshotProviderWriter
 };
 use n42_primitives::Snapshot;
 use reth_storage_errors::provider::{ProviderResult, RootMismatch};
@@ -80,6 +80,9 @@ use std::{
 };
 use tracing::{debug, trace};
 
+/// Checkpoint of the `APos` signer index, the last canonical block it covers.
+pub const SIGNER_INDEX_STAGE: StageId = StageId::Other("APosSigners");
+
 /// A [`DatabaseProvider`] that holds a read-only database transaction.
 pub type DatabaseProviderRO<DB, N> = DatabaseProvider<<DB as Database>::TX, N>;
 
@@ -148,6 +151,10 @@ pub struct DatabaseProvider<TX, N: NodeTypes> {
     prune_modes: PruneModes,
     /// Node storage handler.
     storage: Arc<N::Storage>,
+    /// `APos` consensus state waiting for its blocks, written on commit.
+    consensus_state: Option<PendingConsensusState>,
+    /// Commit writing the `APos` consensus state and signer index with the transaction.
+    consensus_commit: Option<fn(Self) -> ProviderResult<bool>>,
 }
 
 impl<TX, N: NodeTypes> DatabaseProvider<TX, N> {
@@ -155,6 +162,13 @@ impl<TX, N: NodeTypes> DatabaseProvider<TX, N> {
     pub const fn prune_modes_ref(&self) -> &PruneModes {
         &self.prune_modes
     }
+
+    /// Sets the buffer of `APos` consensus state read by this provider, and written by its commit
+    /// for the blocks in the database.
+    pub fn with_consensus_state(mut self, consensus_state: PendingConsensusState) -> Self {
+        self.consensus_state = Some(consensus_state);
+        self
+    }
 }
 
 impl<TX: DbTx + 'static, N: NodeTypes> DatabaseProvider<TX, N> {
@@ -244,7 +258,15 @@ impl<TX: DbTxMut, N: NodeTypes> DatabaseProvider<TX, N> {
         prune_modes: PruneModes,
         storage: Arc<N::Storage>,
     ) -> Self {
-        Self { tx, chain_spec, static_file_provider, prune_modes, storage }
+        Self {
+            tx,
+            chain_spec,
+            static_file_provider,
+            prune_modes,
+            storage,
+            consensus_state: None,
+            consensus_commit: None,
+        }
     }
 }
 
@@ -511,7 +533,15 @@ impl<TX: DbTx + 'static, N: NodeTypesForProvider> DatabaseProvider<TX, N> {
         prune_modes: PruneModes,
         storage: Arc<N::Storage>,
     ) -> Self {
-        Self { tx, chain_spec, static_file_provider, prune_modes, storage }
+        Self {
+            tx,
+            chain_spec,
+            static_file_provider,
+            prune_modes,
+            storage,
+            consensus_state: None,
+            consensus_commit: None,
+        }
     }
 
     /// Consume `DbTx` or `DbTxMut`.
@@ -792,8 +822,39 @@ impl<TX: DbTx + 'static, N: NodeTypesForProvider> DatabaseProvider<TX, N> {
 
 impl<TX: DbTxMut + DbTx + 'static, N: NodeTypes> DatabaseProvider<TX, N> {
     /// Commit database transaction.
+    ///
+    /// The `APos` consensus state and signer index are written with it if the provider was
+    /// created with [`Self::with_consensus_writes`].
     pub fn commit(self) -> ProviderResult<bool> {
-        Ok(self.tx.commit()?)
+        DBProvider::commit(self)
+    }
+
+    /// Removes the `APos` consensus state of blocks missing from the database, or not canonical.
+    ///
+    /// Returns the number of removed snapshots and signers.
+    pub fn remove_stale_consensus_state(&self) -> ProviderResult<usize> {
+        let mut removed = 0;
+
+        let mut cursor = self.tx.cursor_write::<tables::SnapshotsByHash>()?;
+        let mut walker = cursor.walk(None)?;
+        while let Some((hash, snapshot)) = walker.next().transpose()? {
+            let number = self.tx.get::<tables::HeaderNumbers>(hash)?;
+            if number != Some(snapshot.number) || snapshot.hash != hash {
+                walker.delete_current()?;
+                removed += 1;
+            }
+        }
+
+        let mut cursor = self.tx.cursor_write::<tables::SignersByHash>()?;
+        let mut walker = cursor.walk(None)?;
+        while let Some((hash, _)) = walker.next().transpose()? {
+            if self.tx.get::<tables::HeaderNumbers>(hash)?.is_none() {
+                walker.delete_current()?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
     }
 
     /// Load shard and remove it. If list is empty, last shard was full or
@@ -1633,6 +1694,11 @@ impl<TX: DbTx + 'static, N: NodeTypes<ChainSpec: EthereumHardforks>> SnapshotPro
     }
 
     fn load_snapshot_by_hash(&self, block_hash: &BlockHash) -> ProviderResult<Option<Snapshot>> {
+        if let Some(snapshot) =
+            self.consensus_state.as_ref().and_then(|state| state.snapshot(block_hash))
+        {
+            return Ok(Some(snapshot))
+        }
         Ok(self.tx.get::<tables::SnapshotsByHash>(block_hash.clone())?)
     }
 }
@@ -1648,10 +1714,125 @@ impl<TX: DbTxMut, N: NodeTypes<ChainSpec: EthereumHardforks>> SnapshotProviderWr
         Ok(self.tx.put::<tables::SnapshotsByHash>(block_hash.clone(), snapshot)?)
     }
 
-    fn save_signer_by_hash(&self, block_hash: &BlockHash,  signer: Address) -> ProviderResult<()> {
+    fn save_signer_by_hash(&self, block_hash: &BlockHash, _block_number: BlockNumber, signer: Address) -> ProviderResult<()> {
         Ok(self.tx.put::<tables::SignersByHash>(block_hash.clone(), signer)?)
     }
 }
+
+impl<TX: DbTx + 'static, N: NodeTypes> SignerBlocksProvider for DatabaseProvider<TX, N> {
+    fn signer_blocks(
+        &self,
+        signer: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> ProviderResult<Vec<BlockNumHash>> {
+        self.tx
+            .cursor_read::<tables::SignerBlocks>()?
+            .walk_range(AddressBlockNumber::range(signer, range))?
+            .map(|entry| {
+                let (key, hash) = entry?;
+                Ok(BlockNumHash::new(key.block_number(), hash))
+            })
+            .collect()
+    }
+
+    fn block_signers(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> ProviderResult<Vec<(BlockNumber, Address)>> {
+        Ok(self
+            .tx
+            .cursor_read::<tables::CanonicalSigners>()?
+            .walk_range(range)?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+impl<TX: DbTxMut + DbTx + 'static, N: NodeTypes> DatabaseProvider<TX, N> {
+    /// Adds a canonical block to the `APos` signer index.
+    ///
+    /// Blocks without a recoverable seal, like the genesis, are not indexed.
+    pub(crate) fn insert_block_signer<H: reth_primitives_traits::BlockHeader>(
+        &self,
+        header: &H,
+        hash: BlockHash,
+    ) -> ProviderResult<()> {
+        let Ok(signer) = recover_address_generic(header) else { return Ok(()) };
+        self.tx.put::<tables::CanonicalSigners>(header.number(), signer)?;
+        self.tx.put::<tables::SignerBlocks>(AddressBlockNumber((signer, header.number())), hash)?;
+        Ok(())
+    }
+
+    /// Removes the canonical blocks above `block` from the `APos` signer index.
+    pub(crate) fn remove_block_signers_above(&self, block: BlockNumber) -> ProviderResult<()> {
+        let mut cursor = self.tx.cursor_write::<tables::CanonicalSigners>()?;
+        let mut walker = cursor.walk(Some(block + 1))?;
+        while let Some((number, signer)) = walker.next().transpose()? {
+            self.tx.delete::<tables::SignerBlocks>(AddressBlockNumber((signer, number)), None)?;
+            walker.delete_current()?;
+        }
+        Ok(())
+    }
+}
+
+impl<TX: DbTxMut + DbTx + 'static, N: NodeTypesForProvider> DatabaseProvider<TX, N> {
+    /// Makes the commit of this provider write the buffered `APos` consensus state of the blocks
+    /// in the database, and bring the signer index up to date.
+    pub fn with_consensus_writes(mut self) -> Self {
+        self.consensus_commit = Some(Self::commit_consensus_writes);
+        self
+    }
+
+    fn commit_consensus_writes(self) -> ProviderResult<bool> {
+        self.update_block_signers()?;
+
+        let Some(consensus_state) = self.consensus_state.clone() else {
+            return Ok(self.tx.commit()?)
+        };
+        let write =
+            consensus_state.to_write(|hash| Ok(self.tx.get::<tables::HeaderNumbers>(*hash)?))?;
+        for (hash, snapshot) in &write.snapshots {
+            self.tx.put::<tables::SnapshotsByHash>(*hash, snapshot.clone())?;
+        }
+        for (hash, signer) in &write.signers {
+            self.tx.put::<tables::SignersByHash>(*hash, *signer)?;
+        }
+        let committed = self.tx.commit()?;
+        consensus_state.remove_written(&write);
+        Ok(committed)
+    }
+
+    /// Brings the `APos` signer index in line with the canonical headers.
+    ///
+    /// Indexes the headers above [`SIGNER_INDEX_STAGE`], however they were written, and drops the
+    /// unwound ones. On a database created before the index existed, this indexes the whole
+    /// chain. Returns the number of indexed blocks.
+    pub fn update_block_signers(&self) -> ProviderResult<u64> {
+        let mut tip = self.last_block_number()?;
+        if let Some(checkpoint) = self.get_stage_checkpoint(StageId::Headers)? {
+            tip = tip.min(checkpoint.block_number);
+        }
+        let indexed = self
+            .get_stage_checkpoint(SIGNER_INDEX_STAGE)?
+            .map(|checkpoint| checkpoint.block_number)
+            .unwrap_or_default();
+        if indexed == tip {
+            return Ok(0)
+        }
+
+        let mut count = 0;
+        if indexed > tip {
+            self.remove_block_signers_above(tip)?;
+        } else {
+            for header in self.sealed_headers_range(indexed + 1..=tip)? {
+                self.insert_block_signer(header.header(), header.hash())?;
+                count += 1;
+            }
+        }
+        self.save_stage_checkpoint(SIGNER_INDEX_STAGE, StageCheckpoint::new(tip))?;
+        Ok(count)
+    }
+}
+
 impl<TX: DbTx + 'static, N: NodeTypesForProvider> BlockBodyIndicesProvider
     for DatabaseProvider<TX, N>
 {
@@ -3011,6 +3192,17 @@ impl<TX: DbTxMut + DbTx + 'static, N: NodeTypesForProvider + 'static> BlockWrite
     ) -> ProviderResult<()> {
         for hash in self.canonical_hashes_range(block + 1, self.last_block_number()? + 1)? {
             self.tx.delete::<tables::HeaderNumbers>(hash, None)?;
+            self.tx.delete::<tables::SnapshotsByHash>(hash, None)?;
+            self.tx.delete::<tables::SignersByHash>(hash, None)?;
+        }
+
+        self.remove_block_signers_above(block)?;
+        // blocks inserted again before the commit are indexed by it
+        if self
+            .get_stage_checkpoint(SIGNER_INDEX_STAGE)?
+            .is_some_and(|checkpoint| checkpoint.block_number > block)
+        {
+            self.save_stage_checkpoint(SIGNER_INDEX_STAGE, StageCheckpoint::new(block))?;
         }
 
         // Only prune canonical headers after we've removed the block hashes as we rely on data from
@@ -3227,6 +3419,13 @@ impl<TX: DbTx + 'static, N: NodeTypes + 'static> DBProvider for DatabaseProvider
         self.tx
     }
 
+    fn commit(self) -> ProviderResult<bool> {
+        match self.consensus_commit {
+            Some(commit) => commit(self),
+            None => Ok(self.tx.commit()?),
+        }
+    }
+
     fn prune_modes_ref(&self) -> &PruneModes {
         self.prune_modes_ref()
     }
@@ -1,5 +1,7 @@
-use alloy_eips::BlockHashOrNumber;
+use alloc::vec::Vec;
+use alloy_eips::{BlockHashOrNumber, BlockNumHash};
 use alloy_primitives::{Address, BlockNumber, BlockHash};
+use core::ops::RangeInclusive;
 use reth_storage_errors::provider::ProviderResult;
 use n42_primitives::Snapshot;
 
@@ -17,7 +19,30 @@ pub trait SnapshotProviderWriter{
     fn save_snapshot(&self, id: BlockNumber, snapshot: Snapshot) -> ProviderResult<bool>;
 
     /// save snapshot by hash
+    ///
+    /// Providers persisting blocks in the background keep the snapshot until the block is
+    /// persisted, and persist it along with the block.
     fn save_snapshot_by_hash(&self, block_hash: &BlockHash,  snapshot: Snapshot) -> ProviderResult<()>;
 
-    fn save_signer_by_hash(&self, block_hash: &BlockHash,  signer: Address) -> ProviderResult<()>;
+    /// save the signer of the block `block_hash` at `block_number`, like the snapshots
+    fn save_signer_by_hash(&self, block_hash: &BlockHash, block_number: BlockNumber, signer: Address) -> ProviderResult<()>;
+}
+
+/// Index of the canonical blocks by their `APos` sealer.
+///
+/// The index is written when blocks are committed to the database and unwound with them.
+#[auto_impl::auto_impl(&, Arc)]
+pub trait SignerBlocksProvider {
+    /// Returns the canonical blocks in `range` sealed by `signer`, in ascending order.
+    fn signer_blocks(
+        &self,
+        signer: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> ProviderResult<Vec<BlockNumHash>>;
+
+    /// Returns the sealers of the canonical blocks in `range`, in ascending order.
+    fn block_signers(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> ProviderResult<Vec<(BlockNumber, Address)>>;
 }